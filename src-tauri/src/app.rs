@@ -3,7 +3,6 @@ use tauri::{AppHandle, Manager, Runtime};
 
 use crate::ai::generator::SceneGenerator;
 use crate::commands::AppState;
-use crate::link::integration::LinkIntegration;
 use crate::midi::devices::DeviceRegistryFactory;
 use crate::midi::engine::MidiEngine;
 use crate::project::manager::ProjectManager;
@@ -41,24 +40,36 @@ pub fn init<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
         Arc::clone(&midi_engine),
     )));
 
+    // Let the frontend reload the project after edits made from the controller
+    let app_handle = app.clone();
+    project_manager
+        .lock()
+        .unwrap()
+        .set_project_changed_callback(move || {
+            let _ = app_handle.emit_all("project-updated", ());
+        });
+
+    // Report background problems, like devices failing to reconnect
+    let app_handle = app.clone();
+    project_manager
+        .lock()
+        .unwrap()
+        .set_warning_callback(move || {
+            let _ = app_handle.emit_all("warnings-updated", ());
+        });
+
+    // Reopen the last project, with its controller and outputs, if it auto-connects
+    {
+        let project_manager = project_manager.lock().unwrap();
+        if let Err(e) = project_manager.restore_last_project() {
+            project_manager.warn(format!("Failed to restore the last project: {}", e));
+        }
+    }
+
     // Initialize AI scene generator
     let api_key = std::env::var("OPENAI_API_KEY").ok();
     let scene_generator = Arc::new(Mutex::new(SceneGenerator::new(api_key)));
 
-    // Initialize Link integration
-    let default_tempo = 120.0; // Default tempo
-    let mut link = LinkIntegration::new(default_tempo);
-
-    // Set up Link callbacks
-    let midi_engine_for_link = Arc::clone(&midi_engine);
-    link.set_beat_callback(move |beat| {
-        // Handle beat events
-        if let Ok(mut engine) = midi_engine_for_link.lock() {
-            // Update engine with beat information
-            let _ = engine.send_command(crate::midi::engine::MidiCommand::SetTempo(default_tempo));
-        }
-    });
-
     // Create app state
     let state = AppState {
         project_manager: Arc::clone(&project_manager),
@@ -94,10 +105,18 @@ pub fn scan_midi_devices_background<R: Runtime>(app: &AppHandle<R>) {
         loop {
             // Get the state
             if let Some(state) = app_handle.try_state::<AppState>() {
-                let mut project_manager = state.project_manager.lock().unwrap();
-
-                // Scan for devices
-                let _ = project_manager.device_registry.scan_devices();
+                let device_registry =
+                    Arc::clone(&state.project_manager.lock().unwrap().device_registry);
+
+                // Scan for devices outside the project manager's lock, since new
+                // devices take a moment to answer, then reconnect any that were
+                // unplugged and came back
+                if let Ok(devices) = device_registry.scan_devices() {
+                    let project_manager = state.project_manager.lock().unwrap();
+                    if let Ok(true) = project_manager.sync_devices(&devices) {
+                        let _ = app_handle.emit_all("controllers-updated", ());
+                    }
+                }
 
                 // Emit an event to notify the frontend
                 let _ = app_handle.emit_all("devices-updated", ());
@@ -4,10 +4,18 @@ use std::sync::{Arc, Mutex};
 use tauri::{command, State};
 
 use crate::ai::generator::{GeneratedScene, GenerationParams, SceneGenerator};
+use crate::midi::controller::Color;
 use crate::midi::devices::MidiDevice;
-use crate::models::project::Project;
+use crate::midi::learn::{LearnAction, MidiMapping};
+use crate::midi::loop_guard::{EchoWindow, SuppressedEvent};
+use crate::midi::recorder::TrackSplit;
+use crate::midi::simulator::Led;
+use crate::midi::thru::ThruRoute;
+use crate::models::grid::ControllerView;
+use crate::models::project::{ControllerButton, Project};
 use crate::models::scene::Scene;
 use crate::project::manager::ProjectManager;
+use crate::project::midi_import::ImportOptions;
 use crate::project::storage::ProjectMeta;
 
 /// App state containing shared resources
@@ -160,6 +168,23 @@ pub async fn export_project(
     }
 }
 
+#[tauri::command]
+pub async fn import_midi_scenes(
+    path: String,
+    options: ImportOptions,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<String>>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.import_midi_file(PathBuf::from(path), &options) {
+        Ok(ids) => Ok(CommandResponse::success(ids)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to import MIDI file: {}",
+            e
+        ))),
+    }
+}
+
 #[tauri::command]
 pub async fn close_project(state: State<'_, AppState>) -> Result<CommandResponse<bool>, String> {
     let mut project_manager = state.project_manager.lock().unwrap();
@@ -208,6 +233,22 @@ pub async fn get_scene(
     }
 }
 
+#[tauri::command]
+pub async fn update_scene(
+    scene: Scene,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.update_scene(scene) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to update scene: {}",
+            e
+        ))),
+    }
+}
+
 #[tauri::command]
 pub async fn activate_scene(
     id: String,
@@ -224,6 +265,48 @@ pub async fn activate_scene(
     }
 }
 
+#[tauri::command]
+pub async fn get_active_scenes(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<String>>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    Ok(CommandResponse::success(project_manager.active_scene_ids()))
+}
+
+#[tauri::command]
+pub async fn release_group(
+    group: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.release_group(&group) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to release group: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn set_column_group(
+    column: u8,
+    name: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.set_column_group(column, name.as_deref()) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to set column group: {}",
+            e
+        ))),
+    }
+}
+
 #[tauri::command]
 pub async fn assign_scene_to_grid(
     scene_id: String,
@@ -241,6 +324,167 @@ pub async fn assign_scene_to_grid(
     }
 }
 
+#[tauri::command]
+pub async fn get_current_bank(state: State<'_, AppState>) -> Result<CommandResponse<u8>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    Ok(CommandResponse::success(project_manager.current_bank()))
+}
+
+#[tauri::command]
+pub async fn select_scene_bank(
+    bank: u8,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.select_bank(bank) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to select bank: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn add_scene_bank(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<u8>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.add_bank(&name) {
+        Ok(bank) => Ok(CommandResponse::success(bank)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to add bank: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn remove_scene_bank(
+    bank: u8,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.remove_bank(bank) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to remove bank: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn set_grid_size(
+    rows: u8,
+    columns: u8,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<String>>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.set_grid_size(rows, columns) {
+        Ok(unassigned) => Ok(CommandResponse::success(unassigned)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to set grid size: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_controller_views(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<ControllerView>>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    Ok(CommandResponse::success(project_manager.controller_views()))
+}
+
+#[tauri::command]
+pub async fn scroll_controller_viewport(
+    device_id: Option<String>,
+    rows: i8,
+    columns: i8,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Option<ControllerView>>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.scroll_viewport(device_id.as_deref(), rows, columns) {
+        Ok(view) => Ok(CommandResponse::success(view)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to scroll controller: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn select_controller_bank(
+    device_id: String,
+    bank: u8,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.select_controller_bank(&device_id, bank) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to select controller bank: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_fader_mode(
+    device_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    Ok(CommandResponse::success(
+        project_manager.fader_mode(device_id.as_deref()),
+    ))
+}
+
+#[tauri::command]
+pub async fn set_fader_mode(
+    device_id: Option<String>,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.set_fader_mode(device_id.as_deref(), enabled) {
+        Ok(_) => Ok(CommandResponse::success(enabled)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to switch fader mode: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn set_fader_bindings(
+    keys: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.set_fader_bindings(keys) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to bind faders: {}",
+            e
+        ))),
+    }
+}
+
 /// AI generation commands
 
 #[tauri::command]
@@ -298,21 +542,177 @@ pub async fn check_backend_status() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn list_midi_devices(
+pub async fn get_suppressed_events(
     state: State<'_, AppState>,
-) -> Result<CommandResponse<Vec<MidiDevice>>, String> {
+) -> Result<CommandResponse<Vec<SuppressedEvent>>, String> {
     let project_manager = state.project_manager.lock().unwrap();
 
-    // Get devices through the public method instead of accessing private field
-    match ProjectManager::get_midi_devices() {
-        Ok(devices) => Ok(CommandResponse::success(devices)),
+    Ok(CommandResponse::success(
+        project_manager.suppressed_events(),
+    ))
+}
+
+#[tauri::command]
+pub async fn get_warnings(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<String>>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    Ok(CommandResponse::success(project_manager.warnings()))
+}
+
+/// Press a pad of the simulated Launchpad, or release it with velocity 0
+#[tauri::command]
+pub async fn simulator_pad(
+    row: u8,
+    column: u8,
+    velocity: u8,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    // Press without the lock, since the app answers by lighting the pad
+    let simulator = state.project_manager.lock().unwrap().simulator();
+
+    match simulator {
+        Ok(simulator) => {
+            if velocity == 0 {
+                simulator.release_pad(row, column);
+            } else {
+                simulator.press_pad(row, column, velocity);
+            }
+            Ok(CommandResponse::success(true))
+        }
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to press simulator pad: {}",
+            e
+        ))),
+    }
+}
+
+/// Press or release a button of the simulated Launchpad
+#[tauri::command]
+pub async fn simulator_button(
+    button_id: u8,
+    pressed: bool,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let simulator = state.project_manager.lock().unwrap().simulator();
+
+    match simulator {
+        Ok(simulator) => {
+            if pressed {
+                simulator.press_button(button_id);
+            } else {
+                simulator.release_button(button_id);
+            }
+            Ok(CommandResponse::success(true))
+        }
         Err(e) => Ok(CommandResponse::error(&format!(
-            "Failed to get MIDI devices: {}",
+            "Failed to press simulator button: {}",
             e
         ))),
     }
 }
 
+#[tauri::command]
+pub async fn get_simulator_pad(
+    row: u8,
+    column: u8,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Option<Led>>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.simulator() {
+        Ok(simulator) => Ok(CommandResponse::success(simulator.pad(row, column))),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to read simulator pad: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_simulator_button(
+    button_id: u8,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Option<Color>>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.simulator() {
+        Ok(simulator) => Ok(CommandResponse::success(simulator.button(button_id))),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to read simulator button: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn list_echo_windows(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<EchoWindow>>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.list_echo_windows() {
+        Ok(windows) => Ok(CommandResponse::success(windows)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to list echo windows: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn set_echo_window(
+    input_device: String,
+    output_device: String,
+    window_ms: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.set_echo_window(&input_device, &output_device, window_ms) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to set echo window: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn list_midi_devices(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<MidiDevice>>, String> {
+    // The background scan keeps the registry up to date
+    let devices = state.project_manager.lock().unwrap().get_midi_devices();
+
+    Ok(CommandResponse::success(devices))
+}
+
+#[tauri::command]
+pub async fn list_skipped_profiles(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<String>>, String> {
+    let skipped = state
+        .project_manager
+        .lock()
+        .unwrap()
+        .skipped_controller_profiles();
+
+    Ok(CommandResponse::success(skipped))
+}
+
+#[tauri::command]
+pub async fn reload_controller_profiles(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<usize>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    Ok(CommandResponse::success(
+        project_manager.reload_controller_profiles(),
+    ))
+}
+
 #[tauri::command]
 pub async fn connect_controller(
     deviceId: String,
@@ -330,13 +730,62 @@ pub async fn connect_controller(
     }
 }
 
+#[tauri::command]
+pub async fn set_auto_connect(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.set_auto_connect(enabled) {
+        Ok(_) => Ok(CommandResponse::success(enabled)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to set auto-connect: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn set_use_link(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.set_use_link(enabled) {
+        Ok(_) => Ok(CommandResponse::success(enabled)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to set Link sync: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn connect_output(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.connect_output_device(&name) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to connect output: {}",
+            e
+        ))),
+    }
+}
+
 #[tauri::command]
 pub async fn disconnect_controller(
+    device_id: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<CommandResponse<bool>, String> {
     let mut project_manager = state.project_manager.lock().unwrap();
 
-    match project_manager.disconnect_controller() {
+    match project_manager.disconnect_controller(device_id.as_deref()) {
         Ok(_) => Ok(CommandResponse::success(true)),
         Err(e) => Ok(CommandResponse::error(&format!(
             "Failed to disconnect controller: {}",
@@ -360,6 +809,229 @@ pub async fn send_cc(
     }
 }
 
+/// MIDI thru commands
+
+#[tauri::command]
+pub async fn list_thru_routes(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<ThruRoute>>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.list_thru_routes() {
+        Ok(routes) => Ok(CommandResponse::success(routes)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to list thru routes: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn add_thru_route(
+    route: ThruRoute,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<String>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.add_thru_route(route) {
+        Ok(id) => Ok(CommandResponse::success(id)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to add thru route: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn remove_thru_route(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.remove_thru_route(&id) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to remove thru route: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn list_midi_mappings(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Vec<MidiMapping>>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.list_midi_mappings() {
+        Ok(mappings) => Ok(CommandResponse::success(mappings)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to list MIDI mappings: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn add_midi_mapping(
+    mapping: MidiMapping,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<String>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.add_midi_mapping(mapping) {
+        Ok(id) => Ok(CommandResponse::success(id)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to add MIDI mapping: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn remove_midi_mapping(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.remove_midi_mapping(&id) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to remove MIDI mapping: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn start_midi_learn(
+    action: LearnAction,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.start_midi_learn(action) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to start MIDI learn: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn cancel_midi_learn(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.cancel_midi_learn() {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to cancel MIDI learn: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn get_midi_learn(
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<Option<LearnAction>>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    Ok(CommandResponse::success(
+        project_manager.midi_learn_action(),
+    ))
+}
+
+#[tauri::command]
+pub async fn start_recording(state: State<'_, AppState>) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.start_recording() {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to start recording: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn stop_recording(
+    path: String,
+    split: Option<TrackSplit>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<usize>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.stop_recording(PathBuf::from(path), split.unwrap_or_default()) {
+        Ok(count) => Ok(CommandResponse::success(count)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to save recording: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn is_recording(state: State<'_, AppState>) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+    Ok(CommandResponse::success(project_manager.is_recording()))
+}
+
+#[tauri::command]
+pub async fn panic(
+    defaults: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.panic(defaults.unwrap_or(false)) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to send panic: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn set_controller_button(
+    button: ControllerButton,
+    button_id: Option<u8>,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.set_controller_button(button, button_id) {
+        Ok(_) => Ok(CommandResponse::success(true)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to set controller button: {}",
+            e
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn set_panic_sends_defaults(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<CommandResponse<bool>, String> {
+    let project_manager = state.project_manager.lock().unwrap();
+
+    match project_manager.set_panic_sends_defaults(enabled) {
+        Ok(_) => Ok(CommandResponse::success(enabled)),
+        Err(e) => Ok(CommandResponse::error(&format!(
+            "Failed to set panic defaults: {}",
+            e
+        ))),
+    }
+}
+
 #[command]
 pub fn debug_midi_parameters(
     deviceId: String,
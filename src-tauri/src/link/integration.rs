@@ -8,16 +8,16 @@ use std::time::Duration;
 /// This provides synchronization with other music applications
 /// using the Ableton Link protocol
 pub struct LinkIntegration {
-    // The underlying Ableton Link instance
-    link: rusty_link::AblLink,
+    // The underlying Ableton Link instance, shared with the timing thread
+    link: Arc<rusty_link::AblLink>,
     // Whether Link is currently enabled
     enabled: Arc<Mutex<bool>>,
     // Current tempo in BPM
     tempo: Arc<Mutex<f64>>,
     // Current beat position
     beat_position: Arc<Mutex<f64>>,
-    // Callback to invoke on beat events
-    beat_callback: Arc<Mutex<Option<Box<dyn Fn(u32) + Send>>>>,
+    // Callback to invoke on beat events, with the beat position and tempo
+    beat_callback: Arc<Mutex<Option<Box<dyn Fn(f64, f64) + Send>>>>,
     // Callback to invoke on bar events (assuming 4/4 time signature)
     bar_callback: Arc<Mutex<Option<Box<dyn Fn(u32) + Send>>>>,
     // Thread handle for the timing thread
@@ -28,7 +28,7 @@ impl LinkIntegration {
     /// Create a new Link integration
     pub fn new(initial_tempo: f64) -> Self {
         // Create the Link instance with initial tempo
-        let link = rusty_link::AblLink::new(initial_tempo);
+        let link = Arc::new(rusty_link::AblLink::new(initial_tempo));
 
         // Start with Link disabled
         link.enable(false);
@@ -104,65 +104,57 @@ impl LinkIntegration {
         phase
     }
 
-    /// Start the Link timing thread
-    // In link/integration.rs, modify the start method to not clone AblLink directly
+    /// Enable Link and start the timing thread
     pub fn start(&mut self) {
         // Stop any existing thread
         self.stop();
+        self.enable(true);
 
         let enabled = Arc::clone(&self.enabled);
         let beat_position = Arc::clone(&self.beat_position);
+        let tempo = Arc::clone(&self.tempo);
         let beat_callback = Arc::clone(&self.beat_callback);
         let bar_callback = Arc::clone(&self.bar_callback);
-
-        // Instead of cloning the Link instance, create an Arc to share it
-        use std::sync::Mutex;
-        let link = Arc::new(Mutex::new(rusty_link::AblLink::new(self.get_tempo())));
-        let link_clone = Arc::clone(&link);
+        let link = Arc::clone(&self.link);
 
         let handle = thread::spawn(move || {
-            let mut last_beat = 0;
-            let mut last_bar = 0;
+            let mut last_beat = None;
+            let mut last_bar = None;
+            let mut last_tempo = None;
 
             while *enabled.lock().unwrap() {
-                if link_clone.lock().unwrap().num_peers() > 0 {
-                    let mut session_state = SessionState::new();
-                    link_clone
-                        .lock()
-                        .unwrap()
-                        .capture_app_session_state(&mut session_state);
-
-                    // Get the current beat position
-                    let beat =
-                        session_state.beat_at_time(link_clone.lock().unwrap().clock_micros(), 4.0);
-
-                    // Rest of the thread function remains the same,
-                    // just replace any reference to 'link' with 'link_clone'
-
-                    // Update the shared beat position
-                    *beat_position.lock().unwrap() = beat;
-
-                    // Calculate the current beat and bar
-                    let current_beat = beat.floor() as u32;
-                    let current_bar = (current_beat / 4) as u32;
-
-                    // If we've moved to a new beat, trigger the beat callback
-                    if current_beat != last_beat {
-                        if let Some(callback) = &*beat_callback.lock().unwrap() {
-                            callback(current_beat % 4);
-                        }
-
-                        last_beat = current_beat;
+                let mut session_state = SessionState::new();
+                link.capture_app_session_state(&mut session_state);
+
+                // Get the current beat position and tempo
+                let beat = session_state.beat_at_time(link.clock_micros(), 4.0);
+                let session_tempo = session_state.tempo();
+
+                // Update the shared beat position and tempo
+                *beat_position.lock().unwrap() = beat;
+                *tempo.lock().unwrap() = session_tempo;
+
+                // Calculate the current beat and bar
+                let current_beat = beat.floor() as i64;
+                let current_bar = current_beat.div_euclid(4);
+
+                // On a new beat or tempo, trigger the beat callback
+                if last_beat != Some(current_beat) || last_tempo != Some(session_tempo) {
+                    if let Some(callback) = &*beat_callback.lock().unwrap() {
+                        callback(beat, session_tempo);
                     }
 
-                    // If we've moved to a new bar, trigger the bar callback
-                    if current_bar != last_bar {
-                        if let Some(callback) = &*bar_callback.lock().unwrap() {
-                            callback(current_bar);
-                        }
+                    last_beat = Some(current_beat);
+                    last_tempo = Some(session_tempo);
+                }
 
-                        last_bar = current_bar;
+                // If we've moved to a new bar, trigger the bar callback
+                if last_bar != Some(current_bar) {
+                    if let Some(callback) = &*bar_callback.lock().unwrap() {
+                        callback(current_bar.max(0) as u32);
                     }
+
+                    last_bar = Some(current_bar);
                 }
 
                 // Sleep for a short time to avoid using too much CPU
@@ -174,17 +166,13 @@ impl LinkIntegration {
         *thread_handle = Some(handle);
     }
 
-    /// Stop the Link timing thread
+    /// Stop the Link timing thread and disable Link
     pub fn stop(&mut self) {
-        let mut thread_handle = self.thread_handle.lock().unwrap();
-
-        if let Some(handle) = thread_handle.take() {
-            // Set enabled to false to stop the thread
-            {
-                let mut enabled = self.enabled.lock().unwrap();
-                *enabled = false;
-            }
+        // The thread runs while Link is enabled
+        self.enable(false);
 
+        let handle = self.thread_handle.lock().unwrap().take();
+        if let Some(handle) = handle {
             // Wait for the thread to finish
             if handle.join().is_err() {
                 // Handle error if thread panicked
@@ -193,10 +181,11 @@ impl LinkIntegration {
         }
     }
 
-    /// Set the callback to invoke on beat boundaries
+    /// Set the callback to invoke on beat boundaries and tempo changes, with the
+    /// session's beat position and tempo
     pub fn set_beat_callback<F>(&mut self, callback: F)
     where
-        F: Fn(u32) + Send + 'static,
+        F: Fn(f64, f64) + Send + 'static,
     {
         let mut beat_callback = self.beat_callback.lock().unwrap();
         *beat_callback = Some(Box::new(callback));
@@ -37,22 +37,68 @@ fn main() {
             commands::import_project,
             commands::export_project,
             commands::close_project,
+            commands::import_midi_scenes,
             // Scene commands
             commands::create_scene,
             commands::get_scene,
+            commands::update_scene,
             commands::activate_scene,
+            commands::get_active_scenes,
+            commands::release_group,
+            commands::set_column_group,
             commands::assign_scene_to_grid,
+            commands::get_current_bank,
+            commands::select_scene_bank,
+            commands::add_scene_bank,
+            commands::remove_scene_bank,
+            commands::set_grid_size,
+            commands::get_controller_views,
+            commands::scroll_controller_viewport,
+            commands::select_controller_bank,
+            commands::get_fader_mode,
+            commands::set_fader_mode,
+            commands::set_fader_bindings,
             // MIDI device commands
             commands::list_midi_devices,
+            commands::list_skipped_profiles,
             commands::connect_controller,
             commands::disconnect_controller,
+            commands::set_auto_connect,
+            commands::connect_output,
+            commands::set_use_link,
             commands::send_cc,
+            commands::panic,
+            commands::set_controller_button,
+            commands::set_panic_sends_defaults,
+            commands::start_recording,
+            commands::stop_recording,
+            commands::is_recording,
+            // MIDI thru commands
+            commands::list_thru_routes,
+            commands::add_thru_route,
+            commands::remove_thru_route,
+            // MIDI learn commands
+            commands::list_midi_mappings,
+            commands::add_midi_mapping,
+            commands::remove_midi_mapping,
+            commands::start_midi_learn,
+            commands::cancel_midi_learn,
+            commands::get_midi_learn,
             // AI generation commands
             commands::generate_scene,
             commands::save_generated_scene,
             // Diagnostic commands
             commands::check_backend_status,
             commands::debug_connect_controller,
+            commands::get_suppressed_events,
+            commands::get_warnings,
+            commands::simulator_pad,
+            commands::simulator_button,
+            commands::get_simulator_pad,
+            commands::get_simulator_button,
+            commands::reload_controller_profiles,
+            commands::list_echo_windows,
+            commands::set_echo_window,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
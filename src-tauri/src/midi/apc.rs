@@ -0,0 +1,466 @@
+use std::sync::Arc;
+
+use midir::{MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+
+use crate::midi::controller::{select_port, ButtonRole, Color, ControllerEvent, GridController};
+use crate::midi::devices::MidiDevice;
+use crate::midi::palette::NOVATION_PALETTE;
+
+/// Akai manufacturer ID and "all devices" ID that start every APC SysEx
+const AKAI_SYSEX_HEADER: [u8; 3] = [0xF0, 0x47, 0x7F];
+
+/// APC mini mk2 SysEx ID
+const APC_MINI_MK2_ID: u8 = 0x4F;
+
+/// APC40 mk2 SysEx ID
+const APC40_MK2_ID: u8 = 0x29;
+
+/// APC mini mk2 track buttons (below the grid, red LEDs)
+const MINI_TRACK_BUTTONS: std::ops::RangeInclusive<u8> = 100..=107;
+
+/// APC mini mk2 scene buttons (right of the grid, green LEDs)
+const MINI_SCENE_BUTTONS: std::ops::RangeInclusive<u8> = 112..=119;
+
+/// APC mini mk2 fader CCs (8 track faders and the master)
+const MINI_FADERS: std::ops::RangeInclusive<u8> = 48..=56;
+
+/// APC40 mk2 scene launch buttons (RGB)
+const APC40_SCENE_BUTTONS: std::ops::RangeInclusive<u8> = 82..=86;
+
+/// APC mini mk2 shift button
+const MINI_SHIFT: u8 = 122;
+
+/// APC40 mk2 bank select arrows and shift button
+const APC40_BANK_UP: u8 = 94;
+const APC40_BANK_DOWN: u8 = 95;
+const APC40_BANK_RIGHT: u8 = 96;
+const APC40_BANK_LEFT: u8 = 97;
+const APC40_SHIFT: u8 = 98;
+
+/// Akai APC models
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApcModel {
+    /// 8x8 RGB pads, track and scene buttons, 9 faders
+    MiniMk2,
+    /// 5x8 clip grid, faders, track and device knobs
+    Apc40Mk2,
+}
+
+impl ApcModel {
+    /// Detect the model from a MIDI port name
+    pub fn from_port_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+
+        if name.contains("apc mini mk2") {
+            Some(ApcModel::MiniMk2)
+        } else if name.contains("apc40 mkii") || name.contains("apc40 mk2") {
+            Some(ApcModel::Apc40Mk2)
+        } else {
+            None
+        }
+    }
+
+    /// Port name tag of the port that carries LED and control messages
+    ///
+    /// The APC mini mk2 also exposes a "Notes" port for its note mode, which ALSA
+    /// truncates the control port name next to, hence "contr".
+    fn control_port_tag(&self) -> &'static str {
+        match self {
+            ApcModel::MiniMk2 => "apc mini mk2 contr",
+            ApcModel::Apc40Mk2 => "apc40 mkii",
+        }
+    }
+
+    /// Number of pad rows
+    fn rows(&self) -> u8 {
+        match self {
+            ApcModel::MiniMk2 => 8,
+            ApcModel::Apc40Mk2 => 5,
+        }
+    }
+
+    /// Translate an incoming message into a controller event
+    fn parse_event(&self, message: &[u8]) -> Option<ControllerEvent> {
+        let (&status, &data1, &data2) = match message {
+            [status, data1, data2, ..] => (status, data1, data2),
+            _ => return None,
+        };
+        let channel = status & 0x0F;
+        let pad_count = self.rows() * 8;
+
+        match (status & 0xF0, data2) {
+            (0x80, _) | (0x90, 0) if data1 < pad_count => Some(ControllerEvent::PadReleased(data1)),
+            (0x90, velocity) if data1 < pad_count => {
+                Some(ControllerEvent::PadPressed(data1, velocity))
+            }
+            (0x80, _) | (0x90, 0) => Some(ControllerEvent::ButtonReleased(data1)),
+            (0x90, _) => Some(ControllerEvent::ButtonPressed(data1)),
+            (0xB0, value) => self.parse_control_change(channel, data1, value),
+            _ => None,
+        }
+    }
+
+    /// Translate a fader or knob CC into a controller event
+    fn parse_control_change(&self, channel: u8, cc: u8, value: u8) -> Option<ControllerEvent> {
+        match self {
+            ApcModel::MiniMk2 if MINI_FADERS.contains(&cc) => {
+                Some(ControllerEvent::FaderMoved(cc - MINI_FADERS.start(), value))
+            }
+            ApcModel::MiniMk2 => None,
+            ApcModel::Apc40Mk2 => match cc {
+                // Track faders 0-7 on their own channels, then master and crossfader
+                7 => Some(ControllerEvent::FaderMoved(channel, value)),
+                14 => Some(ControllerEvent::FaderMoved(8, value)),
+                15 => Some(ControllerEvent::FaderMoved(9, value)),
+                // Track knobs 0-7, then the device knobs 8-15
+                48..=55 => Some(ControllerEvent::KnobTurned(cc - 48, value)),
+                16..=23 => Some(ControllerEvent::KnobTurned(cc - 16 + 8, value)),
+                // The cue level encoder is relative and not exposed
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Build an APC mini mk2 SysEx setting each pad to a full 8-bit RGB color
+///
+/// Every pad gets its own start/end range, so any set of pads fits in one message.
+fn apc_mini_rgb_sysex(pads: &[(u8, Color)]) -> Vec<u8> {
+    let split = |c: u8| [c >> 7, c & 0x7F];
+    let len = pads.len() * 8;
+
+    let mut message = AKAI_SYSEX_HEADER.to_vec();
+    message.extend_from_slice(&[
+        APC_MINI_MK2_ID,
+        0x24,
+        (len >> 7) as u8 & 0x7F,
+        len as u8 & 0x7F,
+    ]);
+
+    for &(pad, color) in pads {
+        message.extend_from_slice(&[pad, pad]);
+        message.extend_from_slice(&split(color.r));
+        message.extend_from_slice(&split(color.g));
+        message.extend_from_slice(&split(color.b));
+    }
+
+    message.push(0xF7);
+    message
+}
+
+/// Introduction message putting the APC40 mk2 into Alternate Ableton Live mode,
+/// where every LED is under host control
+fn apc40_introduction() -> Vec<u8> {
+    let mut message = AKAI_SYSEX_HEADER.to_vec();
+    message.extend_from_slice(&[APC40_MK2_ID, 0x60, 0x00, 0x04, 0x42, 0x01, 0x00, 0x00, 0xF7]);
+    message
+}
+
+/// Implementation for the Akai APC mini mk2 and APC40 mk2
+pub struct Apc {
+    device: MidiDevice,
+    model: ApcModel,
+    input_connection: Option<MidiInputConnection<()>>,
+    output_connection: Option<MidiOutputConnection>,
+    event_callback: Option<Arc<dyn Fn(ControllerEvent) + Send + Sync>>,
+}
+
+impl Apc {
+    pub fn new(device: MidiDevice, model: ApcModel) -> Self {
+        Apc {
+            device,
+            model,
+            input_connection: None,
+            output_connection: None,
+            event_callback: None,
+        }
+    }
+
+    fn send(&mut self, message: &[u8]) -> Result<(), String> {
+        if let Some(ref mut conn) = self.output_connection {
+            conn.send(message).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Buttons with LEDs, used when clearing
+    fn button_ids(&self) -> Vec<u8> {
+        match self.model {
+            ApcModel::MiniMk2 => MINI_TRACK_BUTTONS.chain(MINI_SCENE_BUTTONS).collect(),
+            ApcModel::Apc40Mk2 => APC40_SCENE_BUTTONS.collect(),
+        }
+    }
+}
+
+impl Clone for Apc {
+    fn clone(&self) -> Self {
+        Apc {
+            device: self.device.clone(),
+            model: self.model,
+            input_connection: None,  // Connections can't be cloned
+            output_connection: None, // Connections can't be cloned
+            event_callback: self.event_callback.clone(),
+        }
+    }
+}
+
+impl GridController for Apc {
+    fn clone_box(&self) -> Box<dyn GridController> {
+        Box::new(self.clone())
+    }
+
+    fn connect(&mut self) -> Result<(), String> {
+        // Connect to MIDI input
+        let midi_in = MidiInput::new("snap-blaster").map_err(|e| e.to_string())?;
+
+        let in_ports = midi_in.ports();
+        let in_names: Vec<String> = in_ports
+            .iter()
+            .map(|p| midi_in.port_name(p).unwrap_or_default())
+            .collect();
+        let in_port = select_port(&in_names, self.model.control_port_tag(), &self.device.name)
+            .map(|i| &in_ports[i])
+            .ok_or_else(|| format!("Could not find MIDI input device: {}", self.device.name))?;
+
+        let callback = {
+            let event_callback = self.event_callback.clone();
+            let model = self.model;
+
+            move |_timestamp, message: &[u8], _: &mut ()| {
+                if let Some(event) = model.parse_event(message) {
+                    if let Some(ref callback) = event_callback {
+                        callback(event);
+                    }
+                }
+            }
+        };
+
+        let input_conn = midi_in
+            .connect(in_port, "apc-input", callback, ())
+            .map_err(|e| e.to_string())?;
+        self.input_connection = Some(input_conn);
+
+        // Connect to MIDI output
+        let midi_out = MidiOutput::new("snap-blaster").map_err(|e| e.to_string())?;
+
+        let out_ports = midi_out.ports();
+        let out_names: Vec<String> = out_ports
+            .iter()
+            .map(|p| midi_out.port_name(p).unwrap_or_default())
+            .collect();
+        let out_port = select_port(&out_names, self.model.control_port_tag(), &self.device.name)
+            .map(|i| &out_ports[i])
+            .ok_or_else(|| format!("Could not find MIDI output device: {}", self.device.name))?;
+
+        let output_conn = midi_out
+            .connect(out_port, "apc-output")
+            .map_err(|e| e.to_string())?;
+        self.output_connection = Some(output_conn);
+
+        if self.model == ApcModel::Apc40Mk2 {
+            self.send(&apc40_introduction())?;
+        }
+
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<(), String> {
+        // Leave the LEDs dark
+        if self.output_connection.is_some() {
+            self.clear()?;
+        }
+
+        self.input_connection = None;
+        self.output_connection = None;
+
+        Ok(())
+    }
+
+    fn set_pad_color(&mut self, grid_id: u8, color: Color) -> Result<(), String> {
+        self.set_pad_colors(&[(grid_id, color)])
+    }
+
+    fn set_pad_colors(&mut self, pads: &[(u8, Color)]) -> Result<(), String> {
+        let (rows, cols) = self.grid_size();
+        let visible: Vec<(u8, Color)> = pads
+            .iter()
+            .filter(|(grid_id, _)| *grid_id < rows * cols)
+            .map(|&(grid_id, color)| (self.map_grid_id(grid_id), color))
+            .collect();
+
+        match self.model {
+            ApcModel::MiniMk2 => {
+                if visible.is_empty() {
+                    return Ok(());
+                }
+                self.send(&apc_mini_rgb_sysex(&visible))
+            }
+            ApcModel::Apc40Mk2 => {
+                // Clip pads take a velocity palette index; channel 0 is solid
+                for (pad, color) in visible {
+                    self.send(&[0x90, pad, NOVATION_PALETTE.nearest(color)])?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn set_button_color(&mut self, button_id: u8, color: Color) -> Result<(), String> {
+        let lit = color.r > 0 || color.g > 0 || color.b > 0;
+
+        let velocity =
+            if self.model == ApcModel::Apc40Mk2 && APC40_SCENE_BUTTONS.contains(&button_id) {
+                NOVATION_PALETTE.nearest(color)
+            } else {
+                // Single-color LEDs are just on or off
+                lit as u8
+            };
+
+        self.send(&[0x90, button_id, velocity])
+    }
+
+    fn clear(&mut self) -> Result<(), String> {
+        let (rows, cols) = self.grid_size();
+        let pads: Vec<(u8, Color)> = (0..rows * cols).map(|id| (id, Color::BLACK)).collect();
+        self.set_pad_colors(&pads)?;
+
+        for button_id in self.button_ids() {
+            self.send(&[0x90, button_id, 0])?;
+        }
+
+        Ok(())
+    }
+
+    fn grid_size(&self) -> (u8, u8) {
+        (self.model.rows(), 8)
+    }
+
+    fn button_role(&self, button_id: u8) -> Option<ButtonRole> {
+        match (self.model, button_id) {
+            (ApcModel::MiniMk2, MINI_SHIFT) => Some(ButtonRole::Shift),
+            (ApcModel::Apc40Mk2, APC40_BANK_UP) => Some(ButtonRole::Up),
+            (ApcModel::Apc40Mk2, APC40_BANK_DOWN) => Some(ButtonRole::Down),
+            (ApcModel::Apc40Mk2, APC40_BANK_RIGHT) => Some(ButtonRole::Right),
+            (ApcModel::Apc40Mk2, APC40_BANK_LEFT) => Some(ButtonRole::Left),
+            (ApcModel::Apc40Mk2, APC40_SHIFT) => Some(ButtonRole::Shift),
+            // Scene buttons select banks from the top
+            (ApcModel::MiniMk2, id) if MINI_SCENE_BUTTONS.contains(&id) => {
+                Some(ButtonRole::Bank(id - MINI_SCENE_BUTTONS.start()))
+            }
+            (ApcModel::Apc40Mk2, id) if APC40_SCENE_BUTTONS.contains(&id) => {
+                Some(ButtonRole::Bank(id - APC40_SCENE_BUTTONS.start()))
+            }
+            _ => None,
+        }
+    }
+
+    fn map_grid_id(&self, app_grid_id: u8) -> u8 {
+        let (rows, cols) = self.grid_size();
+        if app_grid_id >= rows * cols {
+            return 0; // Invalid ID
+        }
+
+        // Both models number pads from the bottom-left, row by row, which matches
+        // the app grid directly
+        app_grid_id
+    }
+
+    fn map_to_app_grid_id(&self, controller_id: u8) -> Option<u8> {
+        let (rows, cols) = self.grid_size();
+        (controller_id < rows * cols).then_some(controller_id)
+    }
+
+    fn set_event_callback(&mut self, callback: Arc<dyn Fn(ControllerEvent) + Send + Sync>) {
+        self.event_callback = Some(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_detection() {
+        assert_eq!(
+            ApcModel::from_port_name("APC mini mk2 Control"),
+            Some(ApcModel::MiniMk2)
+        );
+        assert_eq!(
+            ApcModel::from_port_name("APC40 mkII"),
+            Some(ApcModel::Apc40Mk2)
+        );
+        assert_eq!(ApcModel::from_port_name("APC Key 25"), None);
+    }
+
+    #[test]
+    fn test_apc_mini_rgb_sysex() {
+        let message = apc_mini_rgb_sysex(&[(0, Color::new(255, 128, 1)), (63, Color::BLACK)]);
+
+        assert_eq!(
+            message,
+            vec![
+                0xF0, 0x47, 0x7F, 0x4F, 0x24, 0x00, 16, // header and payload length
+                0, 0, 0x01, 0x7F, 0x01, 0x00, 0x00, 0x01, // pad 0
+                63, 63, 0, 0, 0, 0, 0, 0, // pad 63
+                0xF7,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let mini = ApcModel::MiniMk2;
+        assert!(matches!(
+            mini.parse_event(&[0x96, 12, 127]),
+            Some(ControllerEvent::PadPressed(12, 127))
+        ));
+        assert!(matches!(
+            mini.parse_event(&[0x80, 112, 0]),
+            Some(ControllerEvent::ButtonReleased(112))
+        ));
+        assert!(matches!(
+            mini.parse_event(&[0xB0, 56, 90]),
+            Some(ControllerEvent::FaderMoved(8, 90))
+        ));
+
+        // Notes above the 5x8 grid are buttons on the APC40
+        let apc40 = ApcModel::Apc40Mk2;
+        assert!(matches!(
+            apc40.parse_event(&[0x90, 39, 127]),
+            Some(ControllerEvent::PadPressed(39, 127))
+        ));
+        assert!(matches!(
+            apc40.parse_event(&[0x90, 82, 127]),
+            Some(ControllerEvent::ButtonPressed(82))
+        ));
+        assert!(matches!(
+            apc40.parse_event(&[0xB3, 7, 64]),
+            Some(ControllerEvent::FaderMoved(3, 64))
+        ));
+        assert!(matches!(
+            apc40.parse_event(&[0xB0, 18, 10]),
+            Some(ControllerEvent::KnobTurned(10, 10))
+        ));
+        assert!(apc40.parse_event(&[0xB0, 47, 1]).is_none());
+    }
+
+    #[test]
+    fn test_apc40_grid_size() {
+        let device = MidiDevice {
+            id: "in:APC40 mkII".to_string(),
+            name: "APC40 mkII".to_string(),
+            is_input: true,
+            is_controller: true,
+            model: None,
+            firmware: None,
+        };
+        let apc40 = Apc::new(device, ApcModel::Apc40Mk2);
+
+        assert_eq!(apc40.grid_size(), (5, 8));
+        assert_eq!(apc40.button_role(83), Some(ButtonRole::Bank(1)));
+        assert_eq!(apc40.button_role(APC40_BANK_UP), Some(ButtonRole::Up));
+        assert_eq!(apc40.map_grid_id(39), 39);
+        assert_eq!(apc40.map_to_app_grid_id(39), Some(39));
+        assert_eq!(apc40.map_to_app_grid_id(40), None);
+    }
+}
@@ -3,11 +3,18 @@ use std::thread;
 use std::time::Duration;
 
 use midir::{MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+use serde::{Deserialize, Serialize};
 
+use crate::midi::apc::{Apc, ApcModel};
 use crate::midi::devices::MidiDevice;
+use crate::midi::fire::Fire;
+use crate::midi::monome::Monome;
+use crate::midi::palette::{shade_rgb, PadState, NOVATION_PALETTE};
+use crate::midi::profile::{builtin_profiles, find_profile, ControllerProfile, ProfileController};
+use crate::midi::push::{Push, PushModel};
 
 /// Color representation using RGB
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -52,10 +59,34 @@ pub enum ControllerEvent {
     PadPressed(u8, u8),
     /// Pad released (grid_id)
     PadReleased(u8),
+    /// Pressure on a held pad changed (grid_id, pressure)
+    PadPressure(u8, u8),
     /// Button pressed (id)
     ButtonPressed(u8),
     /// Button released (id)
     ButtonReleased(u8),
+    /// Fader moved (fader index, value)
+    FaderMoved(u8, u8),
+    /// Knob turned (knob index, value)
+    KnobTurned(u8, u8),
+}
+
+/// What a controller button does in the app, beyond reporting its presses
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonRole {
+    /// Stop everything, like the panic button setting
+    Panic,
+    /// Modifier for a second layer of functions
+    Shift,
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Select a bank of scenes, counting from 0
+    Bank(u8),
+    /// Switch the grid between scenes and virtual faders
+    FaderMode,
 }
 
 /// Trait defining functionality for grid controllers
@@ -69,16 +100,81 @@ pub trait GridController: Send + Sync {
     /// Set the color of a pad
     fn set_pad_color(&mut self, grid_id: u8, color: Color) -> Result<(), String>;
 
+    /// Set the colors of several pads at once
+    ///
+    /// Controllers that can update many LEDs in a single message should override
+    /// this; the default sends one update per pad.
+    fn set_pad_colors(&mut self, pads: &[(u8, Color)]) -> Result<(), String> {
+        for &(grid_id, color) in pads {
+            self.set_pad_color(grid_id, color)?;
+        }
+        Ok(())
+    }
+
+    /// Show pads in a display state
+    ///
+    /// The default shades each pad's color for its state. Controllers that can
+    /// flash and pulse their LEDs override this, and `animates_leds`, so queued and
+    /// active pads keep time on the device.
+    fn set_pad_states(&mut self, pads: &[(u8, Color, PadState)]) -> Result<(), String> {
+        let colors: Vec<(u8, Color)> = pads
+            .iter()
+            .map(|&(grid_id, color, state)| (grid_id, shade_rgb(color, state)))
+            .collect();
+        self.set_pad_colors(&colors)
+    }
+
+    /// Whether `set_pad_states` flashes queued pads on the device
+    ///
+    /// When it doesn't, the app flashes them in time with the beat.
+    fn animates_leds(&self) -> bool {
+        false
+    }
+
+    /// Send one MIDI clock tick
+    ///
+    /// Controllers whose flashing and pulsing LEDs follow the clock they
+    /// receive override this; the app sends 24 ticks per beat of its beat clock.
+    fn send_clock(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
     /// Set the color of a button
     fn set_button_color(&mut self, button_id: u8, color: Color) -> Result<(), String>;
 
     /// Clear all pad colors
     fn clear(&mut self) -> Result<(), String>;
 
-    /// Map from application grid id (0-63) to controller-specific id
+    /// Size of the pad grid as (rows, columns)
+    ///
+    /// Controllers smaller than the project's grid show part of it through a
+    /// viewport that the arrow buttons scroll.
+    fn grid_size(&self) -> (u8, u8) {
+        (8, 8)
+    }
+
+    /// Role of a button, if the controller assigns it one
+    fn button_role(&self, _button_id: u8) -> Option<ButtonRole> {
+        None
+    }
+
+    /// Buttons that select scene banks, as (button id, bank)
+    fn bank_buttons(&self) -> Vec<(u8, u8)> {
+        (0..=127)
+            .filter_map(|button_id| match self.button_role(button_id) {
+                Some(ButtonRole::Bank(bank)) => Some((button_id, bank)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Map from a pad's grid id to controller-specific id
+    ///
+    /// Grid ids number the controller's own pads row by row from the bottom-left,
+    /// `row * columns + column` for its `grid_size`.
     fn map_grid_id(&self, app_grid_id: u8) -> u8;
 
-    /// Map from controller-specific id to application grid id (0-63)
+    /// Map from controller-specific id to the pad's grid id
     fn map_to_app_grid_id(&self, controller_id: u8) -> Option<u8>;
 
     /// Register a callback for controller events
@@ -88,11 +184,31 @@ pub trait GridController: Send + Sync {
     fn clone_box(&self) -> Box<dyn GridController>;
 }
 
+/// Function taking the messages a driver would send to a port
+pub(crate) type MessageSink = Arc<dyn Fn(&[u8]) + Send + Sync>;
+
+/// Where a Launchpad MK2 sends its messages
+enum Mk2Output {
+    Port(MidiOutputConnection),
+    /// Messages handed to a function instead of a port, for the simulator
+    Memory(MessageSink),
+}
+
+impl Mk2Output {
+    /// Copy of the output, if it isn't a port connection
+    fn try_clone(&self) -> Option<Mk2Output> {
+        match self {
+            Mk2Output::Port(_) => None,
+            Mk2Output::Memory(output) => Some(Mk2Output::Memory(Arc::clone(output))),
+        }
+    }
+}
+
 /// Implementation for Novation Launchpad MK2
 pub struct LaunchpadMk2 {
     device: MidiDevice,
     input_connection: Option<MidiInputConnection<()>>,
-    output_connection: Option<MidiOutputConnection>,
+    output_connection: Option<Mk2Output>,
     event_callback: Option<Arc<dyn Fn(ControllerEvent) + Send + Sync>>,
 }
 
@@ -106,16 +222,29 @@ impl LaunchpadMk2 {
         }
     }
 
-    /// Convert RGB color to Launchpad's color format
+    /// Create a Launchpad whose messages go to a function rather than a port
+    pub(crate) fn in_memory(device: MidiDevice, output: MessageSink) -> Self {
+        LaunchpadMk2 {
+            output_connection: Some(Mk2Output::Memory(output)),
+            ..LaunchpadMk2::new(device)
+        }
+    }
+
+    /// Convert RGB color to the nearest entry of the Launchpad's velocity palette
     fn rgb_to_launchpad_color(&self, color: Color) -> u8 {
-        // Simple conversion - more accurate would use a lookup table
-        let r = color.r / 85; // 0-3
-        let g = color.g / 85; // 0-3
-        let b = color.b / 85; // 0-3
+        NOVATION_PALETTE.nearest(color)
+    }
 
-        // Launchpad MK2 uses a 4x4x4 color space, encoded as:
-        // 16 * r + 4 * g + b
-        16 * r + 4 * g + b
+    /// Send a message to the device, if connected
+    fn send(&mut self, message: &[u8]) -> Result<(), String> {
+        match self.output_connection {
+            Some(Mk2Output::Port(ref mut conn)) => conn.send(message).map_err(|e| e.to_string()),
+            Some(Mk2Output::Memory(ref output)) => {
+                output(message);
+                Ok(())
+            }
+            None => Ok(()),
+        }
     }
 }
 
@@ -123,8 +252,11 @@ impl Clone for LaunchpadMk2 {
     fn clone(&self) -> Self {
         LaunchpadMk2 {
             device: self.device.clone(),
-            input_connection: None,  // Connections can't be cloned
-            output_connection: None, // Connections can't be cloned
+            input_connection: None, // Connections can't be cloned
+            output_connection: self
+                .output_connection
+                .as_ref()
+                .and_then(Mk2Output::try_clone),
             event_callback: self.event_callback.clone(),
         }
     }
@@ -165,7 +297,12 @@ impl GridController for LaunchpadMk2 {
                 let note = message[1];
                 let velocity = message[2];
 
+                // The right column sends notes too, but is a column of buttons
+                let side_button = note % 10 == 9;
+
                 let event = match (status, velocity) {
+                    (0x90, 0) if side_button => Some(ControllerEvent::ButtonReleased(note)),
+                    (0x90, _) if side_button => Some(ControllerEvent::ButtonPressed(note)),
                     (0x90, 0) => Some(ControllerEvent::PadReleased(note)),
                     (0x90, v) => Some(ControllerEvent::PadPressed(note, v)),
                     (0xB0, 0) => Some(ControllerEvent::ButtonReleased(note)),
@@ -204,26 +341,20 @@ impl GridController for LaunchpadMk2 {
         let output_conn = midi_out
             .connect(out_port, "launchpad-output")
             .map_err(|e| e.to_string())?;
-        self.output_connection = Some(output_conn);
+        self.output_connection = Some(Mk2Output::Port(output_conn));
 
         // Set to programmer mode (for RGB control)
-        if let Some(ref mut conn) = self.output_connection {
-            conn.send(&[0xF0, 0x00, 0x20, 0x29, 0x02, 0x18, 0x22, 0x00, 0xF7])
-                .map_err(|e| e.to_string())?;
+        self.send(&[0xF0, 0x00, 0x20, 0x29, 0x02, 0x18, 0x22, 0x00, 0xF7])?;
 
-            // Wait a moment for the device to process
-            thread::sleep(Duration::from_millis(100));
-        }
+        // Wait a moment for the device to process
+        thread::sleep(Duration::from_millis(100));
 
         Ok(())
     }
 
     fn disconnect(&mut self) -> Result<(), String> {
         // Reset the Launchpad
-        if let Some(ref mut conn) = self.output_connection {
-            conn.send(&[0xF0, 0x00, 0x20, 0x29, 0x02, 0x18, 0x0E, 0x00, 0xF7])
-                .map_err(|e| e.to_string())?;
-        }
+        self.send(&[0xF0, 0x00, 0x20, 0x29, 0x02, 0x18, 0x0E, 0x00, 0xF7])?;
 
         self.input_connection = None;
         self.output_connection = None;
@@ -235,25 +366,48 @@ impl GridController for LaunchpadMk2 {
         let launchpad_id = self.map_grid_id(grid_id);
         let color_value = self.rgb_to_launchpad_color(color);
 
-        if let Some(ref mut conn) = self.output_connection {
-            // Launchpad MK2 uses Note On messages for setting pad colors
-            conn.send(&[0x90, launchpad_id, color_value])
-                .map_err(|e| e.to_string())?;
+        // Launchpad MK2 uses Note On messages for setting pad colors
+        self.send(&[0x90, launchpad_id, color_value])
+    }
+
+    fn set_pad_states(&mut self, pads: &[(u8, Color, PadState)]) -> Result<(), String> {
+        for &(grid_id, color, state) in pads {
+            let launchpad_id = self.map_grid_id(grid_id);
+
+            // Channel 1 is a static colour, channel 2 flashes between it and the
+            // channel 1 colour and channel 3 pulses, both in time with the device
+            let messages = match state {
+                PadState::Queued => vec![
+                    [0x90, launchpad_id, 0],
+                    [0x91, launchpad_id, self.rgb_to_launchpad_color(color)],
+                ],
+                PadState::Active => vec![[0x92, launchpad_id, self.rgb_to_launchpad_color(color)]],
+                _ => vec![[0x90, launchpad_id, NOVATION_PALETTE.shade(color, state)]],
+            };
+
+            for message in messages {
+                self.send(&message)?;
+            }
         }
 
         Ok(())
     }
 
+    fn animates_leds(&self) -> bool {
+        true
+    }
+
+    fn send_clock(&mut self) -> Result<(), String> {
+        self.send(&[MIDI_CLOCK])
+    }
+
     fn set_button_color(&mut self, button_id: u8, color: Color) -> Result<(), String> {
         let color_value = self.rgb_to_launchpad_color(color);
 
-        if let Some(ref mut conn) = self.output_connection {
-            // Launchpad MK2 uses CC messages for setting button colors
-            conn.send(&[0xB0, button_id, color_value])
-                .map_err(|e| e.to_string())?;
-        }
-
-        Ok(())
+        // Launchpad MK2 uses CC messages for the top row and notes for the
+        // right column
+        let status = if button_id >= 104 { 0xB0 } else { 0x90 };
+        self.send(&[status, button_id, color_value])
     }
 
     fn clear(&mut self) -> Result<(), String> {
@@ -263,13 +417,26 @@ impl GridController for LaunchpadMk2 {
         }
 
         // Set all buttons to black
-        for i in 104..112 {
+        for i in (104..112).chain((1..=8).map(|row| row * 10 + 9)) {
             self.set_button_color(i, Color::BLACK)?;
         }
 
         Ok(())
     }
 
+    fn button_role(&self, button_id: u8) -> Option<ButtonRole> {
+        // The first four buttons of the top row are arrows, and the right
+        // column selects banks from the top
+        match button_id {
+            104 => Some(ButtonRole::Up),
+            105 => Some(ButtonRole::Down),
+            106 => Some(ButtonRole::Left),
+            107 => Some(ButtonRole::Right),
+            19..=89 if button_id % 10 == 9 => Some(ButtonRole::Bank((89 - button_id) / 10)),
+            _ => None,
+        }
+    }
+
     fn map_grid_id(&self, app_grid_id: u8) -> u8 {
         if app_grid_id >= 64 {
             return 0; // Invalid ID
@@ -313,8 +480,24 @@ impl GridController for LaunchpadMk2 {
 /// Factory to create controllers based on device type
 pub struct ControllerFactory;
 
+/// Driver chosen for a device
+enum Driver {
+    Profile(Box<ControllerProfile>),
+    LaunchpadMk2,
+    LaunchpadMk3(LaunchpadModel),
+    Apc(ApcModel),
+    Push(PushModel),
+    Fire,
+    Monome,
+}
+
 impl ControllerFactory {
-    pub fn create_controller(device: MidiDevice) -> Result<Box<dyn GridController>, String> {
+    /// Create the driver for a controller device, checking the given user
+    /// profiles before the built-in drivers
+    pub fn create_controller(
+        device: MidiDevice,
+        user_profiles: &[ControllerProfile],
+    ) -> Result<Box<dyn GridController>, String> {
         if !device.is_controller {
             return Err(format!(
                 "Device '{}' is not a supported controller",
@@ -322,56 +505,303 @@ impl ControllerFactory {
             ));
         }
 
-        // Determine controller type based on device name
-        if device.name.to_lowercase().contains("launchpad") {
-            if device.name.to_lowercase().contains("mk2") {
-                return Ok(Box::new(LaunchpadMk2::new(device)));
-            } else {
-                // Future: implement other Launchpad variants
+        // Prefer the model the device reported, falling back to its port name
+        let driver = device
+            .model
+            .iter()
+            .chain(std::iter::once(&device.name))
+            .find_map(|name| Self::driver_for(name, user_profiles));
+
+        let controller: Box<dyn GridController> = match driver {
+            Some(Driver::Profile(profile)) => Box::new(ProfileController::new(device, *profile)),
+            Some(Driver::LaunchpadMk2) => Box::new(LaunchpadMk2::new(device)),
+            Some(Driver::LaunchpadMk3(model)) => Box::new(LaunchpadMk3::new(device, model)),
+            Some(Driver::Apc(model)) => Box::new(Apc::new(device, model)),
+            Some(Driver::Push(model)) => Box::new(Push::new(device, model)),
+            Some(Driver::Fire) => Box::new(Fire::new(device)),
+            Some(Driver::Monome) => Box::new(Monome::new(device)),
+            None if device.name.to_lowercase().contains("launchpad") => {
                 return Err(format!("Unsupported Launchpad variant: {}", device.name));
             }
+            None => return Err(format!("Unsupported controller type: {}", device.name)),
+        };
+
+        Ok(controller)
+    }
+
+    /// Whether there is a driver for a device name, used when a device doesn't
+    /// identify itself
+    pub fn supports_name(name: &str, user_profiles: &[ControllerProfile]) -> bool {
+        Self::driver_for(name, user_profiles).is_some()
+    }
+
+    /// Pick a driver from a model or port name
+    fn driver_for(name: &str, user_profiles: &[ControllerProfile]) -> Option<Driver> {
+        // User profiles come first so they can replace a built-in driver
+        if let Some(profile) = find_profile(user_profiles, name) {
+            return Some(Driver::Profile(Box::new(profile.clone())));
+        }
+
+        if let Some(model) = LaunchpadModel::from_port_name(name) {
+            return Some(Driver::LaunchpadMk3(model));
+        }
+
+        if let Some(model) = ApcModel::from_port_name(name) {
+            return Some(Driver::Apc(model));
+        }
+
+        if let Some(model) = PushModel::from_port_name(name) {
+            return Some(Driver::Push(model));
+        }
+
+        if Fire::matches_port_name(name) {
+            return Some(Driver::Fire);
+        }
+
+        if Monome::matches_model(name) {
+            return Some(Driver::Monome);
+        }
+
+        let lowercase = name.to_lowercase();
+        if lowercase.contains("launchpad") && lowercase.contains("mk2") {
+            return Some(Driver::LaunchpadMk2);
+        }
+
+        // Devices without a native driver may still have a built-in profile
+        find_profile(&builtin_profiles(), name)
+            .map(|profile| Driver::Profile(Box::new(profile.clone())))
+    }
+}
+
+/// MIDI timing clock message, sent 24 times per beat
+pub const MIDI_CLOCK: u8 = 0xF8;
+
+/// SysEx header shared by Novation Mk3-family devices, followed by the device ID
+const NOVATION_SYSEX_HEADER: [u8; 5] = [0xF0, 0x00, 0x20, 0x29, 0x02];
+
+/// Build a Mk3-family LED lighting SysEx that sets each LED to an RGB color
+///
+/// Each entry is an LED index (the note or CC number in programmer mode) and its
+/// color. Channels are scaled from 0-255 down to the device's 0-127 range.
+fn rgb_lighting_sysex(device_id: u8, leds: &[(u8, Color)]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(NOVATION_SYSEX_HEADER.len() + 3 + leds.len() * 5);
+    message.extend_from_slice(&NOVATION_SYSEX_HEADER);
+    message.push(device_id);
+    message.push(0x03); // LED lighting command
+
+    for &(index, color) in leds {
+        // Lighting type 3 is an RGB colour
+        message.extend_from_slice(&[0x03, index, color.r >> 1, color.g >> 1, color.b >> 1]);
+    }
+
+    message.push(0xF7);
+    message
+}
+
+/// Build an LED lighting SysEx showing pads in a display state
+///
+/// Queued pads flash and active pads pulse using the device's own timing; other
+/// states are shaded RGB colours.
+fn state_lighting_sysex(device_id: u8, leds: &[(u8, Color, PadState)]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(NOVATION_SYSEX_HEADER.len() + 3 + leds.len() * 5);
+    message.extend_from_slice(&NOVATION_SYSEX_HEADER);
+    message.push(device_id);
+    message.push(0x03); // LED lighting command
+
+    for &(index, color, state) in leds {
+        match state {
+            // Lighting type 1 flashes between two palette entries, here off and the colour
+            PadState::Queued => {
+                message.extend_from_slice(&[0x01, index, 0, NOVATION_PALETTE.nearest(color)])
+            }
+            // Lighting type 2 pulses a palette entry
+            PadState::Active => {
+                message.extend_from_slice(&[0x02, index, NOVATION_PALETTE.nearest(color)])
+            }
+            _ => {
+                let color = shade_rgb(color, state);
+                message.extend_from_slice(&[0x03, index, color.r >> 1, color.g >> 1, color.b >> 1]);
+            }
+        }
+    }
+
+    message.push(0xF7);
+    message
+}
+
+/// Novation Launchpad models using the Mk3-family programmer protocol
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LaunchpadModel {
+    X,
+    MiniMk3,
+    ProMk3,
+}
+
+impl LaunchpadModel {
+    /// Detect the model from a MIDI port name
+    pub fn from_port_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+
+        if name.contains("lpx") || name.contains("launchpad x") {
+            Some(LaunchpadModel::X)
+        } else if name.contains("lpminimk3") || name.contains("launchpad mini mk3") {
+            Some(LaunchpadModel::MiniMk3)
+        } else if name.contains("lppromk3") || name.contains("launchpad pro mk3") {
+            Some(LaunchpadModel::ProMk3)
+        } else {
+            None
+        }
+    }
+
+    /// SysEx device ID
+    fn device_id(&self) -> u8 {
+        match self {
+            LaunchpadModel::X => 0x0C,
+            LaunchpadModel::MiniMk3 => 0x0D,
+            LaunchpadModel::ProMk3 => 0x0E,
+        }
+    }
+
+    /// Port name tag of the MIDI port pair that accepts programmer-mode messages
+    ///
+    /// These devices also expose a DAW port pair (and a DIN pair on the Pro),
+    /// which is reserved for DAW integrations and ignores programmer mode.
+    fn midi_port_tag(&self) -> &'static str {
+        match self {
+            LaunchpadModel::X => "lpx midi",
+            LaunchpadModel::MiniMk3 => "lpminimk3 midi",
+            LaunchpadModel::ProMk3 => "lppromk3 midi",
+        }
+    }
+
+    /// SysEx switching programmer mode on or off
+    fn programmer_mode(&self, enabled: bool) -> [u8; 9] {
+        [
+            0xF0,
+            0x00,
+            0x20,
+            0x29,
+            0x02,
+            self.device_id(),
+            0x0E,
+            enabled as u8,
+            0xF7,
+        ]
+    }
+
+    /// Role of a button in programmer mode
+    ///
+    /// The X and Mini have four arrows at the left of the top row; the Pro has
+    /// shift and left/right in its top row and no up/down arrows. The right
+    /// column selects banks.
+    fn button_role(&self, button_id: u8) -> Option<ButtonRole> {
+        match (self, button_id) {
+            (LaunchpadModel::ProMk3, 90) => Some(ButtonRole::Shift),
+            (LaunchpadModel::ProMk3, 91) => Some(ButtonRole::Left),
+            (LaunchpadModel::ProMk3, 92) => Some(ButtonRole::Right),
+            // Every model selects banks with the right column, from the top
+            (_, 19..=89) if button_id % 10 == 9 => Some(ButtonRole::Bank((89 - button_id) / 10)),
+            (LaunchpadModel::ProMk3, _) => None,
+            (_, 91) => Some(ButtonRole::Up),
+            (_, 92) => Some(ButtonRole::Down),
+            (_, 93) => Some(ButtonRole::Left),
+            (_, 94) => Some(ButtonRole::Right),
+            _ => None,
         }
+    }
 
-        Err(format!("Unsupported controller type: {}", device.name))
+    /// CC numbers of the buttons around the grid in programmer mode
+    pub fn button_ids(&self) -> Vec<u8> {
+        // Top row and right column; 99 is the logo LED
+        let mut ids: Vec<u8> = (91..=99).chain((1..=8).map(|row| row * 10 + 9)).collect();
+
+        if *self == LaunchpadModel::ProMk3 {
+            // Shift (90), left column and the two rows below the grid
+            ids.push(90);
+            ids.extend((1..=8).map(|row| row * 10));
+            ids.extend(1..=8);
+            ids.extend(101..=108);
+        }
+
+        ids
     }
 }
 
+/// Pick a controller port from the available port names
+///
+/// Prefers a port whose lowercased name contains `tag` (for devices that expose
+/// several ports and only accept control messages on one). With several units
+/// of a model plugged in, the tagged port named most like the port the device
+/// was registered under belongs to the same unit. Without a tagged port, the
+/// exact registered name is used.
+pub(crate) fn select_port(names: &[String], tag: &str, registered: &str) -> Option<usize> {
+    names
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| name.to_lowercase().contains(tag))
+        .min_by_key(|(_, name)| edit_distance(name, registered))
+        .map(|(index, _)| index)
+        .or_else(|| names.iter().position(|name| name == registered))
+}
 
-/// Implementation for Novation Launchpad X
-pub struct LaunchpadX {
+/// Number of single-character edits that turn one name into another
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != *b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j + 1] + 1).min(row[j] + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Implementation for Novation Launchpad X, Mini Mk3 and Pro Mk3
+pub struct LaunchpadMk3 {
     device: MidiDevice,
+    model: LaunchpadModel,
     input_connection: Option<MidiInputConnection<()>>,
     output_connection: Option<MidiOutputConnection>,
     event_callback: Option<Arc<dyn Fn(ControllerEvent) + Send + Sync>>,
 }
 
-impl LaunchpadX {
-    pub fn new(device: MidiDevice) -> Self {
-        LaunchpadX {
+impl LaunchpadMk3 {
+    pub fn new(device: MidiDevice, model: LaunchpadModel) -> Self {
+        LaunchpadMk3 {
             device,
+            model,
             input_connection: None,
             output_connection: None,
             event_callback: None,
         }
     }
 
-    /// Convert RGB color to Launchpad X's color format
-    fn rgb_to_launchpad_color(&self, color: Color) -> u8 {
-        // Simple conversion - more accurate would use a lookup table
-        let r = color.r / 85; // 0-3
-        let g = color.g / 85; // 0-3
-        let b = color.b / 85; // 0-3
+    /// Send an RGB lighting SysEx for the given LED indices
+    fn send_rgb(&mut self, leds: &[(u8, Color)]) -> Result<(), String> {
+        if leds.is_empty() {
+            return Ok(());
+        }
 
-        // Launchpad X uses a 4x4x4 color space, encoded as:
-        // 16 * r + 4 * g + b
-        16 * r + 4 * g + b
+        if let Some(ref mut conn) = self.output_connection {
+            conn.send(&rgb_lighting_sysex(self.model.device_id(), leds))
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
     }
 }
 
-impl Clone for LaunchpadX {
+impl Clone for LaunchpadMk3 {
     fn clone(&self) -> Self {
-        LaunchpadX {
+        LaunchpadMk3 {
             device: self.device.clone(),
+            model: self.model,
             input_connection: None,  // Connections can't be cloned
             output_connection: None, // Connections can't be cloned
             event_callback: self.event_callback.clone(),
@@ -379,7 +809,7 @@ impl Clone for LaunchpadX {
     }
 }
 
-impl GridController for LaunchpadX {
+impl GridController for LaunchpadMk3 {
     fn clone_box(&self) -> Box<dyn GridController> {
         Box::new(self.clone())
     }
@@ -388,16 +818,14 @@ impl GridController for LaunchpadX {
         // Connect to MIDI input
         let midi_in = MidiInput::new("snap-blaster").map_err(|e| e.to_string())?;
 
-        // Find the port
+        // Find the programmer-mode port, whichever port of the device was chosen
         let in_ports = midi_in.ports();
-        let in_port = in_ports
+        let in_names: Vec<String> = in_ports
             .iter()
-            .find(|p| {
-                midi_in
-                    .port_name(p)
-                    .map(|name| name == self.device.name)
-                    .unwrap_or(false)
-            })
+            .map(|p| midi_in.port_name(p).unwrap_or_default())
+            .collect();
+        let in_port = select_port(&in_names, self.model.midi_port_tag(), &self.device.name)
+            .map(|i| &in_ports[i])
             .ok_or_else(|| format!("Could not find MIDI input device: {}", self.device.name))?;
 
         // Create a callback to handle incoming MIDI messages
@@ -409,16 +837,18 @@ impl GridController for LaunchpadX {
                     return;
                 }
 
-                // Parse MIDI message based on Launchpad X protocol
+                // Parse MIDI message based on the programmer mode protocol
                 let status = message[0];
                 let note = message[1];
                 let velocity = message[2];
 
                 let event = match (status, velocity) {
-                    (0x90, 0) => Some(ControllerEvent::PadReleased(note)),
+                    (0x80, _) | (0x90, 0) => Some(ControllerEvent::PadReleased(note)),
                     (0x90, v) => Some(ControllerEvent::PadPressed(note, v)),
+                    // Polyphonic aftertouch from the X and Pro
+                    (0xA0, p) => Some(ControllerEvent::PadPressure(note, p)),
                     (0xB0, 0) => Some(ControllerEvent::ButtonReleased(note)),
-                    (0xB0, v) => Some(ControllerEvent::ButtonPressed(note)),
+                    (0xB0, _) => Some(ControllerEvent::ButtonPressed(note)),
                     _ => None,
                 };
 
@@ -438,16 +868,13 @@ impl GridController for LaunchpadX {
         // Connect to MIDI output
         let midi_out = MidiOutput::new("snap-blaster").map_err(|e| e.to_string())?;
 
-        // Find the port
         let out_ports = midi_out.ports();
-        let out_port = out_ports
+        let out_names: Vec<String> = out_ports
             .iter()
-            .find(|p| {
-                midi_out
-                    .port_name(p)
-                    .map(|name| name == self.device.name)
-                    .unwrap_or(false)
-            })
+            .map(|p| midi_out.port_name(p).unwrap_or_default())
+            .collect();
+        let out_port = select_port(&out_names, self.model.midi_port_tag(), &self.device.name)
+            .map(|i| &out_ports[i])
             .ok_or_else(|| format!("Could not find MIDI output device: {}", self.device.name))?;
 
         let output_conn = midi_out
@@ -455,9 +882,9 @@ impl GridController for LaunchpadX {
             .map_err(|e| e.to_string())?;
         self.output_connection = Some(output_conn);
 
-        // Set to programmer mode (for RGB control) - Launchpad X specific SysEx command
+        // Set to programmer mode (for RGB control)
         if let Some(ref mut conn) = self.output_connection {
-            conn.send(&[0xF0, 0x00, 0x20, 0x29, 0x02, 0x0D, 0x0E, 0x01, 0xF7])
+            conn.send(&self.model.programmer_mode(true))
                 .map_err(|e| e.to_string())?;
 
             // Wait a moment for the device to process
@@ -468,9 +895,9 @@ impl GridController for LaunchpadX {
     }
 
     fn disconnect(&mut self) -> Result<(), String> {
-        // Reset the Launchpad X
+        // Return the Launchpad to live mode
         if let Some(ref mut conn) = self.output_connection {
-            conn.send(&[0xF0, 0x00, 0x20, 0x29, 0x02, 0x0D, 0x0E, 0x00, 0xF7])
+            conn.send(&self.model.programmer_mode(false))
                 .map_err(|e| e.to_string())?;
         }
 
@@ -481,42 +908,67 @@ impl GridController for LaunchpadX {
     }
 
     fn set_pad_color(&mut self, grid_id: u8, color: Color) -> Result<(), String> {
-        let launchpad_id = self.map_grid_id(grid_id);
-        let color_value = self.rgb_to_launchpad_color(color);
+        self.set_pad_colors(&[(grid_id, color)])
+    }
+
+    fn set_pad_colors(&mut self, pads: &[(u8, Color)]) -> Result<(), String> {
+        let leds: Vec<(u8, Color)> = pads
+            .iter()
+            .filter(|(grid_id, _)| *grid_id < 64)
+            .map(|&(grid_id, color)| (self.map_grid_id(grid_id), color))
+            .collect();
+
+        self.send_rgb(&leds)
+    }
+
+    fn set_pad_states(&mut self, pads: &[(u8, Color, PadState)]) -> Result<(), String> {
+        let leds: Vec<(u8, Color, PadState)> = pads
+            .iter()
+            .filter(|(grid_id, _, _)| *grid_id < 64)
+            .map(|&(grid_id, color, state)| (self.map_grid_id(grid_id), color, state))
+            .collect();
+        if leds.is_empty() {
+            return Ok(());
+        }
 
         if let Some(ref mut conn) = self.output_connection {
-            // Launchpad X uses Note On messages for setting pad colors
-            conn.send(&[0x90, launchpad_id, color_value])
+            conn.send(&state_lighting_sysex(self.model.device_id(), &leds))
                 .map_err(|e| e.to_string())?;
         }
 
         Ok(())
     }
 
-    fn set_button_color(&mut self, button_id: u8, color: Color) -> Result<(), String> {
-        let color_value = self.rgb_to_launchpad_color(color);
+    fn animates_leds(&self) -> bool {
+        true
+    }
 
+    fn send_clock(&mut self) -> Result<(), String> {
         if let Some(ref mut conn) = self.output_connection {
-            // Launchpad X uses CC messages for setting button colors
-            conn.send(&[0xB0, button_id, color_value])
-                .map_err(|e| e.to_string())?;
+            conn.send(&[MIDI_CLOCK]).map_err(|e| e.to_string())?;
         }
 
         Ok(())
     }
 
-    fn clear(&mut self) -> Result<(), String> {
-        // Set all pads to black
-        for i in 0..64 {
-            self.set_pad_color(i, Color::BLACK)?;
-        }
+    fn set_button_color(&mut self, button_id: u8, color: Color) -> Result<(), String> {
+        // In programmer mode the LED index of a button is its CC number
+        self.send_rgb(&[(button_id, color)])
+    }
 
-        // Set all buttons to black
-        for i in 104..112 {
-            self.set_button_color(i, Color::BLACK)?;
-        }
+    fn clear(&mut self) -> Result<(), String> {
+        // Pads and every button LED in one message
+        let leds: Vec<(u8, Color)> = (0..64)
+            .map(|grid_id| self.map_grid_id(grid_id))
+            .chain(self.model.button_ids())
+            .map(|index| (index, Color::BLACK))
+            .collect();
+
+        self.send_rgb(&leds)
+    }
 
-        Ok(())
+    fn button_role(&self, button_id: u8) -> Option<ButtonRole> {
+        self.model.button_role(button_id)
     }
 
     fn map_grid_id(&self, app_grid_id: u8) -> u8 {
@@ -524,12 +976,12 @@ impl GridController for LaunchpadX {
             return 0; // Invalid ID
         }
 
-        // Map from linear 0-63 to Launchpad X's 9x9 grid
-        // Launchpad X has a similar layout to the MK2:
-        // 11 12 13 14 15 16 17 18 19
-        // 21 22 23 24 25 26 27 28 29
+        // Map from linear 0-63 to the programmer mode layout, which all
+        // Mk3-family models share:
+        // 11 12 13 14 15 16 17 18
+        // 21 22 23 24 25 26 27 28
         // ...
-        // 81 82 83 84 85 86 87 88 89
+        // 81 82 83 84 85 86 87 88
 
         let row = app_grid_id / 8;
         let col = app_grid_id % 8;
@@ -545,7 +997,7 @@ impl GridController for LaunchpadX {
         let col = controller_id % 10;
 
         // Validate the ID is within the grid
-        if row < 1 || row > 8 || col < 1 || col > 8 {
+        if !(1..=8).contains(&row) || !(1..=8).contains(&col) {
             return None;
         }
 
@@ -557,4 +1009,146 @@ impl GridController for LaunchpadX {
     fn set_event_callback(&mut self, callback: Arc<dyn Fn(ControllerEvent) + Send + Sync>) {
         self.event_callback = Some(callback);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb_lighting_sysex() {
+        let message = rgb_lighting_sysex(
+            LaunchpadModel::X.device_id(),
+            &[(11, Color::new(255, 128, 0)), (99, Color::WHITE)],
+        );
+
+        assert_eq!(
+            message,
+            vec![
+                0xF0, 0x00, 0x20, 0x29, 0x02, 0x0C, 0x03, // header, lighting command
+                0x03, 11, 127, 64, 0, // pad 11, RGB
+                0x03, 99, 127, 127, 127, // logo LED, RGB
+                0xF7,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_state_lighting_sysex() {
+        let message = state_lighting_sysex(
+            LaunchpadModel::X.device_id(),
+            &[
+                (11, Color::RED, PadState::Queued),
+                (12, Color::RED, PadState::Active),
+                (13, Color::BLACK, PadState::Assigned),
+            ],
+        );
+
+        assert_eq!(
+            message,
+            vec![
+                0xF0, 0x00, 0x20, 0x29, 0x02, 0x0C, 0x03, // header, lighting command
+                0x01, 11, 0, 5, // flashing red
+                0x02, 12, 5, // pulsing red
+                0x03, 13, 0, 0, 0, // off
+                0xF7,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_launchpad_model_detection() {
+        let model = |name| LaunchpadModel::from_port_name(name);
+
+        assert_eq!(model("Launchpad X LPX MIDI Out"), Some(LaunchpadModel::X));
+        assert_eq!(model("MIDIIN2 (LPX MIDI)"), Some(LaunchpadModel::X));
+        assert_eq!(model("LPMiniMK3 DAW"), Some(LaunchpadModel::MiniMk3));
+        assert_eq!(
+            model("Launchpad Pro MK3 LPProMK3 DIN"),
+            Some(LaunchpadModel::ProMk3)
+        );
+        assert_eq!(model("Launchpad MK2"), None);
+
+        assert_eq!(LaunchpadModel::MiniMk3.programmer_mode(true)[5], 0x0D);
+        assert_eq!(LaunchpadModel::ProMk3.programmer_mode(false)[7], 0x00);
+    }
+
+    #[test]
+    fn test_launchpad_port_selection() {
+        let names: Vec<String> = ["LPX DAW In", "LPX MIDI In", "Other"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        // The DAW port was registered, but the MIDI port is used
+        let x_tag = LaunchpadModel::X.midi_port_tag();
+        let pro_tag = LaunchpadModel::ProMk3.midi_port_tag();
+        assert_eq!(select_port(&names, x_tag, "LPX DAW In"), Some(1));
+        assert_eq!(select_port(&names, pro_tag, "Other"), Some(2));
+        assert_eq!(select_port(&names, pro_tag, "Missing"), None);
+    }
+
+    #[test]
+    fn test_port_selection_with_two_units() {
+        let tag = LaunchpadModel::X.midi_port_tag();
+
+        // ALSA numbers each unit's ports after its client
+        let alsa: Vec<String> = [
+            "Launchpad X:Launchpad X LPX DAW In 20:0",
+            "Launchpad X:Launchpad X LPX MIDI In 20:1",
+            "Launchpad X:Launchpad X LPX DAW In 24:0",
+            "Launchpad X:Launchpad X LPX MIDI In 24:1",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(select_port(&alsa, tag, &alsa[0]), Some(1));
+        assert_eq!(select_port(&alsa, tag, &alsa[2]), Some(3));
+
+        // Windows prefixes the second unit's ports
+        let windows: Vec<String> = ["LPX DAW", "LPX MIDI", "2- LPX DAW", "2- LPX MIDI"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(select_port(&windows, tag, "LPX DAW"), Some(1));
+        assert_eq!(select_port(&windows, tag, "2- LPX DAW"), Some(3));
+        assert_eq!(select_port(&windows, tag, "2- LPX MIDI"), Some(3));
+    }
+
+    #[test]
+    fn test_launchpad_button_ids() {
+        let x = LaunchpadModel::X.button_ids();
+        assert!(x.contains(&91) && x.contains(&19) && x.contains(&89));
+        assert!(!x.contains(&101));
+
+        let pro = LaunchpadModel::ProMk3.button_ids();
+        assert!(pro.contains(&10) && pro.contains(&1) && pro.contains(&108));
+    }
+
+    #[test]
+    fn test_launchpad_button_roles() {
+        assert_eq!(LaunchpadModel::X.button_role(91), Some(ButtonRole::Up));
+        assert_eq!(
+            LaunchpadModel::MiniMk3.button_role(94),
+            Some(ButtonRole::Right)
+        );
+        assert_eq!(LaunchpadModel::X.button_role(95), None);
+
+        assert_eq!(
+            LaunchpadModel::ProMk3.button_role(90),
+            Some(ButtonRole::Shift)
+        );
+        assert_eq!(
+            LaunchpadModel::ProMk3.button_role(91),
+            Some(ButtonRole::Left)
+        );
+        assert_eq!(LaunchpadModel::ProMk3.button_role(93), None);
+
+        assert_eq!(LaunchpadModel::X.button_role(89), Some(ButtonRole::Bank(0)));
+        assert_eq!(
+            LaunchpadModel::ProMk3.button_role(19),
+            Some(ButtonRole::Bank(7))
+        );
+        assert_eq!(LaunchpadModel::X.button_role(80), None);
+    }
+}
@@ -1,7 +1,47 @@
 use midir::{MidiInput, MidiOutput};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::midi::controller::ControllerFactory;
+use crate::midi::identity::{identify_ports, DeviceIdentity};
+use crate::midi::loop_guard::device_key;
+use crate::midi::monome::{discover, MonomeGrid, SERIALOSC_PORT};
+use crate::midi::profile::{
+    load_profiles_from, profiles_stamp, user_profiles_dir, ControllerProfile, ProfilesStamp,
+};
+
+/// How often to ask serialosc for grids, rather than on every scan
+const GRID_DISCOVERY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Pair input ports with the output port of the same device
+///
+/// Names are matched exactly where they can be, and otherwise by device key,
+/// since Windows names the two sides differently (e.g. "LPX MIDI" and
+/// "MIDIOUT2 (LPX MIDI)"). Each output is paired at most once.
+fn port_pairs(in_names: &[String], out_names: &[String]) -> Vec<(String, String)> {
+    let mut unpaired: Vec<&String> = out_names.iter().collect();
+    let mut pairs = Vec::new();
+    let mut unmatched = Vec::new();
+
+    for input in in_names {
+        match unpaired.iter().position(|output| *output == input) {
+            Some(index) => pairs.push((input.clone(), unpaired.remove(index).clone())),
+            None => unmatched.push(input),
+        }
+    }
+
+    for input in unmatched {
+        let key = device_key(input);
+        if let Some(index) = unpaired.iter().position(|output| device_key(output) == key) {
+            pairs.push((input.clone(), unpaired.remove(index).clone()));
+        }
+    }
+
+    pairs
+}
 
 /// Structure to represent a MIDI device
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -10,94 +50,278 @@ pub struct MidiDevice {
     pub name: String,
     pub is_input: bool,
     pub is_controller: bool,
+
+    /// Controller model reported by the device's identity reply
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Firmware version reported by the device's identity reply
+    #[serde(default)]
+    pub firmware: Option<String>,
+}
+
+/// User profiles as last read from their directory
+struct CachedProfiles {
+    stamp: ProfilesStamp,
+    profiles: Arc<Vec<ControllerProfile>>,
+    skipped: Vec<String>,
 }
 
 /// Registry of available MIDI devices
 pub struct DeviceRegistry {
     devices: Arc<Mutex<HashMap<String, MidiDevice>>>,
+
+    /// Identity replies by port name, or `None` for ports that didn't answer
+    identities: Mutex<HashMap<String, Option<DeviceIdentity>>>,
+
+    /// Grids found by the last serialosc discovery, and when it ran
+    grids: Mutex<(Option<Instant>, Vec<MidiDevice>)>,
+
+    /// Directory user controller profiles are read from
+    profiles_dir: Option<PathBuf>,
+
+    /// User profiles and the files skipped as invalid, with what the directory
+    /// held when they were read
+    profiles: Mutex<Option<CachedProfiles>>,
 }
 
 impl DeviceRegistry {
     /// Create a new device registry
     pub fn new() -> Self {
+        Self::with_profiles_dir(user_profiles_dir())
+    }
+
+    /// Create a device registry reading user profiles from a directory
+    pub fn with_profiles_dir(profiles_dir: Option<PathBuf>) -> Self {
         DeviceRegistry {
             devices: Arc::new(Mutex::new(HashMap::new())),
+            identities: Mutex::new(HashMap::new()),
+            grids: Mutex::new((None, Vec::new())),
+            profiles_dir,
+            profiles: Mutex::new(None),
         }
     }
 
+    /// User controller profiles, read again only when the directory changes
+    pub fn user_profiles(&self) -> Arc<Vec<ControllerProfile>> {
+        self.with_profiles(|cached| Arc::clone(&cached.profiles))
+            .unwrap_or_default()
+    }
+
+    /// Why user profile files were skipped, one message per file
+    pub fn skipped_profiles(&self) -> Vec<String> {
+        self.with_profiles(|cached| cached.skipped.clone())
+            .unwrap_or_default()
+    }
+
+    /// Look at the cached user profiles, reading them first if the directory
+    /// has changed since they were
+    fn with_profiles<R>(&self, f: impl FnOnce(&CachedProfiles) -> R) -> Option<R> {
+        let dir = self.profiles_dir.as_ref()?;
+
+        let stamp = profiles_stamp(dir);
+        let mut cached = self.profiles.lock().unwrap();
+        if cached.as_ref().is_none_or(|cached| cached.stamp != stamp) {
+            let loaded = load_profiles_from(dir);
+            *cached = Some(CachedProfiles {
+                stamp,
+                profiles: Arc::new(loaded.profiles),
+                skipped: loaded.skipped,
+            });
+        }
+
+        cached.as_ref().map(f)
+    }
+
+    /// Read the user profiles again, even if the directory looks unchanged
+    pub fn reload_profiles(&self) -> Arc<Vec<ControllerProfile>> {
+        *self.profiles.lock().unwrap() = None;
+        self.user_profiles()
+    }
+
     /// Scan for available MIDI devices
+    ///
+    /// Devices with both an input and an output port are sent an identity
+    /// request when they first appear, and replies from known controllers mark
+    /// them as controllers. Port names are only used for devices that don't
+    /// answer.
     pub fn scan_devices(&self) -> Result<Vec<MidiDevice>, String> {
         let mut all_devices = Vec::new();
 
-        // Scan for MIDI input devices
-        if let Ok(midi_in) = MidiInput::new("snap-blaster-scanner") {
-            let in_ports = midi_in.ports();
-
-            for port in in_ports {
-                if let Ok(port_name) = midi_in.port_name(&port) {
-                    let id = format!("in:{}", port_name);
-
-                    // Detect if this might be a controller device
-                    // This is a heuristic based on common controller names
-                    let is_controller = port_name.to_lowercase().contains("launchpad")
-                        || port_name.to_lowercase().contains("apc")
-                        || port_name.to_lowercase().contains("push")
-                        || port_name.to_lowercase().contains("fire")
-                        || port_name.to_lowercase().contains("grid")
-                        || port_name.to_lowercase().contains("lpx")  // Added specific check for Launchpad X
-                        || port_name.to_lowercase().contains("lp x") // Added another variation
-                        || port_name.to_lowercase().contains("midi in"); // Include MIDI In ports
-
-                    let device = MidiDevice {
+        let in_names: Vec<String> = MidiInput::new("snap-blaster-scanner")
+            .map(|midi_in| {
+                midi_in
+                    .ports()
+                    .iter()
+                    .filter_map(|port| midi_in.port_name(port).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let out_names: Vec<String> = MidiOutput::new("snap-blaster-scanner")
+            .map(|midi_out| {
+                midi_out
+                    .ports()
+                    .iter()
+                    .filter_map(|port| midi_out.port_name(port).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let candidates = port_pairs(&in_names, &out_names);
+        let identities = self.cached_identities(&candidates, identify_ports);
+        let output_identities: HashMap<String, DeviceIdentity> = candidates
+            .iter()
+            .filter_map(|(input, output)| Some((output.clone(), identities.get(input)?.clone())))
+            .collect();
+
+        // Devices described by a user profile count as controllers too
+        let profiles = self.user_profiles();
+
+        // Register MIDI input devices
+        for port_name in in_names {
+            let id = format!("in:{}", port_name);
+            let identity = identities.get(&port_name);
+
+            let mut device = match identity {
+                Some(identity) => MidiDevice {
+                    id: id.clone(),
+                    name: port_name.clone(),
+                    is_input: true,
+                    is_controller: identity.controller_model().is_some(),
+                    model: identity.controller_model().map(String::from),
+                    firmware: Some(identity.firmware()),
+                },
+                None => {
+                    // Keep what an earlier scan learned, e.g. while the port is busy
+                    let previous = self.get_device(&id).filter(|d| d.model.is_some());
+
+                    previous.unwrap_or_else(|| MidiDevice {
                         id: id.clone(),
-                        name: port_name,
+                        name: port_name.clone(),
                         is_input: true,
-                        is_controller,
-                    };
+                        is_controller: ControllerFactory::supports_name(&port_name, &profiles),
+                        model: None,
+                        firmware: None,
+                    })
+                }
+            };
+            device.is_controller |= profiles.iter().any(|p| p.matches_port(&port_name));
 
-                    all_devices.push(device.clone());
+            all_devices.push(device.clone());
 
-                    let mut devices = self.devices.lock().unwrap();
-                    devices.insert(id, device);
-                }
+            let mut devices = self.devices.lock().unwrap();
+            devices.insert(id, device);
+        }
+
+        all_devices.extend(self.register_outputs(out_names, &output_identities));
+
+        // Monome grids talk OSC through serialosc rather than MIDI
+        let grids = self.discovered_grids(|| discover(SERIALOSC_PORT));
+        {
+            let mut devices = self.devices.lock().unwrap();
+            for device in grids {
+                all_devices.push(device.clone());
+                devices.insert(device.id.clone(), device);
             }
         }
 
-        // Scan for MIDI output devices
-        if let Ok(midi_out) = MidiOutput::new("snap-blaster-scanner") {
-            let out_ports = midi_out.ports();
-
-            for port in out_ports {
-                if let Ok(port_name) = midi_out.port_name(&port) {
-                    let id = format!("out:{}", port_name);
-
-                    // Skip if this is already registered as an input
-                    if self
-                        .devices
-                        .lock()
-                        .unwrap()
-                        .values()
-                        .any(|d| d.name == port_name && d.is_input)
-                    {
-                        continue;
-                    }
-
-                    let device = MidiDevice {
-                        id: id.clone(),
-                        name: port_name,
-                        is_input: false,
-                        is_controller: false, // Output devices are not controllers
-                    };
+        Ok(all_devices)
+    }
 
-                    all_devices.push(device.clone());
+    /// Identities of the given (input, output) port pairs by input port name,
+    /// asking only those not seen before
+    ///
+    /// Devices are asked once while they stay plugged in, so synths and
+    /// controllers don't get SysEx on every scan during a performance.
+    fn cached_identities(
+        &self,
+        ports: &[(String, String)],
+        identify: impl FnOnce(&[(String, String)]) -> HashMap<String, DeviceIdentity>,
+    ) -> HashMap<String, DeviceIdentity> {
+        let mut cache = self.identities.lock().unwrap();
 
-                    let mut devices = self.devices.lock().unwrap();
-                    devices.insert(id, device);
-                }
+        // Forget unplugged ports, so they are asked again when they come back
+        cache.retain(|name, _| ports.iter().any(|(input, _)| input == name));
+
+        let new_ports: Vec<(String, String)> = ports
+            .iter()
+            .filter(|(input, _)| !cache.contains_key(input))
+            .cloned()
+            .collect();
+        if !new_ports.is_empty() {
+            let mut replies = identify(&new_ports);
+            for (name, _) in new_ports {
+                let identity = replies.remove(&name);
+                cache.insert(name, identity);
             }
         }
 
-        Ok(all_devices)
+        cache
+            .iter()
+            .filter_map(|(name, identity)| Some((name.clone(), identity.clone()?)))
+            .collect()
+    }
+
+    /// Grids plugged into serialosc, asking it again once the discovery
+    /// interval has passed
+    ///
+    /// Discovery waits for replies even when serialosc isn't running, so scans
+    /// in between reuse the last answer.
+    fn discovered_grids(
+        &self,
+        discover: impl FnOnce() -> Result<Vec<MonomeGrid>, String>,
+    ) -> Vec<MidiDevice> {
+        let mut grids = self.grids.lock().unwrap();
+        let (last_discovery, devices) = &mut *grids;
+
+        let due = match last_discovery {
+            Some(last) => last.elapsed() >= GRID_DISCOVERY_INTERVAL,
+            None => true,
+        };
+        if due {
+            *devices = discover()
+                .map(|found| found.iter().map(MonomeGrid::device).collect())
+                .unwrap_or_default();
+            *last_discovery = Some(Instant::now());
+        }
+
+        devices.clone()
+    }
+
+    /// Register MIDI output ports
+    ///
+    /// Outputs that share a name with an input are registered too. Synths that
+    /// send MIDI back usually have both ports under one name, and skipping them
+    /// left no way to send scenes to such a device; the engine's loop guard
+    /// catches their echoes instead.
+    fn register_outputs(
+        &self,
+        out_names: Vec<String>,
+        identities: &HashMap<String, DeviceIdentity>,
+    ) -> Vec<MidiDevice> {
+        let mut devices = self.devices.lock().unwrap();
+
+        out_names
+            .into_iter()
+            .map(|port_name| {
+                let id = format!("out:{}", port_name);
+                let identity = identities.get(&port_name);
+
+                let device = MidiDevice {
+                    id: id.clone(),
+                    name: port_name,
+                    is_input: false,
+                    is_controller: false, // Output devices are not controllers
+                    model: identity
+                        .and_then(|i| i.controller_model())
+                        .map(String::from),
+                    firmware: identity.map(|i| i.firmware()),
+                };
+
+                devices.insert(id, device.clone());
+                device
+            })
+            .collect()
     }
 
     /// Get a list of all devices
@@ -140,3 +364,148 @@ impl DeviceRegistryFactory {
         Ok(registry)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outputs_sharing_an_input_name_are_registered() {
+        let registry = DeviceRegistry::new();
+        registry.devices.lock().unwrap().insert(
+            "in:Minilogue".to_string(),
+            MidiDevice {
+                id: "in:Minilogue".to_string(),
+                name: "Minilogue".to_string(),
+                is_input: true,
+                is_controller: false,
+                model: None,
+                firmware: None,
+            },
+        );
+
+        let outputs = registry.register_outputs(vec!["Minilogue".to_string()], &HashMap::new());
+
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].id, "out:Minilogue");
+        let names: Vec<String> = registry
+            .get_output_devices()
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        assert_eq!(names, vec!["Minilogue"]);
+    }
+
+    #[test]
+    fn test_identity_is_only_requested_from_new_ports() {
+        let registry = DeviceRegistry::new();
+        let launchpad = DeviceIdentity::parse(&[
+            0xF0, 0x7E, 0x00, 0x06, 0x02, 0x00, 0x20, 0x29, 0x03, 0x01, 0x00, 0x00, 0x00, 0x04,
+            0x02, 0x00, 0xF7,
+        ])
+        .unwrap();
+        let ports = vec![
+            ("LPX MIDI".to_string(), "MIDIOUT2 (LPX MIDI)".to_string()),
+            ("Minilogue".to_string(), "Minilogue".to_string()),
+        ];
+
+        let identities = registry.cached_identities(&ports, |asked| {
+            assert_eq!(asked, ports.as_slice());
+            HashMap::from([("LPX MIDI".to_string(), launchpad.clone())])
+        });
+        assert_eq!(identities.get("LPX MIDI"), Some(&launchpad));
+
+        // Known ports, answered or not, aren't asked again
+        let identities = registry.cached_identities(&ports, |_| panic!("asked again"));
+        assert_eq!(identities.len(), 1);
+
+        // Unplugged ports are forgotten and asked again when they come back
+        registry.cached_identities(&ports[1..], |_| panic!("asked again"));
+        let mut asked = Vec::new();
+        registry.cached_identities(&ports, |names| {
+            asked = names.to_vec();
+            HashMap::new()
+        });
+        assert_eq!(asked, ports[..1]);
+    }
+
+    #[test]
+    fn test_ports_are_paired_by_device() {
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        // Windows names the output after the input
+        let pairs = port_pairs(
+            &names(&["LPX MIDI", "2- LPX MIDI", "Keystep"]),
+            &names(&["MIDIOUT2 (LPX MIDI)", "MIDIOUT3 (2- LPX MIDI)", "Minilogue"]),
+        );
+        assert_eq!(
+            pairs,
+            vec![
+                ("LPX MIDI".to_string(), "MIDIOUT2 (LPX MIDI)".to_string()),
+                (
+                    "2- LPX MIDI".to_string(),
+                    "MIDIOUT3 (2- LPX MIDI)".to_string()
+                ),
+            ]
+        );
+
+        // Exact names pair first, so a similar name can't take the output
+        let pairs = port_pairs(
+            &names(&["Minilogue In", "Minilogue"]),
+            &names(&["Minilogue"]),
+        );
+        assert_eq!(
+            pairs,
+            vec![("Minilogue".to_string(), "Minilogue".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_user_profiles_are_cached_until_the_directory_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = DeviceRegistry::with_profiles_dir(Some(dir.path().to_path_buf()));
+        let profile = |name: &str| {
+            r#"{
+                "name": "NAME",
+                "port_patterns": ["NAME"],
+                "grid": { "kind": "note", "rows": [[0]] },
+                "pad_colors": { "type": "palette", "palette": "novation" }
+            }"#
+            .replace("NAME", name)
+        };
+
+        std::fs::write(dir.path().join("first.json"), profile("First")).unwrap();
+        let profiles = registry.user_profiles();
+        assert_eq!(profiles.len(), 1);
+        assert!(Arc::ptr_eq(&profiles, &registry.user_profiles()));
+
+        // A new profile is picked up on the next look
+        std::fs::write(dir.path().join("second.json"), profile("Second")).unwrap();
+        let profiles = registry.user_profiles();
+        assert_eq!(profiles.len(), 2);
+
+        assert!(!Arc::ptr_eq(&profiles, &registry.reload_profiles()));
+
+        // Invalid files are reported rather than loaded
+        std::fs::write(dir.path().join("broken.json"), "{").unwrap();
+        assert_eq!(registry.user_profiles().len(), 2);
+        assert_eq!(registry.skipped_profiles().len(), 1);
+    }
+
+    #[test]
+    fn test_grid_discovery_is_rate_limited() {
+        let registry = DeviceRegistry::new();
+        let grid = MonomeGrid {
+            serial: "m1000010".to_string(),
+            kind: "monome 128".to_string(),
+            port: 14656,
+        };
+
+        let grids = registry.discovered_grids(|| Ok(vec![grid.clone()]));
+        assert_eq!(grids.len(), 1);
+
+        // Scans within the interval keep the grid without asking serialosc
+        let grids = registry.discovered_grids(|| panic!("asked again"));
+        assert_eq!(grids[0].id, grid.device().id);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
@@ -6,8 +6,18 @@ use std::time::{Duration, Instant};
 use midir::{MidiOutput, MidiOutputConnection};
 
 use crate::midi::devices::MidiDevice;
+use crate::midi::loop_guard::{EchoWindow, LoopGuard, SuppressedEvent};
+use crate::midi::recorder::Recorder;
+use crate::midi::thru::{MergePriority, MergeState};
+use crate::models::cc::{self, CCValue};
 use crate::models::scene::Scene;
 
+/// CC number for the "Reset All Controllers" channel mode message
+const CC_RESET_ALL_CONTROLLERS: u8 = 121;
+
+/// CC number for the "All Notes Off" channel mode message
+const CC_ALL_NOTES_OFF: u8 = 123;
+
 /// Types of commands that can be sent to the MIDI engine
 pub enum MidiCommand {
     /// Send a CC message immediately
@@ -26,14 +36,31 @@ pub enum MidiCommand {
         curve: TransitionCurve,
     },
     /// Activate a scene (sends all CC values in the scene)
+    ///
+    /// The amount (0.0-1.0) is how far each CC moves from its current value toward
+    /// the scene's value. `replaces` lists the applied scenes the scene takes over
+    /// from, whose other CCs go back to their earlier values; None takes over from
+    /// every scene.
     ActivateScene {
         scene: Scene,
         quantize_beats: Option<u8>,
+        amount: f64,
+        replaces: Option<Vec<String>>,
     },
+    /// Activate a scene, remembering the values it replaces so it can be released
+    HoldScene { scene: Scene, amount: f64 },
+    /// Undo a held scene, returning its CCs to the values they had before it
+    ReleaseScene {
+        scene_id: String,
+        duration_ms: Option<u32>,
+    },
+    /// Move an applied scene's CCs to a new amount between the values it replaced
+    /// and its own, e.g. as pad pressure changes
+    SetSceneAmount { scene: Scene, amount: f64 },
     /// Morph between two scenes over time
     MorphScenes {
-        start_scene: Scene,
-        end_scene: Scene,
+        start_scene: Box<Scene>,
+        end_scene: Box<Scene>,
         duration_ms: u32,
         curve: TransitionCurve,
     },
@@ -41,6 +68,9 @@ pub enum MidiCommand {
     StopTransitions,
     /// Set the engine's tempo in BPM
     SetTempo(f64),
+    /// Follow an external clock, such as a Link session, by putting the beat
+    /// clock at `beat` now and running it at `tempo`
+    SyncBeat { beat: f64, tempo: f64 },
     /// Request the engine to shut down
     Shutdown,
 }
@@ -54,9 +84,20 @@ pub enum TransitionCurve {
     SCurve,
 }
 
+impl From<cc::TransitionCurve> for TransitionCurve {
+    fn from(curve: cc::TransitionCurve) -> Self {
+        match curve {
+            cc::TransitionCurve::Linear => TransitionCurve::Linear,
+            cc::TransitionCurve::Exponential => TransitionCurve::Exponential,
+            cc::TransitionCurve::Logarithmic => TransitionCurve::Logarithmic,
+            cc::TransitionCurve::SCurve => TransitionCurve::SCurve,
+        }
+    }
+}
+
 impl TransitionCurve {
     /// Applies the curve function to a normalized (0.0-1.0) position
-    fn apply(&self, position: f64) -> f64 {
+    pub(crate) fn apply(&self, position: f64) -> f64 {
         match self {
             TransitionCurve::Linear => position,
             TransitionCurve::Exponential => position * position,
@@ -81,12 +122,526 @@ struct ActiveTransition {
     curve: TransitionCurve,
 }
 
+impl ActiveTransition {
+    /// Value of the transition at the given instant
+    fn value_at(&self, now: Instant) -> u8 {
+        let elapsed = now.duration_since(self.start_time);
+        let position = if self.duration.is_zero() {
+            1.0
+        } else {
+            (elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0)
+        };
+
+        let start = self.start_value as f64;
+        let end = self.end_value as f64;
+        let value = start + (end - start) * self.curve.apply(position);
+        value.round().clamp(0.0, 127.0) as u8
+    }
+
+    /// Whether the transition has reached its end value
+    fn is_finished(&self, now: Instant) -> bool {
+        now.duration_since(self.start_time) >= self.duration
+    }
+}
+
+/// Value part way from a base value toward a target
+///
+/// With no base value to start from, the target is used as is.
+fn scale_value(base: Option<u8>, target: u8, amount: f64) -> u8 {
+    match base {
+        Some(base) => {
+            let value = base as f64 + (target as f64 - base as f64) * amount.clamp(0.0, 1.0);
+            value.round() as u8
+        }
+        None => target,
+    }
+}
+
+/// A connected output port
+struct OutputConnection {
+    name: String,
+    connection: MidiOutputConnection,
+    /// Whether scenes are sent here; thru-only outputs just get their routes
+    scenes: bool,
+}
+
+/// State shared between the engine handle and its processing thread
+struct EngineState {
+    connections: Vec<OutputConnection>,
+    transitions: Vec<ActiveTransition>,
+    /// Last value sent for each (channel, cc_number)
+    current_values: HashMap<(u8, u8), u8>,
+    /// Values replaced by recently applied scenes, by scene ID, used to release
+    /// held scenes and to apply scenes part way
+    bases: HashMap<String, HashMap<(u8, u8), u8>>,
+    /// Recent scene and thru activity used to merge the two
+    merge: MergeState,
+    /// Fingerprints of sent messages used to catch echoes
+    guard: LoopGuard,
+    /// Capture of outgoing messages while recording
+    recorder: Option<Recorder>,
+    tempo: f64, // BPM
+    /// Beat position at an instant, from which the beat clock runs at the tempo
+    beat_origin: (Instant, f64),
+    /// Quantized scene waiting for its beat
+    pending: Option<PendingScene>,
+}
+
+/// A scene activation waiting for a beat boundary
+struct PendingScene {
+    scene: Scene,
+    amount: f64,
+    replaces: Option<Vec<String>>,
+    beat: f64,
+}
+
+/// Snapshot of what the engine is doing, used for controller feedback
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineStatus {
+    /// Scene waiting for its quantized start
+    pub queued_scene: Option<String>,
+    /// Whether any CC transition is running
+    pub transitioning: bool,
+    /// Position on the engine's beat clock
+    pub beat: f64,
+    /// Tempo the beat clock runs at, in BPM
+    pub tempo: f64,
+}
+
+impl EngineState {
+    /// Send raw MIDI bytes to the outputs picked by `to`
+    fn send_to(&mut self, message: &[u8], to: impl Fn(&OutputConnection) -> bool) {
+        let now = Instant::now();
+        for output in self.connections.iter_mut().filter(|o| to(o)) {
+            let _ = output.connection.send(message);
+            self.guard.record_sent(&output.name, message, now);
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record(&output.name, message, now);
+            }
+        }
+    }
+
+    /// Send raw MIDI bytes to every output that plays scenes
+    fn send_raw(&mut self, message: &[u8]) {
+        self.send_to(message, |output| output.scenes);
+    }
+
+    /// Send a CC on behalf of a scene, to the outputs thru activity doesn't
+    /// currently own
+    fn send_scene_cc(&mut self, channel: u8, cc: u8, value: u8) {
+        let now = Instant::now();
+        let (blocked, open): (Vec<&OutputConnection>, Vec<&OutputConnection>) = self
+            .connections
+            .iter()
+            .filter(|o| o.scenes)
+            .partition(|o| self.merge.scene_blocked(&o.name, channel, cc, now));
+        if open.is_empty() && !blocked.is_empty() {
+            return;
+        }
+
+        let open: Vec<String> = open.into_iter().map(|o| o.name.clone()).collect();
+        for output in &open {
+            self.merge.record_scene(output, channel, cc, now);
+        }
+
+        let status_byte = 0xB0 + (channel & 0x0F);
+        self.send_to(&[status_byte, cc, value], |o| open.contains(&o.name));
+        self.current_values.insert((channel & 0x0F, cc), value);
+    }
+
+    /// Process a MIDI CC message
+    fn send_cc(&mut self, channel: u8, cc: u8, value: u8) {
+        // MIDI CC message format: 0xB0 + channel, cc number, value
+        let status_byte = 0xB0 + (channel & 0x0F);
+        self.send_raw(&[status_byte, cc, value]);
+        self.current_values.insert((channel & 0x0F, cc), value);
+    }
+
+    /// Start a transition, replacing any transition already running on the same CC
+    fn start_transition(&mut self, transition: ActiveTransition) {
+        self.transitions
+            .retain(|t| t.channel != transition.channel || t.cc_number != transition.cc_number);
+        self.transitions.push(transition);
+    }
+
+    /// Send a CC value, transitioning from the current value if the CC asks for it
+    fn apply_cc_value(&mut self, cc: &CCValue) {
+        let current = self
+            .current_values
+            .get(&(cc.channel, cc.cc_number))
+            .copied();
+
+        match (cc.get_transition_duration_ms(self.tempo), current) {
+            (Some(duration_ms), Some(start_value)) if start_value != cc.value => {
+                self.start_transition(ActiveTransition {
+                    channel: cc.channel,
+                    cc_number: cc.cc_number,
+                    start_value,
+                    end_value: cc.value,
+                    start_time: Instant::now(),
+                    duration: Duration::from_millis(duration_ms as u64),
+                    curve: cc.curve.into(),
+                });
+            }
+            _ => {
+                // Nothing to transition from, so jump straight to the value
+                self.transitions
+                    .retain(|t| t.channel != cc.channel || t.cc_number != cc.cc_number);
+                self.send_scene_cc(cc.channel, cc.cc_number, cc.value);
+            }
+        }
+    }
+
+    /// Run the beat clock at a new tempo, noting it in any recording
+    fn set_tempo(&mut self, tempo: f64, now: Instant) {
+        self.tempo = tempo;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.set_tempo(tempo, now);
+        }
+    }
+
+    /// Position on the beat clock at an instant
+    fn beat_at(&self, now: Instant) -> f64 {
+        let (origin, origin_beat) = self.beat_origin;
+        let elapsed = now.saturating_duration_since(origin).as_secs_f64();
+        origin_beat + elapsed * self.tempo / 60.0
+    }
+
+    /// Activate a scene straight away, taking over from the scenes it replaces
+    fn activate_scene(&mut self, scene: &Scene, amount: f64, replaces: Option<&[String]>) {
+        let Some(replaces) = replaces else {
+            // Taking over from everything commits the scene, so held scenes can no
+            // longer be undone
+            self.bases.clear();
+            self.record_base(scene);
+            self.apply_scene(scene, amount);
+            return;
+        };
+
+        // Values from before the replaced scenes, oldest scene first
+        let mut earlier: HashMap<(u8, u8), u8> = HashMap::new();
+        for scene_id in replaces {
+            for (key, value) in self.bases.remove(scene_id).unwrap_or_default() {
+                earlier.entry(key).or_insert(value);
+            }
+        }
+
+        // CCs the new scene leaves alone go back to how they were
+        let released: HashMap<(u8, u8), u8> = earlier
+            .iter()
+            .filter(|(&(channel, cc_number), _)| scene.get_cc(channel, cc_number).is_none())
+            .map(|(&key, &value)| (key, value))
+            .collect();
+        self.restore_values(released, None);
+
+        // The rest are now the new scene's to release
+        self.record_base(scene);
+        if let Some(base) = self.bases.get_mut(&scene.id) {
+            for (key, value) in base.iter_mut() {
+                if let Some(&earlier) = earlier.get(key) {
+                    *value = earlier;
+                }
+            }
+        }
+        self.apply_scene(scene, amount);
+    }
+
+    /// Return CCs to earlier values, over a linear transition if a duration is given
+    fn restore_values(&mut self, values: HashMap<(u8, u8), u8>, duration_ms: Option<u32>) {
+        for ((channel, cc_number), value) in values {
+            let current = self.current_values.get(&(channel, cc_number)).copied();
+
+            match (duration_ms, current) {
+                (Some(duration_ms), Some(start_value))
+                    if duration_ms > 0 && start_value != value =>
+                {
+                    self.start_transition(ActiveTransition {
+                        channel,
+                        cc_number,
+                        start_value,
+                        end_value: value,
+                        start_time: Instant::now(),
+                        duration: Duration::from_millis(duration_ms as u64),
+                        curve: TransitionCurve::Linear,
+                    });
+                }
+                _ => {
+                    self.transitions
+                        .retain(|t| t.channel != channel || t.cc_number != cc_number);
+                    self.send_scene_cc(channel, cc_number, value);
+                }
+            }
+        }
+    }
+
+    /// Activate the pending scene once its beat has arrived
+    fn update_pending(&mut self) {
+        let due = match &self.pending {
+            Some(pending) => self.beat_at(Instant::now()) >= pending.beat,
+            None => false,
+        };
+
+        if let Some(pending) = self.pending.take_if(|_| due) {
+            self.activate_scene(&pending.scene, pending.amount, pending.replaces.as_deref());
+        }
+    }
+
+    /// Remember the current values of a scene's CCs before it is applied
+    fn record_base(&mut self, scene: &Scene) {
+        let base = scene
+            .cc_values
+            .values()
+            .filter_map(|cc| {
+                let key = (cc.channel, cc.cc_number);
+                self.current_values.get(&key).map(|&value| (key, value))
+            })
+            .collect();
+        self.bases.insert(scene.id.clone(), base);
+    }
+
+    /// Send a scene's values, part way from its base values if the amount is below 1.0
+    fn apply_scene(&mut self, scene: &Scene, amount: f64) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.mark(&scene.name, Instant::now());
+        }
+
+        let base = self.bases.get(&scene.id).cloned().unwrap_or_default();
+        for cc in scene.cc_values.values() {
+            let value = scale_value(
+                base.get(&(cc.channel, cc.cc_number)).copied(),
+                cc.value,
+                amount,
+            );
+            self.apply_cc_value(&cc.with_value(value));
+        }
+    }
+
+    /// Process a single command, returning false if the engine should stop
+    fn process_command(&mut self, command: MidiCommand) -> bool {
+        match command {
+            MidiCommand::SendCC {
+                channel,
+                cc_number,
+                value,
+            } => {
+                self.transitions
+                    .retain(|t| t.channel != channel || t.cc_number != cc_number);
+                self.send_cc(channel, cc_number, value);
+            }
+            MidiCommand::Transition {
+                channel,
+                cc_number,
+                start_value,
+                end_value,
+                duration_ms,
+                curve,
+            } => {
+                self.start_transition(ActiveTransition {
+                    channel,
+                    cc_number,
+                    start_value,
+                    end_value,
+                    start_time: Instant::now(),
+                    duration: Duration::from_millis(duration_ms as u64),
+                    curve,
+                });
+            }
+            MidiCommand::ActivateScene {
+                scene,
+                quantize_beats,
+                amount,
+                replaces,
+            } => match quantize_beats.filter(|&beats| beats > 0) {
+                // Wait for the next multiple of the quantization, replacing any
+                // scene already waiting
+                Some(beats) => {
+                    let quantum = beats as f64;
+                    let now = self.beat_at(Instant::now());
+                    self.pending = Some(PendingScene {
+                        scene,
+                        amount,
+                        replaces,
+                        beat: (now / quantum).floor() * quantum + quantum,
+                    });
+                }
+                None => {
+                    self.pending = None;
+                    self.activate_scene(&scene, amount, replaces.as_deref());
+                }
+            },
+            MidiCommand::HoldScene { scene, amount } => {
+                // Holding a scene again keeps the values from before the first hold
+                if !self.bases.contains_key(&scene.id) {
+                    self.record_base(&scene);
+                }
+                self.apply_scene(&scene, amount);
+            }
+            MidiCommand::ReleaseScene {
+                scene_id,
+                duration_ms,
+            } => {
+                let base = self.bases.remove(&scene_id).unwrap_or_default();
+                self.restore_values(base, duration_ms);
+            }
+            MidiCommand::SetSceneAmount { scene, amount } => {
+                // Scenes replaced since they were applied have nothing to scale from
+                let Some(base) = self.bases.get(&scene.id).cloned() else {
+                    return true;
+                };
+
+                for cc in scene.cc_values.values() {
+                    let key = (cc.channel, cc.cc_number);
+                    let value = scale_value(base.get(&key).copied(), cc.value, amount);
+
+                    self.transitions
+                        .retain(|t| t.channel != cc.channel || t.cc_number != cc.cc_number);
+                    if self.current_values.get(&key) != Some(&value) {
+                        self.send_scene_cc(cc.channel, cc.cc_number, value);
+                    }
+                }
+            }
+            MidiCommand::MorphScenes {
+                start_scene,
+                end_scene,
+                duration_ms,
+                curve,
+            } => {
+                for end in end_scene.cc_values.values() {
+                    let start_value = start_scene
+                        .get_cc(end.channel, end.cc_number)
+                        .map(|cc| cc.value)
+                        .or_else(|| {
+                            self.current_values
+                                .get(&(end.channel, end.cc_number))
+                                .copied()
+                        })
+                        .unwrap_or(end.value);
+
+                    self.start_transition(ActiveTransition {
+                        channel: end.channel,
+                        cc_number: end.cc_number,
+                        start_value,
+                        end_value: end.value,
+                        start_time: Instant::now(),
+                        duration: Duration::from_millis(duration_ms as u64),
+                        curve,
+                    });
+                }
+            }
+            MidiCommand::StopTransitions => self.transitions.clear(),
+            MidiCommand::SetTempo(tempo) => {
+                // Keep the beat position continuous across the change
+                let now = Instant::now();
+                self.beat_origin = (now, self.beat_at(now));
+                self.set_tempo(tempo, now);
+            }
+            MidiCommand::SyncBeat { beat, tempo } => {
+                let now = Instant::now();
+                self.beat_origin = (now, beat);
+                self.tempo = tempo;
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.sync(beat, tempo, now);
+                }
+            }
+            MidiCommand::Shutdown => return false,
+        }
+
+        true
+    }
+
+    /// Advance all running transitions and send their current values
+    fn update_transitions(&mut self) {
+        if self.transitions.is_empty() {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut updates = Vec::new();
+
+        for transition in &self.transitions {
+            let value = transition.value_at(now);
+            let last = self
+                .current_values
+                .get(&(transition.channel, transition.cc_number))
+                .copied();
+
+            // Only send when the value actually changes to avoid flooding the outputs
+            if last != Some(value) {
+                updates.push((transition.channel, transition.cc_number, value));
+            }
+        }
+
+        for (channel, cc_number, value) in updates {
+            self.send_scene_cc(channel, cc_number, value);
+        }
+
+        self.transitions.retain(|t| !t.is_finished(now));
+    }
+}
+
+/// Cloneable handle for sending messages straight to the engine's outputs
+///
+/// Used by the thru router, which needs to forward messages from MIDI input
+/// callbacks without going through the command queue.
+#[derive(Clone)]
+pub struct OutputHandle {
+    state: Arc<Mutex<EngineState>>,
+}
+
+impl OutputHandle {
+    /// Check an incoming message, returning false if it echoes something we sent
+    pub fn allow_incoming(&self, input: &str, message: &[u8]) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.guard.allow_incoming(input, message, Instant::now())
+    }
+
+    /// Forward a thru message to a named output, applying the merge priority
+    pub fn send_thru(
+        &self,
+        output: &str,
+        message: &[u8],
+        priority: MergePriority,
+    ) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        if !state.merge.allow_thru(output, message, priority, now) {
+            return Ok(());
+        }
+
+        let connection = state
+            .connections
+            .iter_mut()
+            .find(|o| o.name == output)
+            .ok_or_else(|| format!("Output not connected: {}", output))?;
+
+        connection
+            .connection
+            .send(message)
+            .map_err(|e| format!("Failed to send MIDI message: {}", e))?;
+
+        // A thru CC moves the value scenes and transitions start from
+        if let ([status, cc_number, value, ..], true) = (message, connection.scenes) {
+            if status & 0xF0 == 0xB0 {
+                state
+                    .current_values
+                    .insert((status & 0x0F, *cc_number), *value);
+            }
+        }
+
+        state.guard.record_sent(output, message, now);
+        if let Some(recorder) = &mut state.recorder {
+            recorder.record(output, message, now);
+        }
+
+        Ok(())
+    }
+}
+
 /// Main MIDI engine that processes and sends MIDI commands
 pub struct MidiEngine {
     command_queue: Arc<Mutex<VecDeque<MidiCommand>>>,
-    connections: Vec<MidiOutputConnection>,
-    transitions: Vec<ActiveTransition>,
-    tempo: f64, // BPM
+    state: Arc<Mutex<EngineState>>,
     running: Arc<Mutex<bool>>,
     thread_handle: Option<thread::JoinHandle<()>>,
 }
@@ -96,9 +651,18 @@ impl MidiEngine {
     pub fn new() -> Result<Self, String> {
         let engine = MidiEngine {
             command_queue: Arc::new(Mutex::new(VecDeque::new())),
-            connections: Vec::new(),
-            transitions: Vec::new(),
-            tempo: 120.0,
+            state: Arc::new(Mutex::new(EngineState {
+                connections: Vec::new(),
+                transitions: Vec::new(),
+                current_values: HashMap::new(),
+                bases: HashMap::new(),
+                merge: MergeState::default(),
+                guard: LoopGuard::default(),
+                recorder: None,
+                tempo: 120.0,
+                beat_origin: (Instant::now(), 0.0),
+                pending: None,
+            })),
             running: Arc::new(Mutex::new(true)),
             thread_handle: None,
         };
@@ -113,31 +677,32 @@ impl MidiEngine {
         }
 
         let command_queue = Arc::clone(&self.command_queue);
+        let state = Arc::clone(&self.state);
         let running = Arc::clone(&self.running);
 
         let handle = thread::spawn(move || {
-            let mut last_process = Instant::now();
-
             while *running.lock().unwrap() {
-                // Process any pending commands
-                let mut commands = command_queue.lock().unwrap();
-
                 // Process up to 10 commands per iteration to prevent blocking
                 for _ in 0..10 {
-                    if let Some(cmd) = commands.pop_front() {
-                        drop(commands); // Release the lock while processing
-
-                        // Process command (implementation will be expanded)
-                        // Placeholder for command processing
+                    // Only hold the queue lock long enough to pop the next command
+                    let command = command_queue.lock().unwrap().pop_front();
 
-                        // Re-acquire the lock for the next iteration
-                        commands = command_queue.lock().unwrap();
-                    } else {
-                        break;
+                    match command {
+                        Some(cmd) => {
+                            if !state.lock().unwrap().process_command(cmd) {
+                                *running.lock().unwrap() = false;
+                                break;
+                            }
+                        }
+                        None => break,
                     }
                 }
 
-                drop(commands); // Release the lock
+                {
+                    let mut state = state.lock().unwrap();
+                    state.update_pending();
+                    state.update_transitions();
+                }
 
                 // Sleep for a short duration to prevent CPU hogging
                 // 1ms gives us approximately 1000Hz processing rate
@@ -150,7 +715,23 @@ impl MidiEngine {
     }
 
     /// Add a MIDI output device connection
-    pub fn add_output(&mut self, device: &MidiDevice) -> Result<(), String> {
+    ///
+    /// Scenes are sent to the output if `scenes` is set; otherwise it only gets
+    /// thru messages. Adding an output that is already connected just lets it
+    /// play scenes if asked.
+    pub fn add_output(&mut self, device: &MidiDevice, scenes: bool) -> Result<(), String> {
+        if let Some(output) = self
+            .state
+            .lock()
+            .unwrap()
+            .connections
+            .iter_mut()
+            .find(|o| o.name == device.name)
+        {
+            output.scenes |= scenes;
+            return Ok(());
+        }
+
         let midi_out = MidiOutput::new("snap-blaster")
             .map_err(|e| format!("Failed to create MIDI output: {}", e))?;
 
@@ -170,11 +751,100 @@ impl MidiEngine {
         let connection = midi_out
             .connect(port, "midi-connection")
             .map_err(|e| format!("Failed to connect to MIDI port: {}", e))?;
-        self.connections.push(connection);
+        self.state
+            .lock()
+            .unwrap()
+            .connections
+            .push(OutputConnection {
+                name: device.name.clone(),
+                connection,
+                scenes,
+            });
 
         Ok(())
     }
 
+    /// Drop the connection to an output, e.g. after its device is unplugged
+    ///
+    /// Returns whether the output was connected.
+    pub fn remove_output(&mut self, name: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let count = state.connections.len();
+        state.connections.retain(|o| o.name != name);
+
+        state.connections.len() != count
+    }
+
+    /// Drop thru-only outputs other than the `used` ones, e.g. after their
+    /// routes are removed or the project is switched
+    ///
+    /// Outputs that play scenes are kept.
+    pub fn remove_unused_thru_outputs(&mut self, used: &[&str]) {
+        self.state
+            .lock()
+            .unwrap()
+            .connections
+            .retain(|o| o.scenes || used.contains(&o.name.as_str()));
+    }
+
+    /// Set the echo-suppression windows for specific input/output pairs
+    pub fn set_echo_windows(&self, windows: &[EchoWindow]) {
+        self.state.lock().unwrap().guard.set_pair_windows(windows);
+    }
+
+    /// Get the incoming messages recently suppressed as echoes
+    pub fn suppressed_events(&self) -> Vec<SuppressedEvent> {
+        self.state.lock().unwrap().guard.suppressed_events()
+    }
+
+    /// Start capturing every message sent to the outputs
+    ///
+    /// Any recording already in progress is discarded.
+    pub fn start_recording(&self) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let beat = state.beat_at(now);
+        state.recorder = Some(Recorder::new(state.tempo, beat, now));
+    }
+
+    /// Carry on with a stopped recording, unless another one has started since
+    pub fn resume_recording(&self, recorder: Recorder) {
+        self.state.lock().unwrap().recorder.get_or_insert(recorder);
+    }
+
+    /// Stop capturing and return the recording, if one was running
+    pub fn stop_recording(&self) -> Option<Recorder> {
+        self.state.lock().unwrap().recorder.take()
+    }
+
+    /// Check whether a recording is in progress
+    pub fn is_recording(&self) -> bool {
+        self.state.lock().unwrap().recorder.is_some()
+    }
+
+    /// Get the queued scene, transition activity and beat position
+    pub fn status(&self) -> EngineStatus {
+        let state = self.state.lock().unwrap();
+        EngineStatus {
+            queued_scene: state.pending.as_ref().map(|p| p.scene.id.clone()),
+            transitioning: !state.transitions.is_empty(),
+            beat: state.beat_at(Instant::now()),
+            tempo: state.tempo,
+        }
+    }
+
+    /// Get the last value sent for each (channel, cc_number)
+    pub fn current_values(&self) -> HashMap<(u8, u8), u8> {
+        self.state.lock().unwrap().current_values.clone()
+    }
+
+    /// Get a handle for sending directly to the connected outputs
+    pub fn output_handle(&self) -> OutputHandle {
+        OutputHandle {
+            state: Arc::clone(&self.state),
+        }
+    }
+
     /// Send a command to the MIDI engine
     pub fn send_command(&self, command: MidiCommand) -> Result<(), String> {
         let mut queue = self.command_queue.lock().unwrap();
@@ -182,14 +852,31 @@ impl MidiEngine {
         Ok(())
     }
 
-    /// Process a MIDI CC message
-    fn send_cc(&mut self, channel: u8, cc: u8, value: u8) {
-        // MIDI CC message format: 0xB0 + channel, cc number, value
-        let status_byte = 0xB0 + (channel & 0x0F);
+    /// Emergency stop that bypasses the command queue
+    ///
+    /// Drops any queued commands, stops all transitions and sends All Notes Off and
+    /// Reset All Controllers on every channel of every output, thru-only ones
+    /// included, followed by the given default values (if any).
+    pub fn panic(&self, defaults: &[CCValue]) -> Result<(), String> {
+        self.command_queue.lock().unwrap().clear();
+
+        let mut state = self.state.lock().unwrap();
+        state.transitions.clear();
+        state.bases.clear();
+        state.pending = None;
 
-        for connection in &mut self.connections {
-            let _ = connection.send(&[status_byte, cc, value]);
+        for channel in 0..16u8 {
+            let status_byte = 0xB0 + channel;
+            state.send_to(&[status_byte, CC_ALL_NOTES_OFF, 0], |_| true);
+            state.send_to(&[status_byte, CC_RESET_ALL_CONTROLLERS, 0], |_| true);
         }
+        state.current_values.clear();
+
+        for cc in defaults {
+            state.send_cc(cc.channel, cc.cc_number, cc.value);
+        }
+
+        Ok(())
     }
 
     /// Shutdown the MIDI engine
@@ -214,3 +901,197 @@ impl Drop for MidiEngine {
         let _ = self.shutdown();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine_state() -> EngineState {
+        EngineState {
+            connections: Vec::new(),
+            transitions: Vec::new(),
+            current_values: HashMap::new(),
+            bases: HashMap::new(),
+            merge: MergeState::default(),
+            guard: LoopGuard::default(),
+            recorder: None,
+            tempo: 120.0,
+            beat_origin: (Instant::now(), 0.0),
+            pending: None,
+        }
+    }
+
+    #[test]
+    fn test_hold_and_release_scene() {
+        let mut state = engine_state();
+        state.send_cc(0, 1, 10);
+
+        let mut scene = Scene::new("hold", "Hold");
+        scene.add_cc(CCValue::new(0, 1, 100));
+        scene.add_cc(CCValue::new(0, 2, 50));
+
+        state.process_command(MidiCommand::HoldScene {
+            scene: scene.clone(),
+            amount: 1.0,
+        });
+        assert_eq!(state.current_values.get(&(0, 1)), Some(&100));
+
+        // Holding again must not overwrite the values from before the first hold
+        state.process_command(MidiCommand::HoldScene { scene, amount: 1.0 });
+        state.process_command(MidiCommand::ReleaseScene {
+            scene_id: "hold".to_string(),
+            duration_ms: None,
+        });
+
+        // CC 1 goes back to its old value; CC 2 had none, so it is left alone
+        assert_eq!(state.current_values.get(&(0, 1)), Some(&10));
+        assert_eq!(state.current_values.get(&(0, 2)), Some(&50));
+        assert!(state.bases.is_empty());
+    }
+
+    #[test]
+    fn test_release_with_transition() {
+        let mut state = engine_state();
+        state.send_cc(0, 1, 10);
+
+        let mut scene = Scene::new("hold", "Hold");
+        scene.add_cc(CCValue::new(0, 1, 100));
+
+        state.process_command(MidiCommand::HoldScene { scene, amount: 1.0 });
+        state.process_command(MidiCommand::ReleaseScene {
+            scene_id: "hold".to_string(),
+            duration_ms: Some(500),
+        });
+
+        assert_eq!(state.transitions.len(), 1);
+        assert_eq!(state.transitions[0].end_value, 10);
+    }
+
+    #[test]
+    fn test_scene_amount() {
+        let mut state = engine_state();
+        state.send_cc(0, 1, 0);
+
+        let mut scene = Scene::new("expressive", "Expressive");
+        scene.add_cc(CCValue::new(0, 1, 100));
+        scene.add_cc(CCValue::new(0, 2, 80));
+
+        state.process_command(MidiCommand::ActivateScene {
+            scene: scene.clone(),
+            quantize_beats: None,
+            amount: 0.5,
+            replaces: None,
+        });
+        assert_eq!(state.current_values.get(&(0, 1)), Some(&50));
+        // With no previous value there is nothing to scale from
+        assert_eq!(state.current_values.get(&(0, 2)), Some(&80));
+
+        // Pressure rescales from the values before the scene, not the scaled ones
+        state.process_command(MidiCommand::SetSceneAmount {
+            scene: scene.clone(),
+            amount: 0.25,
+        });
+        assert_eq!(state.current_values.get(&(0, 1)), Some(&25));
+
+        // Once another scene is activated, the first can no longer be rescaled
+        state.process_command(MidiCommand::ActivateScene {
+            scene: Scene::new("other", "Other"),
+            quantize_beats: None,
+            amount: 1.0,
+            replaces: None,
+        });
+        state.process_command(MidiCommand::SetSceneAmount { scene, amount: 1.0 });
+        assert_eq!(state.current_values.get(&(0, 1)), Some(&25));
+    }
+
+    #[test]
+    fn test_quantized_activation() {
+        let mut state = engine_state();
+        // Put the clock just past beat 5
+        state.beat_origin = (Instant::now() - Duration::from_millis(2600), 0.0);
+
+        let mut scene = Scene::new("queued", "Queued");
+        scene.add_cc(CCValue::new(0, 1, 100));
+
+        state.process_command(MidiCommand::ActivateScene {
+            scene,
+            quantize_beats: Some(4),
+            amount: 1.0,
+            replaces: None,
+        });
+        let pending = state.pending.as_ref().unwrap();
+        assert_eq!(pending.beat, 8.0);
+
+        state.update_pending();
+        assert!(state.current_values.is_empty());
+
+        // Once the clock reaches the bar the scene goes out
+        state.beat_origin.1 = 3.0;
+        state.update_pending();
+        assert!(state.pending.is_none());
+        assert_eq!(state.current_values.get(&(0, 1)), Some(&100));
+    }
+
+    #[test]
+    fn test_sync_beat_to_external_clock() {
+        let mut state = engine_state();
+        state.beat_origin = (Instant::now() - Duration::from_millis(2600), 0.0);
+
+        state.process_command(MidiCommand::SyncBeat {
+            beat: 6.5,
+            tempo: 90.0,
+        });
+        let beat = state.beat_at(Instant::now());
+        assert!((6.5..6.6).contains(&beat));
+        assert_eq!(state.tempo, 90.0);
+
+        // A tempo change keeps the beat where the clock had it
+        state.process_command(MidiCommand::SetTempo(140.0));
+        let beat = state.beat_at(Instant::now());
+        assert!((6.5..6.6).contains(&beat));
+    }
+
+    #[test]
+    fn test_group_activation_releases_replaced_scene() {
+        let mut state = engine_state();
+        state.send_cc(0, 1, 10);
+        state.send_cc(0, 2, 20);
+        state.send_cc(0, 3, 30);
+
+        let mut kick = Scene::new("kick", "Kick");
+        kick.add_cc(CCValue::new(0, 1, 100));
+        kick.add_cc(CCValue::new(0, 2, 100));
+        let mut sweep = Scene::new("sweep", "Sweep");
+        sweep.add_cc(CCValue::new(0, 3, 100));
+        let mut snare = Scene::new("snare", "Snare");
+        snare.add_cc(CCValue::new(0, 1, 60));
+
+        for (scene, replaces) in [(kick, vec![]), (sweep, vec![])] {
+            state.process_command(MidiCommand::ActivateScene {
+                scene,
+                quantize_beats: None,
+                amount: 1.0,
+                replaces: Some(replaces),
+            });
+        }
+
+        // The snare takes over the kick's group, leaving the sweep alone
+        state.process_command(MidiCommand::ActivateScene {
+            scene: snare,
+            quantize_beats: None,
+            amount: 1.0,
+            replaces: Some(vec!["kick".to_string()]),
+        });
+        assert_eq!(state.current_values.get(&(0, 1)), Some(&60));
+        assert_eq!(state.current_values.get(&(0, 2)), Some(&20));
+        assert_eq!(state.current_values.get(&(0, 3)), Some(&100));
+
+        // Releasing the snare returns to the values from before the group
+        state.process_command(MidiCommand::ReleaseScene {
+            scene_id: "snare".to_string(),
+            duration_ms: None,
+        });
+        assert_eq!(state.current_values.get(&(0, 1)), Some(&10));
+        assert_eq!(state.current_values.get(&(0, 3)), Some(&100));
+    }
+}
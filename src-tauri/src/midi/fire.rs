@@ -0,0 +1,348 @@
+use std::sync::Arc;
+
+use midir::{MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+
+use crate::midi::controller::{select_port, ButtonRole, Color, ControllerEvent, GridController};
+use crate::midi::devices::MidiDevice;
+
+/// Akai manufacturer ID and "all devices" ID that start every Fire SysEx
+const AKAI_SYSEX_HEADER: [u8; 3] = [0xF0, 0x47, 0x7F];
+
+/// Akai Fire SysEx ID
+const FIRE_ID: u8 = 0x43;
+
+/// Note of the top-left pad; the rest follow left to right, top to bottom
+const FIRE_FIRST_PAD_NOTE: u8 = 54;
+
+/// Pad rows and columns
+const FIRE_ROWS: u8 = 4;
+const FIRE_COLUMNS: u8 = 16;
+
+/// Buttons with LEDs, addressed by CC with the same number as the note they send
+///
+/// Mode, pattern, browser and grid buttons, the four mute/solo buttons and their
+/// row LEDs, then the step/note/drum/perform, shift, alt, pattern/song and
+/// transport buttons.
+const FIRE_BUTTONS: std::ops::RangeInclusive<u8> = 0x1A..=0x35;
+
+/// Grid up and down, pattern up and down (used as left and right) and shift
+const FIRE_GRID_UP: u8 = 0x1F;
+const FIRE_GRID_DOWN: u8 = 0x20;
+const FIRE_PATTERN_UP: u8 = 0x22;
+const FIRE_PATTERN_DOWN: u8 = 0x23;
+const FIRE_SHIFT: u8 = 0x30;
+
+/// Track mute buttons, top to bottom
+const FIRE_MUTE_BUTTONS: std::ops::RangeInclusive<u8> = 0x24..=0x27;
+
+/// Brightest level of a button LED's primary color
+const FIRE_BUTTON_ON: u8 = 0x02;
+
+/// Build a Fire pad lighting SysEx setting each pad index (0-63) to an RGB color
+///
+/// Channels are scaled from 0-255 down to the device's 0-127 range.
+fn fire_pad_sysex(pads: &[(u8, Color)]) -> Vec<u8> {
+    let len = pads.len() * 4;
+
+    let mut message = AKAI_SYSEX_HEADER.to_vec();
+    message.extend_from_slice(&[FIRE_ID, 0x65, (len >> 7) as u8 & 0x7F, len as u8 & 0x7F]);
+
+    for &(pad, color) in pads {
+        message.extend_from_slice(&[pad, color.r >> 1, color.g >> 1, color.b >> 1]);
+    }
+
+    message.push(0xF7);
+    message
+}
+
+/// Translate an incoming message into a controller event
+///
+/// Pads report their index (0-63) rather than their note. The four knobs and
+/// the select encoder are relative and not exposed.
+fn parse_event(message: &[u8]) -> Option<ControllerEvent> {
+    let (&status, &note, &velocity) = match message {
+        [status, data1, data2, ..] => (status, data1, data2),
+        _ => return None,
+    };
+    let pad_notes = FIRE_FIRST_PAD_NOTE..FIRE_FIRST_PAD_NOTE + FIRE_ROWS * FIRE_COLUMNS;
+
+    match (status & 0xF0, velocity) {
+        (0x80, _) | (0x90, 0) if pad_notes.contains(&note) => {
+            Some(ControllerEvent::PadReleased(note - FIRE_FIRST_PAD_NOTE))
+        }
+        (0x90, velocity) if pad_notes.contains(&note) => Some(ControllerEvent::PadPressed(
+            note - FIRE_FIRST_PAD_NOTE,
+            velocity,
+        )),
+        (0x80, _) | (0x90, 0) => Some(ControllerEvent::ButtonReleased(note)),
+        (0x90, _) => Some(ControllerEvent::ButtonPressed(note)),
+        _ => None,
+    }
+}
+
+/// Implementation for the Akai Fire
+pub struct Fire {
+    device: MidiDevice,
+    input_connection: Option<MidiInputConnection<()>>,
+    output_connection: Option<MidiOutputConnection>,
+    event_callback: Option<Arc<dyn Fn(ControllerEvent) + Send + Sync>>,
+}
+
+impl Fire {
+    pub fn new(device: MidiDevice) -> Self {
+        Fire {
+            device,
+            input_connection: None,
+            output_connection: None,
+            event_callback: None,
+        }
+    }
+
+    /// Whether a MIDI port name belongs to an Akai Fire ("FL STUDIO FIRE")
+    pub fn matches_port_name(name: &str) -> bool {
+        let name = name.to_lowercase();
+        name.contains("fl studio fire") || name.contains("akai fire")
+    }
+
+    fn send(&mut self, message: &[u8]) -> Result<(), String> {
+        if let Some(ref mut conn) = self.output_connection {
+            conn.send(message).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+impl Clone for Fire {
+    fn clone(&self) -> Self {
+        Fire {
+            device: self.device.clone(),
+            input_connection: None,  // Connections can't be cloned
+            output_connection: None, // Connections can't be cloned
+            event_callback: self.event_callback.clone(),
+        }
+    }
+}
+
+impl GridController for Fire {
+    fn clone_box(&self) -> Box<dyn GridController> {
+        Box::new(self.clone())
+    }
+
+    fn connect(&mut self) -> Result<(), String> {
+        // Connect to MIDI input
+        let midi_in = MidiInput::new("snap-blaster").map_err(|e| e.to_string())?;
+
+        let in_ports = midi_in.ports();
+        let in_names: Vec<String> = in_ports
+            .iter()
+            .map(|p| midi_in.port_name(p).unwrap_or_default())
+            .collect();
+        let in_port = select_port(&in_names, "fl studio fire", &self.device.name)
+            .map(|i| &in_ports[i])
+            .ok_or_else(|| format!("Could not find MIDI input device: {}", self.device.name))?;
+
+        let callback = {
+            let event_callback = self.event_callback.clone();
+
+            move |_timestamp, message: &[u8], _: &mut ()| {
+                if let Some(event) = parse_event(message) {
+                    if let Some(ref callback) = event_callback {
+                        callback(event);
+                    }
+                }
+            }
+        };
+
+        let input_conn = midi_in
+            .connect(in_port, "fire-input", callback, ())
+            .map_err(|e| e.to_string())?;
+        self.input_connection = Some(input_conn);
+
+        // Connect to MIDI output
+        let midi_out = MidiOutput::new("snap-blaster").map_err(|e| e.to_string())?;
+
+        let out_ports = midi_out.ports();
+        let out_names: Vec<String> = out_ports
+            .iter()
+            .map(|p| midi_out.port_name(p).unwrap_or_default())
+            .collect();
+        let out_port = select_port(&out_names, "fl studio fire", &self.device.name)
+            .map(|i| &out_ports[i])
+            .ok_or_else(|| format!("Could not find MIDI output device: {}", self.device.name))?;
+
+        let output_conn = midi_out
+            .connect(out_port, "fire-output")
+            .map_err(|e| e.to_string())?;
+        self.output_connection = Some(output_conn);
+
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<(), String> {
+        // Leave the LEDs dark
+        if self.output_connection.is_some() {
+            self.clear()?;
+        }
+
+        self.input_connection = None;
+        self.output_connection = None;
+
+        Ok(())
+    }
+
+    fn set_pad_color(&mut self, grid_id: u8, color: Color) -> Result<(), String> {
+        self.set_pad_colors(&[(grid_id, color)])
+    }
+
+    fn set_pad_colors(&mut self, pads: &[(u8, Color)]) -> Result<(), String> {
+        let leds: Vec<(u8, Color)> = pads
+            .iter()
+            .filter(|(grid_id, _)| *grid_id < FIRE_ROWS * FIRE_COLUMNS)
+            .map(|&(grid_id, color)| (self.map_grid_id(grid_id), color))
+            .collect();
+
+        if leds.is_empty() {
+            return Ok(());
+        }
+        self.send(&fire_pad_sysex(&leds))
+    }
+
+    fn set_button_color(&mut self, button_id: u8, color: Color) -> Result<(), String> {
+        // Button LEDs have fixed colors, so only on and off are meaningful
+        let lit = color.r > 0 || color.g > 0 || color.b > 0;
+        let value = if lit { FIRE_BUTTON_ON } else { 0 };
+
+        self.send(&[0xB0, button_id, value])
+    }
+
+    fn clear(&mut self) -> Result<(), String> {
+        let pads: Vec<(u8, Color)> = (0..FIRE_ROWS * FIRE_COLUMNS)
+            .map(|id| (id, Color::BLACK))
+            .collect();
+        self.set_pad_colors(&pads)?;
+
+        for button_id in FIRE_BUTTONS {
+            self.send(&[0xB0, button_id, 0])?;
+        }
+
+        Ok(())
+    }
+
+    fn grid_size(&self) -> (u8, u8) {
+        (FIRE_ROWS, FIRE_COLUMNS)
+    }
+
+    fn button_role(&self, button_id: u8) -> Option<ButtonRole> {
+        match button_id {
+            FIRE_GRID_UP => Some(ButtonRole::Up),
+            FIRE_GRID_DOWN => Some(ButtonRole::Down),
+            FIRE_PATTERN_UP => Some(ButtonRole::Left),
+            FIRE_PATTERN_DOWN => Some(ButtonRole::Right),
+            FIRE_SHIFT => Some(ButtonRole::Shift),
+            // The four mute buttons beside the pad rows select banks
+            id if FIRE_MUTE_BUTTONS.contains(&id) => {
+                Some(ButtonRole::Bank(id - FIRE_MUTE_BUTTONS.start()))
+            }
+            _ => None,
+        }
+    }
+
+    fn map_grid_id(&self, app_grid_id: u8) -> u8 {
+        if app_grid_id >= FIRE_ROWS * FIRE_COLUMNS {
+            return 0; // Invalid ID
+        }
+
+        // Grid rows count up from the bottom, but Fire pads are numbered from
+        // the top-left
+        let row = app_grid_id / FIRE_COLUMNS;
+        let col = app_grid_id % FIRE_COLUMNS;
+
+        (FIRE_ROWS - 1 - row) * FIRE_COLUMNS + col
+    }
+
+    fn map_to_app_grid_id(&self, controller_id: u8) -> Option<u8> {
+        if controller_id >= FIRE_ROWS * FIRE_COLUMNS {
+            return None;
+        }
+
+        let fire_row = controller_id / FIRE_COLUMNS;
+        let col = controller_id % FIRE_COLUMNS;
+
+        Some((FIRE_ROWS - 1 - fire_row) * FIRE_COLUMNS + col)
+    }
+
+    fn set_event_callback(&mut self, callback: Arc<dyn Fn(ControllerEvent) + Send + Sync>) {
+        self.event_callback = Some(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fire() -> Fire {
+        Fire::new(MidiDevice {
+            id: "in:FL STUDIO FIRE".to_string(),
+            name: "FL STUDIO FIRE".to_string(),
+            is_input: true,
+            is_controller: true,
+            model: None,
+            firmware: None,
+        })
+    }
+
+    #[test]
+    fn test_fire_grid_mapping() {
+        let fire = fire();
+
+        // The bottom-left of the grid is the bottom-left pad
+        assert_eq!(fire.map_grid_id(0), 48);
+        assert_eq!(fire.map_grid_id(15), 63);
+        // The top row is numbered first on the device
+        assert_eq!(fire.map_grid_id(48), 0);
+        assert_eq!(fire.map_grid_id(63), 15);
+
+        for grid_id in 0..64 {
+            let pad = fire.map_grid_id(grid_id);
+            assert_eq!(fire.map_to_app_grid_id(pad), Some(grid_id));
+        }
+        assert_eq!(fire.map_to_app_grid_id(64), None);
+
+        assert_eq!(fire.button_role(FIRE_GRID_UP), Some(ButtonRole::Up));
+        assert_eq!(fire.button_role(0x33), None);
+        assert_eq!(fire.button_role(0x25), Some(ButtonRole::Bank(1)));
+    }
+
+    #[test]
+    fn test_fire_pad_sysex() {
+        let message = fire_pad_sysex(&[(0, Color::new(255, 128, 0)), (63, Color::WHITE)]);
+
+        assert_eq!(
+            message,
+            vec![
+                0xF0, 0x47, 0x7F, 0x43, 0x65, 0x00, 8, // header and payload length
+                0, 0x7F, 0x40, 0x00, // pad 0
+                63, 0x7F, 0x7F, 0x7F, // pad 63
+                0xF7,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fire_parse_events() {
+        assert!(matches!(
+            parse_event(&[0x90, 54, 100]),
+            Some(ControllerEvent::PadPressed(0, 100))
+        ));
+        assert!(matches!(
+            parse_event(&[0x80, 117, 0]),
+            Some(ControllerEvent::PadReleased(63))
+        ));
+        assert!(matches!(
+            parse_event(&[0x90, 0x33, 127]),
+            Some(ControllerEvent::ButtonPressed(0x33))
+        ));
+        // Knob turns are relative CCs
+        assert!(parse_event(&[0xB0, 0x10, 1]).is_none());
+    }
+}
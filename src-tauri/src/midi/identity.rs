@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use midir::{MidiInput, MidiOutput};
+
+/// Universal Identity Request, addressed to every device
+pub const IDENTITY_REQUEST: [u8; 6] = [0xF0, 0x7E, 0x7F, 0x06, 0x01, 0xF7];
+
+/// How long to wait for devices to answer an identity request
+const REPLY_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Controllers recognised from their identity reply, by manufacturer ID and
+/// family code
+///
+/// Names are chosen so the controller factory matches them to the right driver.
+const KNOWN_CONTROLLERS: &[(&[u8], u16, &str)] = &[
+    (&[0x00, 0x20, 0x29], 0x0069, "Launchpad MK2"),
+    (&[0x00, 0x20, 0x29], 0x0103, "Launchpad X"),
+    (&[0x00, 0x20, 0x29], 0x0113, "Launchpad Mini MK3"),
+    (&[0x00, 0x20, 0x29], 0x0123, "Launchpad Pro MK3"),
+    (&[0x47], 0x0028, "Akai APC mini"),
+    (&[0x47], 0x004F, "Akai APC mini mk2"),
+    (&[0x47], 0x0029, "Akai APC40 mkII"),
+    (&[0x47], 0x0043, "Akai FL Studio Fire"),
+    (&[0x47], 0x0015, "Ableton Push"),
+    (&[0x00, 0x21, 0x1D], 0x3267, "Ableton Push 2"),
+];
+
+/// A device's answer to an identity request
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceIdentity {
+    /// One-byte or three-byte (0x00-prefixed) manufacturer ID
+    pub manufacturer: Vec<u8>,
+    /// Family and model codes, sent least significant byte first
+    pub family: u16,
+    pub model: u16,
+    /// Software revision bytes
+    pub version: Vec<u8>,
+}
+
+impl DeviceIdentity {
+    /// Parse an Identity Reply (F0 7E <device> 06 02 ...)
+    pub fn parse(message: &[u8]) -> Option<Self> {
+        let body = match message {
+            [0xF0, 0x7E, _, 0x06, 0x02, body @ .., 0xF7] => body,
+            _ => return None,
+        };
+
+        let manufacturer_len = if body.first() == Some(&0x00) { 3 } else { 1 };
+        if body.len() < manufacturer_len + 4 {
+            return None;
+        }
+        let (manufacturer, rest) = body.split_at(manufacturer_len);
+
+        Some(DeviceIdentity {
+            manufacturer: manufacturer.to_vec(),
+            family: u16::from_le_bytes([rest[0], rest[1]]),
+            model: u16::from_le_bytes([rest[2], rest[3]]),
+            version: rest[4..].iter().take(4).copied().collect(),
+        })
+    }
+
+    /// Name of the controller model, if it's one we have a driver for
+    pub fn controller_model(&self) -> Option<&'static str> {
+        KNOWN_CONTROLLERS
+            .iter()
+            .find(|(manufacturer, family, _)| {
+                *manufacturer == self.manufacturer.as_slice() && *family == self.family
+            })
+            .map(|(_, _, name)| *name)
+    }
+
+    /// Firmware version as dotted revision numbers
+    pub fn firmware(&self) -> String {
+        self.version
+            .iter()
+            .map(|b| b.to_string())
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+/// Send an identity request to each device's output port, given as (input,
+/// output) port name pairs, and collect the replies by input port name
+///
+/// All ports are asked at once, so a scan waits for a single timeout. Ports that
+/// can't be opened (e.g. because they are already in use) or don't answer are
+/// left out.
+pub fn identify_ports(ports: &[(String, String)]) -> HashMap<String, DeviceIdentity> {
+    let replies: Arc<Mutex<HashMap<String, DeviceIdentity>>> = Arc::default();
+    let mut connections = Vec::new();
+
+    for (name, out_name) in ports {
+        let midi_in = match MidiInput::new("snap-blaster-identify") {
+            Ok(midi_in) => midi_in,
+            Err(_) => continue,
+        };
+        let midi_out = match MidiOutput::new("snap-blaster-identify") {
+            Ok(midi_out) => midi_out,
+            Err(_) => continue,
+        };
+
+        let in_port = midi_in
+            .ports()
+            .into_iter()
+            .find(|p| midi_in.port_name(p).ok().as_ref() == Some(name));
+        let out_port = midi_out
+            .ports()
+            .into_iter()
+            .find(|p| midi_out.port_name(p).ok().as_ref() == Some(out_name));
+        let (Some(in_port), Some(out_port)) = (in_port, out_port) else {
+            continue;
+        };
+
+        let input = {
+            let replies = Arc::clone(&replies);
+            let name = name.clone();
+
+            midi_in.connect(
+                &in_port,
+                "identify-input",
+                move |_timestamp, message: &[u8], _: &mut ()| {
+                    if let Some(identity) = DeviceIdentity::parse(message) {
+                        replies
+                            .lock()
+                            .unwrap()
+                            .entry(name.clone())
+                            .or_insert(identity);
+                    }
+                },
+                (),
+            )
+        };
+        let Ok(input) = input else {
+            continue;
+        };
+
+        if let Ok(mut output) = midi_out.connect(&out_port, "identify-output") {
+            if output.send(&IDENTITY_REQUEST).is_ok() {
+                connections.push((input, output));
+            }
+        }
+    }
+
+    if !connections.is_empty() {
+        thread::sleep(REPLY_TIMEOUT);
+    }
+    drop(connections);
+
+    let replies = replies.lock().unwrap();
+    replies.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_identity_reply() {
+        // Launchpad X, firmware 0.4.2.0
+        let reply = [
+            0xF0, 0x7E, 0x00, 0x06, 0x02, 0x00, 0x20, 0x29, 0x03, 0x01, 0x00, 0x00, 0x00, 0x04,
+            0x02, 0x00, 0xF7,
+        ];
+        let identity = DeviceIdentity::parse(&reply).unwrap();
+
+        assert_eq!(identity.manufacturer, vec![0x00, 0x20, 0x29]);
+        assert_eq!(identity.family, 0x0103);
+        assert_eq!(identity.controller_model(), Some("Launchpad X"));
+        assert_eq!(identity.firmware(), "0.4.2.0");
+    }
+
+    #[test]
+    fn test_known_controllers() {
+        let apc = DeviceIdentity::parse(&[
+            0xF0, 0x7E, 0x00, 0x06, 0x02, 0x47, 0x4F, 0x00, 0x19, 0x00, 0x01, 0x02, 0xF7,
+        ])
+        .unwrap();
+        assert_eq!(apc.controller_model(), Some("Akai APC mini mk2"));
+        assert_eq!(apc.firmware(), "1.2");
+
+        // A synth answering the request is not a controller
+        let synth = DeviceIdentity::parse(&[
+            0xF0, 0x7E, 0x10, 0x06, 0x02, 0x41, 0x10, 0x01, 0x00, 0x00, 0x01, 0xF7,
+        ])
+        .unwrap();
+        assert_eq!(synth.controller_model(), None);
+
+        assert!(DeviceIdentity::parse(&[0xF0, 0x7E, 0x00, 0x06, 0x02, 0xF7]).is_none());
+    }
+}
@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use midir::{MidiInput, MidiInputConnection};
+use serde::{Deserialize, Serialize};
+
+use crate::midi::engine::OutputHandle;
+
+/// Taps further apart than this start a new tempo
+const TAP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Number of recent taps averaged for the tempo
+const TAP_HISTORY: usize = 4;
+
+/// Tempos tap tempo can set, in BPM
+const TAP_TEMPO_RANGE: (f64, f64) = (20.0, 300.0);
+
+/// Incoming message a mapping responds to
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum MidiTrigger {
+    /// A CC, e.g. from a footswitch or knob
+    ControlChange { channel: u8, cc_number: u8 },
+    /// A note, e.g. from a drum pad
+    Note { channel: u8, note: u8 },
+    /// A program change
+    ProgramChange { channel: u8, program: u8 },
+}
+
+impl MidiTrigger {
+    /// The trigger a message matches and its value (0-127)
+    ///
+    /// Note offs have a value of 0 and program changes a value of 127.
+    pub fn from_message(message: &[u8]) -> Option<(Self, u8)> {
+        let (&status, data) = message.split_first()?;
+        let channel = status & 0x0F;
+
+        match (status & 0xF0, data) {
+            (0xB0, &[cc_number, value, ..]) => {
+                Some((MidiTrigger::ControlChange { channel, cc_number }, value))
+            }
+            (0x90, &[note, velocity, ..]) => Some((MidiTrigger::Note { channel, note }, velocity)),
+            (0x80, &[note, ..]) => Some((MidiTrigger::Note { channel, note }, 0)),
+            (0xC0, &[program, ..]) => Some((MidiTrigger::ProgramChange { channel, program }, 127)),
+            _ => None,
+        }
+    }
+}
+
+/// What a learned message does in the app
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum LearnAction {
+    /// Activate a scene
+    TriggerScene {
+        scene_id: String,
+    },
+    /// Activate the scene after the last one activated in the current bank
+    NextScene,
+    /// Activate the scene before the last one activated in the current bank
+    PreviousScene,
+    /// Stop everything, like the panic button setting
+    Panic,
+    BankUp,
+    BankDown,
+    /// Set the tempo from the time between presses
+    TapTempo,
+    /// Move the last activated scene between the values it replaced and its own
+    MorphAmount,
+}
+
+impl LearnAction {
+    /// Whether the action follows the message's value rather than firing on
+    /// presses
+    pub fn continuous(&self) -> bool {
+        matches!(self, LearnAction::MorphAmount)
+    }
+}
+
+/// A message bound to an action
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MidiMapping {
+    /// Unique mapping ID
+    pub id: String,
+
+    /// Input port the message must come from, or None for any input
+    #[serde(default)]
+    pub input_device: Option<String>,
+
+    pub trigger: MidiTrigger,
+
+    pub action: LearnAction,
+}
+
+impl MidiMapping {
+    /// Whether a message from an input port matches this mapping
+    pub fn matches(&self, input_device: &str, trigger: MidiTrigger) -> bool {
+        self.trigger == trigger
+            && self
+                .input_device
+                .as_deref()
+                .is_none_or(|device| device == input_device)
+    }
+}
+
+/// Tempo from the intervals between taps
+#[derive(Debug, Default)]
+pub struct TapTempo {
+    taps: Vec<Instant>,
+}
+
+impl TapTempo {
+    /// Record a tap, returning the tempo once there are two taps in a row
+    pub fn tap(&mut self, now: Instant) -> Option<f64> {
+        if self
+            .taps
+            .last()
+            .is_some_and(|last| now.duration_since(*last) > TAP_TIMEOUT)
+        {
+            self.taps.clear();
+        }
+
+        self.taps.push(now);
+        if self.taps.len() > TAP_HISTORY {
+            self.taps.remove(0);
+        }
+
+        let (first, last) = (self.taps.first()?, self.taps.last()?);
+        let intervals = self.taps.len() - 1;
+        if intervals == 0 {
+            return None;
+        }
+
+        let beat = last.duration_since(*first).as_secs_f64() / intervals as f64;
+        Some((60.0 / beat).clamp(TAP_TEMPO_RANGE.0, TAP_TEMPO_RANGE.1))
+    }
+}
+
+/// Learn mode and the state mappings need between messages
+#[derive(Debug, Default)]
+pub struct MidiLearn {
+    /// Action the next message will be bound to
+    learning: Option<LearnAction>,
+    /// Whether each (input, trigger) was last pressed, so held messages fire once
+    pressed: HashMap<(String, MidiTrigger), bool>,
+    tap_tempo: TapTempo,
+}
+
+impl MidiLearn {
+    /// Bind the next message to an action
+    pub fn start(&mut self, action: LearnAction) {
+        self.learning = Some(action);
+    }
+
+    /// Leave learn mode without binding anything
+    pub fn cancel(&mut self) {
+        self.learning = None;
+    }
+
+    /// Action waiting for a message, if in learn mode
+    pub fn learning(&self) -> Option<&LearnAction> {
+        self.learning.as_ref()
+    }
+
+    /// Finish learn mode with a message, returning the new mapping
+    ///
+    /// Releases are skipped, so a note or footswitch is learned from its press.
+    pub fn learn(
+        &mut self,
+        input_device: &str,
+        trigger: MidiTrigger,
+        value: u8,
+    ) -> Option<MidiMapping> {
+        if value == 0 && !matches!(trigger, MidiTrigger::ProgramChange { .. }) {
+            return None;
+        }
+
+        let action = self.learning.take()?;
+
+        // The press being learned shouldn't fire the new mapping when it repeats
+        self.pressed
+            .insert((input_device.to_string(), trigger), true);
+
+        Some(MidiMapping {
+            id: String::new(),
+            input_device: Some(input_device.to_string()),
+            trigger,
+            action,
+        })
+    }
+
+    /// Actions to perform for a message, with the message's value
+    ///
+    /// Continuous actions follow every value. Others fire when a note or CC
+    /// goes from released (below 64 for CCs) to pressed, and on every program
+    /// change.
+    pub fn actions(
+        &mut self,
+        mappings: &[MidiMapping],
+        input_device: &str,
+        trigger: MidiTrigger,
+        value: u8,
+    ) -> Vec<(LearnAction, u8)> {
+        let is_pressed = match trigger {
+            MidiTrigger::ControlChange { .. } => value >= 64,
+            MidiTrigger::Note { .. } => value > 0,
+            MidiTrigger::ProgramChange { .. } => true,
+        };
+        let was_pressed = match trigger {
+            MidiTrigger::ProgramChange { .. } => false,
+            _ => self
+                .pressed
+                .insert((input_device.to_string(), trigger), is_pressed)
+                .unwrap_or(false),
+        };
+        let press = is_pressed && !was_pressed;
+
+        mappings
+            .iter()
+            .filter(|mapping| mapping.matches(input_device, trigger))
+            .filter(|mapping| press || mapping.action.continuous())
+            .map(|mapping| (mapping.action.clone(), value))
+            .collect()
+    }
+
+    /// Record a tap for tap tempo
+    pub fn tap(&mut self, now: Instant) -> Option<f64> {
+        self.tap_tempo.tap(now)
+    }
+}
+
+/// The scene after (or before) the current one in a list, wrapping around
+///
+/// Without a current scene in the list, stepping forward starts at the first
+/// scene and stepping back at the last.
+pub fn step_scene<'a>(
+    scene_ids: &'a [String],
+    current: Option<&str>,
+    forward: bool,
+) -> Option<&'a String> {
+    if scene_ids.is_empty() {
+        return None;
+    }
+
+    let count = scene_ids.len();
+    let index = match current.and_then(|id| scene_ids.iter().position(|s| s == id)) {
+        Some(index) if forward => (index + 1) % count,
+        Some(index) => (index + count - 1) % count,
+        None if forward => 0,
+        None => count - 1,
+    };
+
+    scene_ids.get(index)
+}
+
+/// Handler for messages on the inputs mappings listen to, given the port name
+pub type LearnInputCallback = Arc<dyn Fn(&str, &[u8]) + Send + Sync>;
+
+/// Listens on MIDI inputs for learn mode and mapped messages
+pub struct LearnInputs {
+    connections: Vec<MidiInputConnection<()>>,
+}
+
+impl LearnInputs {
+    /// Create a listener with no inputs open
+    pub fn new() -> Self {
+        LearnInputs {
+            connections: Vec::new(),
+        }
+    }
+
+    /// Open every input port except the excluded ones and pass their messages on
+    ///
+    /// Inputs already open are closed first. Controllers are excluded since their
+    /// pads and buttons are handled by their own drivers; ports that can't be
+    /// opened are skipped.
+    pub fn start(
+        &mut self,
+        excluded: &[String],
+        outputs: OutputHandle,
+        callback: LearnInputCallback,
+    ) -> Result<(), String> {
+        self.stop();
+
+        let scanner = MidiInput::new("snap-blaster-learn").map_err(|e| e.to_string())?;
+        let port_names: Vec<String> = scanner
+            .ports()
+            .iter()
+            .filter_map(|port| scanner.port_name(port).ok())
+            .filter(|name| !excluded.contains(name))
+            .collect();
+
+        for port_name in port_names {
+            let midi_in = MidiInput::new("snap-blaster-learn").map_err(|e| e.to_string())?;
+            let in_ports = midi_in.ports();
+            let Some(in_port) = in_ports.iter().find(|p| {
+                midi_in
+                    .port_name(p)
+                    .map(|name| name == port_name)
+                    .unwrap_or(false)
+            }) else {
+                continue;
+            };
+
+            let outputs = outputs.clone();
+            let callback = Arc::clone(&callback);
+            let name = port_name.clone();
+            let handler = move |_timestamp, message: &[u8], _: &mut ()| {
+                // Echoes of our own output aren't the user's messages
+                if outputs.allow_incoming(&name, message) {
+                    callback(&name, message);
+                }
+            };
+
+            if let Ok(connection) = midi_in.connect(in_port, "learn-input", handler, ()) {
+                self.connections.push(connection);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Close all inputs
+    pub fn stop(&mut self) {
+        self.connections.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn footswitch() -> MidiTrigger {
+        MidiTrigger::ControlChange {
+            channel: 0,
+            cc_number: 64,
+        }
+    }
+
+    #[test]
+    fn test_trigger_from_message() {
+        assert_eq!(
+            MidiTrigger::from_message(&[0xB2, 64, 127]),
+            Some((
+                MidiTrigger::ControlChange {
+                    channel: 2,
+                    cc_number: 64
+                },
+                127
+            ))
+        );
+        assert_eq!(
+            MidiTrigger::from_message(&[0x99, 36, 100]),
+            Some((
+                MidiTrigger::Note {
+                    channel: 9,
+                    note: 36
+                },
+                100
+            ))
+        );
+        assert_eq!(
+            MidiTrigger::from_message(&[0x89, 36, 64]),
+            Some((
+                MidiTrigger::Note {
+                    channel: 9,
+                    note: 36
+                },
+                0
+            ))
+        );
+        assert_eq!(
+            MidiTrigger::from_message(&[0xC0, 5]),
+            Some((
+                MidiTrigger::ProgramChange {
+                    channel: 0,
+                    program: 5
+                },
+                127
+            ))
+        );
+        assert_eq!(MidiTrigger::from_message(&[0xF8]), None);
+        assert_eq!(MidiTrigger::from_message(&[0xB0, 64]), None);
+    }
+
+    #[test]
+    fn test_learn_then_fire() {
+        let mut learn = MidiLearn::default();
+        learn.start(LearnAction::NextScene);
+
+        // A release doesn't finish learning
+        assert!(learn.learn("Pedal", footswitch(), 0).is_none());
+        let mut mapping = learn.learn("Pedal", footswitch(), 127).unwrap();
+        assert_eq!(mapping.action, LearnAction::NextScene);
+        assert!(learn.learning().is_none());
+        mapping.id = "pedal".to_string();
+        let mappings = vec![mapping];
+
+        // The learned press is still held, so it fires after a release
+        assert!(learn
+            .actions(&mappings, "Pedal", footswitch(), 127)
+            .is_empty());
+        assert!(learn
+            .actions(&mappings, "Pedal", footswitch(), 0)
+            .is_empty());
+        assert_eq!(
+            learn.actions(&mappings, "Pedal", footswitch(), 127),
+            vec![(LearnAction::NextScene, 127)]
+        );
+        // Only the mapping's input matches
+        learn.actions(&mappings, "Other", footswitch(), 0);
+        assert!(learn
+            .actions(&mappings, "Other", footswitch(), 127)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_continuous_actions_follow_values() {
+        let mut learn = MidiLearn::default();
+        let mappings = vec![MidiMapping {
+            id: "morph".to_string(),
+            input_device: None,
+            trigger: footswitch(),
+            action: LearnAction::MorphAmount,
+        }];
+
+        assert_eq!(
+            learn.actions(&mappings, "Knobs", footswitch(), 10),
+            vec![(LearnAction::MorphAmount, 10)]
+        );
+        assert_eq!(
+            learn.actions(&mappings, "Knobs", footswitch(), 11),
+            vec![(LearnAction::MorphAmount, 11)]
+        );
+    }
+
+    #[test]
+    fn test_tap_tempo() {
+        let mut tap_tempo = TapTempo::default();
+        let start = Instant::now();
+
+        assert_eq!(tap_tempo.tap(start), None);
+        assert_eq!(
+            tap_tempo.tap(start + Duration::from_millis(500)),
+            Some(120.0)
+        );
+        assert_eq!(
+            tap_tempo.tap(start + Duration::from_millis(1000)),
+            Some(120.0)
+        );
+
+        // A long pause starts again
+        assert_eq!(tap_tempo.tap(start + Duration::from_secs(5)), None);
+    }
+
+    #[test]
+    fn test_step_scene() {
+        let scenes: Vec<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+
+        assert_eq!(step_scene(&scenes, Some("a"), true).unwrap(), "b");
+        assert_eq!(step_scene(&scenes, Some("c"), true).unwrap(), "a");
+        assert_eq!(step_scene(&scenes, Some("a"), false).unwrap(), "c");
+        assert_eq!(step_scene(&scenes, None, true).unwrap(), "a");
+        assert_eq!(step_scene(&scenes, None, false).unwrap(), "c");
+        assert_eq!(step_scene(&[], None, true), None);
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// How long a sent message is remembered when looking for its echo
+const DEFAULT_FINGERPRINT_TTL: Duration = Duration::from_millis(100);
+
+/// Maximum number of suppressed events kept for diagnostics
+const MAX_SUPPRESSED_EVENTS: usize = 200;
+
+/// An incoming message dropped because it looked like an echo of our own output
+#[derive(Clone, Debug, Serialize)]
+pub struct SuppressedEvent {
+    /// When the echo arrived (RFC 3339)
+    pub timestamp: String,
+    /// Input port the echo arrived on
+    pub input_device: String,
+    /// Output port the original message was sent to
+    pub output_device: String,
+    /// The suppressed message bytes
+    pub message: Vec<u8>,
+}
+
+/// Echo-suppression window for a pair of devices, kept in the project settings
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct EchoWindow {
+    /// Input port the echo arrives on
+    pub input_device: String,
+    /// Output port the original message goes to
+    pub output_device: String,
+    /// How long after sending an echo is looked for
+    pub window_ms: u32,
+}
+
+/// A message we recently sent to an output
+struct Fingerprint {
+    output_device: String,
+    output_key: String,
+    message: Vec<u8>,
+    sent_at: Instant,
+}
+
+/// Whether a word in a port name only describes direction or port numbering
+fn is_port_noise(word: &str) -> bool {
+    let is_alsa_suffix = word.contains(':') && word.chars().all(|c| c.is_ascii_digit() || c == ':');
+
+    matches!(word, "in" | "out" | "input" | "output")
+        || word.starts_with("midiin")
+        || word.starts_with("midiout")
+        || is_alsa_suffix
+}
+
+/// Normalize a port name so the input and output ports of one device compare equal
+///
+/// Strips direction words ("In", "Out", "MIDIIN2 (...)") and ALSA client:port
+/// suffixes such as "20:0".
+pub fn device_key(port_name: &str) -> String {
+    port_name
+        .to_lowercase()
+        .replace(['(', ')'], " ")
+        .split_whitespace()
+        .filter(|word| !is_port_noise(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Detects messages that come back from devices which echo their input
+///
+/// Every message sent to an output is fingerprinted. An incoming message that
+/// matches a fingerprint from the same device (or from an explicitly paired
+/// device) inside the echo window is treated as an echo and suppressed.
+pub struct LoopGuard {
+    ttl: Duration,
+    /// Echo windows for explicit (input key, output key) pairs
+    pair_windows: HashMap<(String, String), Duration>,
+    fingerprints: VecDeque<Fingerprint>,
+    suppressed: VecDeque<SuppressedEvent>,
+}
+
+impl LoopGuard {
+    /// Create a loop guard with the given fingerprint lifetime
+    pub fn new(ttl: Duration) -> Self {
+        LoopGuard {
+            ttl,
+            pair_windows: HashMap::new(),
+            fingerprints: VecDeque::new(),
+            suppressed: VecDeque::new(),
+        }
+    }
+
+    /// Set the echo-suppression window for a specific input/output pair
+    ///
+    /// Useful for hardware loops between different devices, or for devices that
+    /// echo more slowly than the default fingerprint lifetime.
+    pub fn set_pair_window(&mut self, input_device: &str, output_device: &str, window: Duration) {
+        self.pair_windows.insert(
+            (device_key(input_device), device_key(output_device)),
+            window,
+        );
+    }
+
+    /// Replace every pair window with the given ones
+    pub fn set_pair_windows(&mut self, windows: &[EchoWindow]) {
+        self.pair_windows.clear();
+        for window in windows {
+            self.set_pair_window(
+                &window.input_device,
+                &window.output_device,
+                Duration::from_millis(window.window_ms as u64),
+            );
+        }
+    }
+
+    /// Echo window for an input/output pair, if the pair can echo at all
+    fn window_for(&self, input_key: &str, output_key: &str) -> Option<Duration> {
+        self.pair_windows
+            .get(&(input_key.to_string(), output_key.to_string()))
+            .copied()
+            .or_else(|| (input_key == output_key).then_some(self.ttl))
+    }
+
+    /// Longest window any fingerprint needs to be kept for
+    fn max_window(&self) -> Duration {
+        self.pair_windows
+            .values()
+            .copied()
+            .fold(self.ttl, Duration::max)
+    }
+
+    /// Remember a message sent to an output
+    pub fn record_sent(&mut self, output_device: &str, message: &[u8], now: Instant) {
+        self.prune(now);
+        self.fingerprints.push_back(Fingerprint {
+            output_device: output_device.to_string(),
+            output_key: device_key(output_device),
+            message: message.to_vec(),
+            sent_at: now,
+        });
+    }
+
+    /// Check an incoming message, returning false if it should be suppressed as an echo
+    pub fn allow_incoming(&mut self, input_device: &str, message: &[u8], now: Instant) -> bool {
+        self.prune(now);
+
+        let input_key = device_key(input_device);
+        let echo = self.fingerprints.iter().position(|f| {
+            f.message == message
+                && self
+                    .window_for(&input_key, &f.output_key)
+                    .map(|window| now.duration_since(f.sent_at) <= window)
+                    .unwrap_or(false)
+        });
+
+        let Some(index) = echo else {
+            return true;
+        };
+
+        // Each sent message can only echo once
+        let fingerprint = self.fingerprints.remove(index).unwrap();
+
+        if self.suppressed.len() >= MAX_SUPPRESSED_EVENTS {
+            self.suppressed.pop_front();
+        }
+        self.suppressed.push_back(SuppressedEvent {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            input_device: input_device.to_string(),
+            output_device: fingerprint.output_device,
+            message: message.to_vec(),
+        });
+
+        false
+    }
+
+    /// Get the most recently suppressed events, oldest first
+    pub fn suppressed_events(&self) -> Vec<SuppressedEvent> {
+        self.suppressed.iter().cloned().collect()
+    }
+
+    /// Drop fingerprints that are too old to match anything
+    fn prune(&mut self, now: Instant) {
+        let max_window = self.max_window();
+        while let Some(front) = self.fingerprints.front() {
+            if now.duration_since(front.sent_at) > max_window {
+                self.fingerprints.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl Default for LoopGuard {
+    fn default() -> Self {
+        LoopGuard::new(DEFAULT_FINGERPRINT_TTL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_key() {
+        assert_eq!(device_key("Launchpad X In"), device_key("Launchpad X Out"));
+        assert_eq!(
+            device_key("MIDIIN2 (LPX MIDI)"),
+            device_key("MIDIOUT2 (LPX MIDI)")
+        );
+        assert_eq!(
+            device_key("Launchpad X:Launchpad X LPX MIDI 20:1"),
+            "launchpad x:launchpad x lpx midi"
+        );
+        assert_ne!(device_key("Launchpad X"), device_key("Digitakt"));
+    }
+
+    #[test]
+    fn test_echo_from_same_device_is_suppressed() {
+        let mut guard = LoopGuard::default();
+        let start = Instant::now();
+
+        guard.record_sent("Synth Out", &[0xB0, 74, 10], start);
+
+        // Unrelated device and different message pass
+        assert!(guard.allow_incoming("Keyboard In", &[0xB0, 74, 10], start));
+        assert!(guard.allow_incoming("Synth In", &[0xB0, 74, 11], start));
+
+        // The echo is suppressed once, then the fingerprint is consumed
+        assert!(!guard.allow_incoming("Synth In", &[0xB0, 74, 10], start));
+        assert!(guard.allow_incoming("Synth In", &[0xB0, 74, 10], start));
+
+        let events = guard.suppressed_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].input_device, "Synth In");
+        assert_eq!(events[0].output_device, "Synth Out");
+    }
+
+    #[test]
+    fn test_fingerprints_expire() {
+        let mut guard = LoopGuard::new(Duration::from_millis(50));
+        let start = Instant::now();
+
+        guard.record_sent("Synth", &[0x90, 60, 100], start);
+
+        let later = start + Duration::from_millis(80);
+        assert!(guard.allow_incoming("Synth", &[0x90, 60, 100], later));
+    }
+
+    #[test]
+    fn test_pair_window() {
+        let mut guard = LoopGuard::new(Duration::from_millis(50));
+        guard.set_pair_window("Mixer In", "Looper Out", Duration::from_millis(300));
+        let start = Instant::now();
+
+        guard.record_sent("Looper Out", &[0xB1, 7, 100], start);
+
+        let later = start + Duration::from_millis(200);
+        assert!(!guard.allow_incoming("Mixer In", &[0xB1, 7, 100], later));
+
+        // Replacing the windows drops the pair, so only same-device echoes are caught
+        guard.set_pair_windows(&[EchoWindow {
+            input_device: "Synth In".to_string(),
+            output_device: "Looper Out".to_string(),
+            window_ms: 300,
+        }]);
+        guard.record_sent("Looper Out", &[0xB1, 7, 100], start);
+        assert!(guard.allow_incoming("Mixer In", &[0xB1, 7, 100], later));
+        assert!(!guard.allow_incoming("Synth In", &[0xB1, 7, 100], later));
+    }
+}
@@ -1,3 +1,16 @@
+pub mod apc;
 pub mod controller;
 pub mod devices;
 pub mod engine;
+pub mod fire;
+pub mod identity;
+pub mod learn;
+pub mod loop_guard;
+pub mod monome;
+pub mod palette;
+pub mod profile;
+pub mod push;
+pub mod recorder;
+pub mod simulator;
+pub mod smf;
+pub mod thru;
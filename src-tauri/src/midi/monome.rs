@@ -0,0 +1,757 @@
+use std::net::{Ipv4Addr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::midi::controller::{Color, ControllerEvent, GridController};
+use crate::midi::devices::MidiDevice;
+use crate::midi::palette::PadState;
+
+/// Port serialosc listens on for discovery requests
+pub const SERIALOSC_PORT: u16 = 12002;
+
+/// Model given to grids found through serialosc
+pub const MONOME_MODEL: &str = "monome";
+
+/// Prefix of device IDs for grids found through serialosc
+const MONOME_ID_PREFIX: &str = "monome:";
+
+/// Prefix the grid puts on its messages to us
+const MONOME_PREFIX: &str = "/snapblaster";
+
+/// How long to wait for serialosc or a grid to answer
+const REPLY_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Brightest varibright level
+const MAX_LEVEL: u8 = 15;
+
+/// Side of the quads that `/grid/led/level/map` updates
+const QUAD_SIDE: u8 = 8;
+
+/// Argument of an OSC message
+#[derive(Clone, Debug, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Str(String),
+}
+
+/// An OSC message, with the int and string arguments serialosc uses
+#[derive(Clone, Debug, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>,
+}
+
+impl OscMessage {
+    pub fn new(address: &str, args: Vec<OscArg>) -> Self {
+        OscMessage {
+            address: address.to_string(),
+            args,
+        }
+    }
+
+    /// Encode as an OSC packet
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_osc_string(&mut bytes, &self.address);
+
+        let tags: String = std::iter::once(',')
+            .chain(self.args.iter().map(|arg| match arg {
+                OscArg::Int(_) => 'i',
+                OscArg::Str(_) => 's',
+            }))
+            .collect();
+        write_osc_string(&mut bytes, &tags);
+
+        for arg in &self.args {
+            match arg {
+                OscArg::Int(value) => bytes.extend_from_slice(&value.to_be_bytes()),
+                OscArg::Str(value) => write_osc_string(&mut bytes, value),
+            }
+        }
+
+        bytes
+    }
+
+    /// Decode an OSC packet, if it is a message with only int and string arguments
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut offset = 0;
+        let address = read_osc_string(bytes, &mut offset)?;
+        let tags = read_osc_string(bytes, &mut offset)?;
+
+        let mut args = Vec::new();
+        for tag in tags.strip_prefix(',')?.chars() {
+            match tag {
+                'i' => {
+                    let value = bytes.get(offset..offset + 4)?;
+                    args.push(OscArg::Int(i32::from_be_bytes(value.try_into().ok()?)));
+                    offset += 4;
+                }
+                's' => args.push(OscArg::Str(read_osc_string(bytes, &mut offset)?)),
+                _ => return None,
+            }
+        }
+
+        Some(OscMessage { address, args })
+    }
+
+    /// Int argument at an index
+    pub fn int(&self, index: usize) -> Option<i32> {
+        match self.args.get(index)? {
+            OscArg::Int(value) => Some(*value),
+            OscArg::Str(_) => None,
+        }
+    }
+
+    /// String argument at an index
+    pub fn str(&self, index: usize) -> Option<&str> {
+        match self.args.get(index)? {
+            OscArg::Str(value) => Some(value),
+            OscArg::Int(_) => None,
+        }
+    }
+}
+
+/// Write a null-terminated string padded to a multiple of four bytes
+fn write_osc_string(bytes: &mut Vec<u8>, value: &str) {
+    bytes.extend_from_slice(value.as_bytes());
+    bytes.resize((bytes.len() + 1).next_multiple_of(4), 0);
+}
+
+/// Read a padded string, moving the offset past its padding
+fn read_osc_string(bytes: &[u8], offset: &mut usize) -> Option<String> {
+    let rest = bytes.get(*offset..)?;
+    let end = rest.iter().position(|&b| b == 0)?;
+    let value = String::from_utf8(rest[..end].to_vec()).ok()?;
+
+    *offset += (end + 4) & !3;
+    Some(value)
+}
+
+/// A grid serialosc reported
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonomeGrid {
+    pub serial: String,
+    /// Kind of grid, e.g. "monome 128"
+    pub kind: String,
+    /// UDP port the grid listens on
+    pub port: u16,
+}
+
+impl MonomeGrid {
+    /// Device for the registry
+    pub fn device(&self) -> MidiDevice {
+        MidiDevice {
+            id: format!("{}{}", MONOME_ID_PREFIX, self.serial),
+            name: format!("{} ({})", self.kind, self.serial),
+            is_input: true,
+            is_controller: true,
+            model: Some(MONOME_MODEL.to_string()),
+            firmware: None,
+        }
+    }
+}
+
+/// Socket bound to a free local port, giving up on reads after the reply timeout
+fn bind_local() -> Result<UdpSocket, String> {
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).map_err(|e| e.to_string())?;
+    socket
+        .set_read_timeout(Some(REPLY_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+/// Send a message to a local port
+fn send_to(socket: &UdpSocket, port: u16, message: &OscMessage) -> Result<(), String> {
+    socket
+        .send_to(&message.encode(), (Ipv4Addr::LOCALHOST, port))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Read messages until nothing arrives for the reply timeout
+fn receive_replies(socket: &UdpSocket) -> Vec<OscMessage> {
+    let mut buffer = [0u8; 1024];
+    let mut replies = Vec::new();
+
+    // A closed port shows up as an error too, e.g. when serialosc isn't running
+    while let Ok((len, _)) = socket.recv_from(&mut buffer) {
+        if let Some(message) = OscMessage::decode(&buffer[..len]) {
+            replies.push(message);
+        }
+    }
+
+    replies
+}
+
+/// Ask serialosc which grids are plugged in
+pub fn discover(serialosc_port: u16) -> Result<Vec<MonomeGrid>, String> {
+    let socket = bind_local()?;
+    let local_port = socket.local_addr().map_err(|e| e.to_string())?.port();
+
+    send_to(
+        &socket,
+        serialosc_port,
+        &OscMessage::new(
+            "/serialosc/list",
+            vec![
+                OscArg::Str(Ipv4Addr::LOCALHOST.to_string()),
+                OscArg::Int(local_port as i32),
+            ],
+        ),
+    )?;
+
+    let grids = receive_replies(&socket)
+        .into_iter()
+        .filter(|reply| reply.address == "/serialosc/device")
+        .filter_map(|reply| {
+            Some(MonomeGrid {
+                serial: reply.str(0)?.to_string(),
+                kind: reply.str(1)?.to_string(),
+                port: u16::try_from(reply.int(2)?).ok()?,
+            })
+        })
+        .collect();
+
+    Ok(grids)
+}
+
+/// Grid size as (rows, columns) guessed from the kind of grid, e.g. "monome
+/// 128", until it reports its own
+fn size_from_kind(kind: &str) -> (u8, u8) {
+    if kind.contains("256") {
+        (16, 16)
+    } else if kind.contains("128") {
+        (8, 16)
+    } else {
+        (8, 8)
+    }
+}
+
+/// Varibright level for a color shown in a state
+///
+/// Grids have one LED color, so only the color's brightness counts. Lit pads
+/// never go fully dark.
+fn level_for(color: Color, brightness: f64) -> u8 {
+    let peak = color.r.max(color.g).max(color.b);
+    if peak == 0 {
+        return 0;
+    }
+
+    let level = (peak as f64 / 255.0 * brightness * MAX_LEVEL as f64).round();
+    (level as u8).clamp(1, MAX_LEVEL)
+}
+
+/// Implementation for monome grids, through serialosc
+///
+/// Grids have no buttons besides their keys, and number their keys from the
+/// top-left; controller IDs here are `y * columns + x` in those coordinates.
+pub struct Monome {
+    device: MidiDevice,
+    serialosc_port: u16,
+    socket: Option<Arc<UdpSocket>>,
+    /// Port of the grid itself
+    grid_port: Option<u16>,
+    /// Rows and columns, shared with clones so they see the size the grid reports
+    size: Arc<Mutex<(u8, u8)>>,
+    /// Level of each key, by controller ID
+    levels: Vec<u8>,
+    /// Keeps the key thread running until disconnected
+    listening: Arc<AtomicBool>,
+    event_callback: Option<Arc<dyn Fn(ControllerEvent) + Send + Sync>>,
+}
+
+impl Monome {
+    pub fn new(device: MidiDevice) -> Self {
+        Self::with_serialosc(device, SERIALOSC_PORT)
+    }
+
+    /// Create a grid found through serialosc on a different port
+    pub fn with_serialosc(device: MidiDevice, serialosc_port: u16) -> Self {
+        // The name is the kind followed by the serial, which may contain digits too
+        let serial = device.id.strip_prefix(MONOME_ID_PREFIX).unwrap_or_default();
+        let kind = device
+            .name
+            .strip_suffix(&format!(" ({})", serial))
+            .unwrap_or_default();
+        let (rows, columns) = size_from_kind(kind);
+
+        Monome {
+            device,
+            serialosc_port,
+            socket: None,
+            grid_port: None,
+            size: Arc::new(Mutex::new((rows, columns))),
+            levels: vec![0; rows as usize * columns as usize],
+            listening: Arc::new(AtomicBool::new(false)),
+            event_callback: None,
+        }
+    }
+
+    /// Whether a model name is the one given to grids found through serialosc
+    pub fn matches_model(name: &str) -> bool {
+        name == MONOME_MODEL
+    }
+
+    fn size(&self) -> (u8, u8) {
+        *self.size.lock().unwrap()
+    }
+
+    fn send(&self, address: &str, args: Vec<OscArg>) -> Result<(), String> {
+        if let (Some(socket), Some(port)) = (&self.socket, self.grid_port) {
+            let address = format!("{}{}", MONOME_PREFIX, address);
+            send_to(socket, port, &OscMessage::new(&address, args))?;
+        }
+        Ok(())
+    }
+
+    /// Set key levels by grid ID, then send the quads that changed
+    fn set_levels(&mut self, levels: impl Iterator<Item = (u8, u8)>) -> Result<(), String> {
+        // A clone made before connecting learns the real size from the original
+        let (rows, columns) = self.size();
+        self.levels.resize(rows as usize * columns as usize, 0);
+
+        let mut changed = Vec::new();
+        for (grid_id, level) in levels {
+            if grid_id as u16 >= rows as u16 * columns as u16 {
+                continue;
+            }
+
+            let id = self.map_grid_id(grid_id);
+            if self.levels[id as usize] != level {
+                self.levels[id as usize] = level;
+
+                let quad = (id % columns / QUAD_SIDE, id / columns / QUAD_SIDE);
+                if !changed.contains(&quad) {
+                    changed.push(quad);
+                }
+            }
+        }
+
+        for (quad_x, quad_y) in changed {
+            self.send_quad(quad_x * QUAD_SIDE, quad_y * QUAD_SIDE)?;
+        }
+
+        Ok(())
+    }
+
+    /// Send an 8x8 quad of levels with its top-left key at an offset
+    fn send_quad(&self, x_offset: u8, y_offset: u8) -> Result<(), String> {
+        let (rows, columns) = self.size();
+        let mut args = vec![OscArg::Int(x_offset as i32), OscArg::Int(y_offset as i32)];
+        for y in y_offset..y_offset + QUAD_SIDE {
+            for x in x_offset..x_offset + QUAD_SIDE {
+                let level = if x < columns && y < rows {
+                    self.levels[y as usize * columns as usize + x as usize]
+                } else {
+                    0
+                };
+                args.push(OscArg::Int(level as i32));
+            }
+        }
+
+        self.send("/grid/led/level/map", args)
+    }
+}
+
+impl GridController for Monome {
+    fn connect(&mut self) -> Result<(), String> {
+        // Grids change port when replugged, so look the port up each time
+        let serial = self
+            .device
+            .id
+            .strip_prefix(MONOME_ID_PREFIX)
+            .unwrap_or(&self.device.id);
+        let grid = discover(self.serialosc_port)?
+            .into_iter()
+            .find(|grid| grid.serial == serial)
+            .ok_or_else(|| format!("Could not find monome grid: {}", serial))?;
+
+        let socket = bind_local()?;
+        let local_port = socket.local_addr().map_err(|e| e.to_string())?.port();
+
+        // Send the grid's keys to us with our prefix, then ask for its size
+        let setup = [
+            OscMessage::new("/sys/port", vec![OscArg::Int(local_port as i32)]),
+            OscMessage::new(
+                "/sys/host",
+                vec![OscArg::Str(Ipv4Addr::LOCALHOST.to_string())],
+            ),
+            OscMessage::new("/sys/prefix", vec![OscArg::Str(MONOME_PREFIX.to_string())]),
+            OscMessage::new("/sys/info", Vec::new()),
+        ];
+        for message in &setup {
+            send_to(&socket, grid.port, message)?;
+        }
+
+        let size = receive_replies(&socket)
+            .into_iter()
+            .filter(|reply| reply.address == "/sys/size")
+            .find_map(|reply| Some((reply.int(1)?, reply.int(0)?)));
+        if let Some((rows, columns)) = size {
+            let rows = u8::try_from(rows).map_err(|e| e.to_string())?;
+            let columns = u8::try_from(columns).map_err(|e| e.to_string())?;
+            *self.size.lock().unwrap() = (rows, columns);
+        }
+        let (rows, columns) = self.size();
+        self.levels = vec![0; rows as usize * columns as usize];
+
+        // Listen for keys
+        let socket = Arc::new(socket);
+        self.listening.store(false, Ordering::SeqCst);
+        let listening = Arc::new(AtomicBool::new(true));
+        self.listening = Arc::clone(&listening);
+
+        let input = Arc::clone(&socket);
+        let event_callback = self.event_callback.clone();
+        let key_address = format!("{}/grid/key", MONOME_PREFIX);
+        let columns = columns as i32;
+        let keys = rows as i32 * columns;
+        thread::spawn(move || {
+            let mut buffer = [0u8; 1024];
+            while listening.load(Ordering::SeqCst) {
+                // Reads time out, so disconnecting stops the thread
+                let Ok((len, _)) = input.recv_from(&mut buffer) else {
+                    continue;
+                };
+                let Some(message) = OscMessage::decode(&buffer[..len]) else {
+                    continue;
+                };
+                if message.address != key_address {
+                    continue;
+                }
+
+                let (Some(x), Some(y), Some(state)) =
+                    (message.int(0), message.int(1), message.int(2))
+                else {
+                    continue;
+                };
+                let id = y * columns + x;
+                if x < 0 || x >= columns || id < 0 || id >= keys {
+                    continue;
+                }
+
+                // Keys aren't velocity sensitive, so presses are at full velocity
+                let event = if state != 0 {
+                    ControllerEvent::PadPressed(id as u8, 127)
+                } else {
+                    ControllerEvent::PadReleased(id as u8)
+                };
+                if let Some(ref callback) = event_callback {
+                    callback(event);
+                }
+            }
+        });
+
+        self.socket = Some(socket);
+        self.grid_port = Some(grid.port);
+
+        self.clear()
+    }
+
+    fn disconnect(&mut self) -> Result<(), String> {
+        let cleared = self.clear();
+
+        self.listening.store(false, Ordering::SeqCst);
+        self.socket = None;
+        self.grid_port = None;
+
+        cleared
+    }
+
+    fn set_pad_color(&mut self, grid_id: u8, color: Color) -> Result<(), String> {
+        self.set_levels(std::iter::once((grid_id, level_for(color, 1.0))))
+    }
+
+    fn set_pad_colors(&mut self, pads: &[(u8, Color)]) -> Result<(), String> {
+        self.set_levels(
+            pads.iter()
+                .map(|&(grid_id, color)| (grid_id, level_for(color, 1.0))),
+        )
+    }
+
+    fn set_pad_states(&mut self, pads: &[(u8, Color, PadState)]) -> Result<(), String> {
+        self.set_levels(
+            pads.iter()
+                .map(|&(grid_id, color, state)| (grid_id, level_for(color, state.brightness()))),
+        )
+    }
+
+    fn set_button_color(&mut self, _button_id: u8, _color: Color) -> Result<(), String> {
+        // Grids have no buttons besides their keys
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), String> {
+        self.levels.iter_mut().for_each(|level| *level = 0);
+        self.send("/grid/led/level/all", vec![OscArg::Int(0)])
+    }
+
+    fn grid_size(&self) -> (u8, u8) {
+        self.size()
+    }
+
+    fn map_grid_id(&self, app_grid_id: u8) -> u8 {
+        // Grids count rows from the top
+        let (rows, columns) = self.size();
+        let row = app_grid_id / columns;
+        let column = app_grid_id % columns;
+        let y = rows.saturating_sub(row + 1);
+
+        y * columns + column
+    }
+
+    fn map_to_app_grid_id(&self, controller_id: u8) -> Option<u8> {
+        let (rows, columns) = self.size();
+        let y = controller_id / columns;
+        let x = controller_id % columns;
+        if y >= rows {
+            return None;
+        }
+
+        Some((rows - 1 - y) * columns + x)
+    }
+
+    fn set_event_callback(&mut self, callback: Arc<dyn Fn(ControllerEvent) + Send + Sync>) {
+        self.event_callback = Some(callback);
+    }
+
+    fn clone_box(&self) -> Box<dyn GridController> {
+        let mut clone = Monome::with_serialosc(self.device.clone(), self.serialosc_port);
+        clone.size = Arc::clone(&self.size);
+        clone.levels = vec![0; self.levels.len()];
+        Box::new(clone)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// Stands in for serialosc and one grid, answering on a single port
+    struct FakeSerialosc {
+        port: u16,
+        socket: Arc<UdpSocket>,
+        /// Port the app asked the grid to send keys to
+        app_port: Arc<Mutex<Option<u16>>>,
+        received: Arc<Mutex<Vec<OscMessage>>>,
+        running: Arc<AtomicBool>,
+    }
+
+    impl FakeSerialosc {
+        fn start(serial: &str, kind: &str, rows: u8, columns: u8) -> Self {
+            let socket = Arc::new(bind_local().unwrap());
+            let port = socket.local_addr().unwrap().port();
+            let app_port = Arc::new(Mutex::new(None));
+            let received = Arc::new(Mutex::new(Vec::new()));
+            let running = Arc::new(AtomicBool::new(true));
+
+            let (serial, kind) = (serial.to_string(), kind.to_string());
+            let (input, app, log, alive) = (
+                Arc::clone(&socket),
+                Arc::clone(&app_port),
+                Arc::clone(&received),
+                Arc::clone(&running),
+            );
+            thread::spawn(move || {
+                let mut buffer = [0u8; 1024];
+                while alive.load(Ordering::SeqCst) {
+                    let Ok((len, _)) = input.recv_from(&mut buffer) else {
+                        continue;
+                    };
+                    let Some(message) = OscMessage::decode(&buffer[..len]) else {
+                        continue;
+                    };
+
+                    let reply = match message.address.as_str() {
+                        "/serialosc/list" => message.int(1).map(|reply_port| {
+                            let device = OscMessage::new(
+                                "/serialosc/device",
+                                vec![
+                                    OscArg::Str(serial.clone()),
+                                    OscArg::Str(kind.clone()),
+                                    OscArg::Int(port as i32),
+                                ],
+                            );
+                            (reply_port as u16, device)
+                        }),
+                        "/sys/port" => {
+                            *app.lock().unwrap() = message.int(0).map(|p| p as u16);
+                            None
+                        }
+                        "/sys/info" => app.lock().unwrap().map(|app_port| {
+                            let size = OscMessage::new(
+                                "/sys/size",
+                                vec![OscArg::Int(columns as i32), OscArg::Int(rows as i32)],
+                            );
+                            (app_port, size)
+                        }),
+                        _ => None,
+                    };
+                    if let Some((reply_port, reply)) = reply {
+                        send_to(&input, reply_port, &reply).unwrap();
+                    }
+
+                    log.lock().unwrap().push(message);
+                }
+            });
+
+            FakeSerialosc {
+                port,
+                socket,
+                app_port,
+                received,
+                running,
+            }
+        }
+
+        /// Press or release a key, as the grid would
+        fn key(&self, x: i32, y: i32, state: i32) {
+            let app_port = self.app_port.lock().unwrap().expect("not connected");
+            let key = OscMessage::new(
+                "/snapblaster/grid/key",
+                vec![OscArg::Int(x), OscArg::Int(y), OscArg::Int(state)],
+            );
+            send_to(&self.socket, app_port, &key).unwrap();
+        }
+
+        /// Wait for a message at an address
+        fn wait_for(&self, address: &str) -> Option<OscMessage> {
+            let deadline = Instant::now() + Duration::from_secs(2);
+            while Instant::now() < deadline {
+                let received = self.received.lock().unwrap();
+                if let Some(message) = received.iter().rev().find(|m| m.address == address) {
+                    return Some(message.clone());
+                }
+                drop(received);
+                thread::sleep(Duration::from_millis(5));
+            }
+            None
+        }
+    }
+
+    impl Drop for FakeSerialosc {
+        fn drop(&mut self) {
+            self.running.store(false, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_osc_round_trip() {
+        let message = OscMessage::new(
+            "/sys/prefix",
+            vec![OscArg::Str("/snapblaster".to_string()), OscArg::Int(-3)],
+        );
+        let bytes = message.encode();
+
+        assert_eq!(bytes.len() % 4, 0);
+        assert_eq!(OscMessage::decode(&bytes), Some(message));
+        assert_eq!(OscMessage::decode(b"/bad"), None);
+    }
+
+    #[test]
+    fn test_discover_and_press_keys() {
+        let fake = FakeSerialosc::start("m0000128", "monome 128", 8, 16);
+
+        let grids = discover(fake.port).unwrap();
+        assert_eq!(grids.len(), 1);
+        assert_eq!(grids[0].serial, "m0000128");
+
+        let mut monome = Monome::with_serialosc(grids[0].device(), fake.port);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&events);
+        monome.set_event_callback(Arc::new(move |event| {
+            received.lock().unwrap().push(event);
+        }));
+        monome.connect().unwrap();
+        assert_eq!(monome.grid_size(), (8, 16));
+
+        // The top-left key is the first pad of the top row
+        fake.key(0, 0, 1);
+        fake.key(0, 0, 0);
+
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while events.lock().unwrap().len() < 2 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        let events = events.lock().unwrap();
+        match events[..] {
+            [ControllerEvent::PadPressed(id, 127), ControllerEvent::PadReleased(released)] => {
+                assert_eq!(id, released);
+                assert_eq!(monome.map_to_app_grid_id(id), Some(7 * 16));
+            }
+            _ => panic!("unexpected events: {:?}", events),
+        }
+
+        monome.disconnect().unwrap();
+    }
+
+    #[test]
+    fn test_clones_follow_the_reported_size() {
+        // The serial looks like a 128, and the kind doesn't give a size
+        let fake = FakeSerialosc::start("m0000128", "monome grid", 8, 16);
+        let grid = discover(fake.port).unwrap().remove(0);
+
+        let mut monome = Monome::with_serialosc(grid.device(), fake.port);
+        assert_eq!(monome.grid_size(), (8, 8));
+
+        // Copies made before connecting, like the event callback's, see the size
+        // the grid reports
+        let clone = monome.clone_box();
+        monome.connect().unwrap();
+        assert_eq!(clone.grid_size(), (8, 16));
+        assert_eq!(clone.map_to_app_grid_id(0), Some(7 * 16));
+
+        monome.disconnect().unwrap();
+    }
+
+    #[test]
+    fn test_levels_sent_as_maps() {
+        let fake = FakeSerialosc::start("m0000128", "monome 128", 8, 16);
+        let grid = discover(fake.port).unwrap().remove(0);
+
+        let mut monome = Monome::with_serialosc(grid.device(), fake.port);
+        monome.connect().unwrap();
+        assert!(fake.wait_for("/snapblaster/grid/led/level/all").is_some());
+
+        // Pads 0 and 1 are the bottom-left of the left quad
+        monome
+            .set_pad_states(&[
+                (0, Color::GREEN, PadState::Active),
+                (1, Color::GREEN, PadState::Assigned),
+            ])
+            .unwrap();
+
+        let map = fake.wait_for("/snapblaster/grid/led/level/map").unwrap();
+        assert_eq!(map.args.len(), 2 + 64);
+        assert_eq!((map.int(0), map.int(1)), (Some(0), Some(0)));
+        assert_eq!(map.int(2 + 56), Some(15));
+        assert_eq!(map.int(2 + 57), Some(5));
+        assert_eq!(map.int(2), Some(0));
+    }
+
+    #[test]
+    fn test_grid_ids() {
+        let grid = MonomeGrid {
+            serial: "m1000256".to_string(),
+            kind: "monome 256".to_string(),
+            port: 0,
+        };
+        let monome = Monome::new(grid.device());
+
+        assert_eq!(monome.grid_size(), (16, 16));
+        assert_eq!(monome.map_grid_id(0), 240);
+        assert_eq!(monome.map_grid_id(255), 15);
+        for app_grid_id in [0, 17, 128, 255] {
+            assert_eq!(
+                monome.map_to_app_grid_id(monome.map_grid_id(app_grid_id)),
+                Some(app_grid_id)
+            );
+        }
+
+        assert_eq!(level_for(Color::BLACK, 1.0), 0);
+        assert_eq!(level_for(Color::new(10, 0, 0), 0.35), 1);
+    }
+}
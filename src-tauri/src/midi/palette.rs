@@ -0,0 +1,304 @@
+use std::borrow::Cow;
+
+use crate::midi::controller::Color;
+
+/// Display state of a pad, used to pick a brightness for its color
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PadState {
+    /// A scene is assigned but not playing
+    Assigned,
+    /// The scene is playing
+    Active,
+    /// The scene is waiting to start
+    Queued,
+    /// The scene is playing and its CCs are still transitioning
+    Transitioning,
+    /// Part of a fader's level bar
+    Level,
+}
+
+impl PadState {
+    /// Relative lightness for this state
+    pub fn brightness(&self) -> f64 {
+        match self {
+            PadState::Assigned => 0.35,
+            PadState::Active => 1.0,
+            PadState::Queued => 0.65,
+            PadState::Transitioning => 0.5,
+            PadState::Level => 1.0,
+        }
+    }
+}
+
+/// A color in CIELAB space
+#[derive(Clone, Copy, Debug)]
+struct Lab {
+    l: f64,
+    a: f64,
+    b: f64,
+}
+
+/// D65 reference white
+const WHITE_X: f64 = 0.950_47;
+const WHITE_Y: f64 = 1.0;
+const WHITE_Z: f64 = 1.088_83;
+
+fn srgb_to_linear(channel: u8) -> f64 {
+    let c = channel as f64 / 255.0;
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f64) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let v = if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (v * 255.0).round() as u8
+}
+
+impl Lab {
+    fn from_color(color: Color) -> Self {
+        let r = srgb_to_linear(color.r);
+        let g = srgb_to_linear(color.g);
+        let b = srgb_to_linear(color.b);
+
+        let x = (0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b) / WHITE_X;
+        let y = (0.212_672_9 * r + 0.715_152_2 * g + 0.072_175_0 * b) / WHITE_Y;
+        let z = (0.019_333_9 * r + 0.119_192_0 * g + 0.950_304_1 * b) / WHITE_Z;
+
+        let f = |t: f64| {
+            if t > 216.0 / 24389.0 {
+                t.cbrt()
+            } else {
+                (24389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+
+    fn to_color(self) -> Color {
+        let fy = (self.l + 16.0) / 116.0;
+        let fx = fy + self.a / 500.0;
+        let fz = fy - self.b / 200.0;
+
+        let f_inv = |t: f64| {
+            if t.powi(3) > 216.0 / 24389.0 {
+                t.powi(3)
+            } else {
+                (116.0 * t - 16.0) * 27.0 / 24389.0
+            }
+        };
+        let x = f_inv(fx) * WHITE_X;
+        let y = f_inv(fy) * WHITE_Y;
+        let z = f_inv(fz) * WHITE_Z;
+
+        Color::new(
+            linear_to_srgb(3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z),
+            linear_to_srgb(-0.969_266_0 * x + 1.876_010_8 * y + 0.041_556_0 * z),
+            linear_to_srgb(0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z),
+        )
+    }
+
+    /// Squared CIE76 color difference
+    fn distance_squared(&self, other: &Lab) -> f64 {
+        (self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2)
+    }
+}
+
+fn is_black(color: Color) -> bool {
+    color.r == 0 && color.g == 0 && color.b == 0
+}
+
+/// Scale a color's perceptual lightness for a pad state
+///
+/// Used directly by RGB controllers; palette controllers go through
+/// [`Palette::shade`] so the result snaps to an entry they can show.
+pub fn shade_rgb(color: Color, state: PadState) -> Color {
+    let factor = state.brightness();
+    if factor >= 1.0 || is_black(color) {
+        return color;
+    }
+
+    let lab = Lab::from_color(color);
+    Lab {
+        l: lab.l * factor,
+        a: lab.a * factor,
+        b: lab.b * factor,
+    }
+    .to_color()
+}
+
+/// A fixed set of colors a controller can display, keyed by the value it expects
+pub struct Palette {
+    entries: Cow<'static, [(u8, Color)]>,
+}
+
+impl Palette {
+    /// Create a palette from (device value, approximate displayed color) pairs
+    pub const fn new(entries: &'static [(u8, Color)]) -> Self {
+        Palette {
+            entries: Cow::Borrowed(entries),
+        }
+    }
+
+    /// Create a palette from entries built at runtime, e.g. from a controller profile
+    pub fn from_entries(entries: Vec<(u8, Color)>) -> Self {
+        Palette {
+            entries: Cow::Owned(entries),
+        }
+    }
+
+    /// (device value, color) pairs in this palette
+    pub fn entries(&self) -> &[(u8, Color)] {
+        &self.entries
+    }
+
+    /// Device value of the palette entry perceptually closest to a color
+    ///
+    /// Any color other than black maps to a lit entry, so dim colors never
+    /// disappear from the grid.
+    pub fn nearest(&self, color: Color) -> u8 {
+        let want_black = is_black(color);
+        let candidates: Vec<(u8, Lab)> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| want_black || !is_black(*entry))
+            .map(|&(value, entry)| (value, Lab::from_color(entry)))
+            .collect();
+
+        let mut target = Lab::from_color(color);
+
+        // Colors darker than every lit entry are brightened to the dimmest one, so
+        // their hue decides the match rather than their (unshowable) lightness
+        let dimmest = candidates
+            .iter()
+            .map(|(_, lab)| lab.l)
+            .fold(f64::INFINITY, f64::min);
+        if !want_black && target.l > 0.0 && target.l < dimmest {
+            let scale = dimmest / target.l;
+            target = Lab {
+                l: dimmest,
+                a: target.a * scale,
+                b: target.b * scale,
+            };
+        }
+
+        candidates
+            .iter()
+            .map(|(value, lab)| (*value, lab.distance_squared(&target)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(value, _)| value)
+            .unwrap_or(0)
+    }
+
+    /// Device value for a color shown in the given pad state
+    pub fn shade(&self, color: Color, state: PadState) -> u8 {
+        self.nearest(shade_rgb(color, state))
+    }
+}
+
+macro_rules! palette {
+    ($($value:expr => $rgb:expr),* $(,)?) => {
+        &[$(($value, Color {
+            r: (($rgb >> 16) & 0xFF) as u8,
+            g: (($rgb >> 8) & 0xFF) as u8,
+            b: ($rgb & 0xFF) as u8,
+        })),*]
+    };
+}
+
+/// Novation 128-color velocity palette (Launchpad Mk2, X, Mini Mk3 and Pro Mk3)
+///
+/// RGB values are approximations of what the LEDs show.
+pub const NOVATION_PALETTE: Palette = Palette::new(palette![
+    0 => 0x000000, 1 => 0x1E1E1E, 2 => 0x7F7F7F, 3 => 0xFFFFFF,
+    4 => 0xFF4C4C, 5 => 0xFF0000, 6 => 0x590000, 7 => 0x190000,
+    8 => 0xFFBD6C, 9 => 0xFF5400, 10 => 0x591D00, 11 => 0x271B00,
+    12 => 0xFFFF4C, 13 => 0xFFFF00, 14 => 0x595900, 15 => 0x191900,
+    16 => 0x88FF4C, 17 => 0x54FF00, 18 => 0x1D5900, 19 => 0x142B00,
+    20 => 0x4CFF4C, 21 => 0x00FF00, 22 => 0x005900, 23 => 0x001900,
+    24 => 0x4CFF5E, 25 => 0x00FF19, 26 => 0x00590D, 27 => 0x001902,
+    28 => 0x4CFF88, 29 => 0x00FF55, 30 => 0x00591D, 31 => 0x001F12,
+    32 => 0x4CFFB7, 33 => 0x00FF99, 34 => 0x005935, 35 => 0x001912,
+    36 => 0x4CC3FF, 37 => 0x00A9FF, 38 => 0x004152, 39 => 0x001019,
+    40 => 0x4C88FF, 41 => 0x0055FF, 42 => 0x001D59, 43 => 0x000819,
+    44 => 0x4C4CFF, 45 => 0x0000FF, 46 => 0x000059, 47 => 0x000019,
+    48 => 0x874CFF, 49 => 0x5400FF, 50 => 0x190064, 51 => 0x0F0030,
+    52 => 0xFF4CFF, 53 => 0xFF00FF, 54 => 0x590059, 55 => 0x190019,
+    56 => 0xFF4C87, 57 => 0xFF0054, 58 => 0x59001D, 59 => 0x220013,
+    60 => 0xFF1500, 61 => 0x993500, 62 => 0x795100, 63 => 0x436400,
+    64 => 0x033900, 65 => 0x005735, 66 => 0x00547F, 67 => 0x0000FF,
+    68 => 0x00454F, 69 => 0x2500CC, 70 => 0x7F7F7F, 71 => 0x202020,
+    72 => 0xFF0000, 73 => 0xBDFF2D, 74 => 0xAFED06, 75 => 0x64FF09,
+    76 => 0x108B00, 77 => 0x00FF87, 78 => 0x00A9FF, 79 => 0x002AFF,
+    80 => 0x3F00FF, 81 => 0x7A00FF, 82 => 0xB21A7D, 83 => 0x402100,
+    84 => 0xFF4A00, 85 => 0x88E106, 86 => 0x72FF15, 87 => 0x00FF00,
+    88 => 0x3BFF26, 89 => 0x59FF71, 90 => 0x38FFCC, 91 => 0x5B8AFF,
+    92 => 0x3151C6, 93 => 0x877FE9, 94 => 0xD31DFF, 95 => 0xFF005D,
+    96 => 0xFF7F00, 97 => 0xB9B000, 98 => 0x90FF00, 99 => 0x835D07,
+    100 => 0x392B00, 101 => 0x144C10, 102 => 0x0D5038, 103 => 0x15152A,
+    104 => 0x16205A, 105 => 0x693C1C, 106 => 0xA8000A, 107 => 0xDE513D,
+    108 => 0xD86A1C, 109 => 0xFFE126, 110 => 0x9EE12F, 111 => 0x67B50F,
+    112 => 0x1E1E30, 113 => 0xDCFF6B, 114 => 0x80FFBD, 115 => 0x9A99FF,
+    116 => 0x8E66FF, 117 => 0x404040, 118 => 0x757575, 119 => 0xE0FFFF,
+    120 => 0xA00000, 121 => 0x350000, 122 => 0x1AD000, 123 => 0x074200,
+    124 => 0xB9B000, 125 => 0x3F3100, 126 => 0xB35F00, 127 => 0x4B1502,
+]);
+
+/// Akai APC mini (mk1) pad colors; odd values above 1 blink
+pub const APC_MINI_PALETTE: Palette = Palette::new(palette![
+    0 => 0x000000,
+    1 => 0x00FF00,
+    3 => 0xFF0000,
+    5 => 0xFFB000,
+]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nearest_entry() {
+        assert_eq!(NOVATION_PALETTE.nearest(Color::BLACK), 0);
+        assert_eq!(NOVATION_PALETTE.nearest(Color::RED), 5);
+        assert_eq!(NOVATION_PALETTE.nearest(Color::new(250, 40, 0)), 60);
+        assert_eq!(NOVATION_PALETTE.nearest(Color::BLUE), 45);
+        assert_eq!(APC_MINI_PALETTE.nearest(Color::new(255, 140, 0)), 5);
+        assert_eq!(APC_MINI_PALETTE.nearest(Color::CYAN), 1);
+    }
+
+    #[test]
+    fn test_dim_colors_stay_lit() {
+        let very_dark = Color::new(3, 0, 0);
+        assert_ne!(NOVATION_PALETTE.nearest(very_dark), 0);
+        assert_eq!(APC_MINI_PALETTE.nearest(very_dark), 3);
+    }
+
+    #[test]
+    fn test_state_shades() {
+        let active = NOVATION_PALETTE.shade(Color::RED, PadState::Active);
+        let queued = NOVATION_PALETTE.shade(Color::RED, PadState::Queued);
+        let assigned = NOVATION_PALETTE.shade(Color::RED, PadState::Assigned);
+
+        assert_eq!(active, 5);
+        assert_ne!(assigned, 0);
+        assert_ne!(assigned, active);
+        assert_ne!(queued, assigned);
+
+        let dimmed = shade_rgb(Color::RED, PadState::Assigned);
+        assert!(dimmed.r < 255 && dimmed.r > 0);
+        assert_eq!(shade_rgb(Color::RED, PadState::Active).r, 255);
+    }
+}
@@ -0,0 +1,869 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use midir::{MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+use serde::{Deserialize, Serialize};
+
+use crate::midi::controller::{select_port, ButtonRole, Color, ControllerEvent, GridController};
+use crate::midi::devices::MidiDevice;
+use crate::midi::palette::{Palette, APC_MINI_PALETTE, NOVATION_PALETTE};
+use crate::models::grid::GridSize;
+
+/// Profiles shipped with the app
+///
+/// Devices with a native driver use it instead; their profiles document the
+/// format and are a starting point for similar hardware.
+const BUILTIN_PROFILES: [&str; 10] = [
+    include_str!("../../profiles/launchpad_mk2.json"),
+    include_str!("../../profiles/launchpad_x.json"),
+    include_str!("../../profiles/launchpad_mini_mk3.json"),
+    include_str!("../../profiles/launchpad_pro_mk3.json"),
+    include_str!("../../profiles/apc_mini.json"),
+    include_str!("../../profiles/apc_mini_mk2.json"),
+    include_str!("../../profiles/apc40_mk2.json"),
+    include_str!("../../profiles/fire.json"),
+    include_str!("../../profiles/push.json"),
+    include_str!("../../profiles/push2.json"),
+];
+
+/// Kind of channel message a control sends
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    Note,
+    Cc,
+}
+
+impl MessageKind {
+    /// Status byte on a channel
+    fn status(&self, channel: u8) -> u8 {
+        match self {
+            MessageKind::Note => 0x90 | channel,
+            MessageKind::Cc => 0xB0 | channel,
+        }
+    }
+}
+
+/// A note or CC on a channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Control {
+    pub kind: MessageKind,
+    pub number: u8,
+    #[serde(default)]
+    pub channel: u8,
+}
+
+impl Control {
+    fn matches(&self, kind: MessageKind, channel: u8, number: u8) -> bool {
+        self.kind == kind && self.channel == channel && self.number == number
+    }
+}
+
+/// Notes or CCs of the pad grid
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GridLayout {
+    pub kind: MessageKind,
+    #[serde(default)]
+    pub channel: u8,
+
+    /// Numbers of each pad row, bottom row first, left to right
+    ///
+    /// Up to 16 rows or columns and 128 pads in all.
+    pub rows: Vec<Vec<u8>>,
+}
+
+impl GridLayout {
+    /// Width of the widest row
+    fn columns(&self) -> usize {
+        self.rows.iter().map(|row| row.len()).max().unwrap_or(0)
+    }
+
+    /// Control of a pad by its position on the grid (row * columns + column)
+    fn pad(&self, grid_id: u8) -> Option<Control> {
+        let columns = self.columns().max(1);
+        self.control(grid_id as usize / columns, grid_id as usize % columns)
+    }
+
+    fn control(&self, row: usize, col: usize) -> Option<Control> {
+        self.rows.get(row)?.get(col).map(|&number| Control {
+            kind: self.kind,
+            number,
+            channel: self.channel,
+        })
+    }
+}
+
+/// A button, identified in events by its number
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ButtonProfile {
+    #[serde(flatten)]
+    pub control: Control,
+
+    #[serde(default)]
+    pub role: Option<ButtonRole>,
+
+    /// Color format for this button, overriding the profile's `button_colors`
+    #[serde(default)]
+    pub colors: Option<ColorFormat>,
+}
+
+/// Palette used by a color format
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PaletteSpec {
+    /// A built-in palette: "novation" or "apc_mini"
+    Named(String),
+    /// (device value, [r, g, b]) pairs
+    Entries(Vec<(u8, (u8, u8, u8))>),
+}
+
+impl PaletteSpec {
+    fn resolve(&self) -> Result<Palette, String> {
+        match self {
+            PaletteSpec::Named(name) => match name.as_str() {
+                "novation" => Ok(NOVATION_PALETTE),
+                "apc_mini" => Ok(APC_MINI_PALETTE),
+                _ => Err(format!("Unknown palette: {}", name)),
+            },
+            PaletteSpec::Entries(entries) => Ok(Palette::from_entries(
+                entries
+                    .iter()
+                    .map(|&(value, (r, g, b))| (value, Color::new(r, g, b)))
+                    .collect(),
+            )),
+        }
+    }
+}
+
+/// How colors are sent to LEDs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ColorFormat {
+    /// The nearest palette entry, as the velocity of a note-on or the value of a CC
+    Palette {
+        palette: PaletteSpec,
+        /// Message carrying the value; defaults to the control's own kind
+        #[serde(default)]
+        message: Option<MessageKind>,
+    },
+    /// RGB values in SysEx, several LEDs per message
+    RgbSysex(SysexTemplate),
+}
+
+/// Template of an RGB lighting SysEx
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SysexTemplate {
+    /// Bytes before the LED entries, starting with 0xF0
+    pub header: Vec<TemplateByte>,
+    /// Bytes repeated for each LED
+    pub led: Vec<TemplateByte>,
+    /// Bytes after the LED entries
+    #[serde(default = "default_footer")]
+    pub footer: Vec<TemplateByte>,
+    /// Subtracted from a control's number to get its LED index
+    #[serde(default)]
+    pub index_base: u8,
+}
+
+fn default_footer() -> Vec<TemplateByte> {
+    vec![TemplateByte::Literal(0xF7)]
+}
+
+/// A literal byte or a field filled in per message or per LED
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TemplateByte {
+    Literal(u8),
+    Field(TemplateField),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateField {
+    /// LED index
+    Index,
+    /// Color channels scaled to 0-127
+    R,
+    G,
+    B,
+    /// Full 0-255 color channels split into their top bit and low 7 bits
+    RHi,
+    RLo,
+    GHi,
+    GLo,
+    BHi,
+    BLo,
+    /// Byte length of all LED entries, split into two 7-bit halves
+    LengthHi,
+    LengthLo,
+}
+
+impl SysexTemplate {
+    /// Build one message lighting every LED
+    fn build(&self, leds: &[(u8, Color)]) -> Vec<u8> {
+        let length = leds.len() * self.led.len();
+        let value = |byte: &TemplateByte, index: u8, color: Color| match byte {
+            TemplateByte::Literal(value) => *value,
+            TemplateByte::Field(field) => match field {
+                TemplateField::Index => index,
+                TemplateField::R => color.r >> 1,
+                TemplateField::G => color.g >> 1,
+                TemplateField::B => color.b >> 1,
+                TemplateField::RHi => color.r >> 7,
+                TemplateField::RLo => color.r & 0x7F,
+                TemplateField::GHi => color.g >> 7,
+                TemplateField::GLo => color.g & 0x7F,
+                TemplateField::BHi => color.b >> 7,
+                TemplateField::BLo => color.b & 0x7F,
+                TemplateField::LengthHi => (length >> 7) as u8 & 0x7F,
+                TemplateField::LengthLo => length as u8 & 0x7F,
+            },
+        };
+
+        let mut message: Vec<u8> = self
+            .header
+            .iter()
+            .map(|byte| value(byte, 0, Color::BLACK))
+            .collect();
+        for &(index, color) in leds {
+            let index = index.saturating_sub(self.index_base);
+            message.extend(self.led.iter().map(|byte| value(byte, index, color)));
+        }
+        message.extend(self.footer.iter().map(|byte| value(byte, 0, Color::BLACK)));
+        message
+    }
+}
+
+/// Description of a grid controller, loaded from JSON
+///
+/// Incoming messages are matched against the fader, knob, grid and button
+/// controls in that order; note-offs, and note-ons or CCs with value 0, are
+/// releases.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ControllerProfile {
+    pub name: String,
+
+    /// Lowercase substrings of the port names the device shows up under
+    pub port_patterns: Vec<String>,
+
+    /// Lowercase substring of the port to open when the device exposes several
+    #[serde(default)]
+    pub port_tag: Option<String>,
+
+    /// Messages sent after connecting, e.g. to enter a programmer mode
+    #[serde(default)]
+    pub init: Vec<Vec<u8>>,
+
+    /// Messages sent before disconnecting
+    #[serde(default)]
+    pub exit: Vec<Vec<u8>>,
+
+    pub grid: GridLayout,
+
+    #[serde(default)]
+    pub buttons: Vec<ButtonProfile>,
+
+    #[serde(default)]
+    pub faders: Vec<Control>,
+
+    #[serde(default)]
+    pub knobs: Vec<Control>,
+
+    pub pad_colors: ColorFormat,
+
+    /// Color format for buttons; defaults to `pad_colors`
+    #[serde(default)]
+    pub button_colors: Option<ColorFormat>,
+}
+
+impl ControllerProfile {
+    /// Parse and validate a profile
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let profile: ControllerProfile = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    /// Whether a MIDI port name belongs to this device
+    pub fn matches_port(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.port_patterns
+            .iter()
+            .any(|pattern| name.contains(&pattern.to_lowercase()))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Profile name cannot be empty".to_string());
+        }
+        if self.port_patterns.is_empty() {
+            return Err(format!("Profile '{}' has no port patterns", self.name));
+        }
+        let rows = u8::try_from(self.grid.rows.len()).unwrap_or(u8::MAX);
+        let columns = u8::try_from(self.grid.columns()).unwrap_or(u8::MAX);
+        GridSize::new(rows, columns)
+            .map_err(|e| format!("Profile '{}' has an invalid grid: {}", self.name, e))?;
+
+        let controls = self
+            .grid
+            .rows
+            .iter()
+            .flatten()
+            .map(|&number| (number, self.grid.channel))
+            .chain(
+                self.buttons
+                    .iter()
+                    .map(|b| &b.control)
+                    .chain(&self.faders)
+                    .chain(&self.knobs)
+                    .map(|c| (c.number, c.channel)),
+            );
+        for (number, channel) in controls {
+            if number > 127 || channel > 15 {
+                return Err(format!(
+                    "Profile '{}' has an invalid control: {} on channel {}",
+                    self.name, number, channel
+                ));
+            }
+        }
+
+        let formats = std::iter::once(&self.pad_colors)
+            .chain(&self.button_colors)
+            .chain(self.buttons.iter().filter_map(|b| b.colors.as_ref()));
+        for format in formats {
+            if let ColorFormat::Palette { palette, .. } = format {
+                palette.resolve()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn button(&self, button_id: u8) -> Option<&ButtonProfile> {
+        self.buttons.iter().find(|b| b.control.number == button_id)
+    }
+
+    /// Translate an incoming message into a controller event
+    fn parse_event(&self, message: &[u8]) -> Option<ControllerEvent> {
+        let (&status, &number, &value) = match message {
+            [status, data1, data2, ..] => (status, data1, data2),
+            _ => return None,
+        };
+        let channel = status & 0x0F;
+        let on_grid = |kind: MessageKind| {
+            self.grid.kind == kind
+                && self.grid.channel == channel
+                && self.grid.rows.iter().flatten().any(|&n| n == number)
+        };
+
+        // Polyphonic aftertouch from a grid of note pads
+        if status & 0xF0 == 0xA0 {
+            return on_grid(MessageKind::Note)
+                .then_some(ControllerEvent::PadPressure(number, value));
+        }
+
+        let (kind, pressed) = match status & 0xF0 {
+            0x80 => (MessageKind::Note, false),
+            0x90 => (MessageKind::Note, value > 0),
+            0xB0 => (MessageKind::Cc, value > 0),
+            _ => return None,
+        };
+        let matches = |c: &Control| c.matches(kind, channel, number);
+
+        if let Some(index) = self.faders.iter().position(matches) {
+            return Some(ControllerEvent::FaderMoved(index as u8, value));
+        }
+        if let Some(index) = self.knobs.iter().position(matches) {
+            return Some(ControllerEvent::KnobTurned(index as u8, value));
+        }
+
+        if on_grid(kind) {
+            return Some(if pressed {
+                ControllerEvent::PadPressed(number, value)
+            } else {
+                ControllerEvent::PadReleased(number)
+            });
+        }
+
+        if self.buttons.iter().any(|b| matches(&b.control)) {
+            return Some(if pressed {
+                ControllerEvent::ButtonPressed(number)
+            } else {
+                ControllerEvent::ButtonReleased(number)
+            });
+        }
+
+        None
+    }
+}
+
+/// Parse the profiles shipped with the app
+pub fn builtin_profiles() -> Vec<ControllerProfile> {
+    BUILTIN_PROFILES
+        .iter()
+        .map(|json| ControllerProfile::from_json(json).expect("Built-in profile is invalid"))
+        .collect()
+}
+
+/// Directory user profiles are loaded from
+pub fn user_profiles_dir() -> Option<PathBuf> {
+    let mut dir = dirs::config_dir()?;
+    dir.push("snap-blaster");
+    dir.push("controllers");
+    Some(dir)
+}
+
+/// Each `.json` profile in a directory with when it was last modified
+///
+/// Comparing two of these notices profiles being added, removed or edited
+/// without parsing them.
+pub type ProfilesStamp = Vec<(PathBuf, Option<SystemTime>)>;
+
+/// Paths of the `.json` files in a directory, in name order
+fn profile_paths(dir: &Path) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Note what a profile directory holds, to tell later whether it changed
+pub fn profiles_stamp(dir: &Path) -> ProfilesStamp {
+    profile_paths(dir)
+        .into_iter()
+        .map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+/// Profiles read from a directory, and why any files were skipped
+#[derive(Debug, Default)]
+pub struct LoadedProfiles {
+    pub profiles: Vec<ControllerProfile>,
+    /// One message per invalid file, naming it
+    pub skipped: Vec<String>,
+}
+
+/// Load every `.json` profile in a directory, skipping invalid ones
+pub fn load_profiles_from(dir: &Path) -> LoadedProfiles {
+    let mut loaded = LoadedProfiles::default();
+
+    for path in profile_paths(dir) {
+        let profile = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| ControllerProfile::from_json(&json));
+
+        match profile {
+            Ok(profile) => loaded.profiles.push(profile),
+            Err(e) => loaded.skipped.push(format!(
+                "Skipped controller profile {}: {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+
+    loaded
+}
+
+/// Find the first profile matching a port name
+pub fn find_profile<'a>(
+    profiles: &'a [ControllerProfile],
+    port_name: &str,
+) -> Option<&'a ControllerProfile> {
+    profiles
+        .iter()
+        .find(|profile| profile.matches_port(port_name))
+}
+
+/// Grid controller driven by a profile
+pub struct ProfileController {
+    device: MidiDevice,
+    profile: Arc<ControllerProfile>,
+    input_connection: Option<MidiInputConnection<()>>,
+    output_connection: Option<MidiOutputConnection>,
+    event_callback: Option<Arc<dyn Fn(ControllerEvent) + Send + Sync>>,
+}
+
+impl ProfileController {
+    pub fn new(device: MidiDevice, profile: ControllerProfile) -> Self {
+        ProfileController {
+            device,
+            profile: Arc::new(profile),
+            input_connection: None,
+            output_connection: None,
+            event_callback: None,
+        }
+    }
+
+    fn send(&mut self, message: &[u8]) -> Result<(), String> {
+        if let Some(ref mut conn) = self.output_connection {
+            conn.send(message).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Port name tag to connect to
+    fn port_tag(&self) -> String {
+        self.profile
+            .port_tag
+            .clone()
+            .unwrap_or_else(|| self.device.name.to_lowercase())
+    }
+
+    /// Light controls in a color format
+    fn send_colors(
+        &mut self,
+        format: &ColorFormat,
+        leds: &[(Control, Color)],
+    ) -> Result<(), String> {
+        match format {
+            ColorFormat::Palette { palette, message } => {
+                let palette = palette.resolve()?;
+                for &(control, color) in leds {
+                    let status = message.unwrap_or(control.kind).status(control.channel);
+                    self.send(&[status, control.number, palette.nearest(color)])?;
+                }
+                Ok(())
+            }
+            ColorFormat::RgbSysex(template) => {
+                if leds.is_empty() {
+                    return Ok(());
+                }
+                let leds: Vec<(u8, Color)> = leds
+                    .iter()
+                    .map(|&(control, color)| (control.number, color))
+                    .collect();
+                self.send(&template.build(&leds))
+            }
+        }
+    }
+}
+
+impl Clone for ProfileController {
+    fn clone(&self) -> Self {
+        ProfileController {
+            device: self.device.clone(),
+            profile: Arc::clone(&self.profile),
+            input_connection: None,  // Connections can't be cloned
+            output_connection: None, // Connections can't be cloned
+            event_callback: self.event_callback.clone(),
+        }
+    }
+}
+
+impl GridController for ProfileController {
+    fn clone_box(&self) -> Box<dyn GridController> {
+        Box::new(self.clone())
+    }
+
+    fn connect(&mut self) -> Result<(), String> {
+        let tag = self.port_tag();
+
+        // Connect to MIDI input
+        let midi_in = MidiInput::new("snap-blaster").map_err(|e| e.to_string())?;
+
+        let in_ports = midi_in.ports();
+        let in_names: Vec<String> = in_ports
+            .iter()
+            .map(|p| midi_in.port_name(p).unwrap_or_default())
+            .collect();
+        let in_port = select_port(&in_names, &tag, &self.device.name)
+            .map(|i| &in_ports[i])
+            .ok_or_else(|| format!("Could not find MIDI input device: {}", self.device.name))?;
+
+        let callback = {
+            let event_callback = self.event_callback.clone();
+            let profile = Arc::clone(&self.profile);
+
+            move |_timestamp, message: &[u8], _: &mut ()| {
+                if let Some(event) = profile.parse_event(message) {
+                    if let Some(ref callback) = event_callback {
+                        callback(event);
+                    }
+                }
+            }
+        };
+
+        let input_conn = midi_in
+            .connect(in_port, "profile-input", callback, ())
+            .map_err(|e| e.to_string())?;
+        self.input_connection = Some(input_conn);
+
+        // Connect to MIDI output
+        let midi_out = MidiOutput::new("snap-blaster").map_err(|e| e.to_string())?;
+
+        let out_ports = midi_out.ports();
+        let out_names: Vec<String> = out_ports
+            .iter()
+            .map(|p| midi_out.port_name(p).unwrap_or_default())
+            .collect();
+        let out_port = select_port(&out_names, &tag, &self.device.name)
+            .map(|i| &out_ports[i])
+            .ok_or_else(|| format!("Could not find MIDI output device: {}", self.device.name))?;
+
+        let output_conn = midi_out
+            .connect(out_port, "profile-output")
+            .map_err(|e| e.to_string())?;
+        self.output_connection = Some(output_conn);
+
+        let profile = Arc::clone(&self.profile);
+        for message in &profile.init {
+            self.send(message)?;
+        }
+
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<(), String> {
+        if self.output_connection.is_some() {
+            self.clear()?;
+
+            let profile = Arc::clone(&self.profile);
+            for message in &profile.exit {
+                self.send(message)?;
+            }
+        }
+
+        self.input_connection = None;
+        self.output_connection = None;
+
+        Ok(())
+    }
+
+    fn set_pad_color(&mut self, grid_id: u8, color: Color) -> Result<(), String> {
+        self.set_pad_colors(&[(grid_id, color)])
+    }
+
+    fn set_pad_colors(&mut self, pads: &[(u8, Color)]) -> Result<(), String> {
+        let profile = Arc::clone(&self.profile);
+        let leds: Vec<(Control, Color)> = pads
+            .iter()
+            .filter_map(|&(grid_id, color)| {
+                let control = profile.grid.pad(grid_id)?;
+                Some((control, color))
+            })
+            .collect();
+
+        self.send_colors(&profile.pad_colors, &leds)
+    }
+
+    fn set_button_color(&mut self, button_id: u8, color: Color) -> Result<(), String> {
+        let profile = Arc::clone(&self.profile);
+        let Some(button) = profile.button(button_id) else {
+            return Ok(());
+        };
+
+        let format = button
+            .colors
+            .as_ref()
+            .or(profile.button_colors.as_ref())
+            .unwrap_or(&profile.pad_colors);
+
+        self.send_colors(format, &[(button.control, color)])
+    }
+
+    fn clear(&mut self) -> Result<(), String> {
+        let (rows, cols) = self.grid_size();
+        let pads: Vec<(u8, Color)> = (0..rows * cols).map(|id| (id, Color::BLACK)).collect();
+        self.set_pad_colors(&pads)?;
+
+        let profile = Arc::clone(&self.profile);
+        for button in &profile.buttons {
+            self.set_button_color(button.control.number, Color::BLACK)?;
+        }
+
+        Ok(())
+    }
+
+    fn grid_size(&self) -> (u8, u8) {
+        let grid = &self.profile.grid;
+        (grid.rows.len() as u8, grid.columns() as u8)
+    }
+
+    fn button_role(&self, button_id: u8) -> Option<ButtonRole> {
+        self.profile.button(button_id)?.role
+    }
+
+    fn map_grid_id(&self, app_grid_id: u8) -> u8 {
+        self.profile
+            .grid
+            .pad(app_grid_id)
+            .map(|control| control.number)
+            .unwrap_or(0) // Invalid ID
+    }
+
+    fn map_to_app_grid_id(&self, controller_id: u8) -> Option<u8> {
+        let grid = &self.profile.grid;
+        let columns = grid.columns();
+
+        grid.rows.iter().enumerate().find_map(|(row, numbers)| {
+            let col = numbers.iter().position(|&n| n == controller_id)?;
+            Some((row * columns + col) as u8)
+        })
+    }
+
+    fn set_event_callback(&mut self, callback: Arc<dyn Fn(ControllerEvent) + Send + Sync>) {
+        self.event_callback = Some(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn builtin(name: &str) -> ControllerProfile {
+        builtin_profiles()
+            .into_iter()
+            .find(|p| p.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_builtin_profiles_are_valid() {
+        let profiles = builtin_profiles();
+        assert_eq!(profiles.len(), BUILTIN_PROFILES.len());
+
+        assert_eq!(
+            find_profile(&profiles, "LPX MIDI").map(|p| p.name.as_str()),
+            Some("Launchpad X")
+        );
+    }
+
+    #[test]
+    fn test_sysex_template() {
+        let launchpad = builtin("Launchpad X");
+        let ColorFormat::RgbSysex(template) = &launchpad.pad_colors else {
+            panic!("Launchpad X should use RGB SysEx");
+        };
+        assert_eq!(
+            template.build(&[(11, Color::new(255, 128, 0))]),
+            vec![0xF0, 0x00, 0x20, 0x29, 0x02, 0x0C, 0x03, 0x03, 11, 0x7F, 0x40, 0x00, 0xF7]
+        );
+
+        // Fire LED indices start from its first pad note, with a length header
+        let fire = builtin("Akai Fire");
+        let ColorFormat::RgbSysex(template) = &fire.pad_colors else {
+            panic!("Fire should use RGB SysEx");
+        };
+        assert_eq!(
+            template.build(&[(55, Color::WHITE)]),
+            vec![0xF0, 0x47, 0x7F, 0x43, 0x65, 0x00, 4, 1, 0x7F, 0x7F, 0x7F, 0xF7]
+        );
+    }
+
+    #[test]
+    fn test_profile_events_and_mapping() {
+        let device = MidiDevice {
+            id: "in:APC MINI".to_string(),
+            name: "APC MINI".to_string(),
+            is_input: true,
+            is_controller: true,
+            model: None,
+            firmware: None,
+        };
+        let controller = ProfileController::new(device, builtin("Akai APC mini"));
+        let profile = &controller.profile;
+
+        assert!(matches!(
+            profile.parse_event(&[0x90, 9, 127]),
+            Some(ControllerEvent::PadPressed(9, 127))
+        ));
+        assert!(matches!(
+            profile.parse_event(&[0x80, 64, 0]),
+            Some(ControllerEvent::ButtonReleased(64))
+        ));
+        assert!(matches!(
+            profile.parse_event(&[0xB0, 56, 20]),
+            Some(ControllerEvent::FaderMoved(8, 20))
+        ));
+        assert!(matches!(
+            profile.parse_event(&[0xA0, 9, 50]),
+            Some(ControllerEvent::PadPressure(9, 50))
+        ));
+        assert!(profile.parse_event(&[0xA0, 64, 50]).is_none());
+        assert!(profile.parse_event(&[0x91, 9, 127]).is_none());
+
+        assert_eq!(controller.grid_size(), (8, 8));
+        assert_eq!(controller.map_grid_id(9), 9);
+        assert_eq!(controller.map_to_app_grid_id(63), Some(63));
+        assert_eq!(controller.button_role(98), Some(ButtonRole::Shift));
+
+        // Wider grids number their pads by the full row width
+        let device = MidiDevice {
+            id: "in:FL STUDIO FIRE".to_string(),
+            name: "FL STUDIO FIRE".to_string(),
+            is_input: true,
+            is_controller: true,
+            model: None,
+            firmware: None,
+        };
+        let fire = ProfileController::new(device, builtin("Akai Fire"));
+
+        assert_eq!(fire.grid_size(), (4, 16));
+        assert_eq!(fire.map_grid_id(0), 102);
+        assert_eq!(fire.map_grid_id(63), 69);
+        assert_eq!(fire.map_to_app_grid_id(86), Some(16));
+    }
+
+    #[test]
+    fn test_load_user_profiles() {
+        let dir = tempdir().unwrap();
+        let custom = r#"{
+            "name": "Custom Grid",
+            "port_patterns": ["my grid"],
+            "grid": { "kind": "note", "channel": 2, "rows": [[0, 1], [2, 3]] },
+            "buttons": [{ "kind": "cc", "number": 100, "role": "panic" }],
+            "pad_colors": { "type": "palette", "palette": [[0, [0, 0, 0]], [5, [255, 0, 0]]] }
+        }"#;
+        fs::write(dir.path().join("custom.json"), custom).unwrap();
+        fs::write(dir.path().join("broken.json"), "{").unwrap();
+        fs::write(dir.path().join("notes.txt"), "not a profile").unwrap();
+
+        let loaded = load_profiles_from(dir.path());
+        let profiles = loaded.profiles;
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(loaded.skipped.len(), 1);
+        assert!(loaded.skipped[0].contains("broken.json"));
+
+        let profile = find_profile(&profiles, "My Grid Port 1").unwrap();
+        assert_eq!(profile.grid.channel, 2);
+        assert_eq!(profile.buttons[0].role, Some(ButtonRole::Panic));
+        assert!(matches!(
+            profile.parse_event(&[0x92, 3, 64]),
+            Some(ControllerEvent::PadPressed(3, 64))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_profiles_rejected() {
+        let unknown_palette = r#"{
+            "name": "Bad",
+            "port_patterns": ["bad"],
+            "grid": { "kind": "note", "rows": [[0]] },
+            "pad_colors": { "type": "palette", "palette": "rainbow" }
+        }"#;
+        assert!(ControllerProfile::from_json(unknown_palette).is_err());
+
+        let too_wide = r#"{
+            "name": "Wide",
+            "port_patterns": ["wide"],
+            "grid": { "kind": "note", "rows": [[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]] },
+            "pad_colors": { "type": "palette", "palette": "novation" }
+        }"#;
+        assert!(ControllerProfile::from_json(too_wide).is_err());
+    }
+}
@@ -0,0 +1,400 @@
+use std::sync::Arc;
+
+use midir::{MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+
+use crate::midi::controller::{select_port, ButtonRole, Color, ControllerEvent, GridController};
+use crate::midi::devices::MidiDevice;
+use crate::midi::palette::NOVATION_PALETTE;
+
+/// Ableton manufacturer ID and Push 2 device ID that start every Push 2 SysEx
+const PUSH2_SYSEX_HEADER: [u8; 6] = [0xF0, 0x00, 0x21, 0x1D, 0x01, 0x01];
+
+/// Akai manufacturer ID and Push 1 product ID (Akai built the first Push)
+const PUSH1_SYSEX_HEADER: [u8; 4] = [0xF0, 0x47, 0x7F, 0x15];
+
+/// Note of the bottom-left pad; the rest follow row by row, 8 per row
+const PUSH_FIRST_PAD_NOTE: u8 = 36;
+
+/// Buttons with RGB LEDs that take a palette index: the two rows of buttons
+/// around the display and the scene launch column
+const PUSH_RGB_BUTTONS: [std::ops::RangeInclusive<u8>; 3] = [20..=27, 36..=43, 102..=109];
+
+/// Scene launch column, bottom to top
+const PUSH_SCENE_BUTTONS: std::ops::RangeInclusive<u8> = 36..=43;
+
+/// Encoder CCs, which are relative and not exposed as buttons
+const PUSH_ENCODERS: [u8; 11] = [14, 15, 71, 72, 73, 74, 75, 76, 77, 78, 79];
+
+/// Arrow and shift button CCs, the same on both models
+const PUSH_LEFT: u8 = 44;
+const PUSH_RIGHT: u8 = 45;
+const PUSH_UP: u8 = 46;
+const PUSH_DOWN: u8 = 47;
+const PUSH_SHIFT: u8 = 49;
+
+/// Ableton Push models, used in user mode with MIDI only
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PushModel {
+    Push1,
+    Push2,
+}
+
+impl PushModel {
+    /// Detect the model from a MIDI port name
+    pub fn from_port_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+
+        if name.contains("push 2") || name.contains("push2") {
+            Some(PushModel::Push2)
+        } else if name.contains("ableton push") {
+            Some(PushModel::Push1)
+        } else {
+            None
+        }
+    }
+
+    /// Port name tag of the user port, which leaves the live port to Ableton Live
+    fn user_port_tag(&self) -> &'static str {
+        match self {
+            PushModel::Push1 => "push user port",
+            PushModel::Push2 => "push 2 user port",
+        }
+    }
+
+    /// SysEx switching between user mode and Live mode
+    fn user_mode(&self, enabled: bool) -> Vec<u8> {
+        let mut message = match self {
+            PushModel::Push1 => [&PUSH1_SYSEX_HEADER[..], &[0x62, 0x00, 0x01]].concat(),
+            PushModel::Push2 => [&PUSH2_SYSEX_HEADER[..], &[0x0A]].concat(),
+        };
+        message.push(enabled as u8);
+        message.push(0xF7);
+        message
+    }
+
+    /// Value lighting a single-color button LED fully
+    fn button_on(&self) -> u8 {
+        match self {
+            PushModel::Push1 => 4,
+            PushModel::Push2 => 127,
+        }
+    }
+
+    /// Translate an incoming message into a controller event
+    fn parse_event(&self, message: &[u8]) -> Option<ControllerEvent> {
+        let (&status, &data1, &data2) = match message {
+            [status, data1, data2, ..] => (status, data1, data2),
+            _ => return None,
+        };
+        let pad_notes = PUSH_FIRST_PAD_NOTE..PUSH_FIRST_PAD_NOTE + 64;
+
+        match (status & 0xF0, data2) {
+            // Notes below the pads are encoder touches
+            (0x80, _) | (0x90, 0) if pad_notes.contains(&data1) => {
+                Some(ControllerEvent::PadReleased(data1))
+            }
+            (0x90, velocity) if pad_notes.contains(&data1) => {
+                Some(ControllerEvent::PadPressed(data1, velocity))
+            }
+            (0xA0, pressure) if pad_notes.contains(&data1) => {
+                Some(ControllerEvent::PadPressure(data1, pressure))
+            }
+            (0xB0, _) if PUSH_ENCODERS.contains(&data1) => None,
+            (0xB0, 0) => Some(ControllerEvent::ButtonReleased(data1)),
+            (0xB0, _) => Some(ControllerEvent::ButtonPressed(data1)),
+            _ => None,
+        }
+    }
+}
+
+/// Build a Push 2 SysEx setting a palette entry's RGB and white LED values
+fn push2_palette_entry(index: u8, color: Color) -> Vec<u8> {
+    let split = |c: u8| [c & 0x7F, c >> 7];
+    let white = ((color.r as u16 + color.g as u16 + color.b as u16) / 3) as u8;
+
+    let mut message = PUSH2_SYSEX_HEADER.to_vec();
+    message.extend_from_slice(&[0x03, index]);
+    for channel in [color.r, color.g, color.b, white] {
+        message.extend_from_slice(&split(channel));
+    }
+    message.push(0xF7);
+    message
+}
+
+/// Implementation for Ableton Push 1 and Push 2 in user mode
+///
+/// Pads and button LEDs use the Novation velocity palette, which Push 1 shares.
+/// Push 2 has its own palette, so its entries are reprogrammed to match on
+/// connect.
+pub struct Push {
+    device: MidiDevice,
+    model: PushModel,
+    input_connection: Option<MidiInputConnection<()>>,
+    output_connection: Option<MidiOutputConnection>,
+    event_callback: Option<Arc<dyn Fn(ControllerEvent) + Send + Sync>>,
+}
+
+impl Push {
+    pub fn new(device: MidiDevice, model: PushModel) -> Self {
+        Push {
+            device,
+            model,
+            input_connection: None,
+            output_connection: None,
+            event_callback: None,
+        }
+    }
+
+    fn send(&mut self, message: &[u8]) -> Result<(), String> {
+        if let Some(ref mut conn) = self.output_connection {
+            conn.send(message).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Buttons with LEDs, used when clearing
+    fn button_ids(&self) -> Vec<u8> {
+        [3, 9]
+            .into_iter()
+            .chain(20..=63)
+            .chain(85..=90)
+            .chain(102..=119)
+            .collect()
+    }
+}
+
+impl Clone for Push {
+    fn clone(&self) -> Self {
+        Push {
+            device: self.device.clone(),
+            model: self.model,
+            input_connection: None,  // Connections can't be cloned
+            output_connection: None, // Connections can't be cloned
+            event_callback: self.event_callback.clone(),
+        }
+    }
+}
+
+impl GridController for Push {
+    fn clone_box(&self) -> Box<dyn GridController> {
+        Box::new(self.clone())
+    }
+
+    fn connect(&mut self) -> Result<(), String> {
+        // Connect to MIDI input
+        let midi_in = MidiInput::new("snap-blaster").map_err(|e| e.to_string())?;
+
+        let in_ports = midi_in.ports();
+        let in_names: Vec<String> = in_ports
+            .iter()
+            .map(|p| midi_in.port_name(p).unwrap_or_default())
+            .collect();
+        let in_port = select_port(&in_names, self.model.user_port_tag(), &self.device.name)
+            .map(|i| &in_ports[i])
+            .ok_or_else(|| format!("Could not find MIDI input device: {}", self.device.name))?;
+
+        let callback = {
+            let event_callback = self.event_callback.clone();
+            let model = self.model;
+
+            move |_timestamp, message: &[u8], _: &mut ()| {
+                if let Some(event) = model.parse_event(message) {
+                    if let Some(ref callback) = event_callback {
+                        callback(event);
+                    }
+                }
+            }
+        };
+
+        let input_conn = midi_in
+            .connect(in_port, "push-input", callback, ())
+            .map_err(|e| e.to_string())?;
+        self.input_connection = Some(input_conn);
+
+        // Connect to MIDI output
+        let midi_out = MidiOutput::new("snap-blaster").map_err(|e| e.to_string())?;
+
+        let out_ports = midi_out.ports();
+        let out_names: Vec<String> = out_ports
+            .iter()
+            .map(|p| midi_out.port_name(p).unwrap_or_default())
+            .collect();
+        let out_port = select_port(&out_names, self.model.user_port_tag(), &self.device.name)
+            .map(|i| &out_ports[i])
+            .ok_or_else(|| format!("Could not find MIDI output device: {}", self.device.name))?;
+
+        let output_conn = midi_out
+            .connect(out_port, "push-output")
+            .map_err(|e| e.to_string())?;
+        self.output_connection = Some(output_conn);
+
+        self.send(&self.model.user_mode(true))?;
+
+        if self.model == PushModel::Push2 {
+            for &(index, color) in NOVATION_PALETTE.entries() {
+                self.send(&push2_palette_entry(index, color))?;
+            }
+            // Reapply the palette so the new entries take effect
+            self.send(&[&PUSH2_SYSEX_HEADER[..], &[0x05, 0xF7]].concat())?;
+        }
+
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<(), String> {
+        // Leave the LEDs dark and hand the Push back to Live
+        if self.output_connection.is_some() {
+            self.clear()?;
+            self.send(&self.model.user_mode(false))?;
+        }
+
+        self.input_connection = None;
+        self.output_connection = None;
+
+        Ok(())
+    }
+
+    fn set_pad_color(&mut self, grid_id: u8, color: Color) -> Result<(), String> {
+        if grid_id >= 64 {
+            return Ok(());
+        }
+
+        let note = self.map_grid_id(grid_id);
+        self.send(&[0x90, note, NOVATION_PALETTE.nearest(color)])
+    }
+
+    fn set_button_color(&mut self, button_id: u8, color: Color) -> Result<(), String> {
+        let value = if PUSH_RGB_BUTTONS.iter().any(|r| r.contains(&button_id)) {
+            NOVATION_PALETTE.nearest(color)
+        } else if color.r > 0 || color.g > 0 || color.b > 0 {
+            self.model.button_on()
+        } else {
+            0
+        };
+
+        self.send(&[0xB0, button_id, value])
+    }
+
+    fn clear(&mut self) -> Result<(), String> {
+        for grid_id in 0..64 {
+            self.set_pad_color(grid_id, Color::BLACK)?;
+        }
+
+        for button_id in self.button_ids() {
+            self.send(&[0xB0, button_id, 0])?;
+        }
+
+        Ok(())
+    }
+
+    fn button_role(&self, button_id: u8) -> Option<ButtonRole> {
+        match button_id {
+            PUSH_LEFT => Some(ButtonRole::Left),
+            PUSH_RIGHT => Some(ButtonRole::Right),
+            PUSH_UP => Some(ButtonRole::Up),
+            PUSH_DOWN => Some(ButtonRole::Down),
+            PUSH_SHIFT => Some(ButtonRole::Shift),
+            // The scene column selects banks from the top (1/4, CC 43)
+            id if PUSH_SCENE_BUTTONS.contains(&id) => {
+                Some(ButtonRole::Bank(PUSH_SCENE_BUTTONS.end() - id))
+            }
+            _ => None,
+        }
+    }
+
+    fn map_grid_id(&self, app_grid_id: u8) -> u8 {
+        if app_grid_id >= 64 {
+            return 0; // Invalid ID
+        }
+
+        // Pads are numbered from the bottom-left, row by row, like the app grid
+        PUSH_FIRST_PAD_NOTE + app_grid_id
+    }
+
+    fn map_to_app_grid_id(&self, controller_id: u8) -> Option<u8> {
+        controller_id
+            .checked_sub(PUSH_FIRST_PAD_NOTE)
+            .filter(|grid_id| *grid_id < 64)
+    }
+
+    fn set_event_callback(&mut self, callback: Arc<dyn Fn(ControllerEvent) + Send + Sync>) {
+        self.event_callback = Some(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_model_detection() {
+        assert_eq!(
+            PushModel::from_port_name("Ableton Push 2 User Port"),
+            Some(PushModel::Push2)
+        );
+        assert_eq!(
+            PushModel::from_port_name("Ableton Push User Port"),
+            Some(PushModel::Push1)
+        );
+        assert_eq!(PushModel::from_port_name("Launchpad X"), None);
+    }
+
+    #[test]
+    fn test_push_sysex() {
+        assert_eq!(
+            PushModel::Push2.user_mode(true),
+            vec![0xF0, 0x00, 0x21, 0x1D, 0x01, 0x01, 0x0A, 0x01, 0xF7]
+        );
+        assert_eq!(
+            PushModel::Push1.user_mode(false),
+            vec![0xF0, 0x47, 0x7F, 0x15, 0x62, 0x00, 0x01, 0x00, 0xF7]
+        );
+        assert_eq!(
+            push2_palette_entry(5, Color::new(255, 0, 3)),
+            vec![0xF0, 0x00, 0x21, 0x1D, 0x01, 0x01, 0x03, 5, 0x7F, 0x01, 0, 0, 3, 0, 86, 0, 0xF7]
+        );
+    }
+
+    #[test]
+    fn test_push_parse_events() {
+        let push = PushModel::Push2;
+        assert!(matches!(
+            push.parse_event(&[0x90, 36, 90]),
+            Some(ControllerEvent::PadPressed(36, 90))
+        ));
+        assert!(matches!(
+            push.parse_event(&[0x80, 99, 0]),
+            Some(ControllerEvent::PadReleased(99))
+        ));
+        assert!(matches!(
+            push.parse_event(&[0xA0, 40, 64]),
+            Some(ControllerEvent::PadPressure(40, 64))
+        ));
+        assert!(matches!(
+            push.parse_event(&[0xB0, 85, 127]),
+            Some(ControllerEvent::ButtonPressed(85))
+        ));
+        // Encoder touches and turns are ignored
+        assert!(push.parse_event(&[0x90, 0, 127]).is_none());
+        assert!(push.parse_event(&[0xB0, 71, 1]).is_none());
+    }
+
+    #[test]
+    fn test_push_grid_mapping() {
+        let device = MidiDevice {
+            id: "in:Ableton Push 2 User Port".to_string(),
+            name: "Ableton Push 2 User Port".to_string(),
+            is_input: true,
+            is_controller: true,
+            model: None,
+            firmware: None,
+        };
+        let push = Push::new(device, PushModel::Push2);
+
+        assert_eq!(push.map_grid_id(0), 36);
+        assert_eq!(push.map_grid_id(63), 99);
+        assert_eq!(push.map_to_app_grid_id(44), Some(8));
+        assert_eq!(push.map_to_app_grid_id(20), None);
+        assert_eq!(push.map_to_app_grid_id(100), None);
+    }
+}
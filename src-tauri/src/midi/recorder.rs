@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::midi::smf::{MidiFile, Track, TrackEvent, DEFAULT_PPQ};
+
+/// How recorded messages are split into tracks on export
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum TrackSplit {
+    /// One track per output port
+    #[default]
+    PerOutput,
+    /// One track per MIDI channel, merging all outputs
+    PerChannel,
+}
+
+/// A message sent to an output while recording
+struct RecordedMessage {
+    beat: f64,
+    output: String,
+    message: Vec<u8>,
+}
+
+/// Beats in a bar, matching the quantum the Link session is joined with
+const BEATS_PER_BAR: f64 = 4.0;
+
+/// Captures outgoing MIDI with timestamps in beats
+///
+/// Beats follow the engine's beat clock, including every tempo change and Link
+/// resync it receives. The export starts at the bar the recording began in, so
+/// its bars line up with the session's.
+pub struct Recorder {
+    /// Instant at which `origin_beat` was reached
+    origin: Instant,
+    origin_beat: f64,
+    /// Beat clock position of the first tick in the export
+    start_beat: f64,
+    tempo: f64,
+    tempo_changes: Vec<(f64, f64)>,
+    markers: Vec<(f64, String)>,
+    messages: Vec<RecordedMessage>,
+}
+
+impl Recorder {
+    /// Start a recording at the given tempo and beat clock position
+    pub fn new(tempo: f64, beat: f64, now: Instant) -> Self {
+        let start_beat = (beat / BEATS_PER_BAR).floor() * BEATS_PER_BAR;
+
+        Recorder {
+            origin: now,
+            origin_beat: beat,
+            start_beat,
+            tempo,
+            tempo_changes: vec![(start_beat, tempo)],
+            markers: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    /// Beat clock position of an instant
+    pub fn beat_at(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.origin).as_secs_f64();
+        self.origin_beat + elapsed * self.tempo / 60.0
+    }
+
+    /// Follow a tempo change
+    pub fn set_tempo(&mut self, tempo: f64, now: Instant) {
+        if tempo <= 0.0 || tempo == self.tempo {
+            return;
+        }
+
+        self.origin_beat = self.beat_at(now);
+        self.origin = now;
+        self.tempo = tempo;
+        self.tempo_changes.push((self.origin_beat, tempo));
+    }
+
+    /// Follow the beat clock being moved to an external position and tempo
+    pub fn sync(&mut self, beat: f64, tempo: f64, now: Instant) {
+        self.origin = now;
+        self.origin_beat = beat;
+
+        if tempo > 0.0 && tempo != self.tempo {
+            self.tempo = tempo;
+            self.tempo_changes.push((beat, tempo));
+        }
+    }
+
+    /// Record a message sent to an output
+    pub fn record(&mut self, output: &str, message: &[u8], now: Instant) {
+        self.messages.push(RecordedMessage {
+            beat: self.beat_at(now),
+            output: output.to_string(),
+            message: message.to_vec(),
+        });
+    }
+
+    /// Add a named marker, e.g. for a scene activation
+    pub fn mark(&mut self, name: &str, now: Instant) {
+        self.markers.push((self.beat_at(now), name.to_string()));
+    }
+
+    /// Number of messages recorded so far
+    pub fn message_count(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Build a Type 1 MIDI file from the recording
+    ///
+    /// The first track holds the tempo map and markers; recorded messages follow in
+    /// one track per output or per channel. System real-time and other non-channel
+    /// messages except SysEx can't be stored in an SMF and are left out.
+    pub fn to_midi_file(&self, split: TrackSplit) -> MidiFile {
+        let ppq = DEFAULT_PPQ;
+        let to_tick = |beat: f64| ((beat - self.start_beat) * ppq as f64).round().max(0.0) as u32;
+
+        let mut file = MidiFile::new(ppq);
+
+        let mut conductor = Track::new();
+        conductor.push(0, TrackEvent::TrackName("Snap-Blaster".to_string()));
+        for (beat, tempo) in &self.tempo_changes {
+            conductor.push(to_tick(*beat), TrackEvent::Tempo(*tempo));
+        }
+        for (beat, name) in &self.markers {
+            conductor.push(to_tick(*beat), TrackEvent::Marker(name.clone()));
+        }
+        file.tracks.push(conductor);
+
+        // BTreeMap keeps the track order stable between exports
+        let mut tracks: BTreeMap<(u8, String), Track> = BTreeMap::new();
+
+        for recorded in &self.messages {
+            let Some(&status) = recorded.message.first() else {
+                continue;
+            };
+
+            let event = match status {
+                0x80..=0xEF => TrackEvent::Midi(recorded.message.clone()),
+                0xF0 => TrackEvent::SysEx(recorded.message.clone()),
+                _ => continue,
+            };
+
+            // SysEx has no channel, so it stays with its output
+            let key = match (split, status) {
+                (TrackSplit::PerChannel, 0x80..=0xEF) => {
+                    let channel = status & 0x0F;
+                    (channel, format!("Channel {}", channel + 1))
+                }
+                _ => (0, recorded.output.clone()),
+            };
+
+            tracks
+                .entry(key)
+                .or_insert_with_key(|(_, name)| {
+                    let mut track = Track::new();
+                    track.push(0, TrackEvent::TrackName(name.clone()));
+                    track
+                })
+                .push(to_tick(recorded.beat), event);
+        }
+
+        file.tracks.extend(tracks.into_values());
+        file
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_beats_follow_tempo_changes() {
+        let start = Instant::now();
+        let mut recorder = Recorder::new(120.0, 0.0, start);
+
+        // Two beats at 120 BPM, then two more at 60 BPM
+        let one_second = start + Duration::from_secs(1);
+        assert!((recorder.beat_at(one_second) - 2.0).abs() < 1e-9);
+
+        recorder.set_tempo(60.0, one_second);
+        let three_seconds = start + Duration::from_secs(3);
+        assert!((recorder.beat_at(three_seconds) - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_export_starts_at_session_bar() {
+        let start = Instant::now();
+        // Recording starts halfway through the third bar of the session
+        let mut recorder = Recorder::new(120.0, 10.0, start);
+        recorder.record("Synth", &[0xB0, 74, 10], start);
+
+        // Link moves the clock on a beat and slows down
+        let one_second = start + Duration::from_secs(1);
+        recorder.sync(13.0, 60.0, one_second);
+        assert!((recorder.beat_at(start + Duration::from_secs(2)) - 14.0).abs() < 1e-9);
+        recorder.record("Synth", &[0xB0, 74, 20], start + Duration::from_secs(2));
+
+        let file = recorder.to_midi_file(TrackSplit::PerOutput);
+        let ppq = DEFAULT_PPQ as u32;
+        let tempos: Vec<_> = file.tracks[0]
+            .events
+            .iter()
+            .filter(|e| matches!(e.event, TrackEvent::Tempo(_)))
+            .map(|e| e.tick)
+            .collect();
+        assert_eq!(tempos, vec![0, 5 * ppq]);
+
+        let synth = &file.tracks[1];
+        assert_eq!(synth.events[1].tick, 2 * ppq);
+        assert_eq!(synth.events[2].tick, 6 * ppq);
+    }
+
+    #[test]
+    fn test_export_tracks() {
+        let start = Instant::now();
+        let mut recorder = Recorder::new(120.0, 0.0, start);
+
+        recorder.mark("Intro", start);
+        recorder.record("Synth", &[0xB0, 74, 10], start);
+        recorder.record("Drums", &[0xB9, 7, 100], start + Duration::from_millis(500));
+        recorder.record("Synth", &[0xB1, 74, 20], start + Duration::from_millis(500));
+        recorder.record("Synth", &[0xF8], start + Duration::from_millis(500));
+
+        let by_output = recorder.to_midi_file(TrackSplit::PerOutput);
+        assert_eq!(by_output.tracks.len(), 3);
+        assert!(by_output.tracks[0]
+            .events
+            .iter()
+            .any(|e| e.event == TrackEvent::Marker("Intro".to_string())));
+
+        // Drums sort before Synth; the clock message is dropped
+        let synth = &by_output.tracks[2];
+        assert_eq!(synth.events.len(), 3);
+        assert_eq!(synth.events[2].tick, DEFAULT_PPQ as u32);
+
+        let by_channel = recorder.to_midi_file(TrackSplit::PerChannel);
+        assert_eq!(by_channel.tracks.len(), 4);
+        assert_eq!(
+            by_channel.tracks[3].events[0].event,
+            TrackEvent::TrackName("Channel 10".to_string())
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::midi::controller::{ButtonRole, Color, ControllerEvent, GridController, LaunchpadMk2};
+use crate::midi::devices::MidiDevice;
+use crate::midi::palette::{PadState, NOVATION_PALETTE};
+
+/// Device ID the simulator connects under
+pub const SIMULATOR_DEVICE_ID: &str = "simulator";
+
+/// Pad rows and columns of the simulated Launchpad
+const SIMULATOR_ROWS: u8 = 8;
+const SIMULATOR_COLUMNS: u8 = 8;
+
+/// How a Launchpad LED is lit, from the MIDI channel of its last message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Lighting {
+    Static,
+    /// Flashing between off and the color
+    Flashing,
+    Pulsing,
+}
+
+/// A lit pad on the simulated grid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Led {
+    /// Palette color the device shows
+    pub color: Color,
+    pub lighting: Lighting,
+}
+
+/// What the simulated device holds, shared by every clone of it
+#[derive(Default)]
+struct SimulatorState {
+    connected: bool,
+    /// Pad lights keyed by Launchpad note
+    pads: HashMap<u8, Led>,
+    /// Button lights keyed by Launchpad note or CC
+    buttons: HashMap<u8, Color>,
+    event_callback: Option<Arc<dyn Fn(ControllerEvent) + Send + Sync>>,
+}
+
+impl SimulatorState {
+    /// Light LEDs from a message the Launchpad MK2 driver sent
+    fn receive(&mut self, message: &[u8]) {
+        let &[status, note, value] = message else {
+            // Mode changes are SysEx, and leave the LEDs alone
+            return;
+        };
+
+        let color = palette_color(value);
+        let lighting = match status {
+            0x90 => Lighting::Static,
+            0x91 => Lighting::Flashing,
+            0x92 => Lighting::Pulsing,
+            0xB0 => {
+                self.buttons.insert(note, color);
+                return;
+            }
+            _ => return,
+        };
+
+        // The right column takes notes, but is a column of buttons
+        if note % 10 == 9 {
+            self.buttons.insert(note, color);
+        } else {
+            self.pads.insert(note, Led { color, lighting });
+        }
+    }
+}
+
+/// Color shown for a Launchpad palette value
+fn palette_color(value: u8) -> Color {
+    NOVATION_PALETTE
+        .entries()
+        .iter()
+        .find(|(entry, _)| *entry == value)
+        .map(|&(_, color)| color)
+        .unwrap_or(Color::BLACK)
+}
+
+/// Software Launchpad for tests and development builds
+///
+/// Emulates a Launchpad MK2 in memory: a real Launchpad MK2 driver sends its
+/// messages here instead of to a port, and the simulator lights its LEDs from
+/// them so they can be read back. Presses are injected as if they came from the
+/// device. Clones share the same device, so a test can keep one while the app
+/// drives another. Builds with the `simulator` feature connect one under
+/// `SIMULATOR_DEVICE_ID`.
+#[derive(Clone)]
+pub struct SimulatedLaunchpad {
+    /// Driver that encodes the app's lights
+    launchpad: LaunchpadMk2,
+    state: Arc<Mutex<SimulatorState>>,
+}
+
+impl SimulatedLaunchpad {
+    pub fn new() -> Self {
+        let device = MidiDevice {
+            id: SIMULATOR_DEVICE_ID.to_string(),
+            name: "Launchpad MK2 Simulator".to_string(),
+            is_input: true,
+            is_controller: true,
+            model: Some("Launchpad MK2".to_string()),
+            firmware: None,
+        };
+
+        let state = Arc::new(Mutex::new(SimulatorState::default()));
+        let output = Arc::clone(&state);
+        let launchpad = LaunchpadMk2::in_memory(
+            device,
+            Arc::new(move |message: &[u8]| output.lock().unwrap().receive(message)),
+        );
+
+        SimulatedLaunchpad { launchpad, state }
+    }
+
+    /// Whether the app has connected to the device
+    pub fn is_connected(&self) -> bool {
+        self.state.lock().unwrap().connected
+    }
+
+    /// Light of the pad at a row and column, counting from the bottom-left
+    pub fn pad(&self, row: u8, column: u8) -> Option<Led> {
+        let note = self.pad_note(row, column)?;
+        self.state.lock().unwrap().pads.get(&note).copied()
+    }
+
+    /// Color of a button, if it has been lit
+    pub fn button(&self, button_id: u8) -> Option<Color> {
+        self.state.lock().unwrap().buttons.get(&button_id).copied()
+    }
+
+    /// Press the pad at a row and column
+    pub fn press_pad(&self, row: u8, column: u8, velocity: u8) {
+        if let Some(note) = self.pad_note(row, column) {
+            self.send(ControllerEvent::PadPressed(note, velocity.max(1)));
+        }
+    }
+
+    /// Release the pad at a row and column
+    pub fn release_pad(&self, row: u8, column: u8) {
+        if let Some(note) = self.pad_note(row, column) {
+            self.send(ControllerEvent::PadReleased(note));
+        }
+    }
+
+    /// Press a button by its Launchpad note or CC
+    pub fn press_button(&self, button_id: u8) {
+        self.send(ControllerEvent::ButtonPressed(button_id));
+    }
+
+    /// Release a button by its Launchpad note or CC
+    pub fn release_button(&self, button_id: u8) {
+        self.send(ControllerEvent::ButtonReleased(button_id));
+    }
+
+    /// Launchpad note of a pad, if it is on the grid
+    fn pad_note(&self, row: u8, column: u8) -> Option<u8> {
+        if row >= SIMULATOR_ROWS || column >= SIMULATOR_COLUMNS {
+            return None;
+        }
+
+        Some(self.launchpad.map_grid_id(row * SIMULATOR_COLUMNS + column))
+    }
+
+    /// Hand an event to the app, like the device's input port would
+    fn send(&self, event: ControllerEvent) {
+        // Call without the lock, since the app answers by setting lights
+        let callback = {
+            let state = self.state.lock().unwrap();
+            if !state.connected {
+                return;
+            }
+            state.event_callback.clone()
+        };
+
+        if let Some(callback) = callback {
+            callback(event);
+        }
+    }
+}
+
+impl Default for SimulatedLaunchpad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GridController for SimulatedLaunchpad {
+    fn connect(&mut self) -> Result<(), String> {
+        self.state.lock().unwrap().connected = true;
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<(), String> {
+        self.state.lock().unwrap().connected = false;
+        Ok(())
+    }
+
+    fn set_pad_color(&mut self, grid_id: u8, color: Color) -> Result<(), String> {
+        self.launchpad.set_pad_color(grid_id, color)
+    }
+
+    fn set_pad_states(&mut self, pads: &[(u8, Color, PadState)]) -> Result<(), String> {
+        self.launchpad.set_pad_states(pads)
+    }
+
+    fn animates_leds(&self) -> bool {
+        self.launchpad.animates_leds()
+    }
+
+    fn send_clock(&mut self) -> Result<(), String> {
+        self.launchpad.send_clock()
+    }
+
+    fn set_button_color(&mut self, button_id: u8, color: Color) -> Result<(), String> {
+        self.launchpad.set_button_color(button_id, color)
+    }
+
+    fn clear(&mut self) -> Result<(), String> {
+        self.launchpad.clear()
+    }
+
+    fn grid_size(&self) -> (u8, u8) {
+        self.launchpad.grid_size()
+    }
+
+    fn button_role(&self, button_id: u8) -> Option<ButtonRole> {
+        self.launchpad.button_role(button_id)
+    }
+
+    fn map_grid_id(&self, app_grid_id: u8) -> u8 {
+        self.launchpad.map_grid_id(app_grid_id)
+    }
+
+    fn map_to_app_grid_id(&self, controller_id: u8) -> Option<u8> {
+        self.launchpad.map_to_app_grid_id(controller_id)
+    }
+
+    fn set_event_callback(&mut self, callback: Arc<dyn Fn(ControllerEvent) + Send + Sync>) {
+        self.state.lock().unwrap().event_callback = Some(callback);
+    }
+
+    fn clone_box(&self) -> Box<dyn GridController> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leds_read_back() {
+        let mut simulator = SimulatedLaunchpad::new();
+        let green = palette_color(NOVATION_PALETTE.nearest(Color::GREEN));
+
+        // Grid ID 9 is the second pad of the second row, note 22
+        simulator
+            .set_pad_states(&[
+                (9, Color::GREEN, PadState::Active),
+                (10, Color::GREEN, PadState::Queued),
+                (11, Color::GREEN, PadState::Assigned),
+            ])
+            .unwrap();
+        simulator.set_button_color(104, Color::WHITE).unwrap();
+        simulator.set_button_color(89, Color::RED).unwrap();
+
+        assert_eq!(
+            simulator.pad(1, 1),
+            Some(Led {
+                color: green,
+                lighting: Lighting::Pulsing,
+            })
+        );
+        assert_eq!(
+            simulator.pad(1, 2),
+            Some(Led {
+                color: green,
+                lighting: Lighting::Flashing,
+            })
+        );
+
+        // Assigned pads are a dimmer static shade
+        let assigned = simulator.pad(1, 3).unwrap();
+        assert_eq!(assigned.lighting, Lighting::Static);
+        assert_ne!(assigned.color, green);
+        assert_ne!(assigned.color, Color::BLACK);
+
+        assert_eq!(simulator.pad(0, 0), None);
+        assert_eq!(
+            simulator.button(104),
+            Some(palette_color(NOVATION_PALETTE.nearest(Color::WHITE)))
+        );
+        assert_eq!(
+            simulator.button(89),
+            Some(palette_color(NOVATION_PALETTE.nearest(Color::RED)))
+        );
+
+        simulator.clear().unwrap();
+        assert_eq!(
+            simulator.pad(1, 1),
+            Some(Led {
+                color: Color::BLACK,
+                lighting: Lighting::Static,
+            })
+        );
+        assert_eq!(simulator.button(104), Some(Color::BLACK));
+        assert_eq!(simulator.button(89), Some(Color::BLACK));
+    }
+
+    #[test]
+    fn test_presses_reach_the_callback() {
+        let mut simulator = SimulatedLaunchpad::new();
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let received = Arc::clone(&events);
+        simulator.set_event_callback(Arc::new(move |event| {
+            received.lock().unwrap().push(format!("{:?}", event));
+        }));
+
+        // Nothing arrives until the app connects
+        simulator.press_pad(0, 0, 100);
+        assert!(events.lock().unwrap().is_empty());
+
+        simulator.connect().unwrap();
+        simulator.press_pad(0, 0, 100);
+        simulator.release_pad(0, 0);
+        simulator.press_button(89);
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "PadPressed(11, 100)",
+                "PadReleased(11)",
+                "ButtonPressed(89)"
+            ]
+        );
+        assert_eq!(simulator.button_role(89), Some(ButtonRole::Bank(0)));
+    }
+}
@@ -0,0 +1,447 @@
+/// Default resolution for exported files, in ticks per quarter note
+pub const DEFAULT_PPQ: u16 = 480;
+
+/// An event stored in a Standard MIDI File track
+#[derive(Clone, Debug, PartialEq)]
+pub enum TrackEvent {
+    /// A channel message (status byte followed by its data bytes)
+    Midi(Vec<u8>),
+    /// A complete system exclusive message, including the F0 and F7 bytes
+    SysEx(Vec<u8>),
+    /// Tempo change in BPM
+    Tempo(f64),
+    /// Marker meta event
+    Marker(String),
+    /// Track name meta event
+    TrackName(String),
+}
+
+/// A track event at an absolute tick position
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimedEvent {
+    pub tick: u32,
+    pub event: TrackEvent,
+}
+
+/// A single track of a Standard MIDI File
+#[derive(Clone, Debug, Default)]
+pub struct Track {
+    pub events: Vec<TimedEvent>,
+}
+
+impl Track {
+    /// Create an empty track
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an event at an absolute tick position
+    pub fn push(&mut self, tick: u32, event: TrackEvent) -> &mut Self {
+        self.events.push(TimedEvent { tick, event });
+        self
+    }
+
+    /// Encode the track as an MTrk chunk
+    fn to_chunk(&self) -> Vec<u8> {
+        // Events are stored in absolute time, so sort before computing deltas.
+        // The sort is stable, keeping events on the same tick in insertion order.
+        let mut events: Vec<&TimedEvent> = self.events.iter().collect();
+        events.sort_by_key(|e| e.tick);
+
+        let mut data = Vec::new();
+        let mut last_tick = 0;
+
+        for timed in events {
+            write_vlq(&mut data, timed.tick - last_tick);
+            last_tick = timed.tick;
+
+            match &timed.event {
+                TrackEvent::Midi(message) => data.extend_from_slice(message),
+                TrackEvent::SysEx(message) => {
+                    // Stored as F0 <length> <bytes after F0>
+                    data.push(0xF0);
+                    write_vlq(&mut data, message.len().saturating_sub(1) as u32);
+                    data.extend_from_slice(message.get(1..).unwrap_or_default());
+                }
+                TrackEvent::Tempo(bpm) => {
+                    let micros = (60_000_000.0 / bpm).round().clamp(1.0, 0xFF_FFFF as f64) as u32;
+                    data.extend_from_slice(&[0xFF, 0x51, 0x03]);
+                    data.extend_from_slice(&micros.to_be_bytes()[1..]);
+                }
+                TrackEvent::Marker(text) => write_text_meta(&mut data, 0x06, text),
+                TrackEvent::TrackName(text) => write_text_meta(&mut data, 0x03, text),
+            }
+        }
+
+        // End of track
+        data.extend_from_slice(&[0x00, 0xFF, 0x2F, 0x00]);
+
+        let mut chunk = Vec::with_capacity(data.len() + 8);
+        chunk.extend_from_slice(b"MTrk");
+        chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        chunk.extend_from_slice(&data);
+        chunk
+    }
+}
+
+/// A Type 1 (multi-track) Standard MIDI File
+#[derive(Clone, Debug)]
+pub struct MidiFile {
+    /// Ticks per quarter note
+    pub ppq: u16,
+    pub tracks: Vec<Track>,
+}
+
+impl MidiFile {
+    /// Create an empty file with the given resolution
+    pub fn new(ppq: u16) -> Self {
+        MidiFile {
+            ppq,
+            tracks: Vec::new(),
+        }
+    }
+
+    /// Parse a Standard MIDI File (format 0, 1 or 2)
+    ///
+    /// Only the events Snap-Blaster understands are kept: channel messages, SysEx,
+    /// tempo changes, markers and track names. Other meta events are skipped.
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new(bytes);
+
+        if reader.take(4)? != b"MThd" {
+            return Err("Not a Standard MIDI File".to_string());
+        }
+        let header_len = reader.u32()? as usize;
+        let header = reader.take(header_len)?;
+        if header.len() < 6 {
+            return Err("MIDI file header is too short".to_string());
+        }
+
+        let division = u16::from_be_bytes([header[4], header[5]]);
+        if division & 0x8000 != 0 {
+            return Err("SMPTE time division is not supported".to_string());
+        }
+        if division == 0 {
+            return Err("MIDI file has no ticks per quarter note".to_string());
+        }
+
+        let mut file = MidiFile::new(division);
+
+        while !reader.is_empty() {
+            let chunk_type = reader.take(4)?;
+            let chunk_len = reader.u32()? as usize;
+            let chunk = reader.take(chunk_len)?;
+
+            // Unknown chunks must be ignored
+            if chunk_type == b"MTrk" {
+                file.tracks.push(parse_track(chunk)?);
+            }
+        }
+
+        Ok(file)
+    }
+
+    /// Encode the file as Type 1 SMF bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(b"MThd");
+        bytes.extend_from_slice(&6u32.to_be_bytes());
+        bytes.extend_from_slice(&1u16.to_be_bytes());
+        bytes.extend_from_slice(&(self.tracks.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&self.ppq.to_be_bytes());
+
+        for track in &self.tracks {
+            bytes.extend_from_slice(&track.to_chunk());
+        }
+
+        bytes
+    }
+}
+
+/// Cursor over SMF bytes
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, position: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| "Unexpected end of MIDI file".to_string())?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn vlq(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Invalid variable-length quantity".to_string())
+    }
+}
+
+/// Parse the contents of an MTrk chunk into absolute-time events
+fn parse_track(data: &[u8]) -> Result<Track, String> {
+    let mut reader = Reader::new(data);
+    let mut track = Track::new();
+    let mut tick = 0u32;
+    let mut running_status: Option<u8> = None;
+
+    while !reader.is_empty() {
+        tick = tick.saturating_add(reader.vlq()?);
+
+        let first = reader.u8()?;
+        match first {
+            0xFF => {
+                // Running status doesn't carry over meta or SysEx events
+                running_status = None;
+                let meta_type = reader.u8()?;
+                let len = reader.vlq()? as usize;
+                let payload = reader.take(len)?;
+
+                match meta_type {
+                    0x2F => break,
+                    0x51 if len == 3 => {
+                        let micros = u32::from_be_bytes([0, payload[0], payload[1], payload[2]]);
+                        if micros > 0 {
+                            track.push(tick, TrackEvent::Tempo(60_000_000.0 / micros as f64));
+                        }
+                    }
+                    0x06 => {
+                        let text = String::from_utf8_lossy(payload).into_owned();
+                        track.push(tick, TrackEvent::Marker(text));
+                    }
+                    0x03 => {
+                        let text = String::from_utf8_lossy(payload).into_owned();
+                        track.push(tick, TrackEvent::TrackName(text));
+                    }
+                    _ => {}
+                }
+            }
+            0xF0 | 0xF7 => {
+                running_status = None;
+                let len = reader.vlq()? as usize;
+                let payload = reader.take(len)?;
+                // F7 escapes carry arbitrary bytes, so only keep complete SysEx
+                if first == 0xF0 {
+                    let mut message = vec![0xF0];
+                    message.extend_from_slice(payload);
+                    track.push(tick, TrackEvent::SysEx(message));
+                }
+            }
+            0xF1..=0xFE => {
+                return Err(format!("Unexpected system message {:02X} in track", first));
+            }
+            _ => {
+                // Data byte in the status position means running status
+                let (status, first_data) = if first & 0x80 != 0 {
+                    running_status = Some(first);
+                    (first, None)
+                } else {
+                    let status = running_status
+                        .ok_or_else(|| "Running status without a previous status".to_string())?;
+                    (status, Some(first))
+                };
+
+                let data_len = match status & 0xF0 {
+                    0xC0 | 0xD0 => 1,
+                    _ => 2,
+                };
+
+                let mut message = vec![status];
+                if let Some(byte) = first_data {
+                    message.push(byte);
+                }
+                while message.len() < data_len + 1 {
+                    message.push(reader.u8()?);
+                }
+                track.push(tick, TrackEvent::Midi(message));
+            }
+        }
+    }
+
+    Ok(track)
+}
+
+/// Write a variable-length quantity
+fn write_vlq(out: &mut Vec<u8>, value: u32) {
+    let mut buffer = [0u8; 5];
+    let mut index = buffer.len() - 1;
+    let mut remaining = value;
+
+    buffer[index] = (remaining & 0x7F) as u8;
+    remaining >>= 7;
+    while remaining > 0 {
+        index -= 1;
+        buffer[index] = (remaining & 0x7F) as u8 | 0x80;
+        remaining >>= 7;
+    }
+
+    out.extend_from_slice(&buffer[index..]);
+}
+
+/// Write a text meta event of the given type
+fn write_text_meta(out: &mut Vec<u8>, meta_type: u8, text: &str) {
+    out.extend_from_slice(&[0xFF, meta_type]);
+    write_vlq(out, text.len() as u32);
+    out.extend_from_slice(text.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_vlq() {
+        let encode = |value| {
+            let mut out = Vec::new();
+            write_vlq(&mut out, value);
+            out
+        };
+
+        assert_eq!(encode(0), vec![0x00]);
+        assert_eq!(encode(0x7F), vec![0x7F]);
+        assert_eq!(encode(0x80), vec![0x81, 0x00]);
+        assert_eq!(encode(0x3FFF), vec![0xFF, 0x7F]);
+        assert_eq!(encode(0x0FFF_FFFF), vec![0xFF, 0xFF, 0xFF, 0x7F]);
+    }
+
+    #[test]
+    fn test_type1_file_layout() {
+        let mut conductor = Track::new();
+        conductor
+            .push(0, TrackEvent::Tempo(120.0))
+            .push(480, TrackEvent::Marker("Drop".to_string()));
+
+        let mut notes = Track::new();
+        notes
+            .push(960, TrackEvent::Midi(vec![0xB0, 74, 127]))
+            .push(0, TrackEvent::Midi(vec![0xB0, 74, 0]));
+
+        let mut file = MidiFile::new(DEFAULT_PPQ);
+        file.tracks.push(conductor);
+        file.tracks.push(notes);
+        let bytes = file.to_bytes();
+
+        // Header: format 1, two tracks, 480 PPQ
+        assert_eq!(&bytes[0..4], b"MThd");
+        assert_eq!(&bytes[8..14], &[0, 1, 0, 2, 0x01, 0xE0]);
+
+        // Conductor track: tempo of 500000us per quarter, then a marker one beat later
+        assert_eq!(&bytes[14..18], b"MTrk");
+        assert_eq!(&bytes[22..29], &[0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20]);
+        assert_eq!(&bytes[29..33], &[0x83, 0x60, 0xFF, 0x06]);
+
+        // Second track events are sorted by tick
+        let second = bytes.windows(4).rposition(|w| w == b"MTrk").unwrap();
+        assert_eq!(
+            &bytes[second + 8..second + 16],
+            &[0x00, 0xB0, 74, 0, 0x87, 0x40, 0xB0, 74]
+        );
+        assert!(bytes.ends_with(&[0x00, 0xFF, 0x2F, 0x00]));
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        let mut track = Track::new();
+        track
+            .push(0, TrackEvent::TrackName("Synth".to_string()))
+            .push(0, TrackEvent::Tempo(100.0))
+            .push(240, TrackEvent::Midi(vec![0xC2, 5]))
+            .push(480, TrackEvent::Marker("Verse".to_string()))
+            .push(
+                960,
+                TrackEvent::SysEx(vec![0xF0, 0x7E, 0x7F, 0x06, 0x01, 0xF7]),
+            )
+            .push(100_000, TrackEvent::Midi(vec![0xB2, 74, 127]));
+
+        let mut file = MidiFile::new(96);
+        file.tracks.push(track.clone());
+
+        let parsed = MidiFile::parse(&file.to_bytes()).unwrap();
+        assert_eq!(parsed.ppq, 96);
+        assert_eq!(parsed.tracks.len(), 1);
+        assert_eq!(parsed.tracks[0].events, track.events);
+    }
+
+    #[test]
+    fn test_parse_running_status() {
+        #[rustfmt::skip]
+        let bytes = [
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 12,
+            0x00, 0xB0, 7, 100,
+            0x60, 7, 90, // running status CC
+            0x00, 0xFF, 0x2F, 0x00,
+            0x00, // Trailing bytes after end of track are ignored
+        ];
+
+        let parsed = MidiFile::parse(&bytes).unwrap();
+        assert_eq!(
+            parsed.tracks[0].events,
+            vec![
+                TimedEvent {
+                    tick: 0,
+                    event: TrackEvent::Midi(vec![0xB0, 7, 100])
+                },
+                TimedEvent {
+                    tick: 96,
+                    event: TrackEvent::Midi(vec![0xB0, 7, 90])
+                },
+            ]
+        );
+
+        assert!(MidiFile::parse(b"RIFF").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_files() {
+        #[rustfmt::skip]
+        let no_division = [
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 0, 0, 0,
+        ];
+        assert!(MidiFile::parse(&no_division).is_err());
+
+        let with_track = |events: &[u8]| {
+            let mut bytes = vec![b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96];
+            bytes.extend_from_slice(b"MTrk");
+            bytes.extend_from_slice(&(events.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(events);
+            MidiFile::parse(&bytes)
+        };
+
+        // System real-time and common bytes have no place in a track
+        assert!(with_track(&[0x00, 0xF8, 0x00, 0xB0, 7, 100]).is_err());
+
+        // Running status ends at a meta event
+        assert!(with_track(&[0x00, 0xB0, 7, 100, 0x00, 0xFF, 0x06, 0x00, 0x00, 7, 90]).is_err());
+        assert!(with_track(&[0x00, 0xB0, 7, 100, 0x00, 0xB0, 7, 90]).is_ok());
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use midir::{MidiInput, MidiInputConnection};
+use serde::{Deserialize, Serialize};
+
+use crate::midi::engine::OutputHandle;
+
+/// Kinds of MIDI messages a thru route can filter on
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MessageType {
+    NoteOff,
+    NoteOn,
+    PolyPressure,
+    ControlChange,
+    ProgramChange,
+    ChannelPressure,
+    PitchBend,
+    /// System exclusive, clock and other system messages
+    System,
+}
+
+impl MessageType {
+    /// Determine the message type from a status byte
+    pub fn from_status(status: u8) -> Option<Self> {
+        match status & 0xF0 {
+            0x80 => Some(MessageType::NoteOff),
+            0x90 => Some(MessageType::NoteOn),
+            0xA0 => Some(MessageType::PolyPressure),
+            0xB0 => Some(MessageType::ControlChange),
+            0xC0 => Some(MessageType::ProgramChange),
+            0xD0 => Some(MessageType::ChannelPressure),
+            0xE0 => Some(MessageType::PitchBend),
+            0xF0 => Some(MessageType::System),
+            _ => None, // Running status / data bytes
+        }
+    }
+}
+
+/// Filter deciding which messages a thru route passes
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ThruFilter {
+    /// MIDI channels (0-15) to pass; empty passes every channel
+    #[serde(default)]
+    pub channels: Vec<u8>,
+
+    /// Message types to pass; empty passes every type
+    #[serde(default)]
+    pub message_types: Vec<MessageType>,
+
+    /// Inclusive range of CC numbers to pass (only applies to control changes)
+    #[serde(default)]
+    pub cc_range: Option<(u8, u8)>,
+}
+
+impl ThruFilter {
+    /// Check whether a message passes this filter
+    pub fn accepts(&self, message: &[u8]) -> bool {
+        let Some(&status) = message.first() else {
+            return false;
+        };
+        let Some(message_type) = MessageType::from_status(status) else {
+            return false;
+        };
+
+        if !self.message_types.is_empty() && !self.message_types.contains(&message_type) {
+            return false;
+        }
+
+        // System messages have no channel, so the channel filter doesn't apply
+        if message_type != MessageType::System
+            && !self.channels.is_empty()
+            && !self.channels.contains(&(status & 0x0F))
+        {
+            return false;
+        }
+
+        if message_type == MessageType::ControlChange {
+            if let (Some((low, high)), Some(&cc_number)) = (self.cc_range, message.get(1)) {
+                if cc_number < low || cc_number > high {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+/// How thru CCs and scene CCs are merged when both drive the same controller
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum MergePriority {
+    /// Both pass; whichever arrives last wins
+    #[default]
+    LastMessage,
+    /// Thru CCs are dropped for `hold_ms` after a scene sends the same CC
+    Scene { hold_ms: u32 },
+    /// Scene CCs are dropped for `hold_ms` after a thru message moves the same CC
+    Thru { hold_ms: u32 },
+}
+
+/// A thru route from an input device to an output device
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThruRoute {
+    /// Unique route ID
+    pub id: String,
+
+    /// Name of the input port to listen on
+    pub input_device: String,
+
+    /// Name of the output port to forward to
+    pub output_device: String,
+
+    /// Which messages to forward
+    #[serde(default)]
+    pub filter: ThruFilter,
+
+    /// How forwarded CCs compete with scene CCs
+    #[serde(default)]
+    pub priority: MergePriority,
+
+    /// Whether the route is active
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Extract (channel, cc_number) from a control change message
+fn control_change(message: &[u8]) -> Option<(u8, u8)> {
+    match message {
+        [status, cc_number, _, ..] if status & 0xF0 == 0xB0 => Some((status & 0x0F, *cc_number)),
+        _ => None,
+    }
+}
+
+/// Tracks recent scene and thru CC activity to apply merge priorities
+///
+/// Activity is kept per output, so a scene only competes with thru messages
+/// going to the same device.
+#[derive(Default)]
+pub struct MergeState {
+    /// When a scene last sent each (output, channel, cc_number)
+    scene_sent: HashMap<(String, u8, u8), Instant>,
+    /// Scene CCs on (output, channel, cc_number) are blocked until this instant
+    thru_holds: HashMap<(String, u8, u8), Instant>,
+}
+
+impl MergeState {
+    /// Record that a scene sent a CC to an output
+    pub fn record_scene(&mut self, output: &str, channel: u8, cc_number: u8, now: Instant) {
+        self.scene_sent
+            .insert((output.to_string(), channel, cc_number), now);
+    }
+
+    /// Whether a scene CC to an output is currently held off by thru activity
+    pub fn scene_blocked(&self, output: &str, channel: u8, cc_number: u8, now: Instant) -> bool {
+        self.thru_holds
+            .get(&(output.to_string(), channel, cc_number))
+            .map(|until| now < *until)
+            .unwrap_or(false)
+    }
+
+    /// Decide whether a thru message to an output may pass, recording it when it does
+    pub fn allow_thru(
+        &mut self,
+        output: &str,
+        message: &[u8],
+        priority: MergePriority,
+        now: Instant,
+    ) -> bool {
+        let Some((channel, cc_number)) = control_change(message) else {
+            // Only CCs compete with scenes
+            return true;
+        };
+        let key = (output.to_string(), channel, cc_number);
+
+        match priority {
+            MergePriority::LastMessage => true,
+            MergePriority::Scene { hold_ms } => self
+                .scene_sent
+                .get(&key)
+                .map(|sent| now.duration_since(*sent) >= Duration::from_millis(hold_ms as u64))
+                .unwrap_or(true),
+            MergePriority::Thru { hold_ms } => {
+                self.thru_holds
+                    .insert(key, now + Duration::from_millis(hold_ms as u64));
+                true
+            }
+        }
+    }
+}
+
+/// Forwards messages from input devices to engine outputs according to thru routes
+pub struct ThruRouter {
+    connections: Vec<MidiInputConnection<()>>,
+}
+
+impl ThruRouter {
+    /// Create a router with no routes running
+    pub fn new() -> Self {
+        ThruRouter {
+            connections: Vec::new(),
+        }
+    }
+
+    /// Open the inputs used by the enabled routes and start forwarding
+    ///
+    /// Any previously running routes are stopped first. The outputs must already be
+    /// connected to the engine. Routes whose input can't be opened are skipped, so
+    /// one missing device doesn't stop the rest; they are reported in the error.
+    pub fn start(&mut self, routes: &[ThruRoute], outputs: OutputHandle) -> Result<(), String> {
+        self.stop();
+
+        // Group routes by input so each port is only opened once
+        let mut by_input: HashMap<String, Vec<ThruRoute>> = HashMap::new();
+        for route in routes.iter().filter(|r| r.enabled) {
+            by_input
+                .entry(route.input_device.clone())
+                .or_default()
+                .push(route.clone());
+        }
+
+        let mut skipped = Vec::new();
+        for (input_name, input_routes) in by_input {
+            if let Err(e) = self.start_input(input_name, input_routes, outputs.clone()) {
+                skipped.push(e);
+            }
+        }
+
+        if skipped.is_empty() {
+            Ok(())
+        } else {
+            skipped.sort();
+            Err(skipped.join("; "))
+        }
+    }
+
+    /// Open one input and forward its messages along its routes
+    fn start_input(
+        &mut self,
+        input_name: String,
+        input_routes: Vec<ThruRoute>,
+        outputs: OutputHandle,
+    ) -> Result<(), String> {
+        let mut midi_in = MidiInput::new("snap-blaster-thru").map_err(|e| e.to_string())?;
+        midi_in.ignore(midir::Ignore::None);
+
+        let in_ports = midi_in.ports();
+        let in_port = in_ports
+            .iter()
+            .find(|p| {
+                midi_in
+                    .port_name(p)
+                    .map(|name| name == input_name)
+                    .unwrap_or(false)
+            })
+            .ok_or_else(|| format!("Could not find MIDI input device: {}", input_name))?;
+
+        let port_name = input_name.clone();
+        let callback = move |_timestamp, message: &[u8], _: &mut ()| {
+            // Drop echoes of our own output before they can loop back out
+            if !outputs.allow_incoming(&port_name, message) {
+                return;
+            }
+
+            for route in &input_routes {
+                if route.filter.accepts(message) {
+                    let _ = outputs.send_thru(&route.output_device, message, route.priority);
+                }
+            }
+        };
+
+        let connection = midi_in
+            .connect(in_port, "thru-input", callback, ())
+            .map_err(|e| format!("Failed to open MIDI input {}: {}", input_name, e))?;
+        self.connections.push(connection);
+
+        Ok(())
+    }
+
+    /// Stop forwarding and close all inputs
+    pub fn stop(&mut self) {
+        self.connections.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_channels_and_types() {
+        let filter = ThruFilter {
+            channels: vec![0, 9],
+            message_types: vec![MessageType::NoteOn, MessageType::NoteOff],
+            cc_range: None,
+        };
+
+        assert!(filter.accepts(&[0x90, 60, 100]));
+        assert!(filter.accepts(&[0x89, 36, 0]));
+        assert!(!filter.accepts(&[0x91, 60, 100])); // Wrong channel
+        assert!(!filter.accepts(&[0xB0, 1, 64])); // Wrong type
+        assert!(!filter.accepts(&[]));
+    }
+
+    #[test]
+    fn test_filter_cc_range() {
+        let filter = ThruFilter {
+            cc_range: Some((70, 79)),
+            ..Default::default()
+        };
+
+        assert!(filter.accepts(&[0xB3, 74, 10]));
+        assert!(!filter.accepts(&[0xB3, 7, 10]));
+        // The CC range doesn't restrict other message types
+        assert!(filter.accepts(&[0x90, 7, 10]));
+        // Empty filter passes system messages
+        assert!(ThruFilter::default().accepts(&[0xF8]));
+    }
+
+    #[test]
+    fn test_scene_wins_merge() {
+        let mut merge = MergeState::default();
+        let start = Instant::now();
+        let priority = MergePriority::Scene { hold_ms: 500 };
+
+        merge.record_scene("Synth", 0, 74, start);
+
+        let soon = start + Duration::from_millis(100);
+        assert!(!merge.allow_thru("Synth", &[0xB0, 74, 1], priority, soon));
+        assert!(merge.allow_thru(
+            "Synth",
+            &[0xB0, 74, 1],
+            priority,
+            start + Duration::from_millis(600)
+        ));
+        // Other CCs, other outputs and non-CC messages are unaffected
+        assert!(merge.allow_thru("Synth", &[0xB0, 75, 1], priority, start));
+        assert!(merge.allow_thru("Drums", &[0xB0, 74, 1], priority, soon));
+        assert!(merge.allow_thru("Synth", &[0x90, 74, 1], priority, start));
+    }
+
+    #[test]
+    fn test_thru_wins_merge() {
+        let mut merge = MergeState::default();
+        let start = Instant::now();
+        let priority = MergePriority::Thru { hold_ms: 500 };
+
+        assert!(merge.allow_thru("Synth", &[0xB2, 10, 64], priority, start));
+        assert!(merge.scene_blocked("Synth", 2, 10, start + Duration::from_millis(100)));
+        assert!(!merge.scene_blocked("Synth", 2, 10, start + Duration::from_millis(600)));
+        assert!(!merge.scene_blocked("Synth", 2, 11, start));
+        assert!(!merge.scene_blocked("Drums", 2, 10, start));
+    }
+}
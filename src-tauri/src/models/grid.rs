@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+
+/// Dimensions of a pad grid
+///
+/// Positions are numbered row by row from the bottom-left, so position
+/// `row * columns + column` is in row `row` counting up from the bottom.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SavedGridSize")]
+pub struct GridSize {
+    pub rows: u8,
+    pub columns: u8,
+}
+
+/// A grid size as saved, checked by `GridSize::new` before it is used
+#[derive(Deserialize)]
+struct SavedGridSize {
+    rows: u8,
+    columns: u8,
+}
+
+impl TryFrom<SavedGridSize> for GridSize {
+    type Error = String;
+
+    fn try_from(saved: SavedGridSize) -> Result<Self, Self::Error> {
+        GridSize::new(saved.rows, saved.columns)
+    }
+}
+
+impl GridSize {
+    /// Largest number of cells a grid can have, so every position fits in a u8
+    pub const MAX_CELLS: u16 = 256;
+
+    /// Largest number of rows or columns
+    pub const MAX_SIDE: u8 = 16;
+
+    /// Create a grid size, checking it is within the supported range
+    pub fn new(rows: u8, columns: u8) -> Result<Self, String> {
+        if !(1..=Self::MAX_SIDE).contains(&rows) || !(1..=Self::MAX_SIDE).contains(&columns) {
+            return Err(format!(
+                "Grid rows and columns must be between 1 and {}",
+                Self::MAX_SIDE
+            ));
+        }
+        if rows as u16 * columns as u16 > Self::MAX_CELLS {
+            return Err(format!(
+                "A grid can have at most {} positions",
+                Self::MAX_CELLS
+            ));
+        }
+
+        Ok(GridSize { rows, columns })
+    }
+
+    /// Size of a controller's pad grid
+    ///
+    /// Controllers may have more pads than a project has positions, like a
+    /// 16x16 monome grid, as long as each pad's number fits in a u8.
+    pub fn controller(rows: u8, columns: u8) -> Result<Self, String> {
+        if !(1..=Self::MAX_SIDE).contains(&rows) || !(1..=Self::MAX_SIDE).contains(&columns) {
+            return Err(format!(
+                "Grid rows and columns must be between 1 and {}",
+                Self::MAX_SIDE
+            ));
+        }
+
+        Ok(GridSize { rows, columns })
+    }
+
+    /// Number of positions in the grid
+    pub fn cells(&self) -> u16 {
+        self.rows as u16 * self.columns as u16
+    }
+
+    /// Whether a position is inside the grid
+    pub fn contains(&self, position: u8) -> bool {
+        (position as u16) < self.cells()
+    }
+
+    /// Position of a cell, if it is inside the grid
+    pub fn position(&self, row: u8, column: u8) -> Option<u8> {
+        (row < self.rows && column < self.columns).then(|| row * self.columns + column)
+    }
+
+    /// Row and column of a position, if it is inside the grid
+    pub fn coordinates(&self, position: u8) -> Option<(u8, u8)> {
+        self.contains(position)
+            .then(|| (position / self.columns, position % self.columns))
+    }
+
+    /// Every position in the grid, in order
+    pub fn positions(&self) -> impl Iterator<Item = u8> {
+        (0..self.cells()).map(|position| position as u8)
+    }
+}
+
+impl Default for GridSize {
+    fn default() -> Self {
+        GridSize {
+            rows: 8,
+            columns: 8,
+        }
+    }
+}
+
+/// Part of a project's grid shown on a controller with fewer pads
+///
+/// The offset is the project cell under the controller's bottom-left pad.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Viewport {
+    pub row: u8,
+    pub column: u8,
+}
+
+impl Viewport {
+    /// Project position under a controller pad, if the pad is over the project grid
+    pub fn project_position(&self, pad: u8, controller: GridSize, layout: GridSize) -> Option<u8> {
+        let (row, column) = controller.coordinates(pad)?;
+        layout.position(self.row + row, self.column + column)
+    }
+
+    /// Move by whole rows and columns, stopping at the edges of the layout
+    ///
+    /// Returns whether the viewport moved.
+    pub fn scroll(
+        &mut self,
+        rows: i8,
+        columns: i8,
+        controller: GridSize,
+        layout: GridSize,
+    ) -> bool {
+        let max_row = layout.rows.saturating_sub(controller.rows) as i16;
+        let max_column = layout.columns.saturating_sub(controller.columns) as i16;
+
+        let moved = Viewport {
+            row: (self.row as i16 + rows as i16).clamp(0, max_row) as u8,
+            column: (self.column as i16 + columns as i16).clamp(0, max_column) as u8,
+        };
+
+        let changed = moved != *self;
+        *self = moved;
+        changed
+    }
+
+    /// Pull the viewport back inside the layout, e.g. after the layout shrinks
+    pub fn clamp(&mut self, controller: GridSize, layout: GridSize) {
+        self.scroll(0, 0, controller, layout);
+    }
+}
+
+/// Size and position of a connected controller on a project's grid
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControllerView {
+    pub device_id: String,
+    pub size: GridSize,
+    pub viewport: Viewport,
+    /// Bank shown on the controller's pads
+    pub bank: u8,
+    /// Whether the pads show virtual faders instead of scenes
+    pub fader_mode: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_size() {
+        let grid = GridSize::new(4, 16).unwrap();
+
+        assert_eq!(grid.cells(), 64);
+        assert_eq!(grid.position(1, 3), Some(19));
+        assert_eq!(grid.coordinates(19), Some((1, 3)));
+        assert_eq!(grid.position(4, 0), None);
+        assert!(!grid.contains(64));
+
+        assert!(GridSize::new(16, 8).is_ok());
+        assert!(GridSize::new(0, 8).is_err());
+        assert!(GridSize::new(16, 17).is_err());
+
+        // A 16x16 layout uses every position a u8 can hold
+        let full = GridSize::new(16, 16).unwrap();
+        assert_eq!(full.cells(), 256);
+        assert_eq!(full.position(15, 15), Some(255));
+        assert!(full.contains(255));
+
+        // Controllers can be larger than a project's grid
+        let monome = GridSize::controller(16, 16).unwrap();
+        assert_eq!(monome.positions().count(), 256);
+        assert_eq!(monome.coordinates(255), Some((15, 15)));
+        assert!(GridSize::controller(17, 8).is_err());
+    }
+
+    #[test]
+    fn test_saved_grid_size_is_checked() {
+        let grid: GridSize = serde_json::from_str(r#"{"rows":4,"columns":16}"#).unwrap();
+        assert_eq!(grid, GridSize::new(4, 16).unwrap());
+
+        assert!(serde_json::from_str::<GridSize>(r#"{"rows":0,"columns":0}"#).is_err());
+        assert!(serde_json::from_str::<GridSize>(r#"{"rows":20,"columns":20}"#).is_err());
+    }
+
+    #[test]
+    fn test_viewport_mapping() {
+        let controller = GridSize::new(4, 4).unwrap();
+        let layout = GridSize::new(8, 8).unwrap();
+        let viewport = Viewport { row: 4, column: 2 };
+
+        // The controller's bottom-left pad shows row 4, column 2
+        assert_eq!(viewport.project_position(0, controller, layout), Some(34));
+        assert_eq!(viewport.project_position(15, controller, layout), Some(61));
+
+        // A controller larger than the layout leaves the extra pads unused
+        let origin = Viewport::default();
+        assert_eq!(origin.project_position(4, layout, controller), None);
+        assert_eq!(origin.project_position(8, layout, controller), Some(4));
+    }
+
+    #[test]
+    fn test_viewport_scroll() {
+        let controller = GridSize::new(4, 16).unwrap();
+        let layout = GridSize::new(8, 16).unwrap();
+        let mut viewport = Viewport::default();
+
+        assert!(viewport.scroll(1, 0, controller, layout));
+        assert!(viewport.scroll(10, 10, controller, layout));
+        assert_eq!(viewport, Viewport { row: 4, column: 0 });
+        assert!(!viewport.scroll(1, 1, controller, layout));
+
+        viewport.clamp(controller, GridSize::new(6, 16).unwrap());
+        assert_eq!(viewport, Viewport { row: 2, column: 0 });
+    }
+}
@@ -1,3 +1,4 @@
 pub mod cc;
+pub mod grid;
 pub mod project;
 pub mod scene;
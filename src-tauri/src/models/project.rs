@@ -1,8 +1,12 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::midi::learn::{LearnAction, MidiMapping};
+use crate::midi::loop_guard::EchoWindow;
+use crate::midi::thru::ThruRoute;
 use crate::models::cc::CCValue;
+use crate::models::grid::GridSize;
 use crate::models::scene::Scene;
 
 /// Metadata for a CC definition within a project
@@ -75,16 +79,25 @@ impl CCDefinition {
 /// Global project settings
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ProjectSettings {
-    /// Default MIDI output device
-    #[serde(default)]
-    pub default_output_device: Option<String>,
-
-    /// Default controller device
-    #[serde(default)]
-    pub default_controller_device: Option<String>,
+    /// Names of the MIDI output ports scenes were sent to, connected when the
+    /// project auto-connects
+    #[serde(
+        default,
+        alias = "default_output_device",
+        deserialize_with = "deserialize_device_list"
+    )]
+    pub default_output_devices: Vec<String>,
+
+    /// IDs of the controller devices connected with the project
+    #[serde(
+        default,
+        alias = "default_controller_device",
+        deserialize_with = "deserialize_device_list"
+    )]
+    pub default_controller_devices: Vec<String>,
 
     /// Whether to auto-connect to the last used devices on startup
-    #[serde(default)]
+    #[serde(default = "default_auto_connect")]
     pub auto_connect: bool,
 
     /// Default tempo when not synced to Link
@@ -98,25 +111,181 @@ pub struct ProjectSettings {
     /// Default quantization (in beats) for scene transitions
     #[serde(default)]
     pub default_quantization: Option<u8>,
+
+    /// Controller button ID that triggers a MIDI panic
+    #[serde(default)]
+    pub panic_button: Option<u8>,
+
+    /// Whether a panic also sends every CC definition's default value
+    #[serde(default)]
+    pub panic_sends_defaults: bool,
+
+    /// Controller button ID held for editing the grid from the pads
+    #[serde(default)]
+    pub shift_button: Option<u8>,
+
+    /// Controller button ID that switches the grid into fader mode
+    #[serde(default)]
+    pub fader_mode_button: Option<u8>,
+
+    /// Controller button ID that flashes on each beat
+    #[serde(default)]
+    pub beat_indicator_button: Option<u8>,
+
+    /// Echo-suppression windows for device pairs that can loop, e.g. through a
+    /// hardware mixer
+    #[serde(default)]
+    pub echo_windows: Vec<EchoWindow>,
+}
+
+/// Jobs a spare controller button can be given in the project settings
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ControllerButton {
+    /// Sends a MIDI panic
+    Panic,
+    /// Turns the grid into faders and back
+    FaderMode,
+    /// Held to move, copy and clear scenes on the grid
+    Shift,
+    /// Flashes on each beat
+    BeatIndicator,
+}
+
+impl ProjectSettings {
+    /// Button ID given a job, if any
+    pub fn button_mut(&mut self, button: ControllerButton) -> &mut Option<u8> {
+        match button {
+            ControllerButton::Panic => &mut self.panic_button,
+            ControllerButton::FaderMode => &mut self.fader_mode_button,
+            ControllerButton::Shift => &mut self.shift_button,
+            ControllerButton::BeatIndicator => &mut self.beat_indicator_button,
+        }
+    }
+}
+
+/// Read a list of devices, accepting the single device, or none, saved before
+/// projects could remember several
+fn deserialize_device_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Devices {
+        List(Vec<String>),
+        Single(Option<String>),
+    }
+
+    Ok(match Devices::deserialize(deserializer)? {
+        Devices::List(devices) => devices,
+        Devices::Single(device) => device.into_iter().collect(),
+    })
 }
 
 fn default_tempo() -> f64 {
     120.0
 }
 
+fn default_auto_connect() -> bool {
+    true
+}
+
 impl Default for ProjectSettings {
     fn default() -> Self {
         ProjectSettings {
-            default_output_device: None,
-            default_controller_device: None,
-            auto_connect: false,
+            default_output_devices: Vec::new(),
+            default_controller_devices: Vec::new(),
+            auto_connect: default_auto_connect(),
             default_tempo: default_tempo(),
             use_link: false,
             default_quantization: None,
+            panic_button: None,
+            panic_sends_defaults: false,
+            shift_button: None,
+            fader_mode_button: None,
+            beat_indicator_button: None,
+            echo_windows: Vec::new(),
+        }
+    }
+}
+
+/// A page of grid assignments, switched from the controller's bank buttons
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SceneBank {
+    /// Display name
+    #[serde(default)]
+    pub name: String,
+
+    /// Grid assignments (maps grid position to scene id)
+    #[serde(default)]
+    pub grid_assignments: HashMap<u8, String>,
+}
+
+impl SceneBank {
+    /// Create an empty bank
+    pub fn new(name: &str) -> Self {
+        SceneBank {
+            name: name.to_string(),
+            grid_assignments: HashMap::new(),
         }
     }
 }
 
+fn default_banks() -> Vec<SceneBank> {
+    vec![SceneBank::new("Bank 1")]
+}
+
+/// Read scene banks, accepting the single grid of projects saved before banks
+/// existed as the first bank
+fn deserialize_banks<'de, D>(deserializer: D) -> Result<Vec<SceneBank>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    /// A bank as saved, with its grid positions still as strings
+    #[derive(Deserialize)]
+    struct SavedBank {
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        grid_assignments: HashMap<String, String>,
+    }
+
+    // Keys are read as strings, since untagged enums buffer map keys
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Banks {
+        Banks(Vec<SavedBank>),
+        Grid(HashMap<String, String>),
+    }
+
+    let positions = |grid: HashMap<String, String>| -> HashMap<u8, String> {
+        grid.into_iter()
+            .filter_map(|(position, id)| Some((position.parse().ok()?, id)))
+            .collect()
+    };
+
+    let banks = match Banks::deserialize(deserializer)? {
+        Banks::Banks(banks) => banks
+            .into_iter()
+            .map(|bank| SceneBank {
+                name: bank.name,
+                grid_assignments: positions(bank.grid_assignments),
+            })
+            .collect(),
+        Banks::Grid(grid) => {
+            let mut bank = SceneBank::new("Bank 1");
+            bank.grid_assignments = positions(grid);
+            vec![bank]
+        }
+    };
+
+    Ok(if banks.is_empty() {
+        default_banks()
+    } else {
+        banks
+    })
+}
+
 /// Complete project containing scenes and settings
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Project {
@@ -153,11 +322,42 @@ pub struct Project {
     /// Scenes in this project
     pub scenes: HashMap<String, Scene>,
 
-    /// Grid assignments (maps grid position to scene id)
-    pub grid_assignments: HashMap<u8, String>,
+    /// Dimensions of the scene grid
+    #[serde(default)]
+    pub grid_size: GridSize,
+
+    /// Banks of grid assignments; there is always at least one
+    #[serde(
+        default = "default_banks",
+        alias = "grid_assignments",
+        deserialize_with = "deserialize_banks"
+    )]
+    pub banks: Vec<SceneBank>,
+
+    /// MIDI thru routes from inputs to outputs
+    #[serde(default)]
+    pub thru_routes: Vec<ThruRoute>,
+
+    /// CC definition keys ("channel:cc") controlled by each fader in fader mode
+    #[serde(default)]
+    pub fader_bindings: Vec<String>,
+
+    /// Names of exclusive groups formed by whole grid columns, by column
+    #[serde(default)]
+    pub column_groups: HashMap<u8, String>,
+
+    /// Incoming MIDI messages bound to app actions by MIDI Learn
+    #[serde(default)]
+    pub midi_mappings: Vec<MidiMapping>,
 }
 
 impl Project {
+    /// Most banks a project can hold
+    pub const MAX_BANKS: usize = 16;
+
+    /// Most CCs that fader mode can bind, one per grid column
+    pub const MAX_FADERS: usize = 8;
+
     /// Create a new project
     pub fn new(name: &str, author: Option<&str>) -> Self {
         let now = chrono::Utc::now().to_rfc3339();
@@ -173,7 +373,12 @@ impl Project {
             settings: ProjectSettings::default(),
             cc_definitions: HashMap::new(),
             scenes: HashMap::new(),
-            grid_assignments: HashMap::new(),
+            grid_size: GridSize::default(),
+            banks: default_banks(),
+            thru_routes: Vec::new(),
+            fader_bindings: Vec::new(),
+            column_groups: HashMap::new(),
+            midi_mappings: Vec::new(),
         }
     }
 
@@ -190,6 +395,38 @@ impl Project {
         self.cc_definitions.get(&key)
     }
 
+    /// Bind faders to CC definitions by key, in fader order; an empty key
+    /// leaves that fader unbound
+    pub fn set_fader_bindings(&mut self, keys: Vec<String>) -> Result<(), String> {
+        if keys.len() > Self::MAX_FADERS {
+            return Err(format!("At most {} faders can be bound", Self::MAX_FADERS));
+        }
+        if let Some(key) = keys
+            .iter()
+            .find(|k| !k.is_empty() && !self.cc_definitions.contains_key(*k))
+        {
+            return Err(format!("No CC definition for {}", key));
+        }
+
+        self.fader_bindings = keys;
+        Ok(())
+    }
+
+    /// CC definition bound to a fader
+    pub fn fader_definition(&self, fader: usize) -> Option<&CCDefinition> {
+        self.fader_bindings
+            .get(fader)
+            .and_then(|key| self.cc_definitions.get(key))
+    }
+
+    /// Get the default value of every CC definition as CC values
+    pub fn default_cc_values(&self) -> Vec<CCValue> {
+        self.cc_definitions
+            .values()
+            .map(|definition| definition.create_cc_value(None))
+            .collect()
+    }
+
     /// Add a scene
     pub fn add_scene(&mut self, scene: Scene) -> &mut Self {
         self.scenes.insert(scene.id.clone(), scene);
@@ -209,16 +446,30 @@ impl Project {
     /// Remove a scene
     pub fn remove_scene(&mut self, id: &str) -> Option<Scene> {
         // Remove from grid assignments first
-        self.grid_assignments.retain(|_, scene_id| scene_id != id);
+        for bank in &mut self.banks {
+            bank.grid_assignments.retain(|_, scene_id| scene_id != id);
+        }
+
+        // Mappings can't trigger it any more
+        self.midi_mappings.retain(|mapping| {
+            !matches!(&mapping.action, LearnAction::TriggerScene { scene_id } if scene_id == id)
+        });
 
         // Remove the scene
         self.scenes.remove(id)
     }
 
-    /// Assign a scene to a grid position
-    pub fn assign_to_grid(&mut self, scene_id: &str, position: u8) -> Result<(), String> {
-        if position >= 64 {
-            return Err("Grid position must be between 0 and 63".to_string());
+    /// Assign a scene to a grid position in a bank
+    pub fn assign_to_grid(&mut self, scene_id: &str, bank: u8, position: u8) -> Result<(), String> {
+        if bank as usize >= self.banks.len() {
+            return Err(format!("Bank {} does not exist", bank));
+        }
+
+        if !self.grid_size.contains(position) {
+            return Err(format!(
+                "Grid position must be between 0 and {}",
+                self.grid_size.cells() - 1
+            ));
         }
 
         if !self.scenes.contains_key(scene_id) {
@@ -231,22 +482,162 @@ impl Project {
         }
 
         // Update the grid assignment
-        self.grid_assignments.insert(position, scene_id.to_string());
+        self.banks[bank as usize]
+            .grid_assignments
+            .insert(position, scene_id.to_string());
 
         Ok(())
     }
 
-    /// Get the scene assigned to a grid position
-    pub fn get_scene_at_grid(&self, position: u8) -> Option<&Scene> {
-        if position >= 64 {
+    /// Clear a grid position in a bank, returning the ID of the scene that was
+    /// there; the scene stays in the project
+    pub fn unassign_from_grid(&mut self, bank: u8, position: u8) -> Option<String> {
+        let scene_id = self
+            .banks
+            .get_mut(bank as usize)?
+            .grid_assignments
+            .remove(&position)?;
+
+        if let Some(scene) = self.scenes.get_mut(&scene_id) {
+            if scene.grid_position == Some(position) {
+                scene.grid_position = None;
+            }
+        }
+
+        Some(scene_id)
+    }
+
+    /// Get the scene assigned to a grid position in a bank
+    pub fn get_scene_at_grid(&self, bank: u8, position: u8) -> Option<&Scene> {
+        if !self.grid_size.contains(position) {
             return None;
         }
 
-        self.grid_assignments
+        self.banks
+            .get(bank as usize)?
+            .grid_assignments
             .get(&position)
             .and_then(|id| self.scenes.get(id))
     }
 
+    /// Name a grid column as an exclusive group, or clear it with None
+    pub fn set_column_group(&mut self, column: u8, name: Option<&str>) -> Result<(), String> {
+        if column >= self.grid_size.columns {
+            return Err(format!(
+                "Column must be between 0 and {}",
+                self.grid_size.columns - 1
+            ));
+        }
+
+        match name.map(str::trim).filter(|name| !name.is_empty()) {
+            Some(name) => self.column_groups.insert(column, name.to_string()),
+            None => self.column_groups.remove(&column),
+        };
+        Ok(())
+    }
+
+    /// Exclusive group a scene plays in when triggered from a grid position
+    ///
+    /// The scene's own group wins over its column's group; without a position
+    /// the scene's last assigned position is used.
+    pub fn scene_group(&self, scene: &Scene, position: Option<u8>) -> Option<String> {
+        if scene.group.is_some() {
+            return scene.group.clone();
+        }
+
+        let (_, column) = self
+            .grid_size
+            .coordinates(position.or(scene.grid_position)?)?;
+        self.column_groups.get(&column).cloned()
+    }
+
+    /// Add an empty bank and return its index
+    pub fn add_bank(&mut self, name: &str) -> Result<u8, String> {
+        if self.banks.len() >= Self::MAX_BANKS {
+            return Err(format!(
+                "A project can have at most {} banks",
+                Self::MAX_BANKS
+            ));
+        }
+
+        self.banks.push(SceneBank::new(name));
+        Ok((self.banks.len() - 1) as u8)
+    }
+
+    /// Remove a bank; scenes assigned in it stay in the project
+    pub fn remove_bank(&mut self, bank: u8) -> Result<SceneBank, String> {
+        if self.banks.len() <= 1 {
+            return Err("A project must keep at least one bank".to_string());
+        }
+        if bank as usize >= self.banks.len() {
+            return Err(format!("Bank {} does not exist", bank));
+        }
+
+        Ok(self.banks.remove(bank as usize))
+    }
+
+    /// Change the grid dimensions, keeping each scene in the same row and column
+    ///
+    /// Scenes that no longer fit are unassigned; their IDs are returned.
+    pub fn set_grid_size(&mut self, size: GridSize) -> Vec<String> {
+        let old_size = self.grid_size;
+        let mut unassigned = Vec::new();
+
+        for bank in &mut self.banks {
+            let assignments = std::mem::take(&mut bank.grid_assignments);
+            for (position, scene_id) in assignments {
+                let moved = old_size
+                    .coordinates(position)
+                    .and_then(|(row, column)| size.position(row, column));
+
+                if let Some(scene) = self.scenes.get_mut(&scene_id) {
+                    scene.grid_position = moved;
+                }
+                match moved {
+                    Some(moved) => {
+                        bank.grid_assignments.insert(moved, scene_id);
+                    }
+                    None => unassigned.push(scene_id),
+                }
+            }
+        }
+
+        self.column_groups
+            .retain(|&column, _| column < size.columns);
+        self.grid_size = size;
+        unassigned
+    }
+
+    /// Add a thru route, replacing any existing route with the same ID
+    pub fn add_thru_route(&mut self, route: ThruRoute) -> &mut Self {
+        self.thru_routes.retain(|r| r.id != route.id);
+        self.thru_routes.push(route);
+        self
+    }
+
+    /// Remove a thru route
+    pub fn remove_thru_route(&mut self, id: &str) -> Option<ThruRoute> {
+        let index = self.thru_routes.iter().position(|r| r.id == id)?;
+        Some(self.thru_routes.remove(index))
+    }
+
+    /// Add a MIDI mapping, replacing any with the same ID or for the same message
+    /// from the same input
+    pub fn add_midi_mapping(&mut self, mapping: MidiMapping) -> &mut Self {
+        self.midi_mappings.retain(|m| {
+            m.id != mapping.id
+                && (m.trigger != mapping.trigger || m.input_device != mapping.input_device)
+        });
+        self.midi_mappings.push(mapping);
+        self
+    }
+
+    /// Remove a MIDI mapping
+    pub fn remove_midi_mapping(&mut self, id: &str) -> Option<MidiMapping> {
+        let index = self.midi_mappings.iter().position(|m| m.id == id)?;
+        Some(self.midi_mappings.remove(index))
+    }
+
     /// Update the last modified timestamp
     pub fn update_timestamp(&mut self) {
         self.updated_at = chrono::Utc::now().to_rfc3339();
@@ -296,20 +687,158 @@ mod tests {
         let scene = Scene::new("scene-1", "Test Scene");
         project.add_scene(scene);
 
-        let result = project.assign_to_grid("scene-1", 5);
+        let result = project.assign_to_grid("scene-1", 0, 5);
         assert!(result.is_ok());
 
-        let scene_at_grid = project.get_scene_at_grid(5);
+        let scene_at_grid = project.get_scene_at_grid(0, 5);
         assert!(scene_at_grid.is_some());
         assert_eq!(scene_at_grid.unwrap().id, "scene-1");
 
         // Test invalid position
-        let result = project.assign_to_grid("scene-1", 100);
+        let result = project.assign_to_grid("scene-1", 0, 100);
         assert!(result.is_err());
 
         // Test invalid scene ID
-        let result = project.assign_to_grid("non-existent", 10);
+        let result = project.assign_to_grid("non-existent", 0, 10);
         assert!(result.is_err());
+
+        // Test invalid bank
+        let result = project.assign_to_grid("scene-1", 1, 10);
+        assert!(result.is_err());
+
+        assert_eq!(
+            project.unassign_from_grid(0, 5),
+            Some("scene-1".to_string())
+        );
+        assert!(project.get_scene_at_grid(0, 5).is_none());
+        assert_eq!(project.get_scene("scene-1").unwrap().grid_position, None);
+        assert_eq!(project.unassign_from_grid(0, 5), None);
+    }
+
+    #[test]
+    fn test_scene_groups() {
+        let mut project = Project::new("Test Project", None);
+        project.add_scene(Scene::new("filter", "Filter"));
+        project.add_scene(Scene::new("drums", "Drums").with_group("drums fx"));
+        // Column 2 of the default 8x8 grid
+        project.assign_to_grid("filter", 0, 10).unwrap();
+
+        assert!(project.set_column_group(2, Some("bass filter")).is_ok());
+        assert!(project.set_column_group(8, Some("nowhere")).is_err());
+
+        let filter = project.get_scene("filter").unwrap();
+        assert_eq!(
+            project.scene_group(filter, None).as_deref(),
+            Some("bass filter")
+        );
+        assert_eq!(project.scene_group(filter, Some(11)), None);
+
+        // A scene's own group wins over its column's
+        let drums = project.get_scene("drums").unwrap();
+        assert_eq!(
+            project.scene_group(drums, Some(2)).as_deref(),
+            Some("drums fx")
+        );
+
+        assert!(project.set_column_group(2, Some("  ")).is_ok());
+        assert!(project.column_groups.is_empty());
+    }
+
+    #[test]
+    fn test_set_grid_size() {
+        let mut project = Project::new("Test Project", None);
+        project.add_scene(Scene::new("low", "Low"));
+        project.add_scene(Scene::new("high", "High"));
+
+        // Row 1, column 2 and row 6, column 7 of the default 8x8 grid
+        project.assign_to_grid("low", 0, 10).unwrap();
+        project.assign_to_grid("high", 0, 55).unwrap();
+
+        let unassigned = project.set_grid_size(GridSize::new(4, 16).unwrap());
+
+        assert_eq!(unassigned, vec!["high".to_string()]);
+        assert_eq!(project.get_scene_at_grid(0, 18).unwrap().id, "low");
+        assert_eq!(project.get_scene("low").unwrap().grid_position, Some(18));
+        assert_eq!(project.get_scene("high").unwrap().grid_position, None);
+        assert_eq!(project.banks[0].grid_assignments.len(), 1);
+
+        // Positions past 64 are valid on a wider grid
+        assert!(project.assign_to_grid("high", 0, 63).is_ok());
+        assert!(project.assign_to_grid("high", 0, 64).is_err());
+    }
+
+    #[test]
+    fn test_scene_banks() {
+        let mut project = Project::new("Test Project", None);
+        project.add_scene(Scene::new("scene-1", "Test Scene"));
+
+        let bank = project.add_bank("Breakdowns").unwrap();
+        assert_eq!(bank, 1);
+
+        project.assign_to_grid("scene-1", bank, 3).unwrap();
+        assert!(project.get_scene_at_grid(0, 3).is_none());
+        assert_eq!(project.get_scene_at_grid(1, 3).unwrap().id, "scene-1");
+
+        // Removing a scene clears it from every bank
+        project.remove_scene("scene-1");
+        assert!(project.banks[1].grid_assignments.is_empty());
+
+        assert!(project.remove_bank(1).is_ok());
+        assert!(project.remove_bank(0).is_err());
+    }
+
+    #[test]
+    fn test_scene_banks_round_trip() {
+        let mut project = Project::new("Test Project", None);
+        project.add_scene(Scene::new("scene-1", "Test Scene"));
+        let bank = project.add_bank("Breakdowns").unwrap();
+        project.assign_to_grid("scene-1", bank, 3).unwrap();
+
+        let json = serde_json::to_string(&project).unwrap();
+        let project: Project = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(project.banks[1].name, "Breakdowns");
+        assert_eq!(project.get_scene_at_grid(1, 3).unwrap().id, "scene-1");
+    }
+
+    #[test]
+    fn test_legacy_grid_assignments() {
+        let mut json = serde_json::to_value(Project::new("Old Project", None)).unwrap();
+        let fields = json.as_object_mut().unwrap();
+        fields.remove("banks");
+        fields.insert(
+            "grid_assignments".to_string(),
+            serde_json::json!({ "5": "scene-1" }),
+        );
+
+        let project: Project = serde_json::from_value(json).unwrap();
+
+        assert_eq!(project.banks.len(), 1);
+        assert_eq!(
+            project.banks[0].grid_assignments.get(&5).unwrap(),
+            "scene-1"
+        );
+    }
+
+    #[test]
+    fn test_legacy_default_devices() {
+        let mut json = serde_json::to_value(Project::new("Old Project", None)).unwrap();
+        let settings = json["settings"].as_object_mut().unwrap();
+        settings.remove("default_output_devices");
+        settings.remove("default_controller_devices");
+        settings.insert(
+            "default_output_device".to_string(),
+            serde_json::json!("Synth"),
+        );
+        settings.insert(
+            "default_controller_device".to_string(),
+            serde_json::Value::Null,
+        );
+
+        let project: Project = serde_json::from_value(json).unwrap();
+
+        assert_eq!(project.settings.default_output_devices, vec!["Synth"]);
+        assert!(project.settings.default_controller_devices.is_empty());
     }
 
     #[test]
@@ -330,4 +859,51 @@ mod tests {
         assert_eq!(cc_value.value, 64);
         assert_eq!(cc_value.name, Some("Test CC".to_string()));
     }
+
+    #[test]
+    fn test_fader_bindings() {
+        let mut project = Project::new("Test Project", None);
+        project.add_cc_definition(CCDefinition::new(0, 74, "Cutoff"));
+
+        assert!(project.set_fader_bindings(vec!["0:74".to_string()]).is_ok());
+        assert_eq!(project.fader_definition(0).unwrap().name, "Cutoff");
+        assert!(project.fader_definition(1).is_none());
+
+        // Gaps leave faders unbound
+        assert!(project
+            .set_fader_bindings(vec![String::new(), "0:74".to_string()])
+            .is_ok());
+        assert!(project.fader_definition(0).is_none());
+        assert_eq!(project.fader_definition(1).unwrap().name, "Cutoff");
+
+        assert!(project
+            .set_fader_bindings(vec!["0:75".to_string()])
+            .is_err());
+        assert!(project
+            .set_fader_bindings(vec!["0:74".to_string(); Project::MAX_FADERS + 1])
+            .is_err());
+        assert_eq!(
+            project.fader_bindings,
+            vec![String::new(), "0:74".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_default_cc_values() {
+        let mut project = Project::new("Test Project", None);
+
+        let mut volume = CCDefinition::new(0, 7, "Volume");
+        volume.default_value = 100;
+        project.add_cc_definition(volume);
+        project.add_cc_definition(CCDefinition::new(1, 74, "Cutoff"));
+
+        let mut defaults = project.default_cc_values();
+        defaults.sort_by_key(|cc| (cc.channel, cc.cc_number));
+
+        assert_eq!(defaults.len(), 2);
+        assert_eq!((defaults[0].channel, defaults[0].cc_number), (0, 7));
+        assert_eq!(defaults[0].value, 100);
+        assert_eq!((defaults[1].channel, defaults[1].cc_number), (1, 74));
+        assert_eq!(defaults[1].value, 0);
+    }
 }
@@ -1,4 +1,5 @@
-use crate::models::cc::CCValue;
+use crate::midi::engine;
+use crate::models::cc::{CCValue, TransitionCurve};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -21,6 +22,66 @@ impl Default for TriggerMode {
     }
 }
 
+/// How a scene's pad behaves when pressed and released
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum PadMode {
+    /// Pressing the pad activates the scene, releasing it does nothing
+    #[default]
+    Latch,
+    /// The scene is applied while the pad is held and undone on release
+    Momentary,
+    /// Each press switches between the scene and what was there before
+    Toggle,
+}
+
+/// How a pad's velocity or pressure scales how far a scene is applied
+///
+/// The pad's input is shaped by the curve and mapped onto the range, where 0.0
+/// leaves the CCs where they were and 1.0 applies the scene's values in full.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ExpressionRange {
+    /// Response to the pad's input
+    #[serde(default)]
+    pub curve: TransitionCurve,
+
+    /// Amount of the scene applied at the lightest touch
+    #[serde(default)]
+    pub min: f64,
+
+    /// Amount of the scene applied at the hardest touch
+    #[serde(default = "ExpressionRange::full")]
+    pub max: f64,
+}
+
+impl ExpressionRange {
+    fn full() -> f64 {
+        1.0
+    }
+
+    /// Whether both ends of the range are amounts between none and all of the
+    /// scene
+    pub fn is_valid(&self) -> bool {
+        (0.0..=1.0).contains(&self.min) && (0.0..=1.0).contains(&self.max)
+    }
+
+    /// Amount of the scene to apply for a velocity or pressure value
+    pub fn amount(&self, input: u8) -> f64 {
+        let position =
+            engine::TransitionCurve::from(self.curve).apply(input.min(127) as f64 / 127.0);
+        (self.min + (self.max - self.min) * position).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for ExpressionRange {
+    fn default() -> Self {
+        ExpressionRange {
+            curve: TransitionCurve::Linear,
+            min: 0.0,
+            max: 1.0,
+        }
+    }
+}
+
 /// A scene containing a collection of CC values
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Scene {
@@ -38,6 +99,30 @@ pub struct Scene {
     #[serde(default)]
     pub trigger_mode: TriggerMode,
 
+    /// How the scene's pad responds to presses and releases
+    #[serde(default)]
+    pub pad_mode: PadMode,
+
+    /// Time taken to return to the previous values when a momentary or toggled
+    /// scene is released, or None to return immediately
+    #[serde(default)]
+    pub release_transition_ms: Option<u32>,
+
+    /// How pad velocity scales the scene, or None to always apply it in full
+    #[serde(default)]
+    pub velocity: Option<ExpressionRange>,
+
+    /// How pad pressure morphs toward the scene while its pad is held, or None to
+    /// ignore pressure
+    #[serde(default)]
+    pub pressure: Option<ExpressionRange>,
+
+    /// Exclusive group the scene plays in, where triggering it replaces only the
+    /// group's other scenes; None falls back to the project's group for the
+    /// scene's grid column
+    #[serde(default)]
+    pub group: Option<String>,
+
     /// CC values in this scene
     pub cc_values: HashMap<String, CCValue>,
 
@@ -53,7 +138,7 @@ pub struct Scene {
     #[serde(default)]
     pub favorite: bool,
 
-    /// The grid position (0-63) where this scene is assigned
+    /// The grid position where this scene is assigned
     #[serde(default)]
     pub grid_position: Option<u8>,
 
@@ -70,6 +155,11 @@ impl Scene {
             name: name.to_string(),
             description: None,
             trigger_mode: TriggerMode::default(),
+            pad_mode: PadMode::default(),
+            release_transition_ms: None,
+            velocity: None,
+            pressure: None,
+            group: None,
             cc_values: HashMap::new(),
             tags: Vec::new(),
             active: false,
@@ -107,11 +197,11 @@ impl Scene {
     }
 
     /// Set the scene's grid position and color
+    ///
+    /// Every position is on the largest grid, a 16x16 one.
     pub fn set_grid_position(&mut self, position: u8, color: Option<(u8, u8, u8)>) -> &mut Self {
-        if position < 64 {
-            self.grid_position = Some(position);
-            self.color = color;
-        }
+        self.grid_position = Some(position);
+        self.color = color;
         self
     }
 
@@ -136,6 +226,18 @@ impl Scene {
         self
     }
 
+    /// Put the scene in an exclusive group
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.group = Some(group.to_string());
+        self
+    }
+
+    /// Set the pad mode
+    pub fn with_pad_mode(mut self, mode: PadMode) -> Self {
+        self.pad_mode = mode;
+        self
+    }
+
     /// Add tags
     pub fn with_tags(mut self, tags: Vec<String>) -> Self {
         self.tags = tags;
@@ -202,8 +304,29 @@ mod tests {
         assert_eq!(scene.grid_position, Some(42));
         assert_eq!(scene.color, Some((255, 0, 127)));
 
-        // Test with invalid position
-        scene.set_grid_position(200, Some((0, 255, 0)));
-        assert_eq!(scene.grid_position, Some(42)); // Unchanged
+        // The last position of a 16x16 grid
+        scene.set_grid_position(255, Some((0, 255, 0)));
+        assert_eq!(scene.grid_position, Some(255));
+    }
+
+    #[test]
+    fn test_expression_range() {
+        let range = ExpressionRange {
+            curve: TransitionCurve::Exponential,
+            min: 0.25,
+            max: 0.75,
+        };
+
+        assert_eq!(range.amount(0), 0.25);
+        assert_eq!(range.amount(127), 0.75);
+        // Halfway on an exponential curve is a quarter of the way through the range
+        assert!((range.amount(64) - 0.377).abs() < 0.001);
+
+        let inverted = ExpressionRange {
+            min: 1.0,
+            max: 0.0,
+            ..ExpressionRange::default()
+        };
+        assert_eq!(inverted.amount(127), 0.0);
     }
 }
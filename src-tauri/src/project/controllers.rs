@@ -0,0 +1,167 @@
+use crate::midi::controller::{Color, GridController};
+use crate::midi::palette::PadState;
+use crate::models::grid::{ControllerView, GridSize, Viewport};
+use crate::project::pad_modes::PadPlayState;
+use crate::project::shift_layer::ShiftLayer;
+
+/// Lights last sent to a controller
+#[derive(PartialEq)]
+pub struct ControllerLeds {
+    pub pads: Vec<(u8, Color, PadState)>,
+    pub buttons: Vec<(u8, Color)>,
+}
+
+/// A connected grid controller and what it is showing
+///
+/// Each controller has its own bank, viewport and mode, so several can show
+/// different parts of a project at once.
+pub struct ConnectedController {
+    pub device_id: String,
+    pub controller: Box<dyn GridController>,
+    /// Bank shown on the pads
+    pub bank: u8,
+    /// Project cell under the bottom-left pad
+    pub viewport: Viewport,
+    /// Whether the pads show virtual faders instead of scenes
+    pub fader_mode: bool,
+    /// Pad gestures while the shift button is held
+    pub shift: ShiftLayer,
+    /// Scenes held by the pads
+    pub pad_play: PadPlayState,
+    /// Lights last sent, to skip refreshes that change nothing
+    pub last_leds: Option<ControllerLeds>,
+    /// Whether reconnecting after an unplug has failed, so it's reported once
+    pub reconnect_failed: bool,
+    /// Tick of the beat clock last sent to the controller as MIDI clock
+    pub clock_tick: Option<i64>,
+}
+
+impl ConnectedController {
+    /// Wrap a controller showing the bottom-left of the first bank
+    pub fn new(device_id: &str, controller: Box<dyn GridController>) -> Self {
+        ConnectedController {
+            device_id: device_id.to_string(),
+            controller,
+            bank: 0,
+            viewport: Viewport::default(),
+            fader_mode: false,
+            shift: ShiftLayer::default(),
+            pad_play: PadPlayState::default(),
+            last_leds: None,
+            reconnect_failed: false,
+            clock_tick: None,
+        }
+    }
+
+    /// Pad grid size, if the controller reports one within the supported range
+    pub fn size(&self) -> Option<GridSize> {
+        let (rows, columns) = self.controller.grid_size();
+        GridSize::controller(rows, columns).ok()
+    }
+
+    /// Where the controller sits on the project's grid
+    pub fn view(&self) -> Option<ControllerView> {
+        Some(ControllerView {
+            device_id: self.device_id.clone(),
+            size: self.size()?,
+            viewport: self.viewport,
+            bank: self.bank,
+            fader_mode: self.fader_mode,
+        })
+    }
+
+    /// Send lights to the controller and remember them
+    pub fn show(&mut self, leds: ControllerLeds) -> Result<(), String> {
+        // Pads take grid IDs; the controller maps them itself
+        self.controller.set_pad_states(&leds.pads)?;
+        for &(button_id, color) in &leds.buttons {
+            self.controller.set_button_color(button_id, color)?;
+        }
+
+        self.last_leds = Some(leds);
+        Ok(())
+    }
+}
+
+/// Where to put a newly connected controller so it shows pads the others don't
+///
+/// `last` is the bank, viewport and size of the most recently connected
+/// controller. The new one goes beside it if the layout has room to its right,
+/// then on the next bank, and otherwise starts over at the first bank.
+pub fn placement(
+    last: Option<(u8, Viewport, GridSize)>,
+    size: GridSize,
+    layout: GridSize,
+    bank_count: usize,
+) -> (u8, Viewport) {
+    let Some((bank, viewport, last_size)) = last else {
+        return (0, Viewport::default());
+    };
+
+    let beside = viewport.column + last_size.columns;
+    if beside + size.columns <= layout.columns {
+        let mut viewport = Viewport {
+            row: viewport.row,
+            column: beside,
+        };
+        viewport.clamp(size, layout);
+        return (bank, viewport);
+    }
+
+    if (bank as usize) + 1 < bank_count {
+        return (bank + 1, Viewport::default());
+    }
+
+    (0, Viewport::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placement_side_by_side() {
+        let launchpad = GridSize::new(8, 8).unwrap();
+        let wide = GridSize::new(8, 16).unwrap();
+
+        assert_eq!(
+            placement(None, launchpad, wide, 1),
+            (0, Viewport::default())
+        );
+        assert_eq!(
+            placement(
+                Some((0, Viewport::default(), launchpad)),
+                launchpad,
+                wide,
+                1
+            ),
+            (0, Viewport { row: 0, column: 8 })
+        );
+    }
+
+    #[test]
+    fn test_placement_next_bank() {
+        let launchpad = GridSize::new(8, 8).unwrap();
+
+        // No room beside it, so the next controller shows the next bank
+        assert_eq!(
+            placement(
+                Some((0, Viewport::default(), launchpad)),
+                launchpad,
+                launchpad,
+                2
+            ),
+            (1, Viewport::default())
+        );
+        // With no more banks it mirrors the first
+        assert_eq!(
+            placement(
+                Some((1, Viewport::default(), launchpad)),
+                launchpad,
+                launchpad,
+                2
+            ),
+            (0, Viewport::default())
+        );
+    }
+}
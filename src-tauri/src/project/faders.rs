@@ -0,0 +1,106 @@
+use crate::midi::controller::Color;
+use crate::models::project::{CCDefinition, Project};
+
+/// Bar colors for each fader column
+pub const FADER_COLORS: [Color; Project::MAX_FADERS] = [
+    Color::RED,
+    Color::YELLOW,
+    Color::GREEN,
+    Color::CYAN,
+    Color::BLUE,
+    Color::MAGENTA,
+    Color::WHITE,
+    Color {
+        r: 255,
+        g: 96,
+        b: 0,
+    },
+];
+
+/// Value within a definition's range at a fraction (0.0-1.0) of the way up it
+fn value_at(definition: &CCDefinition, fraction: f64) -> u8 {
+    let min = definition.min_value as f64;
+    let max = definition.max_value as f64;
+    (min + (max - min) * fraction.clamp(0.0, 1.0)).round() as u8
+}
+
+/// Fraction of the way up a definition's range a value is
+fn fraction_of(definition: &CCDefinition, value: u8) -> f64 {
+    if definition.max_value <= definition.min_value {
+        return 1.0;
+    }
+    let span = (definition.max_value - definition.min_value) as f64;
+    ((value as f64 - definition.min_value as f64) / span).clamp(0.0, 1.0)
+}
+
+/// Value set by pressing the pad in a fader's column at a row, counting from the
+/// bottom; the bottom row is the minimum and the top row the maximum
+pub fn value_for_row(definition: &CCDefinition, row: u8, rows: u8) -> u8 {
+    if rows <= 1 {
+        return definition.max_value;
+    }
+    value_at(definition, row as f64 / (rows - 1) as f64)
+}
+
+/// Value for a physical fader or knob position (0-127), scaled into the
+/// definition's range
+pub fn value_for_position(definition: &CCDefinition, position: u8) -> u8 {
+    value_at(definition, position.min(127) as f64 / 127.0)
+}
+
+/// Number of pads lit from the bottom of a column to show a value
+///
+/// The bottom pad is always lit so every bound column is visible, and each pad
+/// lights once the value reaches the value that pad sets.
+pub fn lit_rows(definition: &CCDefinition, value: u8, rows: u8) -> u8 {
+    if rows <= 1 {
+        return rows;
+    }
+    let steps = (rows - 1) as f64;
+    (fraction_of(definition, value) * steps + 0.5).floor() as u8 + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(min_value: u8, max_value: u8) -> CCDefinition {
+        CCDefinition {
+            cc_number: 74,
+            channel: 0,
+            name: "Cutoff".to_string(),
+            description: None,
+            min_value,
+            max_value,
+            default_value: min_value,
+            use_transitions: false,
+        }
+    }
+
+    #[test]
+    fn test_virtual_fader_rows() {
+        let full = definition(0, 127);
+
+        assert_eq!(value_for_row(&full, 0, 8), 0);
+        assert_eq!(value_for_row(&full, 7, 8), 127);
+        // Each row lights exactly up to itself
+        for row in 0..8 {
+            assert_eq!(lit_rows(&full, value_for_row(&full, row, 8), 8), row + 1);
+        }
+        assert_eq!(lit_rows(&full, 64, 4), 3);
+    }
+
+    #[test]
+    fn test_fader_ranges() {
+        let narrow = definition(20, 40);
+
+        assert_eq!(value_for_row(&narrow, 0, 8), 20);
+        assert_eq!(value_for_row(&narrow, 7, 8), 40);
+        assert_eq!(value_for_position(&narrow, 127), 40);
+        assert_eq!(value_for_position(&narrow, 64), 30);
+
+        // Values outside the range pin the bar to its ends
+        assert_eq!(lit_rows(&narrow, 0, 8), 1);
+        assert_eq!(lit_rows(&narrow, 127, 8), 8);
+    }
+}
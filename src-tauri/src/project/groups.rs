@@ -0,0 +1,117 @@
+/// Scenes playing together, at most one per exclusive group
+///
+/// Scenes are kept in the order they were applied, with their group. An
+/// ungrouped scene replaces everything, while a grouped scene only replaces the
+/// scenes in its group, so scenes in different groups stack. A held scene covers
+/// the scene below it in its group until it is released.
+#[derive(Debug, Default)]
+pub struct ActiveScenes {
+    scenes: Vec<(String, Option<String>)>,
+}
+
+impl ActiveScenes {
+    /// Make a scene active, returning the IDs of the scenes it replaces, or None
+    /// if it replaces every scene
+    pub fn activate(&mut self, scene_id: &str, group: Option<&str>) -> Option<Vec<String>> {
+        let replaced = match group {
+            Some(group) => Some(self.take_group(group)),
+            None => {
+                self.scenes.clear();
+                None
+            }
+        };
+
+        self.scenes
+            .push((scene_id.to_string(), group.map(String::from)));
+        replaced
+    }
+
+    /// Apply a scene over the others in its group until it is released
+    pub fn hold(&mut self, scene_id: &str, group: Option<&str>) {
+        self.release(scene_id);
+        self.scenes
+            .push((scene_id.to_string(), group.map(String::from)));
+    }
+
+    /// Remove a scene, uncovering whatever it was applied over
+    pub fn release(&mut self, scene_id: &str) {
+        self.scenes.retain(|(id, _)| id != scene_id);
+    }
+
+    /// Remove every scene in a group, returning their IDs, oldest first
+    pub fn take_group(&mut self, group: &str) -> Vec<String> {
+        let (taken, kept) = std::mem::take(&mut self.scenes)
+            .into_iter()
+            .partition(|(_, g)| g.as_deref() == Some(group));
+        self.scenes = kept;
+
+        taken.into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// Whether a scene is playing and not covered by a later scene in its group
+    pub fn is_active(&self, scene_id: &str) -> bool {
+        let Some(index) = self.scenes.iter().rposition(|(id, _)| id == scene_id) else {
+            return false;
+        };
+
+        let group = &self.scenes[index].1;
+        !self.scenes[index + 1..].iter().any(|(_, g)| g == group)
+    }
+
+    /// IDs of the active scenes, oldest first
+    pub fn scene_ids(&self) -> Vec<String> {
+        self.scenes
+            .iter()
+            .map(|(id, _)| id)
+            .filter(|id| self.is_active(id))
+            .cloned()
+            .collect()
+    }
+
+    /// Forget every scene, e.g. after a panic or loading another project
+    pub fn clear(&mut self) {
+        self.scenes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_stack() {
+        let mut active = ActiveScenes::default();
+
+        assert_eq!(active.activate("kick", Some("drums")), Some(vec![]));
+        assert_eq!(active.activate("sweep", Some("bass")), Some(vec![]));
+        assert_eq!(
+            active.activate("snare", Some("drums")),
+            Some(vec!["kick".to_string()])
+        );
+
+        assert_eq!(active.scene_ids(), vec!["sweep", "snare"]);
+
+        // An ungrouped scene replaces everything
+        assert_eq!(active.activate("reset", None), None);
+        assert_eq!(active.scene_ids(), vec!["reset"]);
+    }
+
+    #[test]
+    fn test_held_scene_covers_its_group() {
+        let mut active = ActiveScenes::default();
+        active.activate("kick", Some("drums"));
+        active.activate("sweep", Some("bass"));
+
+        active.hold("roll", Some("drums"));
+        assert!(!active.is_active("kick"));
+        assert!(active.is_active("sweep"));
+        assert!(active.is_active("roll"));
+
+        active.release("roll");
+        assert!(active.is_active("kick"));
+        assert_eq!(active.scene_ids(), vec!["kick", "sweep"]);
+
+        assert_eq!(active.take_group("drums"), vec!["kick".to_string()]);
+        assert_eq!(active.scene_ids(), vec!["sweep"]);
+    }
+}
@@ -1,11 +1,31 @@
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
-
-use crate::midi::controller::{Color, ControllerEvent, GridController};
-use crate::midi::devices::{DeviceRegistry, MidiDevice};
-use crate::midi::engine::{MidiCommand, MidiEngine};
-use crate::models::project::Project;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::link::integration::LinkIntegration;
+use crate::midi::controller::{ButtonRole, Color, ControllerEvent, GridController};
+use crate::midi::devices::{DeviceRegistry, DeviceRegistryFactory, MidiDevice};
+use crate::midi::engine::{EngineStatus, MidiCommand, MidiEngine};
+use crate::midi::learn::{
+    step_scene, LearnAction, LearnInputs, MidiLearn, MidiMapping, MidiTrigger,
+};
+use crate::midi::loop_guard::{EchoWindow, SuppressedEvent};
+use crate::midi::palette::PadState;
+use crate::midi::recorder::TrackSplit;
+use crate::midi::simulator::{SimulatedLaunchpad, SIMULATOR_DEVICE_ID};
+use crate::midi::smf::MidiFile;
+use crate::midi::thru::{ThruRoute, ThruRouter};
+use crate::models::grid::{ControllerView, GridSize};
+use crate::models::project::{CCDefinition, ControllerButton, Project, ProjectSettings};
 use crate::models::scene::Scene;
+use crate::project::controllers::{placement, ConnectedController, ControllerLeds};
+use crate::project::faders::{self, FADER_COLORS};
+use crate::project::groups::ActiveScenes;
+use crate::project::midi_import::{import_scenes, ImportOptions};
+use crate::project::pad_modes::PadAction;
+use crate::project::shift_layer::{self, GridEdit};
 use crate::project::storage::{ProjectMeta, ProjectStorage, StorageError};
 
 /// Errors specific to project management
@@ -14,7 +34,12 @@ pub enum ProjectManagerError {
     StorageError(StorageError),
     MidiError(String),
     InvalidSceneId(String),
+    InvalidScene(String),
     InvalidGridPosition(u8),
+    InvalidGridSize(String),
+    InvalidBank(u8),
+    InvalidGridEdit(String),
+    ControllerNotConnected(Option<String>),
     NoActiveProject,
     NoActiveScene,
     NoAvailableDevices,
@@ -38,9 +63,19 @@ impl std::fmt::Display for ProjectManagerError {
             ProjectManagerError::StorageError(e) => write!(f, "Storage error: {}", e),
             ProjectManagerError::MidiError(e) => write!(f, "MIDI error: {}", e),
             ProjectManagerError::InvalidSceneId(id) => write!(f, "Invalid scene ID: {}", id),
+            ProjectManagerError::InvalidScene(e) => write!(f, "Invalid scene: {}", e),
             ProjectManagerError::InvalidGridPosition(pos) => {
                 write!(f, "Invalid grid position: {}", pos)
             }
+            ProjectManagerError::InvalidGridSize(e) => write!(f, "Invalid grid size: {}", e),
+            ProjectManagerError::InvalidBank(bank) => write!(f, "Invalid bank: {}", bank),
+            ProjectManagerError::InvalidGridEdit(e) => write!(f, "Invalid grid edit: {}", e),
+            ProjectManagerError::ControllerNotConnected(Some(id)) => {
+                write!(f, "Controller not connected: {}", id)
+            }
+            ProjectManagerError::ControllerNotConnected(None) => {
+                write!(f, "No controller connected")
+            }
             ProjectManagerError::NoActiveProject => write!(f, "No active project"),
             ProjectManagerError::NoActiveScene => write!(f, "No active scene"),
             ProjectManagerError::NoAvailableDevices => write!(f, "No available MIDI devices"),
@@ -52,14 +87,170 @@ impl std::error::Error for ProjectManagerError {}
 
 type Result<T> = std::result::Result<T, ProjectManagerError>;
 
+/// Called after the active project changes outside the UI
+pub type ProjectChangedCallback = Arc<dyn Fn() + Send + Sync>;
+
+/// Called after a problem hit in the background is added to the warnings
+pub type WarningCallback = Arc<dyn Fn() + Send + Sync>;
+
+/// Number of background warnings kept for the UI
+const MAX_WARNINGS: usize = 50;
+
+/// How often the controllers' lights are checked against playback
+const LED_REFRESH: Duration = Duration::from_millis(20);
+
+/// MIDI clock ticks per beat
+const CLOCK_TICKS_PER_BEAT: i64 = 24;
+
+/// Display state of a scene's pad
+fn pad_state(scene_id: &str, active: bool, status: &EngineStatus) -> PadState {
+    if status.queued_scene.as_deref() == Some(scene_id) {
+        PadState::Queued
+    } else if active {
+        if status.transitioning {
+            PadState::Transitioning
+        } else {
+            PadState::Active
+        }
+    } else {
+        PadState::Assigned
+    }
+}
+
+/// Light for a pad in fader mode, where each column is a level bar for the CC
+/// bound to it
+fn fader_pad(
+    project: &Project,
+    size: GridSize,
+    pad: u8,
+    values: &HashMap<(u8, u8), u8>,
+) -> (u8, Color, PadState) {
+    let Some((row, column)) = size.coordinates(pad) else {
+        return (pad, Color::BLACK, PadState::Assigned);
+    };
+
+    match project.fader_definition(column as usize) {
+        Some(definition) => {
+            // CCs that haven't been sent yet show their default
+            let value = values
+                .get(&(definition.channel, definition.cc_number))
+                .copied()
+                .unwrap_or(definition.default_value);
+
+            if row < faders::lit_rows(definition, value, size.rows) {
+                (pad, FADER_COLORS[column as usize], PadState::Level)
+            } else {
+                (pad, Color::BLACK, PadState::Assigned)
+            }
+        }
+        None => (pad, Color::BLACK, PadState::Assigned),
+    }
+}
+
+/// Lights for a controller's pads showing the scenes under them
+fn scene_pads(
+    project: &Project,
+    controller: &ConnectedController,
+    size: GridSize,
+    active_scene_ids: &[String],
+    status: &EngineStatus,
+    flash_off: bool,
+) -> Vec<(u8, Color, PadState)> {
+    let selected = controller.shift.selected();
+
+    // Unassigned pads and pads beyond the project grid are left dark
+    size.positions()
+        .map(|pad| {
+            let position = controller
+                .viewport
+                .project_position(pad, size, project.grid_size);
+            let scene =
+                position.and_then(|position| project.get_scene_at_grid(controller.bank, position));
+
+            // The source of a shift edit shows white
+            if selected.is_some() && position == selected {
+                return (pad, Color::WHITE, PadState::Active);
+            }
+
+            match scene {
+                Some(scene) => {
+                    // Default color if not specified
+                    let color = scene
+                        .color
+                        .map(|(r, g, b)| Color::new(r, g, b))
+                        .unwrap_or(Color::GREEN);
+                    let active = active_scene_ids.contains(&scene.id);
+                    let state = pad_state(&scene.id, active, status);
+
+                    if state == PadState::Queued && flash_off {
+                        (pad, Color::BLACK, PadState::Assigned)
+                    } else {
+                        (pad, color, state)
+                    }
+                }
+                None => (pad, Color::BLACK, PadState::Assigned),
+            }
+        })
+        .collect()
+}
+
+/// Connect an output port to the engine by name, for scenes or only for thru
+/// routes
+fn connect_output(
+    midi_engine: &mut MidiEngine,
+    name: &str,
+    scenes: bool,
+) -> std::result::Result<(), String> {
+    midi_engine.add_output(
+        &MidiDevice {
+            id: format!("out:{}", name),
+            name: name.to_string(),
+            is_input: false,
+            is_controller: false,
+            model: None,
+            firmware: None,
+        },
+        scenes,
+    )
+}
+
+/// Color of the beat indicator button, lit for the first quarter of each beat
+/// with the first beat of each 4/4 bar in red
+fn beat_indicator_color(beat: f64) -> Color {
+    if beat.fract() >= 0.25 {
+        Color::BLACK
+    } else if beat.floor().rem_euclid(4.0) == 0.0 {
+        Color::RED
+    } else {
+        Color::GREEN
+    }
+}
+
 /// Manager for projects and scenes
 pub struct ProjectManager {
     storage: ProjectStorage,
     pub(crate) device_registry: Arc<DeviceRegistry>,
     midi_engine: Arc<Mutex<MidiEngine>>,
     active_project: Arc<Mutex<Option<Project>>>,
-    active_scene_id: Arc<Mutex<Option<String>>>,
-    controller: Arc<Mutex<Option<Box<dyn GridController>>>>,
+    active_scenes: Arc<Mutex<ActiveScenes>>,
+    controllers: Arc<Mutex<Vec<ConnectedController>>>,
+    /// Controllers whose devices were unplugged, with the index they had
+    unplugged: Arc<Mutex<Vec<(usize, ConnectedController)>>>,
+    /// Device IDs found by the last scan
+    present_devices: Arc<Mutex<Option<HashSet<String>>>>,
+    current_bank: Arc<Mutex<u8>>,
+    project_changed: Arc<Mutex<Option<ProjectChangedCallback>>>,
+    /// Problems hit in the background, where there's no caller to return them to
+    warnings: Arc<Mutex<VecDeque<String>>>,
+    warning_added: Arc<Mutex<Option<WarningCallback>>>,
+    led_feedback: Arc<Mutex<bool>>,
+    thru_router: Arc<Mutex<ThruRouter>>,
+    midi_learn: Arc<Mutex<MidiLearn>>,
+    learn_inputs: Arc<Mutex<LearnInputs>>,
+    /// Link session followed by the beat clock, once a project has used Link
+    link: Arc<Mutex<Option<LinkIntegration>>>,
+    /// Simulated Launchpad, once one has been connected
+    simulator: Arc<Mutex<Option<SimulatedLaunchpad>>>,
 }
 
 impl ProjectManager {
@@ -74,8 +265,20 @@ impl ProjectManager {
             device_registry,
             midi_engine,
             active_project: Arc::new(Mutex::new(None)),
-            active_scene_id: Arc::new(Mutex::new(None)),
-            controller: Arc::new(Mutex::new(None)),
+            active_scenes: Arc::new(Mutex::new(ActiveScenes::default())),
+            controllers: Arc::new(Mutex::new(Vec::new())),
+            unplugged: Arc::new(Mutex::new(Vec::new())),
+            present_devices: Arc::new(Mutex::new(None)),
+            current_bank: Arc::new(Mutex::new(0)),
+            project_changed: Arc::new(Mutex::new(None)),
+            warnings: Arc::new(Mutex::new(VecDeque::new())),
+            warning_added: Arc::new(Mutex::new(None)),
+            led_feedback: Arc::new(Mutex::new(false)),
+            thru_router: Arc::new(Mutex::new(ThruRouter::new())),
+            midi_learn: Arc::new(Mutex::new(MidiLearn::default())),
+            learn_inputs: Arc::new(Mutex::new(LearnInputs::new())),
+            link: Arc::new(Mutex::new(None)),
+            simulator: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -97,10 +300,7 @@ impl ProjectManager {
         let id = project.id.clone();
 
         self.storage.save_project(&project)?;
-
-        // Set as active project
-        let mut active_project = self.active_project.lock().unwrap();
-        *active_project = Some(project);
+        self.open_project(project)?;
 
         Ok(id)
     }
@@ -111,10 +311,7 @@ impl ProjectManager {
         let id = project.id.clone();
 
         self.storage.save_project(&project)?;
-
-        // Set as active project
-        let mut active_project = self.active_project.lock().unwrap();
-        *active_project = Some(project);
+        self.open_project(project)?;
 
         Ok(id)
     }
@@ -129,21 +326,368 @@ impl ProjectManager {
     /// Load a project and set it as active
     pub fn load_project(&self, id: &str) -> Result<Project> {
         let project = self.storage.load_project(id)?;
+        self.open_project(project)
+    }
+
+    /// Set a project as active, swapping in its clock, routing, mappings and
+    /// controller layout for the previous project's
+    fn open_project(&self, project: Project) -> Result<Project> {
+        // Restored at the next startup
+        if let Err(e) = self.storage.set_last_project(&project.id) {
+            self.warn(format!("Failed to remember the last project: {}", e));
+        }
 
         // Set as active project
         let mut active_project = self.active_project.lock().unwrap();
         *active_project = Some(project.clone());
 
-        // Clear active scene
-        let mut active_scene_id = self.active_scene_id.lock().unwrap();
-        *active_scene_id = None;
+        drop(active_project);
+
+        // Clear the active scenes
+        self.active_scenes.lock().unwrap().clear();
+
+        // Spread the controllers over the new grid, starting from the first bank
+        *self.current_bank.lock().unwrap() = 0;
+        self.arrange_controllers(project.grid_size, project.banks.len());
 
         // Update controller grid
         self.update_controller_grid()?;
 
+        // Echo windows apply to the project's devices whenever they connect
+        self.midi_engine
+            .lock()
+            .unwrap()
+            .set_echo_windows(&project.settings.echo_windows);
+
+        self.start_clock(&project.settings);
+
+        // Start the project's thru routes; a missing device shouldn't stop the load
+        if let Err(e) = self.restart_thru(&project.thru_routes) {
+            self.warn(format!("Failed to start thru routes: {}", e));
+        }
+
+        if project.settings.auto_connect {
+            self.auto_connect(&project.settings, None);
+        }
+
+        // Listen for the project's MIDI mappings
+        self.midi_learn.lock().unwrap().cancel();
+        if let Err(e) = self.restart_learn_inputs() {
+            self.warn(format!("Failed to listen for MIDI mappings: {}", e));
+        }
+
         Ok(project)
     }
 
+    /// Run the beat clock at the project's tempo, or follow the Link session's
+    /// beat and tempo if the project syncs to Link
+    ///
+    /// The engine must not be locked by the caller, since stopping Link waits
+    /// for its timing thread, which feeds the engine.
+    fn start_clock(&self, settings: &ProjectSettings) {
+        let mut link = self.link.lock().unwrap();
+
+        if !settings.use_link {
+            if let Some(link) = link.as_mut() {
+                link.stop();
+            }
+            let tempo = MidiCommand::SetTempo(settings.default_tempo);
+            if let Err(e) = self.midi_engine.lock().unwrap().send_command(tempo) {
+                self.warn(format!("Failed to set the tempo: {}", e));
+            }
+            return;
+        }
+
+        let link = link.get_or_insert_with(|| {
+            let mut link = LinkIntegration::new(settings.default_tempo);
+            let midi_engine = Arc::clone(&self.midi_engine);
+            link.set_beat_callback(move |beat, tempo| {
+                let sync = MidiCommand::SyncBeat { beat, tempo };
+                let _ = midi_engine.lock().unwrap().send_command(sync);
+            });
+            link
+        });
+        if !link.is_enabled() {
+            link.start();
+        }
+    }
+
+    /// Set the tempo of the beat clock
+    ///
+    /// With Link running the tempo is proposed to the session, which the beat
+    /// clock follows. Otherwise it becomes the project's tempo, so restarting the
+    /// clock keeps it.
+    pub fn set_tempo(&self, tempo: f64) -> Result<()> {
+        if let Some(link) = self.link.lock().unwrap().as_mut() {
+            if link.is_enabled() {
+                link.set_tempo(tempo);
+                return Ok(());
+            }
+        }
+
+        {
+            let mut active_project = self.active_project.lock().unwrap();
+            if let Some(project) = active_project.as_mut() {
+                project.settings.default_tempo = tempo;
+                self.storage.save_project(project)?;
+            }
+        }
+
+        self.midi_engine
+            .lock()
+            .unwrap()
+            .send_command(MidiCommand::SetTempo(tempo))?;
+        self.notify_project_changed();
+
+        Ok(())
+    }
+
+    /// Load the project open when the app last closed, if it auto-connects
+    ///
+    /// Returns the project if one was loaded.
+    pub fn restore_last_project(&self) -> Result<Option<Project>> {
+        let Some(id) = self.storage.last_project() else {
+            return Ok(None);
+        };
+
+        let project = self.storage.load_project(&id)?;
+        if !project.settings.auto_connect {
+            return Ok(None);
+        }
+
+        self.open_project(project).map(Some)
+    }
+
+    /// Connect the project's remembered controllers and outputs if they aren't
+    /// already, or only those among `appeared` device IDs if given
+    ///
+    /// Devices that can't be connected are skipped, since they may not be
+    /// plugged in.
+    fn auto_connect(&self, settings: &ProjectSettings, appeared: Option<&[String]>) {
+        let wanted = |id: &str| appeared.is_none_or(|ids| ids.iter().any(|a| a == id));
+
+        for device_id in &settings.default_controller_devices {
+            let connected = self
+                .controllers
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|controller| &controller.device_id == device_id)
+                || self
+                    .unplugged
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .any(|(_, controller)| &controller.device_id == device_id);
+
+            if !connected && wanted(device_id) {
+                if let Err(e) = self.connect_controller(device_id) {
+                    self.warn(format!("Failed to connect controller {}: {}", device_id, e));
+                }
+            }
+        }
+
+        for name in &settings.default_output_devices {
+            if wanted(&format!("out:{}", name)) {
+                let mut midi_engine = self.midi_engine.lock().unwrap();
+                if let Err(e) = connect_output(&mut midi_engine, name, true) {
+                    self.warn(format!("Failed to connect output {}: {}", name, e));
+                }
+            }
+        }
+    }
+
+    /// Follow devices being plugged in and unplugged, given the devices found by
+    /// a scan
+    ///
+    /// Unplugged controllers are set aside with their bank, viewport and mode, and
+    /// reconnected with their lights restored when they come back. Outputs and
+    /// thru routes are reconnected too, and a project that auto-connects picks up
+    /// its default controller and output when they appear. Returns whether the
+    /// connected controllers changed.
+    pub fn sync_devices(&self, devices: &[MidiDevice]) -> Result<bool> {
+        let present: HashSet<String> = devices.iter().map(|d| d.id.clone()).collect();
+        let previous = self
+            .present_devices
+            .lock()
+            .unwrap()
+            .replace(present.clone());
+
+        let unplugged = self.unplug_missing(&present);
+        let replugged = self.replug_present(&present);
+
+        // The first scan only notes what was there to begin with
+        let Some(previous) = previous else {
+            return Ok(unplugged || replugged);
+        };
+
+        // Unplugged outputs are dropped, so they can be connected again when they
+        // come back
+        {
+            let mut midi_engine = self.midi_engine.lock().unwrap();
+            for id in previous.difference(&present) {
+                if let Some(name) = id.strip_prefix("out:") {
+                    midi_engine.remove_output(name);
+                }
+            }
+        }
+
+        let controller_count = self.controllers.lock().unwrap().len();
+        let appeared: Vec<String> = present.difference(&previous).cloned().collect();
+        if !appeared.is_empty() {
+            self.connect_appeared(&appeared);
+        }
+        let connected = self.controllers.lock().unwrap().len() != controller_count;
+
+        // New inputs can send mapped messages too
+        if appeared.iter().any(|id| id.starts_with("in:")) {
+            self.restart_learn_inputs()?;
+        }
+
+        Ok(unplugged || replugged || connected)
+    }
+
+    /// Reconnect what the active project uses among newly appeared devices
+    fn connect_appeared(&self, appeared: &[String]) {
+        let Some((settings, routes)) = self
+            .active_project
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|project| (project.settings.clone(), project.thru_routes.clone()))
+        else {
+            return;
+        };
+
+        // Thru routes open their ports when started, so restart them to pick up
+        // devices that came back
+        let thru_device_back = routes.iter().filter(|r| r.enabled).any(|route| {
+            appeared.contains(&format!("in:{}", route.input_device))
+                || appeared.contains(&format!("out:{}", route.output_device))
+        });
+        if thru_device_back {
+            if let Err(e) = self.restart_thru(&routes) {
+                self.warn(format!("Failed to restart thru routes: {}", e));
+            }
+        }
+
+        if settings.auto_connect {
+            self.auto_connect(&settings, Some(appeared));
+        }
+    }
+
+    /// Set aside connected controllers whose devices are no longer present
+    ///
+    /// Returns whether any were unplugged.
+    fn unplug_missing(&self, present: &HashSet<String>) -> bool {
+        let removed: Vec<(usize, ConnectedController)> = {
+            let mut controllers = self.controllers.lock().unwrap();
+            let mut removed = Vec::new();
+            let mut index = 0;
+            while index < controllers.len() {
+                // Controllers with no device in the registry, like the simulator,
+                // can't be unplugged
+                let device_id = &controllers[index].device_id;
+                if present.contains(device_id)
+                    || self.device_registry.get_device(device_id).is_none()
+                {
+                    index += 1;
+                } else {
+                    // Keep the index it had before any were removed
+                    removed.push((index + removed.len(), controllers.remove(index)));
+                }
+            }
+
+            removed
+        };
+
+        if removed.is_empty() {
+            return false;
+        }
+
+        // Disconnect outside the lock, like disconnect_controller
+        for (index, mut connected) in removed {
+            // The device is gone, so there's nothing to reset
+            let _ = connected.controller.disconnect();
+
+            // Pads held when it was pulled can't be released any more
+            connected.pad_play.clear();
+            connected.shift.set_shift(false);
+            connected.last_leds = None;
+
+            self.unplugged.lock().unwrap().push((index, connected));
+        }
+
+        true
+    }
+
+    /// Reconnect unplugged controllers whose devices are back, in their old
+    /// places, and restore their lights
+    ///
+    /// Returns whether any were reconnected.
+    fn replug_present(&self, present: &HashSet<String>) -> bool {
+        let returning: Vec<(usize, ConnectedController)> = {
+            let mut unplugged = self.unplugged.lock().unwrap();
+            let (returning, missing) = std::mem::take(&mut *unplugged)
+                .into_iter()
+                .partition(|(_, controller)| present.contains(&controller.device_id));
+            *unplugged = missing;
+
+            returning
+        };
+
+        if returning.is_empty() {
+            return false;
+        }
+
+        // The project may have changed while the controllers were away
+        let (layout, bank_count) = self
+            .active_project
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or((GridSize::default(), 1), |project| {
+                (project.grid_size, project.banks.len())
+            });
+
+        let mut replugged = false;
+        for (index, mut connected) in returning {
+            // Ports can take a moment to open after a device appears, so try again
+            // on the next scan, reporting the failure only the first time
+            if let Err(e) = connected.controller.connect() {
+                if !connected.reconnect_failed {
+                    connected.reconnect_failed = true;
+                    self.warn(format!(
+                        "Failed to reconnect controller {}: {}",
+                        connected.device_id, e
+                    ));
+                }
+                self.unplugged.lock().unwrap().push((index, connected));
+                continue;
+            }
+
+            connected.reconnect_failed = false;
+            connected.bank = connected.bank.min(bank_count.saturating_sub(1) as u8);
+            if let Some(size) = connected.size() {
+                connected.viewport.clamp(size, layout);
+            }
+
+            let mut controllers = self.controllers.lock().unwrap();
+            let index = index.min(controllers.len());
+            controllers.insert(index, connected);
+            replugged = true;
+        }
+
+        if replugged {
+            if let Err(e) = self.update_controller_grid() {
+                self.warn(format!("Failed to restore controller lights: {}", e));
+            }
+            self.start_led_feedback();
+        }
+
+        replugged
+    }
+
     /// Get the active project
     pub fn get_active_project(&self) -> Result<Project> {
         let active_project = self.active_project.lock().unwrap();
@@ -192,8 +736,62 @@ impl ProjectManager {
         }
     }
 
+    /// Replace a scene in the active project with an edited copy
+    ///
+    /// The scene is found by its ID. Whether it is playing, and where it sits
+    /// on the grid, are kept from the stored scene.
+    pub fn update_scene(&self, mut scene: Scene) -> Result<()> {
+        for (input, range) in [("Velocity", scene.velocity), ("Pressure", scene.pressure)] {
+            if range.is_some_and(|range| !range.is_valid()) {
+                return Err(ProjectManagerError::InvalidScene(format!(
+                    "{} range must be between 0 and 1",
+                    input
+                )));
+            }
+        }
+
+        // A blank group leaves the scene to its column's group, like a blank
+        // column name
+        scene.group = scene
+            .group
+            .as_deref()
+            .map(str::trim)
+            .filter(|group| !group.is_empty())
+            .map(String::from);
+
+        {
+            let mut active_project = self.active_project.lock().unwrap();
+            let project = active_project
+                .as_mut()
+                .ok_or(ProjectManagerError::NoActiveProject)?;
+
+            let existing = project
+                .get_scene_mut(&scene.id)
+                .ok_or_else(|| ProjectManagerError::InvalidSceneId(scene.id.clone()))?;
+            scene.active = existing.active;
+            scene.grid_position = existing.grid_position;
+            *existing = scene;
+
+            project.update_timestamp();
+            self.storage.save_project(project)?;
+        }
+
+        // The scene's group and color show on the grid
+        self.update_controller_grid()
+    }
+
     /// Activate a scene
     pub fn activate_scene(&self, scene_id: &str) -> Result<()> {
+        self.activate_scene_at(scene_id, None, 1.0)
+    }
+
+    /// Activate a scene, moving its CCs the given amount (0.0-1.0) of the way
+    /// toward the scene's values
+    ///
+    /// The scene replaces the others in its exclusive group, found from the grid
+    /// position it was triggered from if known; an ungrouped scene replaces every
+    /// scene.
+    fn activate_scene_at(&self, scene_id: &str, position: Option<u8>, amount: f64) -> Result<()> {
         let active_project = self.active_project.lock().unwrap();
 
         match &*active_project {
@@ -203,6 +801,13 @@ impl ProjectManager {
                     .get_scene(scene_id)
                     .ok_or_else(|| ProjectManagerError::InvalidSceneId(scene_id.to_string()))?;
 
+                let group = project.scene_group(scene, position);
+                let replaces = self
+                    .active_scenes
+                    .lock()
+                    .unwrap()
+                    .activate(scene_id, group.as_deref());
+
                 // Activate the scene via MIDI engine
                 let midi_engine = self.midi_engine.lock().unwrap();
 
@@ -221,11 +826,18 @@ impl ProjectManager {
                 midi_engine.send_command(MidiCommand::ActivateScene {
                     scene: scene.clone(),
                     quantize_beats,
+                    amount,
+                    replaces: replaces.clone(),
                 })?;
 
-                // Set as active scene
-                let mut active_scene_id = self.active_scene_id.lock().unwrap();
-                *active_scene_id = Some(scene_id.to_string());
+                // The engine forgets the scenes this one replaces, so their pads can
+                // no longer release them
+                for controller in self.controllers.lock().unwrap().iter_mut() {
+                    match &replaces {
+                        Some(scene_ids) => controller.pad_play.forget(scene_ids),
+                        None => controller.pad_play.clear(),
+                    }
+                }
 
                 Ok(())
             }
@@ -233,40 +845,188 @@ impl ProjectManager {
         }
     }
 
-    /// Get the active scene
-    pub fn get_active_scene(&self) -> Result<Scene> {
-        let active_project = self.active_project.lock().unwrap();
-        let active_scene_id = self.active_scene_id.lock().unwrap();
+    /// Handle a controller pad press on a scene, following the scene's pad mode
+    /// and velocity response
+    ///
+    /// `position` is the grid position under the pad, which decides the scene's
+    /// group when it comes from the pad's column.
+    pub fn press_pad(
+        &self,
+        device_id: &str,
+        pad: u8,
+        position: Option<u8>,
+        scene_id: &str,
+        velocity: u8,
+    ) -> Result<()> {
+        let scene = self.scene(scene_id)?;
+        let amount = scene.velocity.map_or(1.0, |range| range.amount(velocity));
+
+        let action = self
+            .with_controller(Some(device_id), |controller| {
+                controller.pad_play.press(pad, &scene)
+            })
+            .flatten();
+
+        match action {
+            Some(action) => self.perform_pad_action(action, position, amount),
+            None => Ok(()),
+        }
+    }
 
-        match (&*active_project, &*active_scene_id) {
-            (Some(project), Some(scene_id)) => {
-                // Find the scene
-                let scene = project
-                    .get_scene(scene_id)
-                    .ok_or_else(|| ProjectManagerError::InvalidSceneId(scene_id.to_string()))?;
+    /// Handle a change of pressure on a held pad, morphing its scene if the scene
+    /// responds to pressure
+    pub fn pad_pressure(&self, device_id: &str, pad: u8, pressure: u8) -> Result<()> {
+        let Some(scene_id) = self
+            .with_controller(Some(device_id), |controller| {
+                controller.pad_play.pressed_scene(pad).map(String::from)
+            })
+            .flatten()
+        else {
+            return Ok(());
+        };
+
+        let scene = self.scene(&scene_id)?;
+        if let Some(range) = scene.pressure {
+            let amount = range.amount(pressure);
+            self.midi_engine
+                .lock()
+                .unwrap()
+                .send_command(MidiCommand::SetSceneAmount { scene, amount })?;
+        }
+
+        Ok(())
+    }
+
+    /// Handle a controller pad release, undoing a momentary scene held by the pad
+    pub fn release_pad(&self, device_id: &str, pad: u8) -> Result<()> {
+        let action = self
+            .with_controller(Some(device_id), |controller| {
+                controller.shift.release(pad);
+                controller.pad_play.release(pad)
+            })
+            .flatten();
+
+        match action {
+            Some(action) => self.perform_pad_action(action, None, 1.0),
+            None => Ok(()),
+        }
+    }
 
-                Ok(scene.clone())
+    fn perform_pad_action(
+        &self,
+        action: PadAction,
+        position: Option<u8>,
+        amount: f64,
+    ) -> Result<()> {
+        match action {
+            PadAction::Activate(scene_id) => self.activate_scene_at(&scene_id, position, amount),
+            PadAction::Hold(scene_id) => {
+                let scene = self.scene(&scene_id)?;
+                let group = self
+                    .active_project
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .and_then(|project| project.scene_group(&scene, position));
+
+                self.midi_engine
+                    .lock()
+                    .unwrap()
+                    .send_command(MidiCommand::HoldScene { scene, amount })?;
+
+                self.active_scenes
+                    .lock()
+                    .unwrap()
+                    .hold(&scene_id, group.as_deref());
+                Ok(())
             }
-            (None, _) => Err(ProjectManagerError::NoActiveProject),
-            (_, None) => Err(ProjectManagerError::NoActiveScene),
+            PadAction::Release(scene_id) => self.release_scene(&scene_id),
         }
     }
 
-    /// Assign a scene to a grid position
-    pub fn assign_scene_to_grid(&self, scene_id: &str, position: u8) -> Result<()> {
-        if position >= 64 {
-            return Err(ProjectManagerError::InvalidGridPosition(position));
+    /// Undo an applied scene, returning its CCs to the values from before it
+    fn release_scene(&self, scene_id: &str) -> Result<()> {
+        let duration_ms = self.scene(scene_id)?.release_transition_ms;
+        self.midi_engine
+            .lock()
+            .unwrap()
+            .send_command(MidiCommand::ReleaseScene {
+                scene_id: scene_id.to_string(),
+                duration_ms,
+            })?;
+
+        // Uncovers whatever the scene was applied over
+        self.active_scenes.lock().unwrap().release(scene_id);
+        Ok(())
+    }
+
+    /// Release every scene playing in an exclusive group, like stopping a clip
+    /// launcher column
+    pub fn release_group(&self, group: &str) -> Result<()> {
+        let scene_ids = self.active_scenes.lock().unwrap().take_group(group);
+        for controller in self.controllers.lock().unwrap().iter_mut() {
+            controller.pad_play.forget(&scene_ids);
+        }
+
+        // Newest first, so each scene returns to the values of the one below it
+        for scene_id in scene_ids.iter().rev() {
+            self.release_scene(scene_id)?;
         }
 
+        Ok(())
+    }
+
+    /// IDs of the scenes playing, oldest first
+    pub fn active_scene_ids(&self) -> Vec<String> {
+        self.active_scenes.lock().unwrap().scene_ids()
+    }
+
+    /// Name a grid column as an exclusive group, or clear it with None
+    pub fn set_column_group(&self, column: u8, name: Option<&str>) -> Result<()> {
+        let mut active_project = self.active_project.lock().unwrap();
+        let project = active_project
+            .as_mut()
+            .ok_or(ProjectManagerError::NoActiveProject)?;
+
+        project.set_column_group(column, name)?;
+        project.update_timestamp();
+        self.storage.save_project(project)?;
+
+        Ok(())
+    }
+
+    /// Get a copy of a scene in the active project
+    fn scene(&self, scene_id: &str) -> Result<Scene> {
+        let active_project = self.active_project.lock().unwrap();
+        let project = active_project
+            .as_ref()
+            .ok_or(ProjectManagerError::NoActiveProject)?;
+
+        project
+            .get_scene(scene_id)
+            .cloned()
+            .ok_or_else(|| ProjectManagerError::InvalidSceneId(scene_id.to_string()))
+    }
+
+    /// Assign a scene to a grid position in the current bank
+    pub fn assign_scene_to_grid(&self, scene_id: &str, position: u8) -> Result<()> {
+        let bank = self.current_bank();
         let mut active_project = self.active_project.lock().unwrap();
 
         match &mut *active_project {
             Some(project) => {
-                project.assign_to_grid(scene_id, position)?;
+                if !project.grid_size.contains(position) {
+                    return Err(ProjectManagerError::InvalidGridPosition(position));
+                }
+
+                project.assign_to_grid(scene_id, bank, position)?;
                 project.update_timestamp();
 
                 self.storage.save_project(project)?;
 
+                // Release the project before the grid refresh takes it again
+                drop(active_project);
+
                 // Update controller if connected
                 self.update_controller_grid()?;
 
@@ -276,125 +1036,1471 @@ impl ProjectManager {
         }
     }
 
-    /// Connect to a MIDI controller
-    pub fn connect_controller(&self, device_id: &str) -> Result<()> {
-        // Find the device
-        let device = self.device_registry.get_device(device_id).ok_or_else(|| {
-            ProjectManagerError::MidiError(format!("Device not found: {}", device_id))
-        })?;
+    /// Index of the bank shown on the grid
+    pub fn current_bank(&self) -> u8 {
+        *self.current_bank.lock().unwrap()
+    }
 
-        // Create the controller
-        let mut controller = crate::midi::controller::ControllerFactory::create_controller(device)?;
+    /// Show a bank on the grid and the first controller
+    pub fn select_bank(&self, bank: u8) -> Result<()> {
+        self.check_bank(bank)?;
 
-        // Connect to the controller
-        controller.connect()?;
+        *self.current_bank.lock().unwrap() = bank;
+        if let Some(controller) = self.controllers.lock().unwrap().first_mut() {
+            controller.bank = bank;
+        }
+        self.update_controller_grid()
+    }
 
-        // Register event callback
-        let active_project = Arc::clone(&self.active_project);
-        let this = Arc::new(self.clone());
+    /// Show a bank on one controller
+    ///
+    /// The first controller follows the bank shown on the grid, so selecting a
+    /// bank on it shows that bank on the grid too.
+    pub fn select_controller_bank(&self, device_id: &str, bank: u8) -> Result<()> {
+        self.check_bank(bank)?;
+
+        let is_first = {
+            let mut controllers = self.controllers.lock().unwrap();
+            let index = controllers
+                .iter()
+                .position(|controller| controller.device_id == device_id)
+                .ok_or_else(|| {
+                    ProjectManagerError::ControllerNotConnected(Some(device_id.to_string()))
+                })?;
+            controllers[index].bank = bank;
+            index == 0
+        };
 
-        // Clone references needed for the callback instead of the controller itself
-        let controller_ref = Arc::new(Mutex::new(controller.clone_box())); // Use clone_box here
+        if is_first {
+            *self.current_bank.lock().unwrap() = bank;
+        }
+        self.update_controller_grid()
+    }
 
-        controller.set_event_callback(Arc::new(move |event| {
-            match event {
-                ControllerEvent::PadPressed(grid_id, _) => {
-                    // Get access to controller clone
-                    if let Ok(controller_guard) = controller_ref.lock() {
-                        // Translate controller grid ID to app grid ID
-                        if let Some(app_grid_id) = controller_guard.map_to_app_grid_id(grid_id) {
-                            // Find scene at this grid position
-                            let active_project_guard = active_project.lock().unwrap();
-
-                            if let Some(project) = &*active_project_guard {
-                                if let Some(scene) = project.get_scene_at_grid(app_grid_id) {
-                                    // Activate the scene
-                                    let scene_id = scene.id.clone();
-                                    drop(active_project_guard); // Release the lock
-
-                                    let _ = this.activate_scene(&scene_id);
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => {} // Ignore other events for now
-            }
-        }));
+    /// Check a bank exists in the active project
+    fn check_bank(&self, bank: u8) -> Result<()> {
+        let bank_count = self
+            .active_project
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|project| project.banks.len())
+            .ok_or(ProjectManagerError::NoActiveProject)?;
+
+        if bank as usize >= bank_count {
+            return Err(ProjectManagerError::InvalidBank(bank));
+        }
 
-        // Store the controller
-        let mut controller_guard = self.controller.lock().unwrap();
-        *controller_guard = Some(controller);
+        Ok(())
+    }
 
-        // Update the controller grid
+    /// Add an empty bank to the active project and return its index
+    pub fn add_bank(&self, name: &str) -> Result<u8> {
+        let bank = {
+            let mut active_project = self.active_project.lock().unwrap();
+            let project = active_project
+                .as_mut()
+                .ok_or(ProjectManagerError::NoActiveProject)?;
+
+            let bank = project.add_bank(name)?;
+            project.update_timestamp();
+            self.storage.save_project(project)?;
+
+            bank
+        };
+
+        // Light the new bank's button
         self.update_controller_grid()?;
 
-        Ok(())
+        Ok(bank)
     }
 
-    /// Update the controller grid with current scene assignments
-    fn update_controller_grid(&self) -> Result<()> {
-        let active_project = self.active_project.lock().unwrap();
-        let mut controller_guard = self.controller.lock().unwrap();
+    /// Remove a bank from the active project
+    pub fn remove_bank(&self, bank: u8) -> Result<()> {
+        {
+            let mut active_project = self.active_project.lock().unwrap();
+            let project = active_project
+                .as_mut()
+                .ok_or(ProjectManagerError::NoActiveProject)?;
+
+            project
+                .remove_bank(bank)
+                .map_err(|_| ProjectManagerError::InvalidBank(bank))?;
+            project.update_timestamp();
+            self.storage.save_project(project)?;
+
+            // Stay on the same bank where possible
+            let bank_count = project.banks.len();
+            let keep_bank = |current: &mut u8| {
+                if *current > bank || *current as usize >= bank_count {
+                    *current = current.saturating_sub(1);
+                }
+            };
 
-        if let (Some(project), Some(controller)) = (&*active_project, &mut *controller_guard) {
-            // Clear the grid
-            controller.clear()?;
+            keep_bank(&mut self.current_bank.lock().unwrap());
+            for controller in self.controllers.lock().unwrap().iter_mut() {
+                keep_bank(&mut controller.bank);
+            }
+        }
 
-            // Set colors for assigned scenes
-            for (&position, scene_id) in &project.grid_assignments {
-                if let Some(scene) = project.get_scene(scene_id) {
-                    // Default color if not specified
-                    let color = scene
-                        .color
-                        .map(|(r, g, b)| Color::new(r, g, b))
-                        .unwrap_or(Color::GREEN);
+        self.update_controller_grid()
+    }
 
-                    // Map from app grid ID to controller-specific ID
-                    let controller_id = controller.map_grid_id(position);
-                    controller.set_pad_color(controller_id, color)?;
-                }
+    /// Change the active project's grid dimensions
+    ///
+    /// Scenes keep their row and column; the IDs of scenes that no longer fit
+    /// are returned.
+    pub fn set_grid_size(&self, rows: u8, columns: u8) -> Result<Vec<String>> {
+        let size = GridSize::new(rows, columns).map_err(ProjectManagerError::InvalidGridSize)?;
+
+        let unassigned = {
+            let mut active_project = self.active_project.lock().unwrap();
+            let project = active_project
+                .as_mut()
+                .ok_or(ProjectManagerError::NoActiveProject)?;
+
+            let unassigned = project.set_grid_size(size);
+            project.update_timestamp();
+            self.storage.save_project(project)?;
+
+            unassigned
+        };
+
+        for controller in self.controllers.lock().unwrap().iter_mut() {
+            if let Some(controller_size) = controller.size() {
+                controller.viewport.clamp(controller_size, size);
             }
         }
+        self.update_controller_grid()?;
 
-        Ok(())
+        Ok(unassigned)
     }
 
-    /// Disconnect from the controller
-    pub fn disconnect_controller(&self) -> Result<()> {
-        let mut controller_guard = self.controller.lock().unwrap();
+    /// Where each connected controller sits on the active project's grid
+    pub fn controller_views(&self) -> Vec<ControllerView> {
+        self.controllers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(ConnectedController::view)
+            .collect()
+    }
 
-        if let Some(controller) = &mut *controller_guard {
-            controller.disconnect()?;
-            *controller_guard = None;
+    /// Scroll a controller's viewport by whole rows and columns
+    ///
+    /// Without a device ID the first controller scrolls.
+    pub fn scroll_viewport(
+        &self,
+        device_id: Option<&str>,
+        rows: i8,
+        columns: i8,
+    ) -> Result<Option<ControllerView>> {
+        let layout = self
+            .active_project
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|project| project.grid_size)
+            .ok_or(ProjectManagerError::NoActiveProject)?;
+
+        let scrolled = self
+            .with_controller(device_id, |controller| {
+                let size = controller.size()?;
+                let moved = controller.viewport.scroll(rows, columns, size, layout);
+                Some((moved, controller.view()?))
+            })
+            .flatten();
+
+        let Some((moved, view)) = scrolled else {
+            return Ok(None);
+        };
+
+        if moved {
+            self.update_controller_grid()?;
         }
 
+        Ok(Some(view))
+    }
+
+    /// Whether a controller, or the first without a device ID, is showing
+    /// virtual faders
+    pub fn fader_mode(&self, device_id: Option<&str>) -> bool {
+        self.with_controller(device_id, |controller| controller.fader_mode)
+            .unwrap_or(false)
+    }
+
+    /// Switch a controller, or the first without a device ID, between scenes
+    /// and virtual faders
+    pub fn set_fader_mode(&self, device_id: Option<&str>, enabled: bool) -> Result<()> {
+        self.with_controller(device_id, |controller| controller.fader_mode = enabled)
+            .ok_or_else(|| {
+                ProjectManagerError::ControllerNotConnected(device_id.map(String::from))
+            })?;
+
+        self.update_controller_grid()
+    }
+
+    /// Choose whether the active project connects its last used controller and
+    /// output when loaded
+    pub fn set_auto_connect(&self, enabled: bool) -> Result<()> {
+        let mut active_project = self.active_project.lock().unwrap();
+        let project = active_project
+            .as_mut()
+            .ok_or(ProjectManagerError::NoActiveProject)?;
+
+        project.settings.auto_connect = enabled;
+        project.update_timestamp();
+        self.storage.save_project(project)?;
+
         Ok(())
     }
 
-    /// Send a single CC value
-    pub fn send_cc(&self, channel: u8, cc_number: u8, value: u8) -> Result<()> {
-        let midi_engine = self.midi_engine.lock().unwrap();
+    /// Choose whether the active project's beat clock follows the Link session
+    pub fn set_use_link(&self, enabled: bool) -> Result<()> {
+        let settings = {
+            let mut active_project = self.active_project.lock().unwrap();
+            let project = active_project
+                .as_mut()
+                .ok_or(ProjectManagerError::NoActiveProject)?;
 
-        midi_engine.send_command(MidiCommand::SendCC {
-            channel,
-            cc_number,
-            value,
-        })?;
+            project.settings.use_link = enabled;
+            project.update_timestamp();
+            self.storage.save_project(project)?;
+            project.settings.clone()
+        };
+
+        self.start_clock(&settings);
 
         Ok(())
     }
 
-    /// Import a project from a file
-    pub fn import_project<P: AsRef<Path>>(&self, path: P) -> Result<String> {
-        let project = self.storage.import_project(path)?;
-        let id = project.id.clone();
+    /// Send scenes to an output, and remember it so the project can connect it
+    /// next time
+    pub fn connect_output_device(&self, name: &str) -> Result<()> {
+        connect_output(&mut self.midi_engine.lock().unwrap(), name, true)?;
 
-        // Set as active project
         let mut active_project = self.active_project.lock().unwrap();
-        *active_project = Some(project);
+        if let Some(project) = active_project.as_mut() {
+            let outputs = &mut project.settings.default_output_devices;
+            if !outputs.iter().any(|output| output == name) {
+                outputs.push(name.to_string());
+                project.update_timestamp();
+                self.storage.save_project(project)?;
+            }
+        }
 
-        Ok(id)
+        Ok(())
+    }
+
+    /// Bind faders to CC definitions, by definition key, in fader order
+    pub fn set_fader_bindings(&self, keys: Vec<String>) -> Result<()> {
+        {
+            let mut active_project = self.active_project.lock().unwrap();
+            let project = active_project
+                .as_mut()
+                .ok_or(ProjectManagerError::NoActiveProject)?;
+
+            project.set_fader_bindings(keys)?;
+            project.update_timestamp();
+            self.storage.save_project(project)?;
+        }
+
+        self.update_controller_grid()
+    }
+
+    /// Set a fader's CC from a physical fader or knob position (0-127)
+    pub fn move_fader(&self, fader: usize, position: u8) -> Result<()> {
+        let definition = self.fader_definition(fader)?;
+        match definition {
+            Some(definition) => self.send_cc(
+                definition.channel,
+                definition.cc_number,
+                faders::value_for_position(&definition, position),
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Set the fader of a pad's column to the value of the pad's row, on a
+    /// controller of the given size
+    fn press_fader_pad(&self, size: GridSize, pad: u8) -> Result<()> {
+        let Some((row, column)) = size.coordinates(pad) else {
+            return Ok(());
+        };
+
+        match self.fader_definition(column as usize)? {
+            Some(definition) => self.send_cc(
+                definition.channel,
+                definition.cc_number,
+                faders::value_for_row(&definition, row, size.rows),
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// CC definition bound to a fader in the active project
+    fn fader_definition(&self, fader: usize) -> Result<Option<CCDefinition>> {
+        let active_project = self.active_project.lock().unwrap();
+        let project = active_project
+            .as_ref()
+            .ok_or(ProjectManagerError::NoActiveProject)?;
+
+        Ok(project.fader_definition(fader).cloned())
+    }
+
+    /// Whether a controller button is the shift button, by its role or the
+    /// project's settings
+    fn is_shift_button(&self, role: Option<ButtonRole>, button_id: u8) -> bool {
+        role == Some(ButtonRole::Shift)
+            || self
+                .active_project
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_some_and(|p| p.settings.shift_button == Some(button_id))
+    }
+
+    /// Hold or let go of a controller's shift layer
+    fn set_shift(&self, device_id: &str, held: bool) -> Result<()> {
+        self.with_controller(Some(device_id), |controller| {
+            controller.shift.set_shift(held)
+        });
+
+        // Clear the selected pad's light
+        self.update_controller_grid()
+    }
+
+    /// Handle a controller pad pressed while shift is held
+    ///
+    /// `position` is the grid position under the pad in the controller's bank
+    /// and `assigned` whether a scene is there.
+    fn press_shift_pad(
+        &self,
+        device_id: &str,
+        bank: u8,
+        pad: u8,
+        position: Option<u8>,
+        assigned: bool,
+    ) -> Result<()> {
+        let edit = self
+            .with_controller(Some(device_id), |controller| {
+                controller.shift.press(pad, position, assigned)
+            })
+            .flatten();
+
+        match edit {
+            Some(edit) => self.edit_grid(bank, &edit),
+            // Light the selected pad
+            None => self.update_controller_grid(),
+        }
+    }
+
+    /// Edit a bank's grid and save the project
+    pub fn edit_grid(&self, bank: u8, edit: &GridEdit) -> Result<()> {
+        // Copy the live values out before locking the project, so the engine
+        // and the project are never locked together here
+        let live_values = self.midi_engine.lock().unwrap().current_values();
+
+        {
+            let mut active_project = self.active_project.lock().unwrap();
+            let project = active_project
+                .as_mut()
+                .ok_or(ProjectManagerError::NoActiveProject)?;
+
+            shift_layer::apply_edit(project, bank, edit, &live_values)
+                .map_err(ProjectManagerError::InvalidGridEdit)?;
+            project.update_timestamp();
+            self.storage.save_project(project)?;
+        }
+
+        self.update_controller_grid()?;
+        self.notify_project_changed();
+
+        Ok(())
+    }
+
+    /// Set the callback run after the active project is changed from the controller
+    pub fn set_project_changed_callback<F>(&self, callback: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        *self.project_changed.lock().unwrap() = Some(Arc::new(callback));
+    }
+
+    fn notify_project_changed(&self) {
+        let callback = self.project_changed.lock().unwrap().clone();
+        if let Some(callback) = callback {
+            callback();
+        }
+    }
+
+    /// Set the callback run after a background problem is added to the warnings
+    pub fn set_warning_callback<F>(&self, callback: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        *self.warning_added.lock().unwrap() = Some(Arc::new(callback));
+    }
+
+    /// Note a problem hit in the background, dropping the oldest once full
+    pub(crate) fn warn(&self, message: String) {
+        {
+            let mut warnings = self.warnings.lock().unwrap();
+            if warnings.len() == MAX_WARNINGS {
+                warnings.pop_front();
+            }
+            warnings.push_back(message);
+        }
+
+        let callback = self.warning_added.lock().unwrap().clone();
+        if let Some(callback) = callback {
+            callback();
+        }
+    }
+
+    /// Get the recent background problems, oldest first
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Run a closure on a connected controller, or the first one without a
+    /// device ID
+    fn with_controller<T>(
+        &self,
+        device_id: Option<&str>,
+        f: impl FnOnce(&mut ConnectedController) -> T,
+    ) -> Option<T> {
+        let mut controllers = self.controllers.lock().unwrap();
+        let controller = match device_id {
+            Some(device_id) => controllers
+                .iter_mut()
+                .find(|controller| controller.device_id == device_id),
+            None => controllers.first_mut(),
+        }?;
+
+        Some(f(controller))
+    }
+
+    /// Spread the connected controllers over a project's grid and banks
+    fn arrange_controllers(&self, layout: GridSize, bank_count: usize) {
+        let mut last = None;
+        for controller in self.controllers.lock().unwrap().iter_mut() {
+            controller.pad_play.clear();
+
+            let Some(size) = controller.size() else {
+                continue;
+            };
+            (controller.bank, controller.viewport) = placement(last, size, layout, bank_count);
+            last = Some((controller.bank, controller.viewport, size));
+        }
+    }
+
+    /// Connect to a MIDI controller, alongside any already connected
+    ///
+    /// The new controller is placed to show pads the others don't: beside the
+    /// last one on a grid wide enough, or else on the next bank.
+    pub fn connect_controller(&self, device_id: &str) -> Result<()> {
+        if device_id == SIMULATOR_DEVICE_ID {
+            return self.connect_simulator();
+        }
+
+        // Find the device
+        let device = self.device_registry.get_device(device_id).ok_or_else(|| {
+            ProjectManagerError::MidiError(format!("Device not found: {}", device_id))
+        })?;
+
+        // Create the controller
+        let user_profiles = self.device_registry.user_profiles();
+        let controller =
+            crate::midi::controller::ControllerFactory::create_controller(device, &user_profiles)?;
+
+        self.attach_controller(device_id, controller)
+    }
+
+    /// Connect a simulated Launchpad in place of hardware
+    ///
+    /// Only builds with the `simulator` feature offer it, so development builds
+    /// and scripts can play the grid without a device.
+    fn connect_simulator(&self) -> Result<()> {
+        if !cfg!(any(test, feature = "simulator")) {
+            return Err(ProjectManagerError::MidiError(
+                "This build has no controller simulator".to_string(),
+            ));
+        }
+
+        let simulator = SimulatedLaunchpad::new();
+        self.attach_controller(SIMULATOR_DEVICE_ID, Box::new(simulator.clone()))?;
+        *self.simulator.lock().unwrap() = Some(simulator);
+
+        Ok(())
+    }
+
+    /// The connected simulated Launchpad, to press its pads and read its lights
+    pub fn simulator(&self) -> Result<SimulatedLaunchpad> {
+        self.simulator
+            .lock()
+            .unwrap()
+            .clone()
+            .filter(SimulatedLaunchpad::is_connected)
+            .ok_or_else(|| {
+                ProjectManagerError::ControllerNotConnected(Some(SIMULATOR_DEVICE_ID.to_string()))
+            })
+    }
+
+    /// Connect a controller that has already been created
+    ///
+    /// This is how tests connect extra `SimulatedLaunchpad`s, which have no
+    /// device in the registry.
+    pub fn attach_controller(
+        &self,
+        device_id: &str,
+        mut controller: Box<dyn GridController>,
+    ) -> Result<()> {
+        // Reconnecting a controller starts it afresh
+        self.release_controllers(Some(device_id))?;
+
+        // Register event callback
+        let this = Arc::new(self.clone());
+        let id = device_id.to_string();
+
+        // Clone references needed for the callback instead of the controller itself
+        let controller_ref = Arc::new(Mutex::new(controller.clone_box())); // Use clone_box here
+
+        controller.set_event_callback(Arc::new(move |event| {
+            match event {
+                ControllerEvent::PadPressed(grid_id, velocity) => {
+                    // Translate controller grid ID to its pad on the grid
+                    let pad = controller_ref
+                        .lock()
+                        .ok()
+                        .and_then(|c| c.map_to_app_grid_id(grid_id));
+
+                    if let Some(pad) = pad {
+                        let _ = this.press_controller_pad(&id, grid_id, pad, velocity);
+                    }
+                }
+                ControllerEvent::PadPressure(grid_id, pressure) => {
+                    let _ = this.pad_pressure(&id, grid_id, pressure);
+                }
+                ControllerEvent::PadReleased(grid_id) => {
+                    // Keyed by controller pad, so scrolling or switching banks while
+                    // holding still releases the right scene
+                    let _ = this.release_pad(&id, grid_id);
+                }
+                ControllerEvent::ButtonPressed(button_id) => {
+                    let role = controller_ref
+                        .lock()
+                        .ok()
+                        .and_then(|c| c.button_role(button_id));
+
+                    let _ = this.press_controller_button(&id, role, button_id);
+                }
+                ControllerEvent::ButtonReleased(button_id) => {
+                    let role = controller_ref
+                        .lock()
+                        .ok()
+                        .and_then(|c| c.button_role(button_id));
+
+                    if this.is_shift_button(role, button_id) {
+                        let _ = this.set_shift(&id, false);
+                    }
+                }
+                // Physical faders and knobs ride the same CCs as the virtual faders
+                ControllerEvent::FaderMoved(fader, value)
+                | ControllerEvent::KnobTurned(fader, value) => {
+                    let _ = this.move_fader(fader as usize, value);
+                }
+            }
+        }));
+
+        // Connect after registering the callback, since the input callback captures it
+        controller.connect()?;
+
+        let (layout, bank_count) = self
+            .active_project
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or((GridSize::default(), 1), |project| {
+                (project.grid_size, project.banks.len())
+            });
+
+        // Store the controller
+        {
+            let mut connected = ConnectedController::new(device_id, controller);
+            let mut controllers = self.controllers.lock().unwrap();
+
+            let last = controllers
+                .last()
+                .and_then(|last| Some((last.bank, last.viewport, last.size()?)));
+            if let Some(size) = connected.size() {
+                (connected.bank, connected.viewport) = placement(last, size, layout, bank_count);
+            }
+
+            controllers.push(connected);
+        }
+
+        // Remember the controller so the project can connect it next time
+        {
+            let mut active_project = self.active_project.lock().unwrap();
+            if let Some(project) = active_project.as_mut() {
+                let controllers = &mut project.settings.default_controller_devices;
+                if !controllers.iter().any(|id| id == device_id) {
+                    controllers.push(device_id.to_string());
+                    self.storage.save_project(project)?;
+                }
+            }
+        }
+
+        // Update the controller grid
+        self.update_controller_grid()?;
+        self.start_led_feedback();
+
+        // Its input no longer counts as a MIDI mapping input
+        self.restart_learn_inputs()?;
+
+        Ok(())
+    }
+
+    /// Handle a pad pressed on a controller
+    ///
+    /// `grid_id` is the controller's own pad ID and `pad` the pad's position on
+    /// the controller's grid.
+    fn press_controller_pad(
+        &self,
+        device_id: &str,
+        grid_id: u8,
+        pad: u8,
+        velocity: u8,
+    ) -> Result<()> {
+        let Some((size, bank, viewport, fader_mode, shifted)) = self
+            .with_controller(Some(device_id), |controller| {
+                Some((
+                    controller.size()?,
+                    controller.bank,
+                    controller.viewport,
+                    controller.fader_mode,
+                    controller.shift.is_active(),
+                ))
+            })
+            .flatten()
+        else {
+            return Ok(());
+        };
+
+        // In fader mode the pad sets its column's fader
+        if fader_mode {
+            return self.press_fader_pad(size, pad);
+        }
+
+        // Find the scene under the pad in the controller's bank
+        let (position, scene_id) = {
+            let active_project = self.active_project.lock().unwrap();
+            let Some(project) = active_project.as_ref() else {
+                return Ok(());
+            };
+
+            let position = viewport.project_position(pad, size, project.grid_size);
+            let scene_id = position
+                .and_then(|position| project.get_scene_at_grid(bank, position))
+                .map(|scene| scene.id.clone());
+
+            (position, scene_id)
+        };
+
+        // With shift held the pad edits the grid instead
+        if shifted {
+            return self.press_shift_pad(device_id, bank, grid_id, position, scene_id.is_some());
+        }
+
+        match scene_id {
+            // Trigger the scene according to its pad mode
+            Some(scene_id) => self.press_pad(device_id, grid_id, position, &scene_id, velocity),
+            None => Ok(()),
+        }
+    }
+
+    /// Handle a button pressed on a controller, with the role the controller
+    /// gives it
+    fn press_controller_button(
+        &self,
+        device_id: &str,
+        role: Option<ButtonRole>,
+        button_id: u8,
+    ) -> Result<()> {
+        if self.is_shift_button(role, button_id) {
+            return self.set_shift(device_id, true);
+        }
+
+        // Arrows scroll the controller around a larger grid
+        let scroll = match role {
+            Some(ButtonRole::Up) => Some((1, 0)),
+            Some(ButtonRole::Down) => Some((-1, 0)),
+            Some(ButtonRole::Left) => Some((0, -1)),
+            Some(ButtonRole::Right) => Some((0, 1)),
+            _ => None,
+        };
+        if let Some((rows, columns)) = scroll {
+            return self
+                .scroll_viewport(Some(device_id), rows, columns)
+                .map(|_| ());
+        }
+
+        // Bank buttons past the project's last bank do nothing
+        if let Some(ButtonRole::Bank(bank)) = role {
+            return self.select_controller_bank(device_id, bank);
+        }
+
+        let is_fader_button = role == Some(ButtonRole::FaderMode)
+            || self
+                .active_project
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_some_and(|p| p.settings.fader_mode_button == Some(button_id));
+        if is_fader_button {
+            let enabled = !self.fader_mode(Some(device_id));
+            return self.set_fader_mode(Some(device_id), enabled);
+        }
+
+        let is_panic_role = role == Some(ButtonRole::Panic);
+
+        let panic_defaults = self.active_project.lock().unwrap().as_ref().and_then(|p| {
+            (is_panic_role || p.settings.panic_button == Some(button_id))
+                .then_some(p.settings.panic_sends_defaults)
+        });
+
+        match panic_defaults {
+            Some(send_defaults) => self.panic(send_defaults),
+            None => Ok(()),
+        }
+    }
+
+    /// Update the controller grids with current scene assignments
+    fn update_controller_grid(&self) -> Result<()> {
+        self.refresh_controller_leds(true)
+    }
+
+    /// Show the current scene assignments and playback state on every controller
+    ///
+    /// Unless forced, nothing is sent to a controller whose lights haven't
+    /// changed since the last refresh.
+    fn refresh_controller_leds(&self, force: bool) -> Result<()> {
+        // Take the engine's status first; activating a scene locks the project
+        // before the engine
+        let status = self.midi_engine.lock().unwrap().status();
+        let active_scene_ids = self.active_scene_ids();
+        let any_faders = self
+            .controllers
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|controller| controller.fader_mode);
+        let fader_values = any_faders.then(|| self.midi_engine.lock().unwrap().current_values());
+
+        let active_project = self.active_project.lock().unwrap();
+        let Some(project) = active_project.as_ref() else {
+            return Ok(());
+        };
+
+        // A controller that fails to update shouldn't stop the others
+        let mut result = Ok(());
+        for connected in self.controllers.lock().unwrap().iter_mut() {
+            let Some(size) = connected.size() else {
+                continue;
+            };
+
+            // Controllers that can't flash their own LEDs show queued pads for the
+            // first half of each beat
+            let flash_off = !connected.controller.animates_leds() && status.beat.fract() >= 0.5;
+
+            // Build the whole grid so the controller can send it in one batch
+            let pads: Vec<(u8, Color, PadState)> =
+                match fader_values.as_ref().filter(|_| connected.fader_mode) {
+                    Some(values) => size
+                        .positions()
+                        .map(|pad| fader_pad(project, size, pad, values))
+                        .collect(),
+                    None => scene_pads(
+                        project,
+                        connected,
+                        size,
+                        &active_scene_ids,
+                        &status,
+                        flash_off,
+                    ),
+                };
+
+            // Light the button of the bank the controller shows
+            let mut buttons: Vec<(u8, Color)> = connected
+                .controller
+                .bank_buttons()
+                .into_iter()
+                .map(|(button_id, button_bank)| {
+                    let color = if button_bank == connected.bank {
+                        Color::WHITE
+                    } else {
+                        Color::BLACK
+                    };
+                    (button_id, color)
+                })
+                .collect();
+
+            if let Some(button_id) = project.settings.beat_indicator_button {
+                buttons.push((button_id, beat_indicator_color(status.beat)));
+            }
+
+            let leds = ControllerLeds { pads, buttons };
+            if !force && connected.last_leds.as_ref() == Some(&leds) {
+                continue;
+            }
+
+            if let Err(e) = connected.show(leds) {
+                result = Err(e.into());
+            }
+        }
+
+        result
+    }
+
+    /// Keep the controllers' lights following playback while any are connected
+    fn start_led_feedback(&self) {
+        let mut running = self.led_feedback.lock().unwrap();
+        if *running {
+            return;
+        }
+        *running = true;
+
+        let this = self.clone();
+        thread::spawn(move || loop {
+            thread::sleep(LED_REFRESH);
+
+            // Stop once every controller has been disconnected. The flag is held
+            // while checking so a controller connected meanwhile can't be missed.
+            {
+                let mut running = this.led_feedback.lock().unwrap();
+                if this.controllers.lock().unwrap().is_empty() {
+                    *running = false;
+                    break;
+                }
+            }
+
+            this.send_controller_clock();
+            let _ = this.refresh_controller_leds(false);
+        });
+    }
+
+    /// Send MIDI clock from the engine's beat clock to controllers that animate
+    /// their LEDs, so their flashing and pulsing keeps time with the beat
+    fn send_controller_clock(&self) {
+        let beat = self.midi_engine.lock().unwrap().status().beat;
+        let tick = (beat * CLOCK_TICKS_PER_BEAT as f64).floor() as i64;
+
+        for connected in self.controllers.lock().unwrap().iter_mut() {
+            if !connected.controller.animates_leds() {
+                continue;
+            }
+
+            // Catch up on the ticks since the last refresh, starting afresh when
+            // the beat clock jumps, such as when it syncs to Link
+            let ticks = match connected.clock_tick {
+                Some(last) if (0..=CLOCK_TICKS_PER_BEAT).contains(&(tick - last)) => tick - last,
+                _ => 1,
+            };
+            for _ in 0..ticks {
+                if connected.controller.send_clock().is_err() {
+                    break;
+                }
+            }
+            connected.clock_tick = Some(tick);
+        }
+    }
+
+    /// Disconnect a controller, or every controller without a device ID
+    ///
+    /// Disconnected controllers are no longer connected with the project.
+    pub fn disconnect_controller(&self, device_id: Option<&str>) -> Result<()> {
+        {
+            let mut active_project = self.active_project.lock().unwrap();
+            if let Some(project) = active_project.as_mut() {
+                let controllers = &mut project.settings.default_controller_devices;
+                let count = controllers.len();
+                controllers.retain(|id| device_id.is_some_and(|device_id| id != device_id));
+                if controllers.len() != count {
+                    self.storage.save_project(project)?;
+                }
+            }
+        }
+
+        self.release_controllers(device_id)
+    }
+
+    /// Disconnect a controller, or every controller without a device ID, leaving
+    /// the project's settings alone
+    fn release_controllers(&self, device_id: Option<&str>) -> Result<()> {
+        let removed: Vec<ConnectedController> = {
+            let mut controllers = self.controllers.lock().unwrap();
+            let (removed, kept) =
+                std::mem::take(&mut *controllers)
+                    .into_iter()
+                    .partition(|controller| {
+                        device_id.is_none() || device_id == Some(controller.device_id.as_str())
+                    });
+            *controllers = kept;
+
+            removed
+        };
+
+        // Unplugged controllers are forgotten too, so they don't come back
+        self.unplugged.lock().unwrap().retain(|(_, controller)| {
+            !(device_id.is_none() || device_id == Some(controller.device_id.as_str()))
+        });
+
+        // Disconnect outside the lock, since a controller's input callback may be
+        // waiting on it
+        for mut connected in removed {
+            connected.controller.disconnect()?;
+        }
+
+        // Its input can be used for MIDI mappings now
+        self.restart_learn_inputs()
+    }
+
+    /// Send a single CC value
+    pub fn send_cc(&self, channel: u8, cc_number: u8, value: u8) -> Result<()> {
+        let midi_engine = self.midi_engine.lock().unwrap();
+
+        midi_engine.send_command(MidiCommand::SendCC {
+            channel,
+            cc_number,
+            value,
+        })?;
+
+        Ok(())
+    }
+
+    /// Get the thru routes of the active project
+    pub fn list_thru_routes(&self) -> Result<Vec<ThruRoute>> {
+        let active_project = self.active_project.lock().unwrap();
+
+        match &*active_project {
+            Some(project) => Ok(project.thru_routes.clone()),
+            None => Err(ProjectManagerError::NoActiveProject),
+        }
+    }
+
+    /// Add or replace a thru route in the active project and restart routing
+    pub fn add_thru_route(&self, mut route: ThruRoute) -> Result<String> {
+        if route.id.is_empty() {
+            route.id = uuid::Uuid::new_v4().to_string();
+        }
+        let id = route.id.clone();
+
+        let routes = {
+            let mut active_project = self.active_project.lock().unwrap();
+            let project = active_project
+                .as_mut()
+                .ok_or(ProjectManagerError::NoActiveProject)?;
+
+            project.add_thru_route(route);
+            project.update_timestamp();
+            self.storage.save_project(project)?;
+
+            project.thru_routes.clone()
+        };
+
+        self.restart_thru(&routes)?;
+
+        Ok(id)
+    }
+
+    /// Remove a thru route from the active project and restart routing
+    pub fn remove_thru_route(&self, id: &str) -> Result<()> {
+        let routes = {
+            let mut active_project = self.active_project.lock().unwrap();
+            let project = active_project
+                .as_mut()
+                .ok_or(ProjectManagerError::NoActiveProject)?;
+
+            project.remove_thru_route(id);
+            project.update_timestamp();
+            self.storage.save_project(project)?;
+
+            project.thru_routes.clone()
+        };
+
+        self.restart_thru(&routes)
+    }
+
+    /// Connect the outputs used by the given routes and restart the thru router
+    ///
+    /// Routes whose devices are missing are skipped and reported in the error,
+    /// while the rest keep running.
+    fn restart_thru(&self, routes: &[ThruRoute]) -> Result<()> {
+        let mut skipped = Vec::new();
+        let mut running = Vec::new();
+        let outputs = {
+            let mut midi_engine = self.midi_engine.lock().unwrap();
+
+            for route in routes.iter().filter(|r| r.enabled) {
+                match connect_output(&mut midi_engine, &route.output_device, false) {
+                    Ok(_) => running.push(route.clone()),
+                    Err(e) => skipped.push(e),
+                }
+            }
+
+            // Outputs opened only for removed or disabled routes are closed
+            let used: Vec<&str> = running.iter().map(|r| r.output_device.as_str()).collect();
+            midi_engine.remove_unused_thru_outputs(&used);
+
+            midi_engine.output_handle()
+        };
+
+        if let Err(e) = self.thru_router.lock().unwrap().start(&running, outputs) {
+            skipped.push(e);
+        }
+
+        if skipped.is_empty() {
+            Ok(())
+        } else {
+            Err(skipped.join("; ").into())
+        }
+    }
+
+    /// Get the active project's MIDI mappings
+    pub fn list_midi_mappings(&self) -> Result<Vec<MidiMapping>> {
+        let active_project = self.active_project.lock().unwrap();
+
+        match &*active_project {
+            Some(project) => Ok(project.midi_mappings.clone()),
+            None => Err(ProjectManagerError::NoActiveProject),
+        }
+    }
+
+    /// Add or replace a MIDI mapping in the active project
+    pub fn add_midi_mapping(&self, mapping: MidiMapping) -> Result<String> {
+        let id = self.store_midi_mapping(mapping)?;
+        self.restart_learn_inputs()?;
+
+        Ok(id)
+    }
+
+    /// Remove a MIDI mapping from the active project
+    pub fn remove_midi_mapping(&self, id: &str) -> Result<()> {
+        {
+            let mut active_project = self.active_project.lock().unwrap();
+            let project = active_project
+                .as_mut()
+                .ok_or(ProjectManagerError::NoActiveProject)?;
+
+            project.remove_midi_mapping(id);
+            project.update_timestamp();
+            self.storage.save_project(project)?;
+        }
+
+        self.restart_learn_inputs()
+    }
+
+    /// Save a mapping without touching the inputs, so it can be called from one
+    fn store_midi_mapping(&self, mut mapping: MidiMapping) -> Result<String> {
+        if mapping.id.is_empty() {
+            mapping.id = uuid::Uuid::new_v4().to_string();
+        }
+        let id = mapping.id.clone();
+
+        let mut active_project = self.active_project.lock().unwrap();
+        let project = active_project
+            .as_mut()
+            .ok_or(ProjectManagerError::NoActiveProject)?;
+
+        if let LearnAction::TriggerScene { scene_id } = &mapping.action {
+            if project.get_scene(scene_id).is_none() {
+                return Err(ProjectManagerError::InvalidSceneId(scene_id.clone()));
+            }
+        }
+
+        project.add_midi_mapping(mapping);
+        project.update_timestamp();
+        self.storage.save_project(project)?;
+
+        Ok(id)
+    }
+
+    /// Bind the next message from a MIDI input to an action
+    ///
+    /// The mapping is saved to the active project when the message arrives, and
+    /// the project changed callback is called.
+    pub fn start_midi_learn(&self, action: LearnAction) -> Result<()> {
+        {
+            let active_project = self.active_project.lock().unwrap();
+            let project = active_project
+                .as_ref()
+                .ok_or(ProjectManagerError::NoActiveProject)?;
+
+            if let LearnAction::TriggerScene { scene_id } = &action {
+                if project.get_scene(scene_id).is_none() {
+                    return Err(ProjectManagerError::InvalidSceneId(scene_id.clone()));
+                }
+            }
+        }
+
+        self.midi_learn.lock().unwrap().start(action);
+        self.restart_learn_inputs()
+    }
+
+    /// Leave learn mode without binding anything
+    pub fn cancel_midi_learn(&self) -> Result<()> {
+        self.midi_learn.lock().unwrap().cancel();
+        self.restart_learn_inputs()
+    }
+
+    /// Action waiting to be bound, if in learn mode
+    pub fn midi_learn_action(&self) -> Option<LearnAction> {
+        self.midi_learn.lock().unwrap().learning().cloned()
+    }
+
+    /// Listen on the MIDI inputs while there are mappings or learn mode is on
+    fn restart_learn_inputs(&self) -> Result<()> {
+        let listening = self.midi_learn.lock().unwrap().learning().is_some()
+            || self
+                .active_project
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_some_and(|project| !project.midi_mappings.is_empty());
+
+        if !listening {
+            self.learn_inputs.lock().unwrap().stop();
+            return Ok(());
+        }
+
+        // Controller pads and buttons are handled by their drivers
+        let excluded: Vec<String> = self
+            .controllers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|controller| controller.device_id.strip_prefix("in:").map(String::from))
+            .collect();
+        let outputs = self.midi_engine.lock().unwrap().output_handle();
+
+        let this = self.clone();
+        self.learn_inputs.lock().unwrap().start(
+            &excluded,
+            outputs,
+            Arc::new(move |input_device, message| {
+                let _ = this.handle_midi_input(input_device, message);
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    /// Handle a message from a MIDI input, learning it or performing the actions
+    /// mapped to it
+    pub fn handle_midi_input(&self, input_device: &str, message: &[u8]) -> Result<()> {
+        let Some((trigger, value)) = MidiTrigger::from_message(message) else {
+            return Ok(());
+        };
+
+        // In learn mode messages are bound rather than acted on
+        let learned = {
+            let mut midi_learn = self.midi_learn.lock().unwrap();
+            if midi_learn.learning().is_none() {
+                None
+            } else {
+                Some(midi_learn.learn(input_device, trigger, value))
+            }
+        };
+        if let Some(mapping) = learned {
+            if let Some(mapping) = mapping {
+                self.store_midi_mapping(mapping)?;
+                self.notify_project_changed();
+            }
+            return Ok(());
+        }
+
+        let mappings = self.list_midi_mappings()?;
+        let actions =
+            self.midi_learn
+                .lock()
+                .unwrap()
+                .actions(&mappings, input_device, trigger, value);
+
+        for (action, value) in actions {
+            self.perform_learn_action(action, value)?;
+        }
+
+        Ok(())
+    }
+
+    fn perform_learn_action(&self, action: LearnAction, value: u8) -> Result<()> {
+        match action {
+            LearnAction::TriggerScene { scene_id } => self.activate_scene(&scene_id),
+            LearnAction::NextScene | LearnAction::PreviousScene => {
+                let bank = *self.current_bank.lock().unwrap();
+                let scene_ids: Vec<String> = {
+                    let active_project = self.active_project.lock().unwrap();
+                    let project = active_project
+                        .as_ref()
+                        .ok_or(ProjectManagerError::NoActiveProject)?;
+
+                    project
+                        .grid_size
+                        .positions()
+                        .filter_map(|position| project.get_scene_at_grid(bank, position))
+                        .map(|scene| scene.id.clone())
+                        .collect()
+                };
+
+                let current = self.active_scene_ids().last().cloned();
+                let forward = action == LearnAction::NextScene;
+                match step_scene(&scene_ids, current.as_deref(), forward) {
+                    Some(scene_id) => self.activate_scene(scene_id),
+                    None => Ok(()),
+                }
+            }
+            LearnAction::Panic => {
+                let send_defaults = self
+                    .active_project
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .is_some_and(|project| project.settings.panic_sends_defaults);
+                self.panic(send_defaults)
+            }
+            LearnAction::BankUp | LearnAction::BankDown => {
+                let bank = *self.current_bank.lock().unwrap();
+                let bank_count = self
+                    .active_project
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .map_or(0, |project| project.banks.len());
+
+                // Stop at the first and last banks
+                let next = if action == LearnAction::BankUp {
+                    bank.checked_add(1)
+                        .filter(|&next| (next as usize) < bank_count)
+                } else {
+                    bank.checked_sub(1)
+                };
+                match next {
+                    Some(next) => self.select_bank(next),
+                    None => Ok(()),
+                }
+            }
+            LearnAction::TapTempo => {
+                let tempo = self.midi_learn.lock().unwrap().tap(Instant::now());
+                match tempo {
+                    Some(tempo) => self.set_tempo(tempo),
+                    None => Ok(()),
+                }
+            }
+            LearnAction::MorphAmount => {
+                let Some(scene_id) = self.active_scene_ids().last().cloned() else {
+                    return Ok(());
+                };
+
+                let scene = self.scene(&scene_id)?;
+                self.midi_engine
+                    .lock()
+                    .unwrap()
+                    .send_command(MidiCommand::SetSceneAmount {
+                        scene,
+                        amount: value as f64 / 127.0,
+                    })?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Get incoming messages recently suppressed as feedback-loop echoes
+    pub fn suppressed_events(&self) -> Vec<SuppressedEvent> {
+        self.midi_engine.lock().unwrap().suppressed_events()
+    }
+
+    /// Get the active project's echo-suppression windows
+    pub fn list_echo_windows(&self) -> Result<Vec<EchoWindow>> {
+        let active_project = self.active_project.lock().unwrap();
+
+        match &*active_project {
+            Some(project) => Ok(project.settings.echo_windows.clone()),
+            None => Err(ProjectManagerError::NoActiveProject),
+        }
+    }
+
+    /// Set how long echoes from an output are looked for on an input, or remove
+    /// the pair's window with `None`
+    ///
+    /// Ports of the same device already get the default window; this is for
+    /// slower devices and for loops between different devices.
+    pub fn set_echo_window(
+        &self,
+        input_device: &str,
+        output_device: &str,
+        window_ms: Option<u32>,
+    ) -> Result<()> {
+        let windows = {
+            let mut active_project = self.active_project.lock().unwrap();
+            let project = active_project
+                .as_mut()
+                .ok_or(ProjectManagerError::NoActiveProject)?;
+
+            let windows = &mut project.settings.echo_windows;
+            windows.retain(|w| w.input_device != input_device || w.output_device != output_device);
+            if let Some(window_ms) = window_ms {
+                windows.push(EchoWindow {
+                    input_device: input_device.to_string(),
+                    output_device: output_device.to_string(),
+                    window_ms,
+                });
+            }
+            project.update_timestamp();
+            self.storage.save_project(project)?;
+
+            project.settings.echo_windows.clone()
+        };
+
+        self.midi_engine.lock().unwrap().set_echo_windows(&windows);
+
+        Ok(())
+    }
+
+    /// Import scenes from the CC automation in a Standard MIDI File
+    ///
+    /// Creates one scene per snapshot point in the active project and returns their IDs.
+    pub fn import_midi_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &ImportOptions,
+    ) -> Result<Vec<String>> {
+        let bytes = std::fs::read(path).map_err(StorageError::from)?;
+        let file = MidiFile::parse(&bytes)?;
+
+        let mut active_project = self.active_project.lock().unwrap();
+
+        match &mut *active_project {
+            Some(project) => {
+                let ids = import_scenes(&file, options, project)?;
+                self.storage.save_project(project)?;
+                Ok(ids)
+            }
+            None => Err(ProjectManagerError::NoActiveProject),
+        }
+    }
+
+    /// Start recording everything the engine sends
+    pub fn start_recording(&self) -> Result<()> {
+        self.midi_engine.lock().unwrap().start_recording();
+        Ok(())
+    }
+
+    /// Stop recording and write the capture to a Standard MIDI File
+    ///
+    /// Returns the number of messages written.
+    /// If the file can't be written, the recording keeps running so the take
+    /// isn't lost.
+    pub fn stop_recording<P: AsRef<Path>>(&self, path: P, split: TrackSplit) -> Result<usize> {
+        // Serialize with no lock held so the engine keeps running meanwhile
+        let recorder = self
+            .midi_engine
+            .lock()
+            .unwrap()
+            .stop_recording()
+            .ok_or_else(|| ProjectManagerError::MidiError("Not recording".to_string()))?;
+        let bytes = recorder.to_midi_file(split).to_bytes();
+
+        if let Err(e) = std::fs::write(path, bytes) {
+            self.midi_engine.lock().unwrap().resume_recording(recorder);
+            return Err(StorageError::from(e).into());
+        }
+
+        Ok(recorder.message_count())
+    }
+
+    /// Check whether a recording is in progress
+    pub fn is_recording(&self) -> bool {
+        self.midi_engine.lock().unwrap().is_recording()
+    }
+
+    /// Stop everything and silence all outputs
+    ///
+    /// Optionally follows up with the default value of every CC definition in the
+    /// active project. The active scene is cleared because the outputs no longer
+    /// reflect it.
+    pub fn panic(&self, send_defaults: bool) -> Result<()> {
+        let defaults = if send_defaults {
+            let active_project = self.active_project.lock().unwrap();
+            active_project
+                .as_ref()
+                .map(|project| project.default_cc_values())
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
+        self.midi_engine.lock().unwrap().panic(&defaults)?;
+        for controller in self.controllers.lock().unwrap().iter_mut() {
+            controller.pad_play.clear();
+        }
+        self.active_scenes.lock().unwrap().clear();
+
+        Ok(())
+    }
+
+    /// Give a controller button a job in the active project, or take it away
+    /// with None
+    pub fn set_controller_button(
+        &self,
+        button: ControllerButton,
+        button_id: Option<u8>,
+    ) -> Result<()> {
+        let mut active_project = self.active_project.lock().unwrap();
+        let project = active_project
+            .as_mut()
+            .ok_or(ProjectManagerError::NoActiveProject)?;
+
+        // Refreshes only light buttons with a job, so turn off the one losing it
+        let previous = *project.settings.button_mut(button);
+        if let Some(previous) = previous.filter(|&previous| Some(previous) != button_id) {
+            for connected in self.controllers.lock().unwrap().iter_mut() {
+                let _ = connected
+                    .controller
+                    .set_button_color(previous, Color::BLACK);
+            }
+        }
+
+        *project.settings.button_mut(button) = button_id;
+        project.update_timestamp();
+        self.storage.save_project(project)?;
+
+        Ok(())
+    }
+
+    /// Choose whether a panic from the controller or the app also resets every
+    /// CC to its default
+    pub fn set_panic_sends_defaults(&self, enabled: bool) -> Result<()> {
+        let mut active_project = self.active_project.lock().unwrap();
+        let project = active_project
+            .as_mut()
+            .ok_or(ProjectManagerError::NoActiveProject)?;
+
+        project.settings.panic_sends_defaults = enabled;
+        project.update_timestamp();
+        self.storage.save_project(project)?;
+
+        Ok(())
+    }
+
+    /// Import a project from a file
+    pub fn import_project<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        let project = self.storage.import_project(path)?;
+        let id = project.id.clone();
+
+        self.open_project(project)?;
+
+        Ok(id)
     }
 
     /// Export the active project to a file
@@ -412,48 +2518,517 @@ impl ProjectManager {
 
     /// Close the active project
     pub fn close_active_project(&self) -> Result<()> {
-        let mut active_project = self.active_project.lock().unwrap();
-        let mut active_scene_id = self.active_scene_id.lock().unwrap();
+        // Stopped first, since its thread waits on the engine
+        if let Some(link) = self.link.lock().unwrap().as_mut() {
+            link.stop();
+        }
 
-        *active_project = None;
-        *active_scene_id = None;
+        *self.active_project.lock().unwrap() = None;
+        self.active_scenes.lock().unwrap().clear();
+
+        // With no mappings left there's nothing to listen for
+        self.midi_learn.lock().unwrap().cancel();
+        self.learn_inputs.lock().unwrap().stop();
+
+        self.thru_router.lock().unwrap().stop();
+        {
+            let mut midi_engine = self.midi_engine.lock().unwrap();
+            midi_engine.remove_unused_thru_outputs(&[]);
+            midi_engine.set_echo_windows(&[]);
+        }
 
-        // Clear controller grid
-        if let Ok(mut controller_guard) = self.controller.lock() {
-            if let Some(controller) = &mut *controller_guard {
-                let _ = controller.clear();
+        // Clear the controller grids, forgetting held pads and shift
+        if let Ok(mut controllers) = self.controllers.lock() {
+            for connected in controllers.iter_mut() {
+                connected.pad_play.clear();
+                connected.shift.set_shift(false);
+                let _ = connected.controller.clear();
             }
         }
 
         Ok(())
     }
 
-    pub fn get_midi_devices() -> Result<Vec<MidiDevice>> {
-        let device_registry = DeviceRegistryFactory::create().map_err(|e| e.to_string())?;
+    /// Devices found by the latest scan
+    pub fn get_midi_devices(&self) -> Vec<MidiDevice> {
+        self.device_registry.get_all_devices()
+    }
+
+    /// Why user controller profile files were skipped, one message per file
+    pub fn skipped_controller_profiles(&self) -> Vec<String> {
+        self.device_registry.skipped_profiles()
+    }
 
-        Ok(device_registry.get_all_devices())
+    /// Read the user controller profiles again, returning how many were loaded
+    pub fn reload_controller_profiles(&self) -> usize {
+        self.device_registry.reload_profiles().len()
     }
 }
 
 impl Clone for ProjectManager {
     fn clone(&self) -> Self {
         ProjectManager {
-            storage: ProjectStorage::init().unwrap(),
+            storage: self.storage.clone(),
             device_registry: Arc::clone(&self.device_registry),
             midi_engine: Arc::clone(&self.midi_engine),
             active_project: Arc::clone(&self.active_project),
-            active_scene_id: Arc::clone(&self.active_scene_id),
-            controller: Arc::clone(&self.controller),
+            active_scenes: Arc::clone(&self.active_scenes),
+            controllers: Arc::clone(&self.controllers),
+            unplugged: Arc::clone(&self.unplugged),
+            present_devices: Arc::clone(&self.present_devices),
+            current_bank: Arc::clone(&self.current_bank),
+            project_changed: Arc::clone(&self.project_changed),
+            warnings: Arc::clone(&self.warnings),
+            warning_added: Arc::clone(&self.warning_added),
+            led_feedback: Arc::clone(&self.led_feedback),
+            thru_router: Arc::clone(&self.thru_router),
+            midi_learn: Arc::clone(&self.midi_learn),
+            learn_inputs: Arc::clone(&self.learn_inputs),
+            link: Arc::clone(&self.link),
+            simulator: Arc::clone(&self.simulator),
         }
     }
 }
 
-// Factory for DeviceRegistry to fix compiler issues
-pub struct DeviceRegistryFactory;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::controller::{LaunchpadMk2, MIDI_CLOCK};
+    use crate::midi::simulator::Lighting;
+    use crate::models::cc::TransitionCurve;
+    use crate::models::scene::{ExpressionRange, PadMode};
+    use tempfile::{tempdir, TempDir};
+
+    /// Manager with a running engine, saving projects to a temporary directory
+    fn new_manager() -> (ProjectManager, TempDir) {
+        let temp_dir = tempdir().unwrap();
+        let storage = ProjectStorage::new(temp_dir.path().to_path_buf()).unwrap();
+        let midi_engine = Arc::new(Mutex::new(MidiEngine::new().unwrap()));
+        midi_engine.lock().unwrap().start().unwrap();
+        let manager = ProjectManager::new(storage, Arc::new(DeviceRegistry::new()), midi_engine);
+        (manager, temp_dir)
+    }
+
+    /// Manager with a new project open and a simulated Launchpad connected
+    fn manager_with_simulator() -> (ProjectManager, SimulatedLaunchpad, TempDir) {
+        let (manager, temp_dir) = new_manager();
+        manager.create_project("Simulated", None).unwrap();
+
+        manager.connect_controller(SIMULATOR_DEVICE_ID).unwrap();
+        let simulator = manager.simulator().unwrap();
+        assert!(simulator.is_connected());
+
+        (manager, simulator, temp_dir)
+    }
+
+    /// Wait for the engine's beat clock to run at a tempo, returning the tempo
+    /// it ended up at
+    fn wait_for_tempo(manager: &ProjectManager, tempo: f64) -> f64 {
+        // The engine picks up the tempo on its own thread
+        let deadline = Instant::now() + Duration::from_secs(1);
+        loop {
+            let current = manager.midi_engine.lock().unwrap().status().tempo;
+            if current == tempo || Instant::now() >= deadline {
+                return current;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// Wait for the LED refresh to light a pad on the simulator
+    fn wait_for_pad(
+        simulator: &SimulatedLaunchpad,
+        row: u8,
+        column: u8,
+        lighting: Lighting,
+    ) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            let led = simulator.pad(row, column);
+            if led.is_some_and(|led| led.lighting == lighting && led.color != Color::BLACK) {
+                return true;
+            }
+            thread::sleep(LED_REFRESH);
+        }
+        false
+    }
+
+    #[test]
+    fn test_simulated_controller_end_to_end() {
+        let (manager, simulator, _temp_dir) = manager_with_simulator();
+
+        // Assigning scenes lights their pads
+        let first = manager.create_scene("First", None).unwrap();
+        let second = manager.create_scene("Second", None).unwrap();
+        manager.assign_scene_to_grid(&first, 0).unwrap();
+        manager.assign_scene_to_grid(&second, 1).unwrap();
+        assert!(wait_for_pad(&simulator, 0, 0, Lighting::Static));
+        assert!(wait_for_pad(&simulator, 0, 1, Lighting::Static));
+        assert_eq!(simulator.pad(0, 2).map(|led| led.color), Some(Color::BLACK));
+
+        // Activating a scene pulses its pad
+        manager.activate_scene(&first).unwrap();
+        assert!(wait_for_pad(&simulator, 0, 0, Lighting::Pulsing));
+
+        // Pressing a pad on the controller plays its scene
+        simulator.press_pad(0, 1, 127);
+        simulator.release_pad(0, 1);
+        assert!(manager.active_scene_ids().contains(&second));
+        assert!(wait_for_pad(&simulator, 0, 1, Lighting::Pulsing));
+
+        manager.disconnect_controller(None).unwrap();
+        assert!(!simulator.is_connected());
+        assert!(manager.simulator().is_err());
+    }
+
+    #[test]
+    fn test_clock_reaches_animating_controllers() {
+        let (manager, _temp_dir) = new_manager();
+        manager.create_project("Clocked", None).unwrap();
+
+        let ticks = Arc::new(Mutex::new(0));
+        let counted = Arc::clone(&ticks);
+        let device = MidiDevice {
+            id: "launchpad".to_string(),
+            name: "Launchpad MK2".to_string(),
+            is_input: true,
+            is_controller: true,
+            model: Some("Launchpad MK2".to_string()),
+            firmware: None,
+        };
+        let launchpad = LaunchpadMk2::in_memory(
+            device,
+            Arc::new(move |message: &[u8]| {
+                if message == [MIDI_CLOCK] {
+                    *counted.lock().unwrap() += 1;
+                }
+            }),
+        );
+        manager
+            .attach_controller("launchpad", Box::new(launchpad))
+            .unwrap();
+
+        // At 120 BPM the beat clock runs at 48 ticks a second
+        thread::sleep(Duration::from_millis(500));
+        let sent = *ticks.lock().unwrap();
+        assert!((12..=48).contains(&sent), "sent {} ticks", sent);
+
+        manager.disconnect_controller(None).unwrap();
+    }
+
+    #[test]
+    fn test_project_remembers_every_controller() {
+        let (manager, _simulator, _temp_dir) = manager_with_simulator();
+        manager
+            .attach_controller("second", Box::new(SimulatedLaunchpad::new()))
+            .unwrap();
+
+        let remembered = || {
+            let project_id = manager.get_active_project().unwrap().id;
+            let saved = manager.storage.load_project(&project_id).unwrap();
+            saved.settings.default_controller_devices
+        };
+        assert_eq!(remembered(), vec!["simulator", "second"]);
+
+        // Controllers disconnected on purpose aren't connected next time
+        manager.disconnect_controller(Some("simulator")).unwrap();
+        assert_eq!(remembered(), vec!["second"]);
+    }
+
+    #[test]
+    fn test_update_scene_persists_edits() {
+        let (manager, _temp_dir) = new_manager();
+
+        let project_id = manager.create_project("Edits", None).unwrap();
+        let id = manager.create_scene("Drop", None).unwrap();
+        manager.assign_scene_to_grid(&id, 3).unwrap();
+
+        let mut scene = manager.scene(&id).unwrap();
+        scene.pad_mode = PadMode::Momentary;
+        scene.release_transition_ms = Some(250);
+        scene.velocity = Some(ExpressionRange {
+            curve: TransitionCurve::Exponential,
+            min: 0.2,
+            max: 0.8,
+        });
+        scene.pressure = Some(ExpressionRange::default());
+        manager.update_scene(scene).unwrap();
+
+        let saved = manager.storage.load_project(&project_id).unwrap();
+        let scene = saved.get_scene(&id).unwrap();
+        assert_eq!(scene.pad_mode, PadMode::Momentary);
+        assert_eq!(scene.release_transition_ms, Some(250));
+        assert_eq!(
+            scene.velocity.map(|v| (v.curve, v.min, v.max)),
+            Some((TransitionCurve::Exponential, 0.2, 0.8))
+        );
+        assert!(scene.pressure.is_some());
+
+        // Ranges go from none of the scene to all of it
+        let mut out_of_range = scene.clone();
+        out_of_range.velocity = Some(ExpressionRange {
+            max: 1.5,
+            ..ExpressionRange::default()
+        });
+        assert!(manager.update_scene(out_of_range).is_err());
+
+        // Scenes join a group by name, or fall back to their column's group
+        let mut grouped = scene.clone();
+        grouped.group = Some(" Drums ".to_string());
+        manager.update_scene(grouped.clone()).unwrap();
+        assert_eq!(manager.scene(&id).unwrap().group.as_deref(), Some("Drums"));
+
+        grouped.group = Some(String::new());
+        manager.update_scene(grouped).unwrap();
+        assert_eq!(manager.scene(&id).unwrap().group, None);
+        assert_eq!(
+            saved.get_scene_at_grid(0, 3).map(|s| s.id.as_str()),
+            Some(id.as_str())
+        );
+
+        assert!(manager
+            .update_scene(Scene::new("missing", "Missing"))
+            .is_err());
+    }
+
+    /// Give a controller button a job and check it was saved with the project
+    ///
+    /// The tests use the top row's last four buttons, which have no job of their
+    /// own on a Launchpad MK2.
+    fn set_saved_button(manager: &ProjectManager, button: ControllerButton, button_id: u8) {
+        manager
+            .set_controller_button(button, Some(button_id))
+            .unwrap();
+
+        let project_id = manager.get_active_project().unwrap().id;
+        let mut saved = manager.storage.load_project(&project_id).unwrap();
+        assert_eq!(*saved.settings.button_mut(button), Some(button_id));
+    }
+
+    #[test]
+    fn test_panic_button_sends_defaults() {
+        let (manager, simulator, _temp_dir) = manager_with_simulator();
+        set_saved_button(&manager, ControllerButton::Panic, 111);
+
+        let mut cutoff = CCDefinition::new(0, 74, "Cutoff");
+        cutoff.default_value = 64;
+        if let Some(project) = manager.active_project.lock().unwrap().as_mut() {
+            project.add_cc_definition(cutoff);
+        }
+        manager.set_panic_sends_defaults(true).unwrap();
+
+        let scene = manager.create_scene("Scene", None).unwrap();
+        manager.activate_scene(&scene).unwrap();
+        simulator.press_button(111);
+        assert!(manager.active_scene_ids().is_empty());
+        assert_eq!(
+            manager
+                .midi_engine
+                .lock()
+                .unwrap()
+                .current_values()
+                .get(&(0, 74)),
+            Some(&64)
+        );
+
+        let project_id = manager.get_active_project().unwrap().id;
+        let saved = manager.storage.load_project(&project_id).unwrap();
+        assert!(saved.settings.panic_sends_defaults);
+    }
+
+    #[test]
+    fn test_fader_mode_button_toggles() {
+        let (manager, simulator, _temp_dir) = manager_with_simulator();
+        set_saved_button(&manager, ControllerButton::FaderMode, 110);
+
+        simulator.press_button(110);
+        assert!(manager.fader_mode(Some("simulator")));
+        simulator.press_button(110);
+        assert!(!manager.fader_mode(Some("simulator")));
+    }
+
+    #[test]
+    fn test_shift_button_is_held() {
+        let (manager, simulator, _temp_dir) = manager_with_simulator();
+        set_saved_button(&manager, ControllerButton::Shift, 109);
+
+        let shift_held = || {
+            manager
+                .with_controller(Some("simulator"), |controller| controller.shift.is_active())
+                .unwrap()
+        };
+        simulator.press_button(109);
+        assert!(shift_held());
+        simulator.release_button(109);
+        assert!(!shift_held());
+    }
+
+    #[test]
+    fn test_beat_indicator_button_lights() {
+        let (manager, simulator, _temp_dir) = manager_with_simulator();
+        set_saved_button(&manager, ControllerButton::BeatIndicator, 108);
+
+        assert!(simulator.button(108).is_none());
+        manager.update_controller_grid().unwrap();
+        assert!(simulator.button(108).is_some());
+    }
+
+    #[test]
+    fn test_moved_button_goes_dark() {
+        let (manager, mut simulator, _temp_dir) = manager_with_simulator();
+        manager
+            .set_controller_button(ControllerButton::BeatIndicator, Some(108))
+            .unwrap();
+        simulator.set_button_color(108, Color::RED).unwrap();
+
+        manager
+            .set_controller_button(ControllerButton::BeatIndicator, Some(109))
+            .unwrap();
+        assert_eq!(simulator.button(108), Some(Color::BLACK));
+
+        simulator.set_button_color(109, Color::RED).unwrap();
+        manager
+            .set_controller_button(ControllerButton::BeatIndicator, None)
+            .unwrap();
+        assert_eq!(simulator.button(109), Some(Color::BLACK));
+    }
+
+    #[test]
+    fn test_failed_recording_write_keeps_the_take() {
+        let (manager, temp_dir) = new_manager();
+
+        manager.start_recording().unwrap();
+        let missing = temp_dir.path().join("missing").join("take.mid");
+        assert!(manager
+            .stop_recording(&missing, TrackSplit::default())
+            .is_err());
+        assert!(manager.is_recording());
+
+        let path = temp_dir.path().join("take.mid");
+        manager
+            .stop_recording(&path, TrackSplit::default())
+            .unwrap();
+        assert!(!manager.is_recording());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_project_tempo_runs_the_clock() {
+        let (manager, _temp_dir) = new_manager();
+
+        let mut project = Project::new("Slow", None);
+        project.settings.default_tempo = 90.0;
+        manager.storage.save_project(&project).unwrap();
+        manager.load_project(&project.id).unwrap();
+
+        assert_eq!(wait_for_tempo(&manager, 90.0), 90.0);
+    }
+
+    #[test]
+    fn test_set_tempo_survives_restarting_the_clock() {
+        let (manager, _temp_dir) = new_manager();
+        let id = manager.create_project("Tapped", None).unwrap();
+
+        manager.set_tempo(100.0).unwrap();
+        assert_eq!(wait_for_tempo(&manager, 100.0), 100.0);
+
+        // Reopening the project restarts the clock at the project's tempo
+        let settings = manager.get_active_project().unwrap().settings;
+        manager.start_clock(&settings);
+        assert_eq!(wait_for_tempo(&manager, 100.0), 100.0);
+
+        let saved = manager.storage.load_project(&id).unwrap();
+        assert_eq!(saved.settings.default_tempo, 100.0);
+    }
+
+    #[test]
+    fn test_switching_projects_resets_playback() {
+        let (manager, temp_dir) = new_manager();
+        let play_scene = || {
+            let scene = manager.create_scene("Scene", None).unwrap();
+            manager.activate_scene(&scene).unwrap();
+        };
+
+        manager.create_project("First", None).unwrap();
+        play_scene();
+        let path = temp_dir.path().join("first.json");
+        manager.export_active_project(&path).unwrap();
+
+        // Every way of making a project active leaves the last one's scenes behind
+        manager.create_project("Second", None).unwrap();
+        assert!(manager.active_scene_ids().is_empty());
+
+        play_scene();
+        manager.create_from_template("Third", None).unwrap();
+        assert!(manager.active_scene_ids().is_empty());
+
+        play_scene();
+        let imported = manager.import_project(&path).unwrap();
+        assert!(manager.active_scene_ids().is_empty());
+        assert_eq!(manager.storage.last_project(), Some(imported));
+    }
+
+    #[test]
+    fn test_midi_learn_next_scene() {
+        let (manager, _temp_dir) = new_manager();
+
+        manager.create_project("Learn", None).unwrap();
+        let first = manager.create_scene("First", None).unwrap();
+        let second = manager.create_scene("Second", None).unwrap();
+        manager.assign_scene_to_grid(&first, 0).unwrap();
+        manager.assign_scene_to_grid(&second, 1).unwrap();
+
+        // Learn a footswitch without opening real inputs
+        manager
+            .midi_learn
+            .lock()
+            .unwrap()
+            .start(LearnAction::NextScene);
+        manager
+            .handle_midi_input("Pedal", &[0xB0, 64, 127])
+            .unwrap();
+        assert!(manager.midi_learn_action().is_none());
+
+        let mappings = manager.list_midi_mappings().unwrap();
+        assert_eq!(mappings.len(), 1);
+        assert_eq!(mappings[0].input_device.as_deref(), Some("Pedal"));
+
+        // Each press steps through the bank's scenes
+        manager.handle_midi_input("Pedal", &[0xB0, 64, 0]).unwrap();
+        manager
+            .handle_midi_input("Pedal", &[0xB0, 64, 127])
+            .unwrap();
+        assert_eq!(manager.active_scene_ids(), vec![first.clone()]);
+
+        manager.handle_midi_input("Pedal", &[0xB0, 64, 0]).unwrap();
+        manager
+            .handle_midi_input("Pedal", &[0xB0, 64, 127])
+            .unwrap();
+        assert_eq!(manager.active_scene_ids(), vec![second]);
+
+        // Other inputs don't match the learned mapping
+        manager.handle_midi_input("Keys", &[0xB0, 64, 127]).unwrap();
+        assert_eq!(manager.active_scene_ids().len(), 1);
+    }
 
-impl DeviceRegistryFactory {
-    pub fn create() -> Result<DeviceRegistry> {
-        crate::midi::devices::DeviceRegistryFactory::create()
-            .map_err(|e| ProjectManagerError::MidiError(e))
+    #[test]
+    fn test_close_project_resets_learn_and_shift() {
+        let (manager, simulator, _temp_dir) = manager_with_simulator();
+        manager
+            .set_controller_button(ControllerButton::Shift, Some(109))
+            .unwrap();
+        simulator.press_button(109);
+
+        manager
+            .midi_learn
+            .lock()
+            .unwrap()
+            .start(LearnAction::NextScene);
+        manager.close_active_project().unwrap();
+
+        assert!(manager.midi_learn_action().is_none());
+        assert!(!manager
+            .with_controller(Some("simulator"), |controller| controller.shift.is_active())
+            .unwrap());
     }
 }
@@ -0,0 +1,338 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::midi::engine;
+use crate::midi::smf::{MidiFile, TrackEvent};
+use crate::models::cc::TransitionCurve;
+use crate::models::project::{CCDefinition, Project};
+use crate::models::scene::Scene;
+
+/// Minimum number of CC changes between snapshots before they count as a ramp
+const MIN_RAMP_STEPS: usize = 3;
+
+/// Curves tried when fitting a ramp
+const CURVES: [TransitionCurve; 4] = [
+    TransitionCurve::Linear,
+    TransitionCurve::Exponential,
+    TransitionCurve::Logarithmic,
+    TransitionCurve::SCurve,
+];
+
+/// Where snapshots of the CC state are taken
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum SnapshotPoints {
+    /// At every marker in the file, naming each scene after its marker
+    Markers,
+    /// At the start of each listed bar (1-based)
+    Bars(Vec<u32>),
+}
+
+/// Options for importing scenes from a MIDI file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImportOptions {
+    /// Where to take snapshots
+    pub points: SnapshotPoints,
+
+    /// Encode CC ramps leading up to each snapshot as transitions
+    #[serde(default)]
+    pub detect_ramps: bool,
+
+    /// Beats per bar used to locate bar numbers
+    #[serde(default = "default_beats_per_bar")]
+    pub beats_per_bar: u32,
+}
+
+fn default_beats_per_bar() -> u32 {
+    4
+}
+
+/// A CC change at an absolute tick
+struct CCEvent {
+    tick: u32,
+    channel: u8,
+    cc_number: u8,
+    value: u8,
+}
+
+/// Create one scene per snapshot point holding the CC state at that point
+///
+/// CC definitions missing from the project are created automatically. Returns
+/// the IDs of the new scenes in snapshot order.
+pub fn import_scenes(
+    file: &MidiFile,
+    options: &ImportOptions,
+    project: &mut Project,
+) -> Result<Vec<String>, String> {
+    let ppq = file.ppq as u32;
+
+    let mut events: Vec<CCEvent> = file
+        .tracks
+        .iter()
+        .flat_map(|track| &track.events)
+        .filter_map(|timed| match timed.event {
+            TrackEvent::Midi(ref message) if message.len() == 3 && message[0] & 0xF0 == 0xB0 => {
+                Some(CCEvent {
+                    tick: timed.tick,
+                    channel: message[0] & 0x0F,
+                    cc_number: message[1],
+                    value: message[2],
+                })
+            }
+            _ => None,
+        })
+        .collect();
+    events.sort_by_key(|e| e.tick);
+
+    let mut points: Vec<(u32, String)> = match &options.points {
+        SnapshotPoints::Markers => file
+            .tracks
+            .iter()
+            .flat_map(|track| &track.events)
+            .filter_map(|timed| match &timed.event {
+                TrackEvent::Marker(name) => Some((timed.tick, name.clone())),
+                _ => None,
+            })
+            .collect(),
+        SnapshotPoints::Bars(bars) => bars
+            .iter()
+            .map(|bar| {
+                let index = bar
+                    .checked_sub(1)
+                    .ok_or_else(|| "Bars are numbered from 1".to_string())?;
+                let tick = index
+                    .checked_mul(options.beats_per_bar)
+                    .and_then(|beats| beats.checked_mul(ppq))
+                    .ok_or_else(|| format!("Bar {} is beyond the end of a MIDI file", bar))?;
+                Ok((tick, format!("Bar {}", bar)))
+            })
+            .collect::<Result<_, String>>()?,
+    };
+    points.sort_by_key(|(tick, _)| *tick);
+
+    if points.is_empty() {
+        return Err("No snapshot points found in MIDI file".to_string());
+    }
+
+    // BTreeMap keeps the CCs in a stable order
+    let mut state: BTreeMap<(u8, u8), u8> = BTreeMap::new();
+    let mut next_event = 0;
+    let mut scene_ids = Vec::new();
+
+    for (tick, name) in points {
+        let previous = state.clone();
+        let mut moves: BTreeMap<(u8, u8), Vec<(u32, u8)>> = BTreeMap::new();
+
+        while let Some(event) = events.get(next_event).filter(|e| e.tick <= tick) {
+            let key = (event.channel, event.cc_number);
+            state.insert(key, event.value);
+            moves
+                .entry(key)
+                .or_default()
+                .push((event.tick, event.value));
+            next_event += 1;
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let mut scene = Scene::new(&id, &name);
+
+        for (&(channel, cc_number), &value) in &state {
+            if project.get_cc_definition(channel, cc_number).is_none() {
+                let name = format!("CC {} (ch {})", cc_number, channel + 1);
+                project.add_cc_definition(CCDefinition::new(channel, cc_number, &name));
+            }
+
+            let definition = project.get_cc_definition(channel, cc_number).unwrap();
+            let mut cc = definition.create_cc_value(Some(value));
+            cc.transition = false;
+
+            let ramp = match (
+                previous.get(&(channel, cc_number)),
+                moves.get(&(channel, cc_number)),
+            ) {
+                (Some(&start), Some(steps)) if options.detect_ramps => {
+                    detect_ramp(start, steps, ppq)
+                }
+                _ => None,
+            };
+            if let Some((beats, curve)) = ramp {
+                cc = cc.with_transition_beats(beats, curve);
+            }
+
+            scene.add_cc(cc);
+        }
+
+        project.add_scene(scene);
+        scene_ids.push(id);
+    }
+
+    project.update_timestamp();
+
+    Ok(scene_ids)
+}
+
+/// Detect a monotonic ramp from `start` through `steps`, returning its length in
+/// beats and the best-fitting curve
+fn detect_ramp(start: u8, steps: &[(u32, u8)], ppq: u32) -> Option<(f32, TransitionCurve)> {
+    if steps.len() < MIN_RAMP_STEPS {
+        return None;
+    }
+
+    let (first_tick, _) = steps[0];
+    let (last_tick, end) = *steps.last().unwrap();
+    if end == start || last_tick == first_tick {
+        return None;
+    }
+
+    let rising = end > start;
+    let mut last_value = start;
+    for &(_, value) in steps {
+        if (rising && value < last_value) || (!rising && value > last_value) {
+            return None;
+        }
+        last_value = value;
+    }
+
+    // The first change is one step into the ramp, so the ramp began a step earlier
+    let step = (last_tick - first_tick) / (steps.len() as u32 - 1);
+    let ramp_start = first_tick.saturating_sub(step);
+    let duration = (last_tick - ramp_start) as f64;
+
+    let span = end as f64 - start as f64;
+    let error = |curve: TransitionCurve| -> f64 {
+        let curve = engine::TransitionCurve::from(curve);
+        steps
+            .iter()
+            .map(|&(tick, value)| {
+                let position = (tick - ramp_start) as f64 / duration;
+                let actual = (value as f64 - start as f64) / span;
+                (curve.apply(position) - actual).powi(2)
+            })
+            .sum()
+    };
+
+    let curve = CURVES
+        .into_iter()
+        .min_by(|a, b| error(*a).total_cmp(&error(*b)))
+        .unwrap();
+
+    Some(((duration / ppq as f64) as f32, curve))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::smf::Track;
+
+    fn cc(value: u8) -> TrackEvent {
+        TrackEvent::Midi(vec![0xB0, 74, value])
+    }
+
+    #[test]
+    fn test_import_at_markers() {
+        let mut track = Track::new();
+        track
+            .push(0, cc(10))
+            .push(0, TrackEvent::Marker("Intro".to_string()))
+            .push(960, TrackEvent::Midi(vec![0xB3, 7, 100]))
+            .push(1920, TrackEvent::Marker("Drop".to_string()));
+        let mut file = MidiFile::new(480);
+        file.tracks.push(track);
+
+        let mut project = Project::new("Import", None);
+        let options = ImportOptions {
+            points: SnapshotPoints::Markers,
+            detect_ramps: false,
+            beats_per_bar: 4,
+        };
+
+        let ids = import_scenes(&file, &options, &mut project).unwrap();
+        assert_eq!(ids.len(), 2);
+
+        let intro = project.get_scene(&ids[0]).unwrap();
+        assert_eq!(intro.name, "Intro");
+        assert_eq!(intro.cc_values.len(), 1);
+
+        let drop = project.get_scene(&ids[1]).unwrap();
+        assert_eq!(drop.get_cc(0, 74).unwrap().value, 10);
+        assert_eq!(drop.get_cc(3, 7).unwrap().value, 100);
+
+        // Definitions are created for every CC seen
+        assert!(project.get_cc_definition(3, 7).is_some());
+    }
+
+    #[test]
+    fn test_import_detects_ramps() {
+        let mut track = Track::new();
+        track.push(0, cc(0));
+        // Linear ramp over bar 2
+        for (i, value) in [25, 50, 75, 100].into_iter().enumerate() {
+            track.push(1920 + 480 * (i as u32 + 1), cc(value));
+        }
+        // Logarithmic ramp back down over bar 4
+        for (i, value) in [50, 29, 13, 0].into_iter().enumerate() {
+            track.push(5760 + 480 * (i as u32 + 1), cc(value));
+        }
+        let mut file = MidiFile::new(480);
+        file.tracks.push(track);
+
+        let mut project = Project::new("Import", None);
+        let options = ImportOptions {
+            points: SnapshotPoints::Bars(vec![1, 3, 5]),
+            detect_ramps: true,
+            beats_per_bar: 4,
+        };
+
+        let ids = import_scenes(&file, &options, &mut project).unwrap();
+        let bar1 = project.get_scene(&ids[0]).unwrap().get_cc(0, 74).unwrap();
+        let bar3 = project.get_scene(&ids[1]).unwrap().get_cc(0, 74).unwrap();
+        let bar5 = project.get_scene(&ids[2]).unwrap().get_cc(0, 74).unwrap();
+
+        assert!(!bar1.transition);
+
+        assert_eq!(bar3.value, 100);
+        assert_eq!(bar3.transition_beats, Some(4.0));
+        assert_eq!(bar3.curve, TransitionCurve::Linear);
+
+        assert_eq!(bar5.value, 0);
+        assert_eq!(bar5.curve, TransitionCurve::Logarithmic);
+    }
+
+    #[test]
+    fn test_import_without_markers_fails() {
+        let mut project = Project::new("Import", None);
+        let options = ImportOptions {
+            points: SnapshotPoints::Markers,
+            detect_ramps: false,
+            beats_per_bar: 4,
+        };
+
+        assert!(import_scenes(&MidiFile::new(480), &options, &mut project).is_err());
+    }
+
+    #[test]
+    fn test_import_past_the_last_tick_fails() {
+        let mut project = Project::new("Import", None);
+        let options = ImportOptions {
+            points: SnapshotPoints::Bars(vec![1, u32::MAX]),
+            detect_ramps: false,
+            beats_per_bar: 4,
+        };
+
+        assert!(import_scenes(&MidiFile::new(480), &options, &mut project).is_err());
+        assert!(project.scenes.is_empty());
+    }
+
+    #[test]
+    fn test_import_bar_zero_fails() {
+        let mut project = Project::new("Import", None);
+        let options = ImportOptions {
+            points: SnapshotPoints::Bars(vec![0]),
+            detect_ramps: false,
+            beats_per_bar: 4,
+        };
+
+        assert!(import_scenes(&MidiFile::new(480), &options, &mut project).is_err());
+    }
+}
@@ -1,2 +1,8 @@
+pub mod controllers;
+pub mod faders;
+pub mod groups;
 pub mod manager;
+pub mod midi_import;
+pub mod pad_modes;
+pub mod shift_layer;
 pub mod storage;
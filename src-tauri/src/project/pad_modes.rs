@@ -0,0 +1,151 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::scene::{PadMode, Scene};
+
+/// What to do in response to a pad press or release
+#[derive(Debug, PartialEq, Eq)]
+pub enum PadAction {
+    /// Activate a scene, replacing anything held
+    Activate(String),
+    /// Apply a scene so it can be undone later
+    Hold(String),
+    /// Undo a held scene
+    Release(String),
+}
+
+/// Scenes applied from controller pads, for releasing and pressure
+#[derive(Debug, Default)]
+pub struct PadPlayState {
+    /// Held scenes
+    held: HashSet<String>,
+    /// Momentary scenes by the controller pad holding them
+    pads: HashMap<u8, String>,
+    /// Scenes applied by pads that are still down, for pressure to act on
+    pressed: HashMap<u8, String>,
+}
+
+impl PadPlayState {
+    /// Work out what a press on a controller pad does to the scene under it
+    ///
+    /// Latched scenes replace held ones; the caller forgets the scenes they
+    /// replace once it knows which they are.
+    pub fn press(&mut self, pad: u8, scene: &Scene) -> Option<PadAction> {
+        let action = match scene.pad_mode {
+            PadMode::Latch => Some(PadAction::Activate(scene.id.clone())),
+            PadMode::Momentary => {
+                // Already held on another pad
+                if self.held.contains(&scene.id) {
+                    return None;
+                }
+                self.pads.insert(pad, scene.id.clone());
+                self.hold(scene)
+            }
+            PadMode::Toggle => {
+                if self.held.remove(&scene.id) {
+                    Some(PadAction::Release(scene.id.clone()))
+                } else {
+                    self.hold(scene)
+                }
+            }
+        };
+
+        if matches!(action, Some(PadAction::Activate(_) | PadAction::Hold(_))) {
+            self.pressed.insert(pad, scene.id.clone());
+        }
+        action
+    }
+
+    /// Work out what releasing a controller pad does
+    pub fn release(&mut self, pad: u8) -> Option<PadAction> {
+        self.pressed.remove(&pad);
+        let scene_id = self.pads.remove(&pad)?;
+
+        self.held
+            .remove(&scene_id)
+            .then_some(PadAction::Release(scene_id))
+    }
+
+    /// Scene applied by a pad that is still down
+    pub fn pressed_scene(&self, pad: u8) -> Option<&str> {
+        self.pressed.get(&pad).map(String::as_str)
+    }
+
+    /// Forget held scenes that have been replaced, so releasing their pads no
+    /// longer undoes anything
+    pub fn forget(&mut self, scene_ids: &[String]) {
+        self.held.retain(|id| !scene_ids.contains(id));
+        self.pads.retain(|_, id| !scene_ids.contains(id));
+    }
+
+    /// Forget every held scene, e.g. after a panic or loading another project
+    pub fn clear(&mut self) {
+        self.held.clear();
+        self.pads.clear();
+        self.pressed.clear();
+    }
+
+    fn hold(&mut self, scene: &Scene) -> Option<PadAction> {
+        self.held.insert(scene.id.clone());
+        Some(PadAction::Hold(scene.id.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_momentary_pad() {
+        let mut state = PadPlayState::default();
+        let scene = Scene::new("hold", "Hold").with_pad_mode(PadMode::Momentary);
+
+        assert_eq!(
+            state.press(3, &scene),
+            Some(PadAction::Hold("hold".to_string()))
+        );
+        // A second pad with the same scene does nothing while the first is held
+        assert_eq!(state.press(4, &scene), None);
+        assert_eq!(state.pressed_scene(4), None);
+        assert_eq!(state.release(4), None);
+        assert_eq!(state.pressed_scene(3), Some("hold"));
+
+        assert_eq!(
+            state.release(3),
+            Some(PadAction::Release("hold".to_string()))
+        );
+        assert_eq!(state.release(3), None);
+    }
+
+    #[test]
+    fn test_toggle_pad() {
+        let mut state = PadPlayState::default();
+        let scene = Scene::new("toggle", "Toggle").with_pad_mode(PadMode::Toggle);
+
+        assert_eq!(
+            state.press(0, &scene),
+            Some(PadAction::Hold("toggle".to_string()))
+        );
+        // Releasing a toggle pad leaves the scene on
+        assert_eq!(state.release(0), None);
+        assert_eq!(
+            state.press(0, &scene),
+            Some(PadAction::Release("toggle".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_forget_replaced_scenes() {
+        let mut state = PadPlayState::default();
+        let held = Scene::new("hold", "Hold").with_pad_mode(PadMode::Momentary);
+        let latched = Scene::new("latch", "Latch");
+
+        state.press(1, &held);
+        assert_eq!(
+            state.press(2, &latched),
+            Some(PadAction::Activate("latch".to_string()))
+        );
+        state.forget(&["hold".to_string()]);
+        assert_eq!(state.release(1), None);
+        assert_eq!(state.pressed_scene(2), Some("latch"));
+    }
+}
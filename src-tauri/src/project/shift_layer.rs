@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::models::cc::CCValue;
+use crate::models::project::Project;
+use crate::models::scene::Scene;
+
+/// A grid edit made from the controller while shift is held
+///
+/// Positions are grid positions in the current bank.
+#[derive(Debug, PartialEq, Eq)]
+pub enum GridEdit {
+    /// Save the live CC values as a new scene on an empty pad
+    Capture { position: u8 },
+    /// Assign the same scene to another pad, replacing what was there
+    Copy { from: u8, to: u8 },
+    /// Assign a copy of the scene, with its own ID, to an empty pad
+    Duplicate { from: u8, to: u8 },
+    /// Move a scene to an empty pad
+    Move { from: u8, to: u8 },
+    /// Exchange the scenes on two pads
+    Swap { from: u8, to: u8 },
+    /// Clear a pad's assignment, leaving the scene in the project
+    Delete { position: u8 },
+}
+
+/// Pad picked as the source of an edit
+#[derive(Debug, Clone, Copy)]
+struct Selection {
+    /// Controller pad that picked it
+    pad: u8,
+    position: u8,
+    /// Whether that pad is still down
+    held: bool,
+}
+
+/// Pad gestures made while the shift button is held
+///
+/// With shift held:
+/// - pressing an empty pad captures the live state as a new scene there
+/// - pressing an assigned pad selects it, and pressing it again deletes it
+/// - holding the selected pad and pressing another moves it there, or swaps
+///   the two if the other pad is assigned
+/// - releasing the selected pad and then pressing another duplicates it onto
+///   an empty pad, or copies it over an assigned one
+#[derive(Debug, Default)]
+pub struct ShiftLayer {
+    shift_held: bool,
+    selection: Option<Selection>,
+}
+
+impl ShiftLayer {
+    /// Whether pad presses are being taken as edits
+    pub fn is_active(&self) -> bool {
+        self.shift_held
+    }
+
+    /// Track the shift button; letting go abandons any selected pad
+    pub fn set_shift(&mut self, held: bool) {
+        self.shift_held = held;
+        if !held {
+            self.selection = None;
+        }
+    }
+
+    /// Grid position of the selected pad
+    pub fn selected(&self) -> Option<u8> {
+        self.selection.map(|selection| selection.position)
+    }
+
+    /// Work out what a press on a controller pad does while shift is held
+    ///
+    /// `position` is the grid position under the pad, if it is on the grid,
+    /// and `assigned` whether a scene is there.
+    pub fn press(&mut self, pad: u8, position: Option<u8>, assigned: bool) -> Option<GridEdit> {
+        let position = position?;
+
+        let Some(selection) = self.selection else {
+            if assigned {
+                self.selection = Some(Selection {
+                    pad,
+                    position,
+                    held: true,
+                });
+                return None;
+            }
+            return Some(GridEdit::Capture { position });
+        };
+
+        self.selection = None;
+        let from = selection.position;
+        if from == position {
+            return Some(GridEdit::Delete { position });
+        }
+
+        Some(match (selection.held, assigned) {
+            (true, false) => GridEdit::Move { from, to: position },
+            (true, true) => GridEdit::Swap { from, to: position },
+            (false, false) => GridEdit::Duplicate { from, to: position },
+            (false, true) => GridEdit::Copy { from, to: position },
+        })
+    }
+
+    /// Note a controller pad being let go
+    pub fn release(&mut self, pad: u8) {
+        if let Some(selection) = &mut self.selection {
+            if selection.pad == pad {
+                selection.held = false;
+            }
+        }
+    }
+}
+
+/// Make a grid edit in a bank of a project
+///
+/// `live_values` are the CC values last sent, by (channel, CC number), for
+/// capturing a new scene.
+pub fn apply_edit(
+    project: &mut Project,
+    bank: u8,
+    edit: &GridEdit,
+    live_values: &HashMap<(u8, u8), u8>,
+) -> Result<(), String> {
+    let scene_at = |project: &Project, position: u8| {
+        project
+            .get_scene_at_grid(bank, position)
+            .cloned()
+            .ok_or_else(|| format!("No scene at grid position {}", position))
+    };
+
+    match *edit {
+        GridEdit::Capture { position } => {
+            let id = Uuid::new_v4().to_string();
+            let mut scene = Scene::new(&id, &format!("Capture {}", project.scenes.len() + 1));
+            for (&(channel, cc_number), &value) in live_values {
+                // Named after the project's definition where there is one
+                let cc = match project.get_cc_definition(channel, cc_number) {
+                    Some(definition) => definition.create_cc_value(Some(value)),
+                    None => CCValue::new(channel, cc_number, value),
+                };
+                scene.add_cc(cc);
+            }
+
+            project.add_scene(scene);
+            project.assign_to_grid(&id, bank, position)
+        }
+        GridEdit::Copy { from, to } => {
+            let scene = scene_at(project, from)?;
+            project.assign_to_grid(&scene.id, bank, to)
+        }
+        GridEdit::Duplicate { from, to } => {
+            let scene = scene_at(project, from)?;
+            let id = Uuid::new_v4().to_string();
+            project.add_scene(scene.duplicate(&id, &format!("{} copy", scene.name)));
+            project.assign_to_grid(&id, bank, to)
+        }
+        GridEdit::Move { from, to } => {
+            let scene = scene_at(project, from)?;
+            project.unassign_from_grid(bank, from);
+            project.assign_to_grid(&scene.id, bank, to)
+        }
+        GridEdit::Swap { from, to } => {
+            let first = scene_at(project, from)?;
+            let second = scene_at(project, to)?;
+            project.assign_to_grid(&second.id, bank, from)?;
+            project.assign_to_grid(&first.id, bank, to)
+        }
+        GridEdit::Delete { position } => project
+            .unassign_from_grid(bank, position)
+            .map(|_| ())
+            .ok_or_else(|| format!("No scene at grid position {}", position)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::project::CCDefinition;
+
+    fn shifted() -> ShiftLayer {
+        let mut layer = ShiftLayer::default();
+        layer.set_shift(true);
+        layer
+    }
+
+    #[test]
+    fn test_shift_capture_and_delete() {
+        let mut layer = shifted();
+
+        assert_eq!(
+            layer.press(0, Some(5), false),
+            Some(GridEdit::Capture { position: 5 })
+        );
+        // Pads off the project grid do nothing
+        assert_eq!(layer.press(1, None, false), None);
+
+        assert_eq!(layer.press(2, Some(7), true), None);
+        assert_eq!(layer.selected(), Some(7));
+        layer.release(2);
+        assert_eq!(
+            layer.press(2, Some(7), true),
+            Some(GridEdit::Delete { position: 7 })
+        );
+        assert_eq!(layer.selected(), None);
+    }
+
+    #[test]
+    fn test_shift_held_source_moves() {
+        let mut layer = shifted();
+
+        layer.press(0, Some(0), true);
+        assert_eq!(
+            layer.press(1, Some(1), false),
+            Some(GridEdit::Move { from: 0, to: 1 })
+        );
+
+        layer.press(0, Some(0), true);
+        assert_eq!(
+            layer.press(1, Some(1), true),
+            Some(GridEdit::Swap { from: 0, to: 1 })
+        );
+    }
+
+    #[test]
+    fn test_shift_tapped_source_copies() {
+        let mut layer = shifted();
+
+        layer.press(0, Some(0), true);
+        layer.release(0);
+        assert_eq!(
+            layer.press(1, Some(1), false),
+            Some(GridEdit::Duplicate { from: 0, to: 1 })
+        );
+
+        layer.press(0, Some(0), true);
+        layer.release(0);
+        assert_eq!(
+            layer.press(1, Some(1), true),
+            Some(GridEdit::Copy { from: 0, to: 1 })
+        );
+    }
+
+    #[test]
+    fn test_releasing_shift_drops_selection() {
+        let mut layer = shifted();
+
+        layer.press(0, Some(0), true);
+        layer.set_shift(false);
+        assert!(!layer.is_active());
+        assert_eq!(layer.selected(), None);
+    }
+
+    fn project_with_scenes() -> Project {
+        let mut project = Project::new("Test Project", None);
+        project.add_scene(Scene::new("a", "A"));
+        project.add_scene(Scene::new("b", "B"));
+        project.assign_to_grid("a", 0, 0).unwrap();
+        project.assign_to_grid("b", 0, 1).unwrap();
+        project
+    }
+
+    fn scene_id_at(project: &Project, position: u8) -> Option<&str> {
+        project
+            .get_scene_at_grid(0, position)
+            .map(|scene| scene.id.as_str())
+    }
+
+    #[test]
+    fn test_apply_grid_edits() {
+        let mut project = project_with_scenes();
+        let values = HashMap::new();
+
+        apply_edit(&mut project, 0, &GridEdit::Swap { from: 0, to: 1 }, &values).unwrap();
+        assert_eq!(scene_id_at(&project, 0), Some("b"));
+        assert_eq!(scene_id_at(&project, 1), Some("a"));
+
+        apply_edit(&mut project, 0, &GridEdit::Move { from: 0, to: 2 }, &values).unwrap();
+        assert_eq!(scene_id_at(&project, 0), None);
+        assert_eq!(scene_id_at(&project, 2), Some("b"));
+
+        apply_edit(&mut project, 0, &GridEdit::Copy { from: 2, to: 1 }, &values).unwrap();
+        assert_eq!(scene_id_at(&project, 1), Some("b"));
+
+        apply_edit(
+            &mut project,
+            0,
+            &GridEdit::Duplicate { from: 1, to: 3 },
+            &values,
+        )
+        .unwrap();
+        let copy = project.get_scene_at_grid(0, 3).unwrap();
+        assert_ne!(copy.id, "b");
+        assert_eq!(copy.name, "B copy");
+
+        apply_edit(&mut project, 0, &GridEdit::Delete { position: 3 }, &values).unwrap();
+        assert_eq!(scene_id_at(&project, 3), None);
+        // The deleted pad's scene stays in the project
+        assert_eq!(project.scenes.len(), 3);
+
+        assert!(apply_edit(&mut project, 0, &GridEdit::Delete { position: 3 }, &values).is_err());
+    }
+
+    #[test]
+    fn test_capture_live_values() {
+        let mut project = project_with_scenes();
+        project.add_cc_definition(CCDefinition::new(0, 74, "Cutoff"));
+        let values = HashMap::from([((0, 74), 90), ((1, 7), 100)]);
+
+        apply_edit(&mut project, 0, &GridEdit::Capture { position: 4 }, &values).unwrap();
+
+        let scene = project.get_scene_at_grid(0, 4).unwrap();
+        assert_eq!(scene.get_cc(0, 74).unwrap().value, 90);
+        assert_eq!(scene.get_cc(0, 74).unwrap().name.as_deref(), Some("Cutoff"));
+        assert_eq!(scene.get_cc(1, 7).unwrap().value, 100);
+    }
+}
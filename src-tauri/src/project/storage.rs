@@ -69,6 +69,7 @@ impl From<&Project> for ProjectMeta {
 }
 
 /// Project storage manager
+#[derive(Clone)]
 pub struct ProjectStorage {
     projects_dir: PathBuf,
 }
@@ -182,6 +183,20 @@ impl ProjectStorage {
         Ok(projects)
     }
 
+    /// Remember the last loaded project, so it can be restored at startup
+    pub fn set_last_project(&self, id: &str) -> Result<(), StorageError> {
+        fs::write(self.projects_dir.join("last_project"), id)?;
+        Ok(())
+    }
+
+    /// ID of the last loaded project, if it still exists
+    pub fn last_project(&self) -> Option<String> {
+        let id = fs::read_to_string(self.projects_dir.join("last_project")).ok()?;
+        let id = id.trim().to_string();
+
+        self.get_project_path(&id).exists().then_some(id)
+    }
+
     /// Import a project from a file
     pub fn import_project<P: AsRef<Path>>(&self, path: P) -> Result<Project, StorageError> {
         let json = fs::read_to_string(path)?;
@@ -327,4 +342,22 @@ mod tests {
 
         assert_eq!(projects.len(), 3);
     }
+
+    #[test]
+    fn test_last_project() {
+        let temp_dir = tempdir().unwrap();
+        let storage = ProjectStorage::new(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(storage.last_project(), None);
+
+        let project = Project::new("Test Project", None);
+        storage.save_project(&project).unwrap();
+        storage.set_last_project(&project.id).unwrap();
+        assert_eq!(storage.last_project(), Some(project.id.clone()));
+
+        // Isn't mistaken for a project
+        assert_eq!(storage.list_projects().unwrap().len(), 1);
+
+        storage.delete_project(&project.id).unwrap();
+        assert_eq!(storage.last_project(), None);
+    }
 }
@@ -1,5 +1,5 @@
 use crate::components::*;
-use crate::models::{MidiDevice, Project, Scene};
+use crate::models::{ControllerView, MidiDevice, Project, Scene};
 use crate::tauri_commands::*;
 use leptos::prelude::*;
 use leptos::task::spawn_local;
@@ -16,7 +16,10 @@ pub fn App() -> impl IntoView {
     /* ---------- state ---------- */
     let (proj, set_proj) = create_signal(None::<Project>);
     let (scene, set_scene) = create_signal(None::<Scene>);
+    let (bank, set_bank) = create_signal(0u8);
+    let (active_scenes, set_active_scenes) = create_signal(Vec::<String>::new());
     let (devices, set_dev) = create_signal(Vec::<MidiDevice>::new());
+    let (controller_views, set_controller_views) = create_signal(Vec::<ControllerView>::new());
     let (loading, set_load) = create_signal(false);
     let (error, set_err) = create_signal(None::<String>);
 
@@ -39,7 +42,120 @@ pub fn App() -> impl IntoView {
         }
     });
 
+    // Escape triggers a MIDI panic, Shift+Escape also resets CCs to their defaults.
+    // Escape in a text field or an open dialog is left to cancel that instead.
+    let _panic_shortcut = window_event_listener(leptos::ev::keydown, move |e| {
+        if e.key() != "Escape" || e.default_prevented() {
+            return;
+        }
+        let in_field = e
+            .target()
+            .and_then(|t| t.dyn_into::<web_sys::Element>().ok())
+            .is_some_and(|el| {
+                matches!(el.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+                    || el.closest(".dialog-overlay").ok().flatten().is_some()
+            });
+        let dialog_open = show_proj.get_untracked()
+            || show_set.get_untracked()
+            || show_ai.get_untracked()
+            || error.get_untracked().is_some();
+        if in_field || dialog_open {
+            return;
+        }
+        let defaults = e.shift_key()
+            || proj
+                .get_untracked()
+                .map(|p| p.settings.panic_sends_defaults)
+                .unwrap_or(false);
+        spawn_local(async move {
+            if let Err(e) = panic_command(defaults).await {
+                set_err.set(Some(e));
+            }
+        });
+    });
+
+    // Edits made from the controller's shift layer change the project under us
+    if let Err(e) = listen("project-updated", move || {
+        spawn_local(async move {
+            if let Ok(p) = get_active_project().await {
+                set_proj.set(Some(p));
+            }
+        });
+    }) {
+        console_log!("Project updates unavailable: {}", e);
+    }
+
+    // The backend may have reopened the last project at startup
+    spawn_local(async move {
+        if let Ok(p) = get_active_project().await {
+            set_proj.set(Some(p));
+        }
+    });
+
+    // Controllers are set aside and reconnected as they're unplugged and plugged back in
+    if let Err(e) = listen("controllers-updated", move || {
+        spawn_local(async move {
+            if let Ok(views) = get_controller_views_command().await {
+                set_controller_views.set(views);
+            }
+        });
+    }) {
+        console_log!("Controller updates unavailable: {}", e);
+    }
+
+    // Switching banks on the grid takes the first controller with it
+    create_effect(move |_| {
+        bank.track();
+        spawn_local(async move {
+            if let Ok(views) = get_controller_views_command().await {
+                set_controller_views.set(views);
+            }
+        });
+    });
+
     /* ---------- helpers ---------- */
+    let toggle_auto_connect = move |e: leptos::ev::Event| {
+        let enabled = event_target::<web_sys::HtmlInputElement>(&e).checked();
+        spawn_local(async move {
+            match set_auto_connect_command(enabled).await {
+                Ok(_) => {
+                    if let Ok(p) = get_active_project().await {
+                        set_proj.set(Some(p));
+                    }
+                }
+                Err(e) => set_err.set(Some(e)),
+            }
+        });
+    };
+
+    let toggle_link = move |e: leptos::ev::Event| {
+        let enabled = event_target::<web_sys::HtmlInputElement>(&e).checked();
+        spawn_local(async move {
+            match set_use_link_command(enabled).await {
+                Ok(_) => {
+                    if let Ok(p) = get_active_project().await {
+                        set_proj.set(Some(p));
+                    }
+                }
+                Err(e) => set_err.set(Some(e)),
+            }
+        });
+    };
+
+    let connect_output = move |e: leptos::ev::Event| {
+        let name = event_target::<web_sys::HtmlSelectElement>(&e).value();
+        spawn_local(async move {
+            match connect_output_command(name).await {
+                Ok(_) => {
+                    if let Ok(p) = get_active_project().await {
+                        set_proj.set(Some(p));
+                    }
+                }
+                Err(e) => set_err.set(Some(e)),
+            }
+        });
+    };
+
     let load_project = move |id: String| {
         set_load.set(true);
         spawn_local(async move {
@@ -47,6 +163,12 @@ pub fn App() -> impl IntoView {
                 Ok(p) => {
                     set_proj.set(Some(p));
                     set_scene.set(None);
+                    set_bank.set(0);
+                    set_active_scenes.set(Vec::new());
+                    // Loading a project spreads the controllers out from the first bank
+                    if let Ok(views) = get_controller_views_command().await {
+                        set_controller_views.set(views);
+                    }
                 }
                 Err(e) => set_err.set(Some(e)),
             }
@@ -75,6 +197,24 @@ pub fn App() -> impl IntoView {
                         set_scene.set(Some(s.clone()));
                     }
                 }
+                // Scenes in other groups keep playing
+                if let Ok(ids) = get_active_scenes_command().await {
+                    set_active_scenes.set(ids);
+                }
+            }
+        });
+    };
+
+    let update_scene = move |s: Scene| {
+        spawn_local(async move {
+            match update_scene_command(s.clone()).await {
+                Ok(_) => {
+                    set_scene.set(Some(s));
+                    if let Ok(p) = get_active_project().await {
+                        set_proj.set(Some(p));
+                    }
+                }
+                Err(e) => set_err.set(Some(e)),
             }
         });
     };
@@ -100,6 +240,22 @@ pub fn App() -> impl IntoView {
                 <h1>"Snap‑Blaster"</h1>
                 <button on:click=move |_| set_show_proj.set(true)>"Projects"</button>
                 <button on:click=move |_| set_show_set.set(true) >"Settings"</button>
+                <button class="panic-button"
+                        title="MIDI panic (Esc, Shift+Esc to also reset CCs)"
+                        on:click=move |e| {
+                            let defaults = e.shift_key()
+                                || proj
+                                    .get_untracked()
+                                    .map(|p| p.settings.panic_sends_defaults)
+                                    .unwrap_or(false);
+                            spawn_local(async move {
+                                if let Err(e) = panic_command(defaults).await {
+                                    set_err.set(Some(e));
+                                }
+                            })
+                        }>
+                    "Panic"
+                </button>
             </header>
 
             /* ----- body ----- */
@@ -133,11 +289,107 @@ pub fn App() -> impl IntoView {
                         devices=devices
                         on_connect=Callback::new(move |d| spawn_local(async move {
                             match connect_controller_command(d).await {
-                                Ok(_) => console_log!("Connected to device successfully"),
+                                Ok(_) => {
+                                    console_log!("Connected to device successfully");
+                                    if let Ok(views) = get_controller_views_command().await {
+                                        set_controller_views.set(views);
+                                    }
+                                }
                                 Err(e) => console_log!("Error connecting: {}", e),
                             }
                         }))
                     />
+                    <Show when=move || proj.get().is_some() fallback=|| ().into_view()>
+                        <select on:change=connect_output>
+                            <option value="" disabled=true
+                                    selected=move || proj.get().is_some_and(|p| p.settings.default_output_devices.is_empty())>
+                                "Send scenes to..."
+                            </option>
+                            {move || {
+                                let current = proj.get().and_then(|p| p.settings.default_output_devices.last().cloned());
+                                devices.get().into_iter().filter(|d| !d.is_input).map(|d| {
+                                    let selected = current.as_deref() == Some(d.name.as_str());
+                                    view! { <option value=d.name.clone() selected=selected>{d.name.clone()}</option> }
+                                }).collect::<Vec<_>>()
+                            }}
+                        </select>
+                        <label class="checkbox-label">
+                            <input type="checkbox"
+                                   prop:checked=move || proj.get().is_some_and(|p| p.settings.auto_connect)
+                                   on:change=toggle_auto_connect />
+                            "Connect these devices on startup"
+                        </label>
+                        <label class="checkbox-label">
+                            <input type="checkbox"
+                                   prop:checked=move || proj.get().is_some_and(|p| p.settings.use_link)
+                                   on:change=toggle_link />
+                            "Sync to Ableton Link"
+                        </label>
+                    </Show>
+
+                    <Show when=move || proj.get().is_some() fallback=|| ().into_view()>
+                        <h2>"Grid Layout"</h2>
+                        {move || view!{
+                            <grid_layout::GridLayoutPanel
+                                grid_size=proj.get().map(|p| p.grid_size).unwrap_or_default()
+                                bank_names=proj.get().map(|p| p.banks.iter().map(|b| b.name.clone()).collect()).unwrap_or_default()
+                                controller_views=controller_views
+                                set_controller_views=set_controller_views
+                                on_changed=Callback::new(move |p| set_proj.set(Some(p)))
+                            />
+                        }}
+                    </Show>
+
+                    <Show when=move || proj.get().is_some() fallback=|| ().into_view()>
+                        <h2>"Faders"</h2>
+                        {move || view!{
+                            <faders::FaderPanel
+                                project=proj.get().unwrap()
+                                controller_views=controller_views
+                                set_controller_views=set_controller_views
+                                on_changed=Callback::new(move |p| set_proj.set(Some(p)))
+                            />
+                        }}
+                    </Show>
+
+                    <Show when=move || proj.get().is_some() fallback=|| ().into_view()>
+                        <h2>"Controller Buttons"</h2>
+                        {move || view!{
+                            <controller_buttons::ControllerButtonsPanel
+                                project=proj.get().unwrap()
+                                on_changed=Callback::new(move |p| set_proj.set(Some(p)))
+                            />
+                        }}
+                    </Show>
+
+                    <Show when=move || proj.get().is_some() fallback=|| ().into_view()>
+                        <h2>"MIDI Thru"</h2>
+                        <thru_routes::ThruRoutePanel devices=devices />
+                    </Show>
+
+                    <Show when=move || proj.get().is_some() fallback=|| ().into_view()>
+                        <h2>"MIDI Learn"</h2>
+                        {move || view!{
+                            <midi_learn::MidiLearnPanel
+                                project=proj.get().unwrap()
+                                devices=devices
+                                on_changed=Callback::new(move |p| set_proj.set(Some(p)))
+                            />
+                        }}
+                    </Show>
+
+                    <Show when=move || proj.get().is_some() fallback=|| ().into_view()>
+                        <h2>"Import MIDI"</h2>
+                        <midi_import::MidiImportPanel
+                            on_imported=Callback::new(move |p| set_proj.set(Some(p)))
+                        />
+                    </Show>
+
+                    <h2>"Recording"</h2>
+                    <recorder::RecorderPanel />
+
+                    <h2>"Diagnostics"</h2>
+                    <DiagnosticPanel />
                 </aside>
 
                 /* main area */
@@ -149,9 +401,24 @@ pub fn App() -> impl IntoView {
                             fallback=move || view!{ <div class="grid-placeholder">"Please load a project"</div> }
                         >
                             {move || view!{
+                                <banks::BankTabs
+                                    project=proj.get().unwrap()
+                                    bank=bank
+                                    set_bank=set_bank
+                                    on_changed=Callback::new(move |p| set_proj.set(Some(p)))
+                                    on_error=Callback::new(move |e| set_err.set(Some(e)))
+                                />
+                                <groups::ColumnGroups
+                                    project=proj.get().unwrap()
+                                    set_active_scenes=set_active_scenes
+                                    on_changed=Callback::new(move |p| set_proj.set(Some(p)))
+                                    on_error=Callback::new(move |e| set_err.set(Some(e)))
+                                />
                                 <grid::SceneGrid
                                     project=proj.get().unwrap()
-                                    active_scene=scene
+                                    bank=bank.get()
+                                    active_scenes=active_scenes
+                                    controller_views=controller_views
                                     on_activate=Callback::new(activate_scene.clone())
                                     on_assign=Callback::new(assign_scene.clone())
                                 />
@@ -169,7 +436,7 @@ pub fn App() -> impl IntoView {
                                 <scene_editor::SceneEditor
                                     scene=scene.get().unwrap()
                                     cc_definitions=proj.get().map(|p| p.cc_definitions.clone()).unwrap_or_default()
-                                    on_update=Callback::new(|_| {/* TODO */})
+                                    on_update=Callback::new(update_scene)
                                 />
                             }}
                         </Show>
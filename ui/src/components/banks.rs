@@ -0,0 +1,90 @@
+use crate::models::Project;
+use crate::tauri_commands::{
+    add_scene_bank_command, get_active_project, get_current_bank_command,
+    remove_scene_bank_command, select_scene_bank_command,
+};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+#[component]
+pub fn BankTabs(
+    project: Project,
+    bank: ReadSignal<u8>,
+    set_bank: WriteSignal<u8>,
+    on_changed: Callback<Project>,
+    on_error: Callback<String>,
+) -> impl IntoView {
+    let bank_count = project.banks.len();
+
+    let select = move |index: u8| {
+        spawn_local(async move {
+            match select_scene_bank_command(index).await {
+                Ok(_) => set_bank.set(index),
+                Err(e) => on_error.run(e),
+            }
+        });
+    };
+
+    // Reload the project and follow the backend's current bank
+    let refresh = move || {
+        spawn_local(async move {
+            if let Ok(project) = get_active_project().await {
+                on_changed.run(project);
+            }
+            if let Ok(current) = get_current_bank_command().await {
+                set_bank.set(current);
+            }
+        });
+    };
+
+    let add = move |_| {
+        let name = format!("Bank {}", bank_count + 1);
+        spawn_local(async move {
+            match add_scene_bank_command(name).await {
+                Ok(index) => {
+                    let _ = select_scene_bank_command(index).await;
+                    refresh();
+                }
+                Err(e) => on_error.run(e),
+            }
+        });
+    };
+
+    let remove = move |_| {
+        let index = bank.get_untracked();
+        spawn_local(async move {
+            match remove_scene_bank_command(index).await {
+                Ok(_) => refresh(),
+                Err(e) => on_error.run(e),
+            }
+        });
+    };
+
+    view! {
+        <div class="bank-tabs">
+            {project
+                .banks
+                .iter()
+                .enumerate()
+                .map(|(index, b)| {
+                    let index = index as u8;
+                    let name = if b.name.is_empty() { format!("Bank {}", index + 1) } else { b.name.clone() };
+                    view! {
+                        <button class="bank-tab"
+                                class:selected=move || bank.get() == index
+                                on:click=move |_| select(index)>
+                            {name}
+                        </button>
+                    }
+                })
+                .collect_view()}
+            <button class="bank-tab" title="Add bank" on:click=add>"+"</button>
+            <button class="bank-tab"
+                    title="Remove the selected bank"
+                    disabled=bank_count <= 1
+                    on:click=remove>
+                "−"
+            </button>
+        </div>
+    }
+}
@@ -0,0 +1,94 @@
+use crate::models::{ControllerButton, Project};
+use crate::tauri_commands::{
+    get_active_project, set_controller_button_command, set_panic_sends_defaults_command,
+};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+
+/// Jobs a spare controller button can be given, with their labels
+const BUTTONS: &[(ControllerButton, &str)] = &[
+    (ControllerButton::Panic, "Panic"),
+    (ControllerButton::FaderMode, "Fader mode"),
+    (ControllerButton::Shift, "Shift"),
+    (ControllerButton::BeatIndicator, "Beat indicator"),
+];
+
+/// Spare controller buttons given jobs by the project, by the button ID the
+/// controller sends (its note or CC number)
+#[component]
+pub fn ControllerButtonsPanel(project: Project, on_changed: Callback<Project>) -> impl IntoView {
+    let (status, set_status) = create_signal(String::new());
+
+    let reload = move || async move {
+        set_status.set(String::new());
+        if let Ok(project) = get_active_project().await {
+            on_changed.run(project);
+        }
+    };
+
+    let assign = move |button: ControllerButton, value: String| {
+        // A blank field frees the button
+        let button_id = match value.trim() {
+            "" => None,
+            value => match value.parse::<u8>() {
+                Ok(id) if id < 128 => Some(id),
+                _ => {
+                    set_status.set("Button IDs go from 0 to 127".to_string());
+                    return;
+                }
+            },
+        };
+
+        spawn_local(async move {
+            match set_controller_button_command(button, button_id).await {
+                Ok(_) => reload().await,
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    let toggle_defaults = move |e: leptos::ev::Event| {
+        let enabled = event_target::<HtmlInputElement>(&e).checked();
+        spawn_local(async move {
+            match set_panic_sends_defaults_command(enabled).await {
+                Ok(_) => reload().await,
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    view! {
+        <div class="controller-buttons">
+            {BUTTONS
+                .iter()
+                .map(|&(button, label)| {
+                    let current = project.settings.button(button);
+                    view! {
+                        <label class="controller-button">
+                            {label}
+                            <input type="number"
+                                   min="0"
+                                   max="127"
+                                   placeholder="None"
+                                   value=current.map(|id| id.to_string()).unwrap_or_default()
+                                   on:change=move |e| assign(button, event_target::<HtmlInputElement>(&e).value()) />
+                        </label>
+                    }
+                })
+                .collect_view()}
+            <label class="checkbox-label">
+                <input type="checkbox"
+                       prop:checked=project.settings.panic_sends_defaults
+                       on:change=toggle_defaults />
+                "Panic also resets CCs to their defaults"
+            </label>
+            <div class="controller-buttons-status">{move || status.get()}</div>
+        </div>
+    }
+}
+
+fn event_target<T: JsCast>(e: &leptos::ev::Event) -> T {
+    e.target().unwrap().unchecked_into()
+}
@@ -1,7 +1,12 @@
 use leptos::prelude::*;
 use leptos::task::spawn_local;
 use wasm_bindgen::JsCast;
-use crate::tauri_commands::{check_backend_status, debug_connect_controller};
+use crate::models::SuppressedEvent;
+use crate::tauri_commands::{
+    check_backend_status, debug_connect_controller, get_suppressed_events, get_warnings, listen,
+    list_skipped_profiles, reload_controller_profiles,
+};
+use crate::console_log;
 
 #[component]
 pub fn DiagnosticPanel() -> impl IntoView {
@@ -9,6 +14,22 @@ pub fn DiagnosticPanel() -> impl IntoView {
     let (is_checking, set_checking) = create_signal(false);
     let (device_id, set_device_id) = create_signal("".to_string());
     let (connect_result, set_connect_result) = create_signal("Not tested".to_string());
+    let (suppressed, set_suppressed) = create_signal(Vec::<SuppressedEvent>::new());
+    let (warnings, set_warnings) = create_signal(Vec::<String>::new());
+
+    // Problems in the background, like devices failing to reconnect, are kept by
+    // the backend until asked for
+    let refresh_warnings = move || {
+        spawn_local(async move {
+            if let Ok(w) = get_warnings().await {
+                set_warnings.set(w);
+            }
+        });
+    };
+    refresh_warnings();
+    if let Err(e) = listen("warnings-updated", refresh_warnings) {
+        console_log!("Warning updates unavailable: {}", e);
+    }
 
     let check_status = move |_| {
         set_checking.set(true);
@@ -40,6 +61,35 @@ pub fn DiagnosticPanel() -> impl IntoView {
         });
     };
 
+    // User profiles are reread when their directory changes; this forces it
+    let (profiles_result, set_profiles_result) = create_signal(String::new());
+    let (skipped_profiles, set_skipped_profiles) = create_signal(Vec::<String>::new());
+    let refresh_skipped = move || {
+        spawn_local(async move {
+            if let Ok(skipped) = list_skipped_profiles().await {
+                set_skipped_profiles.set(skipped);
+            }
+        });
+    };
+    refresh_skipped();
+    let reload_profiles = move |_| {
+        spawn_local(async move {
+            match reload_controller_profiles().await {
+                Ok(count) => set_profiles_result.set(format!("{} user profiles loaded", count)),
+                Err(e) => set_profiles_result.set(format!("Error: {}", e)),
+            }
+            refresh_skipped();
+        });
+    };
+
+    let refresh_suppressed = move |_| {
+        spawn_local(async move {
+            if let Ok(events) = get_suppressed_events().await {
+                set_suppressed.set(events);
+            }
+        });
+    };
+
     view! {
         <div class="diagnostic-panel">
             <h3>"Backend Diagnostics"</h3>
@@ -77,6 +127,46 @@ pub fn DiagnosticPanel() -> impl IntoView {
                     <span class="value">{move || connect_result.get()}</span>
                 </div>
             </div>
+
+            <div class="controller-profiles" style="margin-top: 1rem;">
+                <h4>"Controller Profiles"</h4>
+                <button on:click=reload_profiles>"Reload Controller Profiles"</button>
+                <span class="value">{move || profiles_result.get()}</span>
+                <ul>
+                    {move || skipped_profiles.get().into_iter().map(|s| view! { <li>{s}</li> }).collect::<Vec<_>>()}
+                </ul>
+            </div>
+
+            <div class="warnings" style="margin-top: 1rem;">
+                <h4>"Warnings"</h4>
+                <p style=move || if warnings.get().is_empty() { "" } else { "display: none;" }>
+                    "No problems reported"
+                </p>
+                <ul>
+                    {move || warnings.get().into_iter().rev().map(|w| view! { <li>{w}</li> }).collect::<Vec<_>>()}
+                </ul>
+            </div>
+
+            <div class="suppressed-events" style="margin-top: 1rem;">
+                <h4>"Suppressed Echoes"</h4>
+                <button on:click=refresh_suppressed>"Refresh"</button>
+                <p style=move || if suppressed.get().is_empty() { "" } else { "display: none;" }>
+                    "No feedback loops detected"
+                </p>
+                <ul>
+                    {move || suppressed.get().into_iter().rev().map(|e| {
+                        let bytes = e.message.iter()
+                            .map(|b| format!("{b:02X}"))
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        view! {
+                            <li>
+                                {format!("{} {} → {}: {}", e.timestamp, e.output_device, e.input_device, bytes)}
+                            </li>
+                        }
+                    }).collect::<Vec<_>>()}
+                </ul>
+            </div>
         </div>
     }
 }
@@ -0,0 +1,102 @@
+use crate::models::{ExpressionRange, TransitionCurve};
+use leptos::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+
+/// Editor for how a pad's velocity or pressure scales a scene
+#[component]
+pub fn ExpressionEditor(
+    label: &'static str,
+    value: ReadSignal<Option<ExpressionRange>>,
+    is_editing: ReadSignal<bool>,
+    on_change: Callback<Option<ExpressionRange>>,
+) -> impl IntoView {
+    // Percentages are easier to type than fractions
+    let set_percent = move |e: leptos::ev::Event, max: bool| {
+        let Ok(percent) = event_target::<HtmlInputElement>(&e).value().parse::<f64>() else {
+            return;
+        };
+        let mut range = value.get_untracked().unwrap_or_default();
+        let amount = (percent / 100.0).clamp(0.0, 1.0);
+        if max {
+            range.max = amount;
+        } else {
+            range.min = amount;
+        }
+        on_change.run(Some(range));
+    };
+
+    let set_curve = move |e: leptos::ev::Event| {
+        let mut range = value.get_untracked().unwrap_or_default();
+        range.curve = match event_target::<HtmlSelectElement>(&e).value().as_str() {
+            "exponential" => TransitionCurve::Exponential,
+            "logarithmic" => TransitionCurve::Logarithmic,
+            "scurve" => TransitionCurve::SCurve,
+            _ => TransitionCurve::Linear,
+        };
+        on_change.run(Some(range));
+    };
+
+    let toggle = move |e: leptos::ev::Event| {
+        let enabled = event_target::<HtmlInputElement>(&e).checked();
+        on_change.run(enabled.then(ExpressionRange::default));
+    };
+
+    view! {
+        <div class="detail-row expression-editor">
+            <span class="label">{label}</span>
+            <span class="value"
+                  style=move || if is_editing.get() { "display:none;" } else { "" }>
+                {move || match value.get() {
+                    Some(range) => format!(
+                        "{:.0}–{:.0}% ({})",
+                        range.min * 100.0,
+                        range.max * 100.0,
+                        curve_label(range.curve)
+                    ),
+                    None => "Off".to_string(),
+                }}
+            </span>
+            <div class="expression-fields"
+                 style=move || if is_editing.get() { "" } else { "display:none;" }>
+                <input type="checkbox"
+                       prop:checked=move || value.get().is_some()
+                       on:change=toggle />
+                <input type="number" min="0" max="100" title="Amount at the lightest touch (%)"
+                       disabled=move || value.get().is_none()
+                       prop:value=move || format!("{:.0}", value.get().unwrap_or_default().min * 100.0)
+                       on:change=move |e| set_percent(e, false) />
+                <input type="number" min="0" max="100" title="Amount at the hardest touch (%)"
+                       disabled=move || value.get().is_none()
+                       prop:value=move || format!("{:.0}", value.get().unwrap_or_default().max * 100.0)
+                       on:change=move |e| set_percent(e, true) />
+                <select disabled=move || value.get().is_none()
+                        prop:value=move || match value.get().unwrap_or_default().curve {
+                            TransitionCurve::Linear      => "linear",
+                            TransitionCurve::Exponential => "exponential",
+                            TransitionCurve::Logarithmic => "logarithmic",
+                            TransitionCurve::SCurve      => "scurve",
+                        }
+                        on:change=set_curve>
+                    <option value="linear">"Linear"</option>
+                    <option value="exponential">"Exponential"</option>
+                    <option value="logarithmic">"Logarithmic"</option>
+                    <option value="scurve">"S-Curve"</option>
+                </select>
+            </div>
+        </div>
+    }
+}
+
+fn curve_label(curve: TransitionCurve) -> &'static str {
+    match curve {
+        TransitionCurve::Linear => "linear",
+        TransitionCurve::Exponential => "exponential",
+        TransitionCurve::Logarithmic => "logarithmic",
+        TransitionCurve::SCurve => "S-curve",
+    }
+}
+
+fn event_target<T: JsCast>(e: &leptos::ev::Event) -> T {
+    e.target().unwrap().unchecked_into()
+}
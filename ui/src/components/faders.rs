@@ -0,0 +1,125 @@
+use crate::models::{ControllerView, Project};
+use crate::tauri_commands::{
+    get_active_project, get_controller_views_command, set_fader_bindings_command,
+    set_fader_mode_command,
+};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlSelectElement;
+
+/// Virtual faders the controller grid can show, matching the backend
+const MAX_FADERS: usize = 8;
+
+#[component]
+pub fn FaderPanel(
+    project: Project,
+    controller_views: ReadSignal<Vec<ControllerView>>,
+    set_controller_views: WriteSignal<Vec<ControllerView>>,
+    on_changed: Callback<Project>,
+) -> impl IntoView {
+    let (status, set_status) = create_signal(String::new());
+
+    let mut definitions: Vec<(String, String)> = project
+        .cc_definitions
+        .iter()
+        .map(|(key, def)| (key.clone(), format!("{} (ch {} cc {})", def.name, def.channel + 1, def.cc_number)))
+        .collect();
+    definitions.sort();
+
+    let bindings = project.fader_bindings.clone();
+
+    let bind = move |fader: usize, key: String| {
+        let mut keys = bindings.clone();
+        if keys.len() <= fader {
+            keys.resize(fader + 1, String::new());
+        }
+        keys[fader] = key;
+        // Trailing unbound faders don't need storing
+        while keys.last().is_some_and(String::is_empty) {
+            keys.pop();
+        }
+
+        spawn_local(async move {
+            match set_fader_bindings_command(keys).await {
+                Ok(_) => {
+                    set_status.set(String::new());
+                    if let Ok(project) = get_active_project().await {
+                        on_changed.run(project);
+                    }
+                }
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    // Each controller switches between scenes and faders on its own
+    let toggle = move |device_id: String, enabled: bool| {
+        spawn_local(async move {
+            match set_fader_mode_command(device_id, enabled).await {
+                Ok(_) => {
+                    if let Ok(views) = get_controller_views_command().await {
+                        set_controller_views.set(views);
+                    }
+                }
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    view! {
+        <div class="fader-panel">
+            {move || {
+                let many = controller_views.get().len() > 1;
+                controller_views
+                    .get()
+                    .into_iter()
+                    .map(|view| {
+                        let label = match (view.fader_mode, many) {
+                            (true, false) => "Show scenes".to_string(),
+                            (false, false) => "Show faders".to_string(),
+                            (true, true) => format!("Show scenes on {}", view.device_id),
+                            (false, true) => format!("Show faders on {}", view.device_id),
+                        };
+                        let device_id = view.device_id.clone();
+                        view! {
+                            <button class:selected=view.fader_mode
+                                    on:click=move |_| toggle(device_id.clone(), !view.fader_mode)>
+                                {label}
+                            </button>
+                        }
+                    })
+                    .collect_view()
+            }}
+            {(0..MAX_FADERS)
+                .map(|fader| {
+                    let bound = project.fader_bindings.get(fader).cloned().unwrap_or_default();
+                    let bind = bind.clone();
+                    view! {
+                        <label class="fader-binding">
+                            {format!("Fader {}", fader + 1)}
+                            <select on:change=move |e| bind(fader, event_target::<HtmlSelectElement>(&e).value())>
+                                <option value="" selected=bound.is_empty()>"—"</option>
+                                {definitions
+                                    .iter()
+                                    .map(|(key, name)| {
+                                        view! {
+                                            <option value=key.clone() selected=*key == bound>
+                                                {name.clone()}
+                                            </option>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </select>
+                        </label>
+                    }
+                })
+                .collect_view()}
+            <div class="fader-panel-status">{move || status.get()}</div>
+        </div>
+    }
+}
+
+fn event_target<T: JsCast>(e: &leptos::ev::Event) -> T {
+    e.target().unwrap().unchecked_into()
+}
@@ -1,26 +1,43 @@
-use crate::models::{Project, Scene, TriggerMode};
+use crate::models::{ControllerView, Project, TriggerMode};
 use leptos::prelude::*;
 use web_sys::DragEvent;
 
 #[component]
 pub fn SceneGrid(
     project: Project,
-    active_scene: ReadSignal<Option<Scene>>,
+    bank: u8,
+    active_scenes: ReadSignal<Vec<String>>,
+    controller_views: ReadSignal<Vec<ControllerView>>,
     on_activate: Callback<String>,
     on_assign: Callback<(String, u8)>,
 ) -> impl IntoView {
     let (dragging, set_drag) = create_signal(None::<String>);
 
+    let size = project.grid_size;
+    let assignments = project
+        .banks
+        .get(bank as usize)
+        .map(|b| b.grid_assignments.clone())
+        .unwrap_or_default();
+    let grid_style = format!("grid-template-columns: repeat({}, 1fr);", size.columns);
+
     /* ---- pre‑compute cell info ---- */
+    // Positions count up from the bottom row, so the top row is drawn first
+    // to match the controller
     let cells = create_memo(move |_| {
-        (0u8..64)
-            .map(|pos| {
-                // Convert directly from u8 grid position
-                let sid = project.grid_assignments.get(&pos).cloned();
+        (0..size.rows)
+            .rev()
+            .flat_map(|row| (0..size.columns).map(move |col| (row, col)))
+            .map(|(row, col)| {
+                let pos = row * size.columns + col;
+                let sid = assignments.get(&pos).cloned();
                 let scene = sid.as_ref().and_then(|id| project.scenes.get(id)).cloned();
-                let active = matches!((active_scene.get(), sid.as_ref()),
-                                      (Some(a), Some(id)) if a.id == *id);
-                (pos, scene, active)
+                let active = sid.as_ref().is_some_and(|id| active_scenes.get().contains(id));
+                let in_view = controller_views
+                    .get()
+                    .iter()
+                    .any(|v| v.bank == bank && v.contains(row, col));
+                (pos, scene, active, in_view)
             })
             .collect::<Vec<_>>()
     });
@@ -43,11 +60,11 @@ pub fn SceneGrid(
 
     /* ---- UI ---- */
     view! {
-        <div class="scene-grid">
+        <div class="scene-grid" style=grid_style>
             <For
                 each=move || cells.get()
-                key=|(pos, _, _)| *pos
-                children=move |(pos, scene_opt, active)| {
+                key=|(pos, _, active, in_view)| (*pos, *active, *in_view)
+                children=move |(pos, scene_opt, active, in_view)| {
                     let style = scene_opt
                         .as_ref()
                         .and_then(|s| s.color.map(|(r, g, b)| format!("background-color:rgb({r},{g},{b});")))
@@ -58,6 +75,7 @@ pub fn SceneGrid(
                         let mut c = "scene-pad".to_string();
                         if scene_opt.is_none() { c.push_str(" empty"); }
                         if active    { c.push_str(" active"); }
+                        if in_view   { c.push_str(" in-view"); }
                         if scene_opt.as_ref().map(|s| !matches!(s.trigger_mode, TriggerMode::Immediate)).unwrap_or(false) {
                             c.push_str(" transition");
                         }
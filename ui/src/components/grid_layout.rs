@@ -0,0 +1,140 @@
+use crate::models::{ControllerView, GridSize, Project};
+use crate::tauri_commands::{
+    get_active_project, get_controller_views_command, scroll_controller_viewport_command,
+    select_controller_bank_command, set_grid_size_command,
+};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlSelectElement;
+
+/// Grid layouts offered in the size picker, as (rows, columns)
+const GRID_SIZES: [(u8, u8); 6] = [(4, 4), (8, 8), (16, 8), (8, 16), (4, 16), (16, 16)];
+
+#[component]
+pub fn GridLayoutPanel(
+    grid_size: GridSize,
+    bank_names: Vec<String>,
+    controller_views: ReadSignal<Vec<ControllerView>>,
+    set_controller_views: WriteSignal<Vec<ControllerView>>,
+    on_changed: Callback<Project>,
+) -> impl IntoView {
+    let (status, set_status) = create_signal(String::new());
+
+    let resize = move |e: leptos::ev::Event| {
+        let value = event_target::<HtmlSelectElement>(&e).value();
+        let Some((rows, columns)) = value
+            .split_once('x')
+            .and_then(|(r, c)| Some((r.parse::<u8>().ok()?, c.parse::<u8>().ok()?)))
+        else {
+            return;
+        };
+
+        spawn_local(async move {
+            match set_grid_size_command(rows, columns).await {
+                Ok(unassigned) => {
+                    set_status.set(if unassigned.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{} scenes no longer fit and were unassigned", unassigned.len())
+                    });
+                    if let Ok(project) = get_active_project().await {
+                        on_changed.run(project);
+                    }
+                    // A smaller grid may have pulled the controllers' viewports back
+                    if let Ok(views) = get_controller_views_command().await {
+                        set_controller_views.set(views);
+                    }
+                }
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    let refresh_views = move || {
+        spawn_local(async move {
+            if let Ok(views) = get_controller_views_command().await {
+                set_controller_views.set(views);
+            }
+        });
+    };
+
+    let scroll = move |device_id: String, rows: i8, columns: i8| {
+        spawn_local(async move {
+            match scroll_controller_viewport_command(device_id, rows, columns).await {
+                Ok(_) => refresh_views(),
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    let select_bank = move |device_id: String, bank: u8| {
+        spawn_local(async move {
+            match select_controller_bank_command(device_id, bank).await {
+                Ok(_) => refresh_views(),
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    view! {
+        <div class="grid-layout">
+            <select on:change=resize>
+                {GRID_SIZES
+                    .iter()
+                    .map(|&(rows, columns)| {
+                        let selected = grid_size == GridSize { rows, columns };
+                        view! {
+                            <option value=format!("{rows}x{columns}") selected=selected>
+                                {format!("{rows} × {columns}")}
+                            </option>
+                        }
+                    })
+                    .collect_view()}
+            </select>
+            {move || {
+                let bank_names = bank_names.clone();
+                controller_views
+                    .get()
+                    .into_iter()
+                    .map(|view| {
+                        let id = view.device_id.clone();
+                        let (left, down, up, right, pick) =
+                            (id.clone(), id.clone(), id.clone(), id.clone(), id.clone());
+                        view! {
+                            <div class="grid-viewport">
+                                <span class="grid-viewport-device" title=id.clone()>{id.clone()}</span>
+                                <select on:change=move |e| {
+                                    if let Ok(bank) = event_target::<HtmlSelectElement>(&e).value().parse() {
+                                        select_bank(pick.clone(), bank);
+                                    }
+                                }>
+                                    {bank_names
+                                        .iter()
+                                        .enumerate()
+                                        .map(|(index, name)| {
+                                            view! {
+                                                <option value=index.to_string() selected=index == view.bank as usize>
+                                                    {name.clone()}
+                                                </option>
+                                            }
+                                        })
+                                        .collect_view()}
+                                </select>
+                                <button title="Scroll left" on:click=move |_| scroll(left.clone(), 0, -1)>"←"</button>
+                                <button title="Scroll down" on:click=move |_| scroll(down.clone(), -1, 0)>"↓"</button>
+                                <button title="Scroll up" on:click=move |_| scroll(up.clone(), 1, 0)>"↑"</button>
+                                <button title="Scroll right" on:click=move |_| scroll(right.clone(), 0, 1)>"→"</button>
+                            </div>
+                        }
+                    })
+                    .collect_view()
+            }}
+            <div class="grid-layout-status">{move || status.get()}</div>
+        </div>
+    }
+}
+
+fn event_target<T: JsCast>(e: &leptos::ev::Event) -> T {
+    e.target().unwrap().unchecked_into()
+}
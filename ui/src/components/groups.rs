@@ -0,0 +1,81 @@
+use crate::models::Project;
+use crate::tauri_commands::{
+    get_active_project, get_active_scenes_command, release_group_command,
+    set_column_group_command,
+};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+
+/// Column headers above the grid naming each column's exclusive group, with a
+/// button to stop whatever the group is playing
+#[component]
+pub fn ColumnGroups(
+    project: Project,
+    set_active_scenes: WriteSignal<Vec<String>>,
+    on_changed: Callback<Project>,
+    on_error: Callback<String>,
+) -> impl IntoView {
+    let columns = project.grid_size.columns;
+    let grid_style = format!("grid-template-columns: repeat({}, 1fr);", columns);
+
+    let rename = move |column: u8, name: String| {
+        let name = Some(name.trim().to_string()).filter(|n| !n.is_empty());
+        spawn_local(async move {
+            match set_column_group_command(column, name).await {
+                Ok(_) => {
+                    if let Ok(project) = get_active_project().await {
+                        on_changed.run(project);
+                    }
+                }
+                Err(e) => on_error.run(e),
+            }
+        });
+    };
+
+    let stop = move |group: String| {
+        spawn_local(async move {
+            match release_group_command(group).await {
+                Ok(_) => {
+                    if let Ok(ids) = get_active_scenes_command().await {
+                        set_active_scenes.set(ids);
+                    }
+                }
+                Err(e) => on_error.run(e),
+            }
+        });
+    };
+
+    view! {
+        <div class="column-groups" style=grid_style>
+            {(0..columns)
+                .map(|column| {
+                    let group = project.column_groups.get(&column).cloned();
+                    let stop_group = group.clone();
+                    view! {
+                        <div class="column-group">
+                            <input type="text"
+                                   placeholder="Group"
+                                   value=group.clone().unwrap_or_default()
+                                   on:change=move |e| rename(column, event_target::<HtmlInputElement>(&e).value()) />
+                            <button title="Stop this group"
+                                    disabled=group.is_none()
+                                    on:click=move |_| {
+                                        if let Some(group) = stop_group.clone() {
+                                            stop(group);
+                                        }
+                                    }>
+                                "■"
+                            </button>
+                        </div>
+                    }
+                })
+                .collect_view()}
+        </div>
+    }
+}
+
+fn event_target<T: JsCast>(e: &leptos::ev::Event) -> T {
+    e.target().unwrap().unchecked_into()
+}
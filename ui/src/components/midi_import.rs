@@ -0,0 +1,97 @@
+use crate::models::{ImportOptions, Project, SnapshotPoints};
+use crate::tauri_commands::{get_active_project, import_midi_scenes_command};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+
+/// Parse a bar list such as "1, 5, 9-12" into bar numbers
+fn parse_bars(text: &str) -> Vec<u32> {
+    text.split(',')
+        .flat_map(|part| {
+            let part = part.trim();
+            match part.split_once('-') {
+                Some((start, end)) => match (start.trim().parse::<u32>(), end.trim().parse::<u32>()) {
+                    (Ok(start), Ok(end)) => (start..=end).collect(),
+                    _ => Vec::new(),
+                },
+                None => part.parse::<u32>().ok().into_iter().collect(),
+            }
+        })
+        .filter(|bar| *bar > 0)
+        .collect()
+}
+
+#[component]
+pub fn MidiImportPanel(on_imported: Callback<Project>) -> impl IntoView {
+    let (path, set_path) = create_signal(String::new());
+    let (mode, set_mode) = create_signal("markers".to_string());
+    let (bars, set_bars) = create_signal(String::new());
+    let (detect_ramps, set_detect_ramps) = create_signal(true);
+    let (status, set_status) = create_signal(String::new());
+
+    let import = move |_| {
+        if path.get().is_empty() {
+            set_status.set("Enter the path of a .mid file".to_string());
+            return;
+        }
+
+        let points = if mode.get() == "bars" {
+            let bars = parse_bars(&bars.get());
+            if bars.is_empty() {
+                set_status.set("Enter the bars to snapshot".to_string());
+                return;
+            }
+            SnapshotPoints::Bars(bars)
+        } else {
+            SnapshotPoints::Markers
+        };
+
+        let options = ImportOptions {
+            points,
+            detect_ramps: detect_ramps.get(),
+            beats_per_bar: 4,
+        };
+
+        let path = path.get();
+        spawn_local(async move {
+            match import_midi_scenes_command(path, options).await {
+                Ok(ids) => {
+                    set_status.set(format!("Imported {} scenes", ids.len()));
+                    if let Ok(project) = get_active_project().await {
+                        on_imported.run(project);
+                    }
+                }
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    view! {
+        <div class="midi-import">
+            <input type="text"
+                   placeholder="MIDI file path (.mid)"
+                   on:input=move |e| set_path.set(event_target::<HtmlInputElement>(&e).value()) />
+            <select on:change=move |e| set_mode.set(event_target::<HtmlSelectElement>(&e).value())>
+                <option value="markers" selected=true>"Snapshot at markers"</option>
+                <option value="bars">"Snapshot at bars"</option>
+            </select>
+            <input type="text"
+                   placeholder="Bars, e.g. 1, 5, 9-12"
+                   disabled=move || mode.get() != "bars"
+                   on:input=move |e| set_bars.set(event_target::<HtmlInputElement>(&e).value()) />
+            <label>
+                <input type="checkbox"
+                       checked=true
+                       on:change=move |e| set_detect_ramps.set(event_target::<HtmlInputElement>(&e).checked()) />
+                "Detect ramps as transitions"
+            </label>
+            <button on:click=import>"Import Scenes"</button>
+            <div class="midi-import-status">{move || status.get()}</div>
+        </div>
+    }
+}
+
+fn event_target<T: JsCast>(e: &leptos::ev::Event) -> T {
+    e.target().unwrap().unchecked_into()
+}
@@ -0,0 +1,246 @@
+use crate::models::{LearnAction, MidiDevice, MidiMapping, MidiTrigger, Project};
+use crate::tauri_commands::{
+    add_midi_mapping_command, cancel_midi_learn_command, get_active_project,
+    get_midi_learn_command, remove_midi_mapping_command, start_midi_learn_command,
+};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlSelectElement;
+
+/// Actions a message can be bound to, by select value
+const ACTIONS: [(&str, &str); 8] = [
+    ("scene", "Trigger scene"),
+    ("next", "Next scene"),
+    ("previous", "Previous scene"),
+    ("panic", "Panic"),
+    ("bank_up", "Bank up"),
+    ("bank_down", "Bank down"),
+    ("tap", "Tap tempo"),
+    ("morph", "Morph amount"),
+];
+
+fn action_key(action: &LearnAction) -> &'static str {
+    match action {
+        LearnAction::TriggerScene { .. } => "scene",
+        LearnAction::NextScene => "next",
+        LearnAction::PreviousScene => "previous",
+        LearnAction::Panic => "panic",
+        LearnAction::BankUp => "bank_up",
+        LearnAction::BankDown => "bank_down",
+        LearnAction::TapTempo => "tap",
+        LearnAction::MorphAmount => "morph",
+    }
+}
+
+fn action_from_key(key: &str, scene_id: String) -> LearnAction {
+    match key {
+        "scene" => LearnAction::TriggerScene { scene_id },
+        "previous" => LearnAction::PreviousScene,
+        "panic" => LearnAction::Panic,
+        "bank_up" => LearnAction::BankUp,
+        "bank_down" => LearnAction::BankDown,
+        "tap" => LearnAction::TapTempo,
+        "morph" => LearnAction::MorphAmount,
+        _ => LearnAction::NextScene,
+    }
+}
+
+fn describe_trigger(trigger: &MidiTrigger) -> String {
+    match trigger {
+        MidiTrigger::ControlChange { channel, cc_number } => {
+            format!("CC {} (ch {})", cc_number, channel + 1)
+        }
+        MidiTrigger::Note { channel, note } => format!("Note {} (ch {})", note, channel + 1),
+        MidiTrigger::ProgramChange { channel, program } => {
+            format!("Program {} (ch {})", program, channel + 1)
+        }
+    }
+}
+
+#[component]
+pub fn MidiLearnPanel(
+    project: Project,
+    devices: ReadSignal<Vec<MidiDevice>>,
+    on_changed: Callback<Project>,
+) -> impl IntoView {
+    let (status, set_status) = create_signal(String::new());
+    let (learning, set_learning) = create_signal(None::<LearnAction>);
+
+    let mut scenes: Vec<(String, String)> = project
+        .scenes
+        .values()
+        .map(|scene| (scene.id.clone(), scene.name.clone()))
+        .collect();
+    scenes.sort_by(|a, b| a.1.cmp(&b.1));
+    let first_scene = scenes.first().map(|(id, _)| id.clone()).unwrap_or_default();
+
+    let (action, set_action) = create_signal("next".to_string());
+    let (scene_id, set_scene_id) = create_signal(first_scene.clone());
+
+    // Learn mode may still be waiting from before the panel was shown
+    spawn_local(async move {
+        if let Ok(action) = get_midi_learn_command().await {
+            set_learning.set(action);
+        }
+    });
+
+    let reload = move || {
+        spawn_local(async move {
+            if let Ok(project) = get_active_project().await {
+                on_changed.run(project);
+            }
+        });
+    };
+
+    let save = move |mapping: MidiMapping| {
+        spawn_local(async move {
+            match add_midi_mapping_command(mapping).await {
+                Ok(_) => {
+                    set_status.set(String::new());
+                    reload();
+                }
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    // The backend saves the mapping and reloads the project when a message arrives
+    let learn = move |_| {
+        let action = action_from_key(&action.get(), scene_id.get());
+        spawn_local(async move {
+            match start_midi_learn_command(action.clone()).await {
+                Ok(_) => {
+                    set_status.set(String::new());
+                    set_learning.set(Some(action));
+                }
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    let cancel = move |_| {
+        spawn_local(async move {
+            match cancel_midi_learn_command().await {
+                Ok(_) => set_learning.set(None),
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    let scenes = StoredValue::new(scenes);
+    let scene_options = move |selected: String| {
+        scenes.with_value(|scenes| {
+            scenes
+                .iter()
+                .map(|(id, name)| {
+                    view! {
+                        <option value=id.clone() selected=*id == selected>{name.clone()}</option>
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let mappings = project.midi_mappings.clone();
+
+    view! {
+        <div class="midi-learn">
+            <ul class="midi-mapping-list">
+                {mappings.into_iter().map(|mapping| {
+                    let id = mapping.id.clone();
+                    let for_input = mapping.clone();
+                    let for_action = mapping.clone();
+                    let for_scene = mapping.clone();
+                    let input = mapping.input_device.clone().unwrap_or_default();
+                    let scene = match &mapping.action {
+                        LearnAction::TriggerScene { scene_id } => Some(scene_id.clone()),
+                        _ => None,
+                    };
+                    let first_scene = first_scene.clone();
+
+                    view! {
+                        <li>
+                            <span class="midi-mapping-trigger">{describe_trigger(&mapping.trigger)}</span>
+                            <select on:change=move |e| {
+                                let value = event_target::<HtmlSelectElement>(&e).value();
+                                let mut mapping = for_input.clone();
+                                mapping.input_device = (!value.is_empty()).then_some(value);
+                                save(mapping);
+                            }>
+                                <option value="" selected=input.is_empty()>"Any input"</option>
+                                {devices.get_untracked().into_iter().filter(|d| d.is_input).map(|d| {
+                                    let selected = d.name == input;
+                                    view! { <option value=d.name.clone() selected=selected>{d.name.clone()}</option> }
+                                }).collect::<Vec<_>>()}
+                            </select>
+                            <select on:change=move |e| {
+                                let key = event_target::<HtmlSelectElement>(&e).value();
+                                let mut mapping = for_action.clone();
+                                mapping.action = action_from_key(&key, first_scene.clone());
+                                save(mapping);
+                            }>
+                                {ACTIONS.iter().map(|&(key, label)| {
+                                    view! { <option value=key selected=key == action_key(&mapping.action)>{label}</option> }
+                                }).collect::<Vec<_>>()}
+                            </select>
+                            {scene.map(|selected| view! {
+                                <select on:change=move |e| {
+                                    let mut mapping = for_scene.clone();
+                                    mapping.action = LearnAction::TriggerScene {
+                                        scene_id: event_target::<HtmlSelectElement>(&e).value(),
+                                    };
+                                    save(mapping);
+                                }>
+                                    {scene_options(selected)}
+                                </select>
+                            })}
+                            <button class="button-small"
+                                    on:click=move |_| {
+                                        let id = id.clone();
+                                        spawn_local(async move {
+                                            match remove_midi_mapping_command(id).await {
+                                                Ok(_) => reload(),
+                                                Err(e) => set_status.set(e),
+                                            }
+                                        });
+                                    }>
+                                "Remove"
+                            </button>
+                        </li>
+                    }
+                }).collect::<Vec<_>>()}
+            </ul>
+
+            <Show
+                when=move || learning.get().is_none()
+                fallback=move || view! {
+                    <div class="midi-learn-waiting">
+                        "Send a MIDI message to bind it…"
+                        <button class="button-small" on:click=cancel>"Cancel"</button>
+                    </div>
+                }
+            >
+                <div class="midi-learn-form">
+                    <select on:change=move |e| set_action.set(event_target::<HtmlSelectElement>(&e).value())>
+                        {ACTIONS.iter().map(|&(key, label)| {
+                            view! { <option value=key selected=key == action.get_untracked()>{label}</option> }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                    <Show when=move || action.get() == "scene" fallback=|| ().into_view()>
+                        <select on:change=move |e| set_scene_id.set(event_target::<HtmlSelectElement>(&e).value())>
+                            {scene_options(scene_id.get_untracked())}
+                        </select>
+                    </Show>
+                    <button on:click=learn>"Learn"</button>
+                </div>
+            </Show>
+
+            <div class="midi-learn-status">{move || status.get()}</div>
+        </div>
+    }
+}
+
+fn event_target<T: JsCast>(e: &leptos::ev::Event) -> T {
+    e.target().unwrap().unchecked_into()
+}
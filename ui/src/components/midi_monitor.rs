@@ -146,9 +146,15 @@ pub fn MidiDeviceList(
                             .map(|d| {
                                 let id = d.id.clone();
                                 let name = d.name.clone();
+                                // Identified devices show their model and firmware
+                                let kind = match (&d.model, &d.firmware) {
+                                    (Some(model), Some(firmware)) => format!("{} v{}", model, firmware),
+                                    (Some(model), None) => model.clone(),
+                                    _ => "Controller".to_string(),
+                                };
                                 view! {
                                     <option value={id}>
-                                        {name} " (Controller)"
+                                        {name} " (" {kind} ")"
                                     </option>
                                 }
                             })
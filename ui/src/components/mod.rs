@@ -1,9 +1,19 @@
 // Re-export all components
+pub mod banks;
 pub mod cc_editor;
+pub mod controller_buttons;
 pub mod dialogs;
+pub mod expression;
+pub mod faders;
 pub mod grid;
+pub mod groups;
+pub mod grid_layout;
+pub mod midi_import;
+pub mod midi_learn;
 pub mod midi_monitor;
+pub mod recorder;
 pub mod scene_editor;
+pub mod thru_routes;
 mod diagnostic;
 
 pub use cc_editor::CCEditor;
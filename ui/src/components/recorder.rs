@@ -0,0 +1,66 @@
+use crate::models::TrackSplit;
+use crate::tauri_commands::{is_recording_command, start_recording_command, stop_recording_command};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+
+#[component]
+pub fn RecorderPanel() -> impl IntoView {
+    let (recording, set_recording) = create_signal(false);
+    let (path, set_path) = create_signal("snap-blaster-recording.mid".to_string());
+    let (split, set_split) = create_signal(TrackSplit::PerOutput);
+    let (status, set_status) = create_signal(String::new());
+
+    spawn_local(async move {
+        if let Ok(r) = is_recording_command().await {
+            set_recording.set(r);
+        }
+    });
+
+    let toggle = move |_| {
+        spawn_local(async move {
+            if recording.get_untracked() {
+                match stop_recording_command(path.get_untracked(), split.get_untracked()).await {
+                    Ok(count) => {
+                        set_recording.set(false);
+                        set_status.set(format!("Saved {} messages", count));
+                    }
+                    Err(e) => set_status.set(e),
+                }
+            } else {
+                match start_recording_command().await {
+                    Ok(_) => {
+                        set_recording.set(true);
+                        set_status.set("Recording…".to_string());
+                    }
+                    Err(e) => set_status.set(e),
+                }
+            }
+        });
+    };
+
+    view! {
+        <div class="recorder-panel">
+            <input type="text"
+                   placeholder="Output file (.mid)"
+                   prop:value=move || path.get()
+                   on:input=move |e| set_path.set(event_target::<HtmlInputElement>(&e).value()) />
+            <select on:change=move |e| {
+                        let value = event_target::<HtmlSelectElement>(&e).value();
+                        set_split.set(if value == "channel" { TrackSplit::PerChannel } else { TrackSplit::PerOutput });
+                    }>
+                <option value="output" selected=true>"Track per output"</option>
+                <option value="channel">"Track per channel"</option>
+            </select>
+            <button class:recording=move || recording.get() on:click=toggle>
+                {move || if recording.get() { "Stop & Save" } else { "Record" }}
+            </button>
+            <div class="recorder-status">{move || status.get()}</div>
+        </div>
+    }
+}
+
+fn event_target<T: JsCast>(e: &leptos::ev::Event) -> T {
+    e.target().unwrap().unchecked_into()
+}
@@ -1,10 +1,11 @@
+use crate::components::expression::ExpressionEditor;
 use crate::components::CCEditor;
-use crate::models::{CCDefinition, Scene, TriggerMode};
+use crate::models::{CCDefinition, PadMode, Scene, TriggerMode};
 use leptos::prelude::*;
 use leptos::*;
 use std::collections::HashMap;
 use wasm_bindgen::JsCast;
-use web_sys::HtmlInputElement;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 
 #[component]
 pub fn SceneEditor(
@@ -16,6 +17,11 @@ pub fn SceneEditor(
     let (name, set_name) = create_signal(scene.name.clone());
     let (desc, set_desc) = create_signal(scene.description.clone().unwrap_or_default());
     let (mode, set_mode) = create_signal(scene.trigger_mode.clone());
+    let (pad_mode, set_pad_mode) = create_signal(scene.pad_mode);
+    let (release_ms, set_release_ms) = create_signal(scene.release_transition_ms);
+    let (velocity, set_velocity) = create_signal(scene.velocity);
+    let (pressure, set_pressure) = create_signal(scene.pressure);
+    let (group, set_group) = create_signal(scene.group.clone().unwrap_or_default());
     let (cc_vals, set_vals) = create_signal(scene.cc_values.clone());
     let (is_edit, set_edit) = create_signal(false);
     let (dirty, set_dirty) = create_signal(false);
@@ -28,6 +34,11 @@ pub fn SceneEditor(
         s2.name = name.get();
         s2.description = Some(desc.get()).filter(|s| !s.is_empty());
         s2.trigger_mode = mode.get();
+        s2.pad_mode = pad_mode.get();
+        s2.release_transition_ms = release_ms.get();
+        s2.velocity = velocity.get();
+        s2.pressure = pressure.get();
+        s2.group = Some(group.get().trim().to_string()).filter(|g| !g.is_empty());
         s2.cc_values = cc_vals.get();
         on_update.run(s2);
         set_edit.set(false);
@@ -39,6 +50,11 @@ pub fn SceneEditor(
         set_name.set(scene_orig.name.clone());
         set_desc.set(scene_orig.description.clone().unwrap_or_default());
         set_mode.set(scene_orig.trigger_mode.clone());
+        set_pad_mode.set(scene_orig.pad_mode);
+        set_release_ms.set(scene_orig.release_transition_ms);
+        set_velocity.set(scene_orig.velocity);
+        set_pressure.set(scene_orig.pressure);
+        set_group.set(scene_orig.group.clone().unwrap_or_default());
         set_vals.set(scene_orig.cc_values.clone());
         set_dirty.set(false);
     };
@@ -88,6 +104,90 @@ pub fn SceneEditor(
                         </span>
                     </div>
 
+                    <div class="detail-row">
+                        <span class="label">"Pad Mode:"</span>
+                        <span class="value"
+                              style=move || if is_edit.get() { "display:none;" } else { "" }>
+                            {move || pad_mode_label(pad_mode.get())}
+                        </span>
+                        <select style=move || if is_edit.get() { "" } else { "display:none;" }
+                                on:change=move |e| {
+                                    let mode = match event_target::<HtmlSelectElement>(&e).value().as_str() {
+                                        "Momentary" => PadMode::Momentary,
+                                        "Toggle" => PadMode::Toggle,
+                                        _ => PadMode::Latch,
+                                    };
+                                    set_pad_mode.set(mode);
+                                    set_dirty.set(true);
+                                }>
+                            {[PadMode::Latch, PadMode::Momentary, PadMode::Toggle]
+                                .into_iter()
+                                .map(|m| view! {
+                                    <option value=format!("{m:?}") selected=move || pad_mode.get() == m>
+                                        {pad_mode_label(m)}
+                                    </option>
+                                })
+                                .collect::<Vec<_>>()}
+                        </select>
+                    </div>
+
+                    <div class="detail-row"
+                         style=move || if pad_mode.get() == PadMode::Latch { "display:none;" } else { "" }>
+                        <span class="label">"Release Time:"</span>
+                        <span class="value"
+                              style=move || if is_edit.get() { "display:none;" } else { "" }>
+                            {move || match release_ms.get() {
+                                Some(ms) if ms > 0 => format!("{ms} ms"),
+                                _ => "Instant".to_string(),
+                            }}
+                        </span>
+                        <input type="number"
+                               min="0"
+                               placeholder="ms"
+                               style=move || if is_edit.get() { "" } else { "display:none;" }
+                               prop:value=move || release_ms.get().map(|ms| ms.to_string()).unwrap_or_default()
+                               on:input=move |e| {
+                                   let value = event_target::<HtmlInputElement>(&e).value();
+                                   set_release_ms.set(value.parse::<u32>().ok().filter(|ms| *ms > 0));
+                                   set_dirty.set(true);
+                               } />
+                    </div>
+
+                    <div class="detail-row">
+                        <span class="label">"Group:"</span>
+                        <span class="value"
+                              style=move || if is_edit.get() { "display:none;" } else { "" }>
+                            {move || {
+                                let g = group.get();
+                                if g.is_empty() { "Column".to_string() } else { g }
+                            }}
+                        </span>
+                        <input type="text"
+                               placeholder="Column"
+                               style=move || if is_edit.get() { "" } else { "display:none;" }
+                               prop:value=move || group.get()
+                               on:input=move |e| {
+                                   set_group.set(event_target::<HtmlInputElement>(&e).value());
+                                   set_dirty.set(true);
+                               } />
+                    </div>
+
+                    <ExpressionEditor label="Velocity:"
+                                      value=velocity
+                                      is_editing=is_edit
+                                      on_change=Callback::new(move |range| {
+                                          set_velocity.set(range);
+                                          set_dirty.set(true);
+                                      }) />
+
+                    <ExpressionEditor label="Pressure:"
+                                      value=pressure
+                                      is_editing=is_edit
+                                      on_change=Callback::new(move |range| {
+                                          set_pressure.set(range);
+                                          set_dirty.set(true);
+                                      }) />
+
                     <div class="detail-row"
                          style=move || if desc.get().is_empty() { "display:none;" } else { "" }>
                         <span class="label">"Description:"</span>
@@ -157,6 +257,14 @@ pub fn SceneEditor(
     }
 }
 
+fn pad_mode_label(mode: PadMode) -> &'static str {
+    match mode {
+        PadMode::Latch => "Latch",
+        PadMode::Momentary => "Momentary (hold)",
+        PadMode::Toggle => "Toggle",
+    }
+}
+
 fn event_target<T: JsCast>(ev: &leptos::ev::Event) -> T {
     ev.target().unwrap().unchecked_into()
 }
@@ -0,0 +1,254 @@
+use crate::models::{EchoWindow, MergePriority, MessageType, MidiDevice, ThruFilter, ThruRoute};
+use crate::tauri_commands::{
+    add_thru_route_command, list_echo_windows_command, list_thru_routes_command,
+    remove_thru_route_command, set_echo_window_command,
+};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+
+/// Parse a comma separated list of 1-based channel numbers into 0-based channels
+fn parse_channels(text: &str) -> Vec<u8> {
+    text.split(',')
+        .filter_map(|c| c.trim().parse::<u8>().ok())
+        .filter(|c| (1..=16).contains(c))
+        .map(|c| c - 1)
+        .collect()
+}
+
+fn describe_route(route: &ThruRoute) -> String {
+    let channels = if route.filter.channels.is_empty() {
+        "all channels".to_string()
+    } else {
+        let list: Vec<String> = route
+            .filter
+            .channels
+            .iter()
+            .map(|c| (c + 1).to_string())
+            .collect();
+        format!("ch {}", list.join(","))
+    };
+    let ccs = route
+        .filter
+        .cc_range
+        .map(|(lo, hi)| format!(", CC {lo}-{hi}"))
+        .unwrap_or_default();
+    let priority = match route.priority {
+        MergePriority::LastMessage => "last wins".to_string(),
+        MergePriority::Scene { hold_ms } => format!("scene wins {hold_ms}ms"),
+        MergePriority::Thru { hold_ms } => format!("thru wins {hold_ms}ms"),
+    };
+
+    format!(
+        "{} → {} ({}{}, {})",
+        route.input_device, route.output_device, channels, ccs, priority
+    )
+}
+
+#[component]
+pub fn ThruRoutePanel(devices: ReadSignal<Vec<MidiDevice>>) -> impl IntoView {
+    let (routes, set_routes) = create_signal(Vec::<ThruRoute>::new());
+    let (input, set_input) = create_signal(String::new());
+    let (output, set_output) = create_signal(String::new());
+    let (channels, set_channels) = create_signal(String::new());
+    let (cc_only, set_cc_only) = create_signal(false);
+    let (cc_low, set_cc_low) = create_signal(0u8);
+    let (cc_high, set_cc_high) = create_signal(127u8);
+    let (priority, set_priority) = create_signal("last".to_string());
+    let (hold_ms, set_hold_ms) = create_signal(500u32);
+    let (status, set_status) = create_signal(String::new());
+    let (echo_windows, set_echo_windows) = create_signal(Vec::<EchoWindow>::new());
+    let (echo_input, set_echo_input) = create_signal(String::new());
+    let (echo_output, set_echo_output) = create_signal(String::new());
+    let (echo_ms, set_echo_ms) = create_signal(300u32);
+
+    let refresh = move || {
+        spawn_local(async move {
+            match list_thru_routes_command().await {
+                Ok(r) => set_routes.set(r),
+                Err(e) => set_status.set(e),
+            }
+            match list_echo_windows_command().await {
+                Ok(w) => set_echo_windows.set(w),
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+    refresh();
+
+    // None removes the pair's window
+    let set_echo_window = move |input: String, output: String, window_ms: Option<u32>| {
+        spawn_local(async move {
+            match set_echo_window_command(input, output, window_ms).await {
+                Ok(_) => {
+                    set_status.set(String::new());
+                    refresh();
+                }
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    let add_route = move |_| {
+        if input.get().is_empty() || output.get().is_empty() {
+            set_status.set("Choose an input and an output".to_string());
+            return;
+        }
+
+        let route = ThruRoute {
+            id: String::new(),
+            input_device: input.get(),
+            output_device: output.get(),
+            filter: ThruFilter {
+                channels: parse_channels(&channels.get()),
+                message_types: if cc_only.get() {
+                    vec![MessageType::ControlChange]
+                } else {
+                    Vec::new()
+                },
+                cc_range: if (cc_low.get(), cc_high.get()) == (0, 127) {
+                    None
+                } else {
+                    Some((cc_low.get(), cc_high.get()))
+                },
+            },
+            priority: match priority.get().as_str() {
+                "scene" => MergePriority::Scene { hold_ms: hold_ms.get() },
+                "thru" => MergePriority::Thru { hold_ms: hold_ms.get() },
+                _ => MergePriority::LastMessage,
+            },
+            enabled: true,
+        };
+
+        spawn_local(async move {
+            match add_thru_route_command(route).await {
+                Ok(_) => {
+                    set_status.set(String::new());
+                    refresh();
+                }
+                Err(e) => set_status.set(e),
+            }
+        });
+    };
+
+    view! {
+        <div class="thru-routes">
+            <ul class="thru-route-list">
+                {move || routes.get().into_iter().map(|route| {
+                    let id = route.id.clone();
+                    view! {
+                        <li>
+                            <span>{describe_route(&route)}</span>
+                            <button class="button-small"
+                                    on:click=move |_| {
+                                        let id = id.clone();
+                                        spawn_local(async move {
+                                            match remove_thru_route_command(id).await {
+                                                Ok(_) => refresh(),
+                                                Err(e) => set_status.set(e),
+                                            }
+                                        });
+                                    }>
+                                "Remove"
+                            </button>
+                        </li>
+                    }
+                }).collect::<Vec<_>>()}
+            </ul>
+
+            <div class="thru-route-form">
+                <select on:change=move |e| set_input.set(event_target::<HtmlSelectElement>(&e).value())>
+                    <option value="" selected=true disabled=true>"Input"</option>
+                    {move || devices.get().into_iter().filter(|d| d.is_input).map(|d| {
+                        view! { <option value=d.name.clone()>{d.name.clone()}</option> }
+                    }).collect::<Vec<_>>()}
+                </select>
+                <select on:change=move |e| set_output.set(event_target::<HtmlSelectElement>(&e).value())>
+                    <option value="" selected=true disabled=true>"Output"</option>
+                    {move || devices.get().into_iter().filter(|d| !d.is_input).map(|d| {
+                        view! { <option value=d.name.clone()>{d.name.clone()}</option> }
+                    }).collect::<Vec<_>>()}
+                </select>
+                <input type="text"
+                       placeholder="Channels, e.g. 1,10 (empty = all)"
+                       on:input=move |e| set_channels.set(event_target::<HtmlInputElement>(&e).value()) />
+                <label>
+                    <input type="checkbox"
+                           on:change=move |e| set_cc_only.set(event_target::<HtmlInputElement>(&e).checked()) />
+                    "CCs only"
+                </label>
+                <div class="thru-cc-range">
+                    "CC "
+                    <input type="number" min="0" max="127" value="0"
+                           on:input=move |e| set_cc_low.set(event_target::<HtmlInputElement>(&e).value().parse().unwrap_or(0)) />
+                    " – "
+                    <input type="number" min="0" max="127" value="127"
+                           on:input=move |e| set_cc_high.set(event_target::<HtmlInputElement>(&e).value().parse().unwrap_or(127)) />
+                </div>
+                <div class="thru-priority">
+                    <select on:change=move |e| set_priority.set(event_target::<HtmlSelectElement>(&e).value())>
+                        <option value="last" selected=true>"Last message wins"</option>
+                        <option value="scene">"Scene wins"</option>
+                        <option value="thru">"Thru wins"</option>
+                    </select>
+                    <input type="number" min="0" value="500"
+                           disabled=move || priority.get() == "last"
+                           on:input=move |e| set_hold_ms.set(event_target::<HtmlInputElement>(&e).value().parse().unwrap_or(0)) />
+                    " ms"
+                </div>
+                <button on:click=add_route>"Add Route"</button>
+            </div>
+
+            <h4>"Echo windows"</h4>
+            <ul class="thru-route-list">
+                {move || echo_windows.get().into_iter().map(|window| {
+                    let EchoWindow { input_device, output_device, window_ms } = window;
+                    let label = format!("{} → {} ({} ms)", output_device, input_device, window_ms);
+                    view! {
+                        <li>
+                            <span>{label}</span>
+                            <button class="button-small"
+                                    on:click=move |_| set_echo_window(input_device.clone(), output_device.clone(), None)>
+                                "Remove"
+                            </button>
+                        </li>
+                    }
+                }).collect::<Vec<_>>()}
+            </ul>
+
+            <div class="thru-route-form">
+                <select on:change=move |e| set_echo_output.set(event_target::<HtmlSelectElement>(&e).value())>
+                    <option value="" selected=true disabled=true>"Sent to"</option>
+                    {move || devices.get().into_iter().filter(|d| !d.is_input).map(|d| {
+                        view! { <option value=d.name.clone()>{d.name.clone()}</option> }
+                    }).collect::<Vec<_>>()}
+                </select>
+                <select on:change=move |e| set_echo_input.set(event_target::<HtmlSelectElement>(&e).value())>
+                    <option value="" selected=true disabled=true>"Echoes on"</option>
+                    {move || devices.get().into_iter().filter(|d| d.is_input).map(|d| {
+                        view! { <option value=d.name.clone()>{d.name.clone()}</option> }
+                    }).collect::<Vec<_>>()}
+                </select>
+                <div class="thru-priority">
+                    <input type="number" min="1" value="300"
+                           on:input=move |e| set_echo_ms.set(event_target::<HtmlInputElement>(&e).value().parse().unwrap_or(0)) />
+                    " ms"
+                </div>
+                <button on:click=move |_| {
+                    if echo_input.get().is_empty() || echo_output.get().is_empty() {
+                        set_status.set("Choose an output and an input".to_string());
+                        return;
+                    }
+                    set_echo_window(echo_input.get(), echo_output.get(), Some(echo_ms.get()));
+                }>"Set Echo Window"</button>
+            </div>
+
+            <div class="thru-status">{move || status.get()}</div>
+        </div>
+    }
+}
+
+fn event_target<T: JsCast>(e: &leptos::ev::Event) -> T {
+    e.target().unwrap().unchecked_into()
+}
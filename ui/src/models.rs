@@ -22,17 +22,101 @@ pub struct Project {
     pub settings: ProjectSettings,
     pub cc_definitions: HashMap<String, CCDefinition>,
     pub scenes: HashMap<String, Scene>,
-    pub grid_assignments: HashMap<u8, String>, // Position -> SceneId (u8 key matches backend)
+    #[serde(default)]
+    pub grid_size: GridSize,
+    #[serde(default)]
+    pub banks: Vec<SceneBank>,
+    #[serde(default)]
+    pub fader_bindings: Vec<String>, // CC definition key per virtual fader
+    #[serde(default)]
+    pub column_groups: HashMap<u8, String>, // Column -> exclusive group name
+    pub thru_routes: Vec<ThruRoute>,
+    #[serde(default)]
+    pub midi_mappings: Vec<MidiMapping>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectSettings {
-    pub default_output_device: Option<String>,
-    pub default_controller_device: Option<String>,
+    #[serde(default)]
+    pub default_output_devices: Vec<String>,
+    #[serde(default)]
+    pub default_controller_devices: Vec<String>,
     pub auto_connect: bool,
     pub default_tempo: f64,
     pub use_link: bool,
     pub default_quantization: Option<u8>,
+    pub panic_button: Option<u8>,
+    pub panic_sends_defaults: bool,
+    #[serde(default)]
+    pub beat_indicator_button: Option<u8>,
+    #[serde(default)]
+    pub shift_button: Option<u8>,
+    #[serde(default)]
+    pub fader_mode_button: Option<u8>,
+    #[serde(default)]
+    pub echo_windows: Vec<EchoWindow>,
+}
+
+// Jobs a spare controller button can be given, matching the backend
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ControllerButton {
+    Panic,
+    FaderMode,
+    Shift,
+    BeatIndicator,
+}
+
+impl ProjectSettings {
+    pub fn button(&self, button: ControllerButton) -> Option<u8> {
+        match button {
+            ControllerButton::Panic => self.panic_button,
+            ControllerButton::FaderMode => self.fader_mode_button,
+            ControllerButton::Shift => self.shift_button,
+            ControllerButton::BeatIndicator => self.beat_indicator_button,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SceneBank {
+    pub name: String,
+    pub grid_assignments: HashMap<u8, String>, // Position -> SceneId (u8 key matches backend)
+}
+
+// Grid positions count row by row from the bottom-left
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct GridSize {
+    pub rows: u8,
+    pub columns: u8,
+}
+
+impl Default for GridSize {
+    fn default() -> Self {
+        GridSize { rows: 8, columns: 8 }
+    }
+}
+
+// Project cell under the controller's bottom-left pad
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct Viewport {
+    pub row: u8,
+    pub column: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ControllerView {
+    pub device_id: String,
+    pub size: GridSize,
+    pub viewport: Viewport,
+    pub bank: u8,
+    pub fader_mode: bool,
+}
+
+impl ControllerView {
+    pub fn contains(&self, row: u8, column: u8) -> bool {
+        (self.viewport.row..self.viewport.row + self.size.rows).contains(&row)
+            && (self.viewport.column..self.viewport.column + self.size.columns).contains(&column)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,12 +140,54 @@ pub enum TriggerMode {
     NextBar,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum PadMode {
+    #[default]
+    Latch,
+    Momentary,
+    Toggle,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ExpressionRange {
+    #[serde(default)]
+    pub curve: TransitionCurve,
+    #[serde(default)]
+    pub min: f64,
+    #[serde(default = "full_amount")]
+    pub max: f64,
+}
+
+fn full_amount() -> f64 {
+    1.0
+}
+
+impl Default for ExpressionRange {
+    fn default() -> Self {
+        ExpressionRange {
+            curve: TransitionCurve::Linear,
+            min: 0.0,
+            max: 1.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Scene {
     pub id: String,
     pub name: String,
     pub description: Option<String>,
     pub trigger_mode: TriggerMode,
+    #[serde(default)]
+    pub pad_mode: PadMode,
+    #[serde(default)]
+    pub release_transition_ms: Option<u32>,
+    #[serde(default)]
+    pub velocity: Option<ExpressionRange>,
+    #[serde(default)]
+    pub pressure: Option<ExpressionRange>,
+    #[serde(default)]
+    pub group: Option<String>,
     pub cc_values: HashMap<String, CCValue>,
     pub tags: Vec<String>,
     pub active: bool,
@@ -71,8 +197,9 @@ pub struct Scene {
 }
 
 // CC value models
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
 pub enum TransitionCurve {
+    #[default]
     Linear,
     Exponential,
     Logarithmic,
@@ -111,6 +238,111 @@ pub struct MidiDevice {
     pub name: String,
     pub is_input: bool,
     pub is_controller: bool,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub firmware: Option<String>,
+}
+
+// MIDI thru models
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum MessageType {
+    NoteOff,
+    NoteOn,
+    PolyPressure,
+    ControlChange,
+    ProgramChange,
+    ChannelPressure,
+    PitchBend,
+    System,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ThruFilter {
+    pub channels: Vec<u8>,
+    pub message_types: Vec<MessageType>,
+    pub cc_range: Option<(u8, u8)>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum MergePriority {
+    LastMessage,
+    Scene { hold_ms: u32 },
+    Thru { hold_ms: u32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ThruRoute {
+    pub id: String,
+    pub input_device: String,
+    pub output_device: String,
+    pub filter: ThruFilter,
+    pub priority: MergePriority,
+    pub enabled: bool,
+}
+
+// MIDI learn models
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum MidiTrigger {
+    ControlChange { channel: u8, cc_number: u8 },
+    Note { channel: u8, note: u8 },
+    ProgramChange { channel: u8, program: u8 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum LearnAction {
+    TriggerScene { scene_id: String },
+    NextScene,
+    PreviousScene,
+    Panic,
+    BankUp,
+    BankDown,
+    TapTempo,
+    MorphAmount,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MidiMapping {
+    pub id: String,
+    pub input_device: Option<String>, // None matches every input
+    pub trigger: MidiTrigger,
+    pub action: LearnAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum SnapshotPoints {
+    Markers,
+    Bars(Vec<u32>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportOptions {
+    pub points: SnapshotPoints,
+    pub detect_ramps: bool,
+    pub beats_per_bar: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum TrackSplit {
+    #[default]
+    PerOutput,
+    PerChannel,
+}
+
+// Echo-suppression window for an input/output pair
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EchoWindow {
+    pub input_device: String,
+    pub output_device: String,
+    pub window_ms: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuppressedEvent {
+    pub timestamp: String,
+    pub input_device: String,
+    pub output_device: String,
+    pub message: Vec<u8>,
 }
 
 // AI Generation models
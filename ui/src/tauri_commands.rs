@@ -61,6 +61,30 @@ where
         .map_err(|e| format!("Failed to deserialize result: {:?}", e))
 }
 
+// Run a callback whenever the backend emits an event
+pub(crate) fn listen(event: &str, callback: impl Fn() + 'static) -> Result<(), String> {
+    let window = web_sys::window().unwrap();
+
+    let tauri = js_sys::Reflect::get(&window, &JsValue::from_str("__TAURI__"))
+        .map_err(|_| "Tauri API not found".to_string())?;
+    let event_api = js_sys::Reflect::get(&tauri, &JsValue::from_str("event"))
+        .map_err(|_| "Tauri event API not found".to_string())?;
+    let listen_fn = js_sys::Reflect::get(&event_api, &JsValue::from_str("listen"))
+        .map_err(|_| "Tauri listen function not found".to_string())?;
+
+    let handler = Closure::<dyn Fn(JsValue)>::new(move |_| callback());
+    listen_fn
+        .dyn_into::<js_sys::Function>()
+        .map_err(|_| "Tauri listen is not a function".to_string())?
+        .call2(&event_api, &JsValue::from_str(event), handler.as_ref())
+        .map_err(|e| format!("Failed to listen for {}: {:?}", event, e))?;
+
+    // Listeners stay registered for the life of the app
+    handler.forget();
+
+    Ok(())
+}
+
 // Project management commands
 
 pub async fn list_projects() -> Result<Vec<ProjectMeta>, String> {
@@ -209,6 +233,24 @@ pub async fn get_scene_command(id: String) -> Result<Scene, String> {
     }
 }
 
+pub async fn update_scene_command(scene: Scene) -> Result<(), String> {
+    #[derive(Serialize)]
+    struct UpdateSceneArgs {
+        scene: Scene,
+    }
+
+    let args = UpdateSceneArgs { scene };
+    let response: CommandResponse<bool> = invoke("update_scene", Some(args)).await?;
+
+    match response {
+        CommandResponse { success: true, .. } => Ok(()),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error updating scene".to_string()),
+    }
+}
+
 pub async fn activate_scene_command(id: String) -> Result<bool, String> {
     #[derive(Serialize)]
     struct ActivateSceneArgs {
@@ -233,6 +275,59 @@ pub async fn activate_scene_command(id: String) -> Result<bool, String> {
     }
 }
 
+pub async fn get_active_scenes_command() -> Result<Vec<String>, String> {
+    let response: CommandResponse<Vec<String>> = invoke("get_active_scenes", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(ids),
+            ..
+        } => Ok(ids),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error getting active scenes".to_string()),
+    }
+}
+
+pub async fn release_group_command(group: String) -> Result<(), String> {
+    #[derive(Serialize)]
+    struct ReleaseGroupArgs {
+        group: String,
+    }
+
+    let args = ReleaseGroupArgs { group };
+    let response: CommandResponse<bool> = invoke("release_group", Some(args)).await?;
+
+    match response {
+        CommandResponse { success: true, .. } => Ok(()),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error releasing group".to_string()),
+    }
+}
+
+pub async fn set_column_group_command(column: u8, name: Option<String>) -> Result<(), String> {
+    #[derive(Serialize)]
+    struct ColumnGroupArgs {
+        column: u8,
+        name: Option<String>,
+    }
+
+    let args = ColumnGroupArgs { column, name };
+    let response: CommandResponse<bool> = invoke("set_column_group", Some(args)).await?;
+
+    match response {
+        CommandResponse { success: true, .. } => Ok(()),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error setting column group".to_string()),
+    }
+}
+
 pub async fn assign_scene_to_grid_command(scene_id: String, position: u8) -> Result<bool, String> {
     #[derive(Serialize)]
     struct AssignSceneArgs {
@@ -258,6 +353,232 @@ pub async fn assign_scene_to_grid_command(scene_id: String, position: u8) -> Res
     }
 }
 
+pub async fn get_current_bank_command() -> Result<u8, String> {
+    let response: CommandResponse<u8> = invoke("get_current_bank", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(bank),
+            ..
+        } => Ok(bank),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error getting current bank".to_string()),
+    }
+}
+
+pub async fn select_scene_bank_command(bank: u8) -> Result<bool, String> {
+    #[derive(Serialize)]
+    struct BankArgs {
+        bank: u8,
+    }
+
+    let response: CommandResponse<bool> =
+        invoke("select_scene_bank", Some(BankArgs { bank })).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(selected),
+            ..
+        } => Ok(selected),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error selecting bank".to_string()),
+    }
+}
+
+pub async fn add_scene_bank_command(name: String) -> Result<u8, String> {
+    #[derive(Serialize)]
+    struct AddBankArgs {
+        name: String,
+    }
+
+    let response: CommandResponse<u8> =
+        invoke("add_scene_bank", Some(AddBankArgs { name })).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(bank),
+            ..
+        } => Ok(bank),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error adding bank".to_string()),
+    }
+}
+
+pub async fn remove_scene_bank_command(bank: u8) -> Result<bool, String> {
+    #[derive(Serialize)]
+    struct BankArgs {
+        bank: u8,
+    }
+
+    let response: CommandResponse<bool> =
+        invoke("remove_scene_bank", Some(BankArgs { bank })).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(removed),
+            ..
+        } => Ok(removed),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error removing bank".to_string()),
+    }
+}
+
+pub async fn set_grid_size_command(rows: u8, columns: u8) -> Result<Vec<String>, String> {
+    #[derive(Serialize)]
+    struct GridSizeArgs {
+        rows: u8,
+        columns: u8,
+    }
+
+    let args = GridSizeArgs { rows, columns };
+    let response: CommandResponse<Vec<String>> = invoke("set_grid_size", Some(args)).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(unassigned),
+            ..
+        } => Ok(unassigned),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error setting grid size".to_string()),
+    }
+}
+
+pub async fn get_controller_views_command() -> Result<Vec<ControllerView>, String> {
+    let response: CommandResponse<Vec<ControllerView>> =
+        invoke("get_controller_views", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(views),
+            ..
+        } => Ok(views),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error getting controller views".to_string()),
+    }
+}
+
+pub async fn scroll_controller_viewport_command(
+    device_id: String,
+    rows: i8,
+    columns: i8,
+) -> Result<Option<ControllerView>, String> {
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ScrollArgs {
+        device_id: String,
+        rows: i8,
+        columns: i8,
+    }
+
+    let args = ScrollArgs {
+        device_id,
+        rows,
+        columns,
+    };
+    let response: CommandResponse<ControllerView> =
+        invoke("scroll_controller_viewport", Some(args)).await?;
+
+    match response {
+        CommandResponse {
+            success: true, data, ..
+        } => Ok(data),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error scrolling controller".to_string()),
+    }
+}
+
+pub async fn select_controller_bank_command(device_id: String, bank: u8) -> Result<(), String> {
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ControllerBankArgs {
+        device_id: String,
+        bank: u8,
+    }
+
+    let args = ControllerBankArgs { device_id, bank };
+    let response: CommandResponse<bool> = invoke("select_controller_bank", Some(args)).await?;
+
+    match response {
+        CommandResponse { success: true, .. } => Ok(()),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error selecting controller bank".to_string()),
+    }
+}
+
+pub async fn set_fader_mode_command(device_id: String, enabled: bool) -> Result<bool, String> {
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct FaderModeArgs {
+        device_id: String,
+        enabled: bool,
+    }
+
+    let args = FaderModeArgs { device_id, enabled };
+    let response: CommandResponse<bool> = invoke("set_fader_mode", Some(args)).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(enabled),
+            ..
+        } => Ok(enabled),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error switching fader mode".to_string()),
+    }
+}
+
+pub async fn set_fader_bindings_command(keys: Vec<String>) -> Result<(), String> {
+    #[derive(Serialize)]
+    struct FaderBindingsArgs {
+        keys: Vec<String>,
+    }
+
+    let args = FaderBindingsArgs { keys };
+    let response: CommandResponse<bool> = invoke("set_fader_bindings", Some(args)).await?;
+
+    match response {
+        CommandResponse { success: true, .. } => Ok(()),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error binding faders".to_string()),
+    }
+}
+
 // MIDI device commands
 
 pub async fn list_midi_devices() -> Result<Vec<MidiDevice>, String> {
@@ -279,6 +600,64 @@ pub async fn list_midi_devices() -> Result<Vec<MidiDevice>, String> {
     }
 }
 
+pub async fn set_auto_connect_command(enabled: bool) -> Result<bool, String> {
+    #[derive(Serialize)]
+    struct AutoConnectArgs {
+        enabled: bool,
+    }
+
+    let args = AutoConnectArgs { enabled };
+    let response: CommandResponse<bool> = invoke("set_auto_connect", Some(args)).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(enabled),
+            ..
+        } => Ok(enabled),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error setting auto-connect".to_string()),
+    }
+}
+
+pub async fn set_use_link_command(enabled: bool) -> Result<(), String> {
+    #[derive(Serialize)]
+    struct UseLinkArgs {
+        enabled: bool,
+    }
+
+    let args = UseLinkArgs { enabled };
+    let response: CommandResponse<bool> = invoke("set_use_link", Some(args)).await?;
+
+    match response {
+        CommandResponse { success: true, .. } => Ok(()),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error setting Link sync".to_string()),
+    }
+}
+
+pub async fn connect_output_command(name: String) -> Result<(), String> {
+    #[derive(Serialize)]
+    struct OutputArgs {
+        name: String,
+    }
+
+    let args = OutputArgs { name };
+    let response: CommandResponse<bool> = invoke("connect_output", Some(args)).await?;
+
+    match response {
+        CommandResponse { success: true, .. } => Ok(()),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error connecting output".to_string()),
+    }
+}
+
 pub async fn disconnect_controller_command() -> Result<bool, String> {
     let response: CommandResponse<bool> = invoke("disconnect_controller", None::<()>).await?;
 
@@ -327,44 +706,506 @@ pub async fn send_cc_command(channel: u8, cc_number: u8, value: u8) -> Result<bo
     }
 }
 
-// AI generation commands
+pub async fn panic_command(defaults: bool) -> Result<bool, String> {
+    #[derive(Serialize)]
+    struct PanicArgs {
+        defaults: bool,
+    }
 
-pub async fn generate_scene_command(params: GenerationParams) -> Result<GeneratedScene, String> {
-    let response: CommandResponse<GeneratedScene> = invoke("generate_scene", Some(params)).await?;
+    let args = PanicArgs { defaults };
+    let response: CommandResponse<bool> = invoke("panic", Some(args)).await?;
 
     match response {
         CommandResponse {
             success: true,
-            data: Some(generated),
+            data: Some(sent),
             ..
-        } => Ok(generated),
+        } => Ok(sent),
         CommandResponse {
             success: false,
             error: Some(err),
             ..
         } => Err(err),
-        _ => Err("Unknown error generating scene".to_string()),
+        _ => Err("Unknown error sending panic".to_string()),
     }
 }
 
-pub async fn save_generated_scene_command(
-    generated_scene: GeneratedScene,
-) -> Result<String, String> {
-    let response: CommandResponse<String> =
-        invoke("save_generated_scene", Some(generated_scene)).await?;
+pub async fn set_controller_button_command(
+    button: ControllerButton,
+    button_id: Option<u8>,
+) -> Result<(), String> {
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ControllerButtonArgs {
+        button: ControllerButton,
+        button_id: Option<u8>,
+    }
+
+    let args = ControllerButtonArgs { button, button_id };
+    let response: CommandResponse<bool> = invoke("set_controller_button", Some(args)).await?;
+
+    match response {
+        CommandResponse { success: true, .. } => Ok(()),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error setting controller button".to_string()),
+    }
+}
+
+pub async fn set_panic_sends_defaults_command(enabled: bool) -> Result<(), String> {
+    #[derive(Serialize)]
+    struct PanicDefaultsArgs {
+        enabled: bool,
+    }
+
+    let args = PanicDefaultsArgs { enabled };
+    let response: CommandResponse<bool> = invoke("set_panic_sends_defaults", Some(args)).await?;
+
+    match response {
+        CommandResponse { success: true, .. } => Ok(()),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error setting panic defaults".to_string()),
+    }
+}
+
+pub async fn import_midi_scenes_command(
+    path: String,
+    options: ImportOptions,
+) -> Result<Vec<String>, String> {
+    #[derive(Serialize)]
+    struct ImportMidiArgs {
+        path: String,
+        options: ImportOptions,
+    }
+
+    let args = ImportMidiArgs { path, options };
+    let response: CommandResponse<Vec<String>> =
+        invoke("import_midi_scenes", Some(args)).await?;
 
     match response {
         CommandResponse {
             success: true,
-            data: Some(id),
+            data: Some(ids),
             ..
-        } => Ok(id),
+        } => Ok(ids),
         CommandResponse {
             success: false,
             error: Some(err),
             ..
         } => Err(err),
-        _ => Err("Unknown error saving generated scene".to_string()),
+        _ => Err("Unknown error importing MIDI file".to_string()),
+    }
+}
+
+// Recording commands
+
+pub async fn start_recording_command() -> Result<bool, String> {
+    let response: CommandResponse<bool> = invoke("start_recording", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(started),
+            ..
+        } => Ok(started),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error starting recording".to_string()),
+    }
+}
+
+pub async fn stop_recording_command(path: String, split: TrackSplit) -> Result<usize, String> {
+    #[derive(Serialize)]
+    struct StopRecordingArgs {
+        path: String,
+        split: TrackSplit,
+    }
+
+    let args = StopRecordingArgs { path, split };
+    let response: CommandResponse<usize> = invoke("stop_recording", Some(args)).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(count),
+            ..
+        } => Ok(count),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error saving recording".to_string()),
+    }
+}
+
+pub async fn is_recording_command() -> Result<bool, String> {
+    let response: CommandResponse<bool> = invoke("is_recording", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(recording),
+            ..
+        } => Ok(recording),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error checking recording state".to_string()),
+    }
+}
+
+// MIDI thru commands
+
+pub async fn list_thru_routes_command() -> Result<Vec<ThruRoute>, String> {
+    let response: CommandResponse<Vec<ThruRoute>> =
+        invoke("list_thru_routes", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(routes),
+            ..
+        } => Ok(routes),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error listing thru routes".to_string()),
+    }
+}
+
+pub async fn add_thru_route_command(route: ThruRoute) -> Result<String, String> {
+    #[derive(Serialize)]
+    struct AddThruRouteArgs {
+        route: ThruRoute,
+    }
+
+    let args = AddThruRouteArgs { route };
+    let response: CommandResponse<String> = invoke("add_thru_route", Some(args)).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(id),
+            ..
+        } => Ok(id),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error adding thru route".to_string()),
+    }
+}
+
+pub async fn remove_thru_route_command(id: String) -> Result<bool, String> {
+    #[derive(Serialize)]
+    struct RemoveThruRouteArgs {
+        id: String,
+    }
+
+    let args = RemoveThruRouteArgs { id };
+    let response: CommandResponse<bool> = invoke("remove_thru_route", Some(args)).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(removed),
+            ..
+        } => Ok(removed),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error removing thru route".to_string()),
+    }
+}
+
+// MIDI learn commands
+
+pub async fn add_midi_mapping_command(mapping: MidiMapping) -> Result<String, String> {
+    #[derive(Serialize)]
+    struct AddMidiMappingArgs {
+        mapping: MidiMapping,
+    }
+
+    let args = AddMidiMappingArgs { mapping };
+    let response: CommandResponse<String> = invoke("add_midi_mapping", Some(args)).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(id),
+            ..
+        } => Ok(id),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error adding MIDI mapping".to_string()),
+    }
+}
+
+pub async fn remove_midi_mapping_command(id: String) -> Result<bool, String> {
+    #[derive(Serialize)]
+    struct RemoveMidiMappingArgs {
+        id: String,
+    }
+
+    let args = RemoveMidiMappingArgs { id };
+    let response: CommandResponse<bool> = invoke("remove_midi_mapping", Some(args)).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(removed),
+            ..
+        } => Ok(removed),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error removing MIDI mapping".to_string()),
+    }
+}
+
+pub async fn start_midi_learn_command(action: LearnAction) -> Result<bool, String> {
+    #[derive(Serialize)]
+    struct StartMidiLearnArgs {
+        action: LearnAction,
+    }
+
+    let args = StartMidiLearnArgs { action };
+    let response: CommandResponse<bool> = invoke("start_midi_learn", Some(args)).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(started),
+            ..
+        } => Ok(started),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error starting MIDI learn".to_string()),
+    }
+}
+
+pub async fn cancel_midi_learn_command() -> Result<bool, String> {
+    let response: CommandResponse<bool> = invoke("cancel_midi_learn", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(cancelled),
+            ..
+        } => Ok(cancelled),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error cancelling MIDI learn".to_string()),
+    }
+}
+
+/// The action waiting for a MIDI message, if learn mode is on
+pub async fn get_midi_learn_command() -> Result<Option<LearnAction>, String> {
+    let response: CommandResponse<LearnAction> = invoke("get_midi_learn", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true, data, ..
+        } => Ok(data),
+        CommandResponse {
+            error: Some(err), ..
+        } => Err(err),
+        _ => Err("Unknown error getting MIDI learn".to_string()),
+    }
+}
+
+// AI generation commands
+
+pub async fn generate_scene_command(params: GenerationParams) -> Result<GeneratedScene, String> {
+    let response: CommandResponse<GeneratedScene> = invoke("generate_scene", Some(params)).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(generated),
+            ..
+        } => Ok(generated),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error generating scene".to_string()),
+    }
+}
+
+pub async fn save_generated_scene_command(
+    generated_scene: GeneratedScene,
+) -> Result<String, String> {
+    let response: CommandResponse<String> =
+        invoke("save_generated_scene", Some(generated_scene)).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(id),
+            ..
+        } => Ok(id),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error saving generated scene".to_string()),
+    }
+}
+
+pub async fn get_suppressed_events() -> Result<Vec<SuppressedEvent>, String> {
+    let response: CommandResponse<Vec<SuppressedEvent>> =
+        invoke("get_suppressed_events", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(events),
+            ..
+        } => Ok(events),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error getting suppressed events".to_string()),
+    }
+}
+
+pub async fn get_warnings() -> Result<Vec<String>, String> {
+    let response: CommandResponse<Vec<String>> = invoke("get_warnings", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(warnings),
+            ..
+        } => Ok(warnings),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error getting warnings".to_string()),
+    }
+}
+
+pub async fn list_skipped_profiles() -> Result<Vec<String>, String> {
+    let response: CommandResponse<Vec<String>> =
+        invoke("list_skipped_profiles", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(skipped),
+            ..
+        } => Ok(skipped),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error listing skipped profiles".to_string()),
+    }
+}
+
+pub async fn reload_controller_profiles() -> Result<usize, String> {
+    let response: CommandResponse<usize> =
+        invoke("reload_controller_profiles", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(count),
+            ..
+        } => Ok(count),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error reloading controller profiles".to_string()),
+    }
+}
+
+pub async fn list_echo_windows_command() -> Result<Vec<EchoWindow>, String> {
+    let response: CommandResponse<Vec<EchoWindow>> =
+        invoke("list_echo_windows", None::<()>).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(windows),
+            ..
+        } => Ok(windows),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error listing echo windows".to_string()),
+    }
+}
+
+pub async fn set_echo_window_command(
+    input_device: String,
+    output_device: String,
+    window_ms: Option<u32>,
+) -> Result<bool, String> {
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct EchoWindowArgs {
+        input_device: String,
+        output_device: String,
+        window_ms: Option<u32>,
+    }
+
+    let args = EchoWindowArgs {
+        input_device,
+        output_device,
+        window_ms,
+    };
+    let response: CommandResponse<bool> = invoke("set_echo_window", Some(args)).await?;
+
+    match response {
+        CommandResponse {
+            success: true,
+            data: Some(done),
+            ..
+        } => Ok(done),
+        CommandResponse {
+            success: false,
+            error: Some(err),
+            ..
+        } => Err(err),
+        _ => Err("Unknown error setting echo window".to_string()),
     }
 }
 